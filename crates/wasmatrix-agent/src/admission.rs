@@ -0,0 +1,173 @@
+//! Pre-instantiation checks on the memory and tables a module declares.
+//!
+//! A module declaring a huge initial memory would only fail once wasmtime tries
+//! to reserve it; reading the sizes up front rejects it with a clear message.
+
+use wasmatrix_core::{CoreError, Result};
+use wasmparser::{Parser, Payload, TypeRef};
+
+/// Bytes in one WebAssembly memory page
+pub const WASM_PAGE_SIZE: u64 = 65_536;
+
+/// Memory and table sizes a module declares, summed over all its memories and
+/// tables, defined or imported. A maximum is `None` when any of them is unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuleResources {
+    pub initial_memory_bytes: u64,
+    pub max_memory_bytes: Option<u64>,
+    pub initial_table_elements: u64,
+    pub max_table_elements: Option<u64>,
+}
+
+impl ModuleResources {
+    fn add_memory(&mut self, initial_pages: u64, maximum_pages: Option<u64>) {
+        let bytes = |pages: u64| pages.saturating_mul(WASM_PAGE_SIZE);
+        self.initial_memory_bytes = self
+            .initial_memory_bytes
+            .saturating_add(bytes(initial_pages));
+        self.max_memory_bytes = self
+            .max_memory_bytes
+            .zip(maximum_pages)
+            .map(|(total, pages)| total.saturating_add(bytes(pages)));
+    }
+
+    fn add_table(&mut self, initial: u32, maximum: Option<u32>) {
+        self.initial_table_elements = self.initial_table_elements.saturating_add(initial.into());
+        self.max_table_elements = self
+            .max_table_elements
+            .zip(maximum)
+            .map(|(total, elements)| total.saturating_add(elements.into()));
+    }
+}
+
+/// Read the memory and table limits from a core module's import, memory and
+/// table sections without compiling it
+pub fn inspect_module_resources(module_bytes: &[u8]) -> Result<ModuleResources> {
+    let mut resources = ModuleResources {
+        max_memory_bytes: Some(0),
+        max_table_elements: Some(0),
+        ..ModuleResources::default()
+    };
+    let invalid = |e: wasmparser::BinaryReaderError| {
+        CoreError::WasmRuntimeError(format!("Invalid Wasm module: {}", e))
+    };
+
+    for payload in Parser::new(0).parse_all(module_bytes) {
+        match payload.map_err(invalid)? {
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    match import.map_err(invalid)?.ty {
+                        TypeRef::Memory(memory) => {
+                            resources.add_memory(memory.initial, memory.maximum)
+                        }
+                        TypeRef::Table(table) => resources.add_table(table.initial, table.maximum),
+                        _ => {}
+                    }
+                }
+            }
+            Payload::MemorySection(memories) => {
+                for memory in memories {
+                    let memory = memory.map_err(invalid)?;
+                    resources.add_memory(memory.initial, memory.maximum);
+                }
+            }
+            Payload::TableSection(tables) => {
+                for table in tables {
+                    let table = table.map_err(invalid)?.ty;
+                    resources.add_table(table.initial, table.maximum);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(resources)
+}
+
+/// Node-wide ceilings on what a single instance may declare; `None` is unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AdmissionLimits {
+    pub max_memory_bytes: Option<u64>,
+    pub max_table_elements: Option<u64>,
+}
+
+impl AdmissionLimits {
+    /// Reject a module whose initial or declared maximum memory exceeds the
+    /// smaller of `instance_max_memory_bytes` and the node limit, or whose
+    /// tables exceed the node's element limit
+    pub fn check(
+        &self,
+        resources: &ModuleResources,
+        instance_max_memory_bytes: Option<u64>,
+    ) -> Result<()> {
+        let memory_limit = match (self.max_memory_bytes, instance_max_memory_bytes) {
+            (Some(node), Some(instance)) => Some(node.min(instance)),
+            (node, instance) => node.or(instance),
+        };
+        if let Some(limit) = memory_limit {
+            if resources.initial_memory_bytes > limit {
+                return Err(CoreError::ResourceExhausted(format!(
+                    "Module requests {} bytes of initial memory, limit is {}",
+                    resources.initial_memory_bytes, limit
+                )));
+            }
+            if let Some(maximum) = resources.max_memory_bytes.filter(|max| *max > limit) {
+                return Err(CoreError::ResourceExhausted(format!(
+                    "Module declares a maximum of {} bytes of memory, limit is {}",
+                    maximum, limit
+                )));
+            }
+        }
+
+        if let Some(limit) = self.max_table_elements {
+            if resources.initial_table_elements > limit {
+                return Err(CoreError::ResourceExhausted(format!(
+                    "Module requests {} initial table elements, limit is {}",
+                    resources.initial_table_elements, limit
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_sums_memory_and_table_limits() {
+        let module = wat::parse_str(
+            r#"(module
+                 (import "env" "table" (table 4 funcref))
+                 (memory 2 10)
+                 (table 3 8 funcref))"#,
+        )
+        .unwrap();
+
+        let resources = inspect_module_resources(&module).unwrap();
+        assert_eq!(resources.initial_memory_bytes, 2 * WASM_PAGE_SIZE);
+        assert_eq!(resources.max_memory_bytes, Some(10 * WASM_PAGE_SIZE));
+        assert_eq!(resources.initial_table_elements, 7);
+        // The imported table has no maximum
+        assert_eq!(resources.max_table_elements, None);
+    }
+
+    #[test]
+    fn test_check_uses_smaller_of_instance_and_node_limit() {
+        let module = wat::parse_str("(module (memory 16))").unwrap();
+        let resources = inspect_module_resources(&module).unwrap();
+        let node = AdmissionLimits {
+            max_memory_bytes: Some(64 * WASM_PAGE_SIZE),
+            max_table_elements: None,
+        };
+
+        assert!(node.check(&resources, None).is_ok());
+        let error = node
+            .check(&resources, Some(8 * WASM_PAGE_SIZE))
+            .unwrap_err();
+        assert!(matches!(error, CoreError::ResourceExhausted(_)));
+        assert!(error.to_string().contains("initial memory"));
+    }
+}
@@ -1 +1,2 @@
+pub mod observability;
 pub mod status_reporting;
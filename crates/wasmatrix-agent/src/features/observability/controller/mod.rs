@@ -0,0 +1,145 @@
+use crate::features::observability::repo::{
+    MetricsRepository, NoopMetricsRepository, ObservabilityRepository,
+};
+use crate::features::observability::service::ObservabilityService;
+use axum::routing::get;
+use axum::Router;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+pub struct ObservabilityController {
+    service: ObservabilityService,
+}
+
+impl ObservabilityController {
+    pub fn new(service: ObservabilityService) -> Self {
+        Self { service }
+    }
+
+    /// Controller backed by its own registry, independent of the global one
+    pub fn standalone() -> Result<Self, String> {
+        let repo = Arc::new(ObservabilityRepository::new()?);
+        Ok(Self::new(ObservabilityService::new(repo)))
+    }
+
+    pub fn record_module_compile(&self, elapsed: Duration) {
+        self.service.record_module_compile(elapsed);
+    }
+
+    pub fn record_instance_instantiate(&self, elapsed: Duration) {
+        self.service.record_instance_instantiate(elapsed);
+    }
+
+    pub fn record_warm_pool_hit(&self) {
+        self.service.record_warm_pool_hit();
+    }
+
+    pub fn record_status_report(&self, succeeded: bool) {
+        self.service.record_status_report(succeeded);
+    }
+
+    pub fn set_active_instances(&self, count: usize) {
+        self.service.set_active_instances(count);
+    }
+
+    pub fn record_instance_crash(&self) {
+        self.service.record_instance_crash();
+    }
+
+    pub fn record_instance_restart(&self) {
+        self.service.record_instance_restart();
+    }
+
+    pub fn render_metrics(&self) -> Result<String, String> {
+        self.service.render_metrics()
+    }
+}
+
+static GLOBAL_OBSERVABILITY: OnceLock<Arc<ObservabilityController>> = OnceLock::new();
+
+pub fn global_observability_controller() -> Arc<ObservabilityController> {
+    GLOBAL_OBSERVABILITY
+        .get_or_init(|| Arc::new(controller_from_metrics_init(ObservabilityRepository::new())))
+        .clone()
+}
+
+/// Build a controller from the result of metrics initialisation, falling back
+/// to no-op metrics so a registry error never takes the process down
+fn controller_from_metrics_init(
+    init: Result<ObservabilityRepository, String>,
+) -> ObservabilityController {
+    let repo = wasmatrix_observability::init_or_fallback(
+        init.map(|repo| Arc::new(repo) as Arc<dyn MetricsRepository>),
+        || Arc::new(NoopMetricsRepository),
+    );
+    ObservabilityController::new(ObservabilityService::new(repo))
+}
+
+/// Agent and provider metrics from the global registries, in text exposition format
+pub fn render_node_metrics() -> String {
+    [
+        global_observability_controller().render_metrics(),
+        wasmatrix_providers::features::observability::controller::global_observability_controller()
+            .render_metrics(),
+    ]
+    .into_iter()
+    .map(|rendered| {
+        rendered.unwrap_or_else(|e| format!("metrics_render_error{{reason=\"{}\"}} 1\n", e))
+    })
+    .collect()
+}
+
+/// HTTP router serving [`render_node_metrics`] at `/metrics`
+pub fn metrics_router() -> Router {
+    Router::new().route("/metrics", get(|| async { render_node_metrics() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_metrics_render_contains_startup_histograms() {
+        let controller = ObservabilityController::standalone().unwrap();
+        controller.record_module_compile(Duration::from_millis(5));
+        controller.record_instance_instantiate(Duration::from_millis(1));
+
+        let rendered = controller.render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_module_compile_seconds_count 1"));
+        assert!(rendered.contains("wasmatrix_instance_instantiate_seconds_count 1"));
+    }
+
+    #[test]
+    fn test_failed_metrics_init_falls_back_to_noop_controller() {
+        let controller =
+            controller_from_metrics_init(Err("duplicate metrics collector".to_string()));
+        controller.record_module_compile(Duration::from_millis(5));
+        controller.record_status_report(false);
+        controller.set_active_instances(3);
+
+        assert_eq!(controller.render_metrics(), Ok(String::new()));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_server_renders_instance_gauges() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, metrics_router()).await });
+        global_observability_controller().record_instance_crash();
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("wasmatrix_agent_active_instances"));
+        assert!(response.contains("wasmatrix_agent_instance_crash_total"));
+        assert!(response.contains("wasmatrix_agent_instance_restart_total"));
+        assert!(response.contains("wasmatrix_messaging_subscriptions"));
+    }
+}
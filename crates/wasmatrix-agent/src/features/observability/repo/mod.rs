@@ -0,0 +1,144 @@
+use wasmatrix_observability::prometheus::{Histogram, HistogramOpts, IntCounter, IntGauge};
+use wasmatrix_observability::MetricsRegistry;
+
+/// Sink for node agent metrics
+pub trait MetricsRepository: Send + Sync {
+    fn observe_module_compile(&self, seconds: f64);
+    fn observe_instance_instantiate(&self, seconds: f64);
+    fn inc_warm_pool_hits(&self);
+    fn inc_status_reports(&self);
+    fn inc_status_report_failures(&self);
+    fn set_active_instances(&self, count: i64);
+    fn inc_instance_crash(&self);
+    fn inc_instance_restart(&self);
+    fn render_metrics(&self) -> Result<String, String>;
+}
+
+pub struct ObservabilityRepository {
+    registry: MetricsRegistry,
+    module_compile_seconds: Histogram,
+    instance_instantiate_seconds: Histogram,
+    warm_pool_hits_total: IntCounter,
+    status_reports_total: IntCounter,
+    status_report_failures_total: IntCounter,
+    active_instances: IntGauge,
+    instance_crash_total: IntCounter,
+    instance_restart_total: IntCounter,
+}
+
+impl ObservabilityRepository {
+    pub fn new() -> Result<Self, String> {
+        let registry = MetricsRegistry::new();
+
+        let module_compile_seconds =
+            registry.register(Histogram::with_opts(HistogramOpts::new(
+                "wasmatrix_module_compile_seconds",
+                "Wasm module compilation time (seconds)",
+            )))?;
+        let instance_instantiate_seconds =
+            registry.register(Histogram::with_opts(HistogramOpts::new(
+                "wasmatrix_instance_instantiate_seconds",
+                "Wasm instance instantiation time (seconds)",
+            )))?;
+        let warm_pool_hits_total = registry.register(IntCounter::new(
+            "wasmatrix_warm_pool_hits_total",
+            "Instance starts served from the warm pool without compiling",
+        ))?;
+        let status_reports_total = registry.register(IntCounter::new(
+            "wasmatrix_status_reports_total",
+            "Status reports attempted to the control plane",
+        ))?;
+        let status_report_failures_total = registry.register(IntCounter::new(
+            "wasmatrix_status_report_failures_total",
+            "Status reports the control plane did not accept",
+        ))?;
+        let active_instances = registry.register(IntGauge::new(
+            "wasmatrix_agent_active_instances",
+            "Instances currently held by this node agent",
+        ))?;
+        let instance_crash_total = registry.register(IntCounter::new(
+            "wasmatrix_agent_instance_crash_total",
+            "Instance crashes detected by this node agent",
+        ))?;
+        let instance_restart_total = registry.register(IntCounter::new(
+            "wasmatrix_agent_instance_restart_total",
+            "Successful instance restarts on this node agent",
+        ))?;
+
+        Ok(Self {
+            registry,
+            module_compile_seconds,
+            instance_instantiate_seconds,
+            warm_pool_hits_total,
+            status_reports_total,
+            status_report_failures_total,
+            active_instances,
+            instance_crash_total,
+            instance_restart_total,
+        })
+    }
+}
+
+impl MetricsRepository for ObservabilityRepository {
+    fn observe_module_compile(&self, seconds: f64) {
+        self.module_compile_seconds.observe(seconds);
+    }
+
+    fn observe_instance_instantiate(&self, seconds: f64) {
+        self.instance_instantiate_seconds.observe(seconds);
+    }
+
+    fn inc_warm_pool_hits(&self) {
+        self.warm_pool_hits_total.inc();
+    }
+
+    fn inc_status_reports(&self) {
+        self.status_reports_total.inc();
+    }
+
+    fn inc_status_report_failures(&self) {
+        self.status_report_failures_total.inc();
+    }
+
+    fn set_active_instances(&self, count: i64) {
+        self.active_instances.set(count);
+    }
+
+    fn inc_instance_crash(&self) {
+        self.instance_crash_total.inc();
+    }
+
+    fn inc_instance_restart(&self) {
+        self.instance_restart_total.inc();
+    }
+
+    fn render_metrics(&self) -> Result<String, String> {
+        self.registry.render()
+    }
+}
+
+/// Stand-in used when the Prometheus registry cannot be built; drops every
+/// observation and renders an empty exposition body
+pub struct NoopMetricsRepository;
+
+impl MetricsRepository for NoopMetricsRepository {
+    fn observe_module_compile(&self, _seconds: f64) {}
+
+    fn observe_instance_instantiate(&self, _seconds: f64) {}
+
+    fn inc_warm_pool_hits(&self) {}
+
+    fn inc_status_reports(&self) {}
+
+    fn inc_status_report_failures(&self) {}
+
+    fn set_active_instances(&self, _count: i64) {}
+
+    fn inc_instance_crash(&self) {}
+
+    fn inc_instance_restart(&self) {}
+
+    fn render_metrics(&self) -> Result<String, String> {
+        Ok(String::new())
+    }
+}
@@ -0,0 +1,49 @@
+use crate::features::observability::repo::MetricsRepository;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub struct ObservabilityService {
+    repo: Arc<dyn MetricsRepository>,
+}
+
+impl ObservabilityService {
+    pub fn new(repo: Arc<dyn MetricsRepository>) -> Self {
+        Self { repo }
+    }
+
+    pub fn record_module_compile(&self, elapsed: Duration) {
+        self.repo.observe_module_compile(elapsed.as_secs_f64());
+    }
+
+    pub fn record_instance_instantiate(&self, elapsed: Duration) {
+        self.repo
+            .observe_instance_instantiate(elapsed.as_secs_f64());
+    }
+
+    pub fn record_warm_pool_hit(&self) {
+        self.repo.inc_warm_pool_hits();
+    }
+
+    pub fn record_status_report(&self, succeeded: bool) {
+        self.repo.inc_status_reports();
+        if !succeeded {
+            self.repo.inc_status_report_failures();
+        }
+    }
+
+    pub fn set_active_instances(&self, count: usize) {
+        self.repo.set_active_instances(count as i64);
+    }
+
+    pub fn record_instance_crash(&self) {
+        self.repo.inc_instance_crash();
+    }
+
+    pub fn record_instance_restart(&self) {
+        self.repo.inc_instance_restart();
+    }
+
+    pub fn render_metrics(&self) -> Result<String, String> {
+        self.repo.render_metrics()
+    }
+}
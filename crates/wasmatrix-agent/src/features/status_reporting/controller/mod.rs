@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use tokio::sync::Notify;
 use tokio::task::JoinHandle;
 use tokio::time;
 use tracing::{debug, warn};
@@ -12,26 +13,74 @@ use crate::features::status_reporting::service::{StatusReportService, StatusRepo
 pub struct StatusReportController {
     service: Arc<StatusReportService>,
     interval: Duration,
+    final_heartbeat: bool,
+    stop: Arc<Notify>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl StatusReportController {
     pub fn new(service: Arc<StatusReportService>, interval: Duration) -> Self {
-        Self { service, interval }
+        Self {
+            service,
+            interval,
+            final_heartbeat: false,
+            stop: Arc::new(Notify::new()),
+            task: Arc::new(Mutex::new(None)),
+        }
     }
 
-    pub fn spawn_periodic_reporting(self: Arc<Self>) -> JoinHandle<()> {
-        tokio::spawn(async move {
-            let mut ticker = time::interval(self.interval);
+    /// Send one last heartbeat after periodic reporting stops in `shutdown`
+    pub fn with_final_heartbeat(mut self, final_heartbeat: bool) -> Self {
+        self.final_heartbeat = final_heartbeat;
+        self
+    }
+
+    /// Start heartbeating every `interval` until `shutdown` is called
+    pub fn spawn_periodic_reporting(self: Arc<Self>) {
+        let controller = self.clone();
+        let handle = tokio::spawn(async move {
+            let mut ticker = time::interval(controller.interval);
             loop {
-                ticker.tick().await;
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = controller.stop.notified() => {
+                        debug!("Periodic status reporting stopped");
+                        return;
+                    }
+                }
 
-                if let Err(error) = self.service.report_heartbeat().await {
-                    warn!(error = %error, "Failed to send heartbeat status report");
+                if let Err(error) = controller.service.report_heartbeat().await {
+                    warn!(
+                        error = %error,
+                        consecutive_failures = controller.service.consecutive_failures(),
+                        "Failed to send heartbeat status report"
+                    );
                 } else {
                     debug!("Heartbeat status report sent");
                 }
             }
-        })
+        });
+        if let Some(previous) = self.task.lock().unwrap().replace(handle) {
+            previous.abort();
+        }
+    }
+
+    /// Stop periodic reporting and wait for the task to exit, then send the
+    /// final heartbeat when enabled
+    pub async fn shutdown(&self) {
+        let handle = self.task.lock().unwrap().take();
+        if let Some(handle) = handle {
+            self.stop.notify_one();
+            if let Err(error) = handle.await {
+                warn!(error = %error, "Periodic status reporting task failed");
+            }
+        }
+
+        if self.final_heartbeat {
+            if let Err(error) = self.service.report_heartbeat().await {
+                warn!(error = %error, "Final heartbeat report failed");
+            }
+        }
     }
 
     pub async fn report_status_change(
@@ -49,3 +98,59 @@ impl StatusReportController {
         self.service.report_heartbeat().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::status_reporting::repo::{StatusReportRepoError, StatusReportRepository};
+    use crate::NodeAgent;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use wasmatrix_proto::v1::InstanceStatusUpdate;
+
+    #[derive(Default)]
+    struct CountingRepo {
+        reports: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl StatusReportRepository for CountingRepo {
+        async fn report_status(
+            &self,
+            _node_id: &str,
+            _instance_updates: Vec<InstanceStatusUpdate>,
+            _capabilities: Vec<String>,
+        ) -> Result<(), StatusReportRepoError> {
+            self.reports.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_periodic_reporting() {
+        let repo = Arc::new(CountingRepo::default());
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let service = Arc::new(StatusReportService::new(
+            "test-node".to_string(),
+            agent,
+            repo.clone(),
+        ));
+        let controller = Arc::new(
+            StatusReportController::new(service, Duration::from_millis(10))
+                .with_final_heartbeat(true),
+        );
+
+        controller.clone().spawn_periodic_reporting();
+        time::sleep(Duration::from_millis(50)).await;
+        assert!(repo.reports.load(Ordering::SeqCst) > 0);
+
+        controller.shutdown().await;
+        let after_shutdown = repo.reports.load(Ordering::SeqCst);
+        time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(repo.reports.load(Ordering::SeqCst), after_shutdown);
+
+        // Nothing left to stop; only the final heartbeat goes out
+        controller.shutdown().await;
+        assert_eq!(repo.reports.load(Ordering::SeqCst), after_shutdown + 1);
+    }
+}
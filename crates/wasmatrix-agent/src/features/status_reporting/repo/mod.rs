@@ -1,9 +1,11 @@
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use async_trait::async_trait;
 use tokio::sync::Mutex;
 use tonic::transport::Channel;
 use tonic::Status;
+use wasmatrix_proto::tls::{self, TlsConfig};
 use wasmatrix_proto::v1::control_plane_service_client::ControlPlaneServiceClient;
 use wasmatrix_proto::v1::{InstanceStatusUpdate, StatusReport};
 
@@ -15,26 +17,50 @@ pub enum StatusReportRepoError {
     Report(String),
 }
 
+/// Destination for node status reports
+#[async_trait]
+pub trait StatusReportRepository: Send + Sync {
+    /// Send a report; a non-empty `capabilities` is the node's complete set of
+    /// provider types and replaces what the control plane has registered
+    async fn report_status(
+        &self,
+        node_id: &str,
+        instance_updates: Vec<InstanceStatusUpdate>,
+        capabilities: Vec<String>,
+    ) -> Result<(), StatusReportRepoError>;
+}
+
+/// Reports status to the control plane over gRPC
 #[derive(Clone)]
 pub struct StatusReportRepo {
     client: Arc<Mutex<ControlPlaneServiceClient<Channel>>>,
 }
 
 impl StatusReportRepo {
-    pub async fn connect(control_plane_addr: &str) -> Result<Self, StatusReportRepoError> {
-        let client = ControlPlaneServiceClient::connect(control_plane_addr.to_string())
+    /// Connect to the control plane, over TLS when `tls` is set
+    pub async fn connect(
+        control_plane_addr: &str,
+        tls: Option<&TlsConfig>,
+    ) -> Result<Self, StatusReportRepoError> {
+        let channel = tls::endpoint(control_plane_addr, tls)
+            .map_err(|e| StatusReportRepoError::Connection(e.to_string()))?
+            .connect()
             .await
             .map_err(|e| StatusReportRepoError::Connection(e.to_string()))?;
 
         Ok(Self {
-            client: Arc::new(Mutex::new(client)),
+            client: Arc::new(Mutex::new(ControlPlaneServiceClient::new(channel))),
         })
     }
+}
 
-    pub async fn report_status(
+#[async_trait]
+impl StatusReportRepository for StatusReportRepo {
+    async fn report_status(
         &self,
         node_id: &str,
         instance_updates: Vec<InstanceStatusUpdate>,
+        capabilities: Vec<String>,
     ) -> Result<(), StatusReportRepoError> {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -45,6 +71,8 @@ impl StatusReportRepo {
             node_id: node_id.to_string(),
             instance_updates,
             timestamp,
+            capabilities,
+            replace_capabilities: true,
         });
 
         let mut client = self.client.lock().await;
@@ -1,9 +1,14 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+use tracing::info;
 use wasmatrix_core::InstanceStatus;
 use wasmatrix_proto::v1::{InstanceStatus as ProtoInstanceStatus, InstanceStatusUpdate};
 
-use crate::features::status_reporting::repo::{StatusReportRepo, StatusReportRepoError};
+use crate::features::observability::controller::{
+    global_observability_controller, ObservabilityController,
+};
+use crate::features::status_reporting::repo::{StatusReportRepoError, StatusReportRepository};
 use crate::NodeAgent;
 
 #[derive(Debug, thiserror::Error)]
@@ -16,18 +21,33 @@ pub enum StatusReportServiceError {
 pub struct StatusReportService {
     node_id: String,
     agent: Arc<NodeAgent>,
-    repo: StatusReportRepo,
+    repo: Arc<dyn StatusReportRepository>,
+    observability: Arc<ObservabilityController>,
+    /// Failed reports since the last one the control plane accepted
+    consecutive_failures: Arc<AtomicU64>,
 }
 
 impl StatusReportService {
-    pub fn new(node_id: String, agent: Arc<NodeAgent>, repo: StatusReportRepo) -> Self {
+    pub fn new(
+        node_id: String,
+        agent: Arc<NodeAgent>,
+        repo: Arc<dyn StatusReportRepository>,
+    ) -> Self {
         Self {
             node_id,
             agent,
             repo,
+            observability: global_observability_controller(),
+            consecutive_failures: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Record report metrics into `observability` instead of the global registry
+    pub fn with_observability(mut self, observability: Arc<ObservabilityController>) -> Self {
+        self.observability = observability;
+        self
+    }
+
     pub async fn report_status_change(
         &self,
         instance_id: String,
@@ -40,12 +60,11 @@ impl StatusReportService {
             error_message,
         };
 
-        self.repo
-            .report_status(&self.node_id, vec![update])
-            .await
-            .map_err(Into::into)
+        self.send(vec![update], Vec::new()).await
     }
 
+    /// Report every instance's status along with the provider types the node
+    /// currently advertises
     pub async fn report_heartbeat(&self) -> Result<(), StatusReportServiceError> {
         let instance_ids = self.agent.list_instances().await;
         let mut updates = Vec::with_capacity(instance_ids.len());
@@ -59,10 +78,35 @@ impl StatusReportService {
             });
         }
 
-        self.repo
-            .report_status(&self.node_id, updates)
-            .await
-            .map_err(Into::into)
+        let capabilities = self.agent.advertised_capabilities().await;
+        self.send(updates, capabilities).await
+    }
+
+    async fn send(
+        &self,
+        updates: Vec<InstanceStatusUpdate>,
+        capabilities: Vec<String>,
+    ) -> Result<(), StatusReportServiceError> {
+        let result = self
+            .repo
+            .report_status(&self.node_id, updates, capabilities)
+            .await;
+        self.observability.record_status_report(result.is_ok());
+
+        if result.is_err() {
+            self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+        } else {
+            let failures = self.consecutive_failures.swap(0, Ordering::Relaxed);
+            if failures > 0 {
+                info!(failures, "Status reporting to control plane recovered");
+            }
+        }
+        result.map_err(Into::into)
+    }
+
+    /// Failed reports since the control plane last accepted one
+    pub fn consecutive_failures(&self) -> u64 {
+        self.consecutive_failures.load(Ordering::Relaxed)
     }
 }
 
@@ -74,3 +118,89 @@ fn proto_status(status: InstanceStatus) -> ProtoInstanceStatus {
         InstanceStatus::Crashed => ProtoInstanceStatus::Crashed,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    struct FailingRepo;
+
+    #[async_trait]
+    impl StatusReportRepository for FailingRepo {
+        async fn report_status(
+            &self,
+            _node_id: &str,
+            _instance_updates: Vec<InstanceStatusUpdate>,
+            _capabilities: Vec<String>,
+        ) -> Result<(), StatusReportRepoError> {
+            Err(StatusReportRepoError::Report("unavailable".to_string()))
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingRepo {
+        capabilities: std::sync::Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl StatusReportRepository for RecordingRepo {
+        async fn report_status(
+            &self,
+            _node_id: &str,
+            _instance_updates: Vec<InstanceStatusUpdate>,
+            capabilities: Vec<String>,
+        ) -> Result<(), StatusReportRepoError> {
+            self.capabilities.lock().unwrap().push(capabilities);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_advertises_current_capabilities() {
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let repo = Arc::new(RecordingRepo::default());
+        let service =
+            StatusReportService::new("test-node".to_string(), agent.clone(), repo.clone())
+                .with_observability(Arc::new(ObservabilityController::standalone().unwrap()));
+
+        service.report_heartbeat().await.unwrap();
+        agent
+            .set_advertised_capabilities(vec!["kv".to_string(), "messaging".to_string()])
+            .await;
+        service.report_heartbeat().await.unwrap();
+        service
+            .report_status_change("i-1".to_string(), InstanceStatus::Running, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *repo.capabilities.lock().unwrap(),
+            vec![
+                vec![],
+                vec!["kv".to_string(), "messaging".to_string()],
+                vec![],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_report_increments_failure_counter() {
+        let observability = Arc::new(ObservabilityController::standalone().unwrap());
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let service =
+            StatusReportService::new("test-node".to_string(), agent, Arc::new(FailingRepo))
+                .with_observability(observability.clone());
+
+        assert!(service.report_heartbeat().await.is_err());
+        assert!(service
+            .report_status_change("i-1".to_string(), InstanceStatus::Crashed, None)
+            .await
+            .is_err());
+
+        let rendered = observability.render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_status_reports_total 2"));
+        assert!(rendered.contains("wasmatrix_status_report_failures_total 2"));
+        assert_eq!(service.consecutive_failures(), 2);
+    }
+}
@@ -0,0 +1,463 @@
+use crate::logs::InstanceLogs;
+use crate::wasi::{WasiCapability, WasiCapabilitySet, WasiConfig};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use wasi_common::sync::WasiCtxBuilder;
+use wasi_common::WasiCtx;
+use wasmatrix_core::{CoreError, Result};
+use wasmtime::{Config, Engine, ExternType, Instance, Linker, Module, Store, Val};
+
+/// Abstraction over compiling, instantiating and invoking Wasm modules.
+///
+/// `NodeAgent` only talks to this trait, so agent logic (crash handling,
+/// restart policy) can be exercised with [`MockHost`] instead of wasmtime.
+pub trait ModuleHost: Send + Sync {
+    /// Compile and instantiate `module_bytes`, linking only the WASI functions in `wasi`
+    /// and exposing `config`'s env vars and args to the WASI context
+    fn instantiate(
+        &self,
+        module_bytes: &[u8],
+        wasi: &WasiCapabilitySet,
+        config: &WasiConfig,
+    ) -> Result<Instantiation>;
+}
+
+/// A freshly created instance and how long each startup phase took
+pub struct Instantiation {
+    pub instance: Box<dyn HostedInstance>,
+    pub compile_time: Duration,
+    pub instantiate_time: Duration,
+    /// Captured stdout/stderr, present when the instance holds `wasi:stdio`
+    pub logs: Option<InstanceLogs>,
+}
+
+/// A live module instance created by a [`ModuleHost`]
+pub trait HostedInstance: Send + Sync {
+    /// Call an exported `() -> ()` function; a trap is returned as an error
+    fn invoke(&mut self, function: &str) -> Result<()>;
+
+    /// Call an exported function of any signature, checking `args` against
+    /// its parameter types first; a trap is returned as an error
+    fn call_export(&mut self, function: &str, args: &[Val]) -> Result<Vec<Val>>;
+}
+
+/// Where wasmtime keeps compiled modules between process restarts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileCache {
+    /// A wasmtime cache configuration TOML file
+    ConfigFile(PathBuf),
+    /// A directory to cache into with wasmtime's default cache settings
+    Directory(PathBuf),
+}
+
+/// Compilation settings for [`WasmtimeHost`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostConfig {
+    /// Compile functions of a module on multiple threads
+    pub parallel_compilation: bool,
+    pub cache: Option<CompileCache>,
+}
+
+impl Default for HostConfig {
+    fn default() -> Self {
+        Self {
+            parallel_compilation: true,
+            cache: None,
+        }
+    }
+}
+
+impl HostConfig {
+    /// Read `WASMTIME_PARALLEL_COMPILATION`, `WASMTIME_CACHE_CONFIG` and
+    /// `WASMTIME_CACHE_DIR`; the config file wins if both cache variables are set
+    pub fn from_env() -> Self {
+        let parallel_compilation = std::env::var("WASMTIME_PARALLEL_COMPILATION")
+            .map(|value| !matches!(value.to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+            .unwrap_or(true);
+        let cache = std::env::var_os("WASMTIME_CACHE_CONFIG")
+            .map(|path| CompileCache::ConfigFile(path.into()))
+            .or_else(|| {
+                std::env::var_os("WASMTIME_CACHE_DIR")
+                    .map(|path| CompileCache::Directory(path.into()))
+            });
+        Self {
+            parallel_compilation,
+            cache,
+        }
+    }
+}
+
+/// [`ModuleHost`] backed by a wasmtime engine
+pub struct WasmtimeHost {
+    engine: Engine,
+}
+
+impl WasmtimeHost {
+    pub fn new() -> Result<Self> {
+        Self::with_config(&HostConfig::default())
+    }
+
+    /// Build the engine with `host_config`.
+    ///
+    /// A cache that cannot be set up is logged and compilation proceeds uncached.
+    pub fn with_config(host_config: &HostConfig) -> Result<Self> {
+        let mut config = Config::new();
+        config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
+        config.parallel_compilation(host_config.parallel_compilation);
+        if let Some(cache) = &host_config.cache {
+            if let Err(error) = load_cache_config(&mut config, cache) {
+                warn!(?cache, error = %error, "Compile cache unavailable; continuing without it");
+            }
+        }
+
+        let engine = Engine::new(&config).map_err(|e| {
+            CoreError::WasmRuntimeError(format!("Failed to create wasmtime engine: {:#}", e))
+        })?;
+
+        Ok(Self { engine })
+    }
+}
+
+fn load_cache_config(config: &mut Config, cache: &CompileCache) -> std::result::Result<(), String> {
+    let config_file = match cache {
+        CompileCache::ConfigFile(path) => path.clone(),
+        CompileCache::Directory(directory) => write_cache_config(directory)?,
+    };
+    config
+        .cache_config_load(&config_file)
+        .map(|_| ())
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// Write a cache config into `directory` and return its path.
+///
+/// Compiled modules go to a `modules` subdirectory because wasmtime's cache
+/// cleanup removes files it does not recognise.
+fn write_cache_config(directory: &Path) -> std::result::Result<PathBuf, String> {
+    let modules = directory.join("modules");
+    std::fs::create_dir_all(&modules).map_err(|e| e.to_string())?;
+    let modules = modules.canonicalize().map_err(|e| e.to_string())?;
+    let modules_path = modules
+        .to_str()
+        .filter(|path| !path.contains('\''))
+        .ok_or_else(|| format!("unsupported cache directory path {:?}", modules))?;
+
+    let config_file = directory.join("wasmtime-cache.toml");
+    let contents = format!("[cache]\nenabled = true\ndirectory = '{}'\n", modules_path);
+    std::fs::write(&config_file, contents).map_err(|e| e.to_string())?;
+    Ok(config_file)
+}
+
+impl ModuleHost for WasmtimeHost {
+    fn instantiate(
+        &self,
+        module_bytes: &[u8],
+        wasi: &WasiCapabilitySet,
+        config: &WasiConfig,
+    ) -> Result<Instantiation> {
+        let compile_started = Instant::now();
+        let module = Module::new(&self.engine, module_bytes).map_err(|e| {
+            CoreError::WasmRuntimeError(format!("Failed to compile Wasm module: {:#}", e))
+        })?;
+        let compile_time = compile_started.elapsed();
+        wasi.check_imports(&module)?;
+
+        let instantiate_started = Instant::now();
+
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .envs(&config.env)
+            .and_then(|builder| builder.args(&config.args))
+            .map_err(|e| {
+                CoreError::WasmRuntimeError(format!("Failed to configure WASI env/args: {:#}", e))
+            })?;
+        let logs = wasi
+            .allows(WasiCapability::Stdio)
+            .then(InstanceLogs::default);
+        if let Some(logs) = &logs {
+            builder.stdout(logs.pipe()).stderr(logs.pipe());
+        }
+        let mut store = Store::new(&self.engine, builder.build());
+        let linker = wasi.build_linker(&self.engine, &mut store)?;
+        check_unsatisfied_imports(&module, &linker, &mut store)?;
+        let instance = linker.instantiate(&mut store, &module).map_err(|e| {
+            CoreError::WasmRuntimeError(format!("Failed to instantiate Wasm module: {:#}", e))
+        })?;
+
+        Ok(Instantiation {
+            instance: Box::new(WasmtimeInstance { store, instance }),
+            compile_time,
+            instantiate_time: instantiate_started.elapsed(),
+            logs,
+        })
+    }
+}
+
+/// Reject modules whose imports the linker cannot satisfy, naming each one,
+/// instead of surfacing wasmtime's generic instantiation error.
+fn check_unsatisfied_imports(
+    module: &Module,
+    linker: &Linker<WasiCtx>,
+    store: &mut Store<WasiCtx>,
+) -> Result<()> {
+    let unsatisfied: Vec<String> = module
+        .imports()
+        .filter(|import| {
+            linker
+                .get(&mut *store, import.module(), import.name())
+                .is_none()
+        })
+        .map(|import| {
+            let kind = match import.ty() {
+                ExternType::Func(_) => "function",
+                ExternType::Global(_) => "global",
+                ExternType::Table(_) => "table",
+                ExternType::Memory(_) => "memory",
+            };
+            format!("{}::{} ({})", import.module(), import.name(), kind)
+        })
+        .collect();
+
+    if unsatisfied.is_empty() {
+        return Ok(());
+    }
+    Err(CoreError::WasmRuntimeError(format!(
+        "Wasm module has imports the host does not provide: {}",
+        unsatisfied.join(", ")
+    )))
+}
+
+struct WasmtimeInstance {
+    store: Store<WasiCtx>,
+    instance: Instance,
+}
+
+impl HostedInstance for WasmtimeInstance {
+    fn invoke(&mut self, function: &str) -> Result<()> {
+        let func = self
+            .instance
+            .get_typed_func::<(), ()>(&mut self.store, function)
+            .map_err(|e| {
+                CoreError::WasmRuntimeError(format!("Export '{}' not callable: {}", function, e))
+            })?;
+        func.call(&mut self.store, ())
+            .map_err(|e| CoreError::WasmRuntimeError(format!("Wasm trap: {}", e)))
+    }
+
+    fn call_export(&mut self, function: &str, args: &[Val]) -> Result<Vec<Val>> {
+        let func = self
+            .instance
+            .get_func(&mut self.store, function)
+            .ok_or_else(|| {
+                CoreError::WasmRuntimeError(format!("Export '{}' is not a function", function))
+            })?;
+        let ty = func.ty(&self.store);
+
+        let params: Vec<_> = ty.params().collect();
+        if params.len() != args.len() {
+            return Err(CoreError::WasmRuntimeError(format!(
+                "Export '{}' takes {} arguments, got {}",
+                function,
+                params.len(),
+                args.len()
+            )));
+        }
+        for (index, (param, arg)) in params.iter().zip(args).enumerate() {
+            let arg_ty = arg.ty();
+            if arg_ty != *param {
+                return Err(CoreError::WasmRuntimeError(format!(
+                    "Export '{}' argument {} must be {}, got {}",
+                    function, index, param, arg_ty
+                )));
+            }
+        }
+
+        let mut results = vec![Val::I32(0); ty.results().len()];
+        func.call(&mut self.store, args, &mut results)
+            .map_err(|e| CoreError::WasmRuntimeError(format!("Wasm trap: {}", e)))?;
+        Ok(results)
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockHostState {
+    trap_on_invocation: Option<u32>,
+    fail_instantiation: bool,
+    instantiations: u32,
+}
+
+/// Programmable [`ModuleHost`] for tests; performs no compilation.
+#[derive(Clone, Default)]
+pub struct MockHost {
+    state: Arc<Mutex<MockHostState>>,
+}
+
+impl MockHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make every instance created afterwards trap on its `n`-th invocation (1-based)
+    pub fn trap_on_invocation(self, n: u32) -> Self {
+        self.lock().trap_on_invocation = Some(n);
+        self
+    }
+
+    pub fn set_fail_instantiation(&self, fail: bool) {
+        self.lock().fail_instantiation = fail;
+    }
+
+    pub fn instantiation_count(&self) -> u32 {
+        self.lock().instantiations
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockHostState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ModuleHost for MockHost {
+    fn instantiate(
+        &self,
+        _module_bytes: &[u8],
+        _wasi: &WasiCapabilitySet,
+        _config: &WasiConfig,
+    ) -> Result<Instantiation> {
+        let mut state = self.lock();
+        if state.fail_instantiation {
+            return Err(CoreError::WasmRuntimeError(
+                "Failed to instantiate Wasm module: mock failure".to_string(),
+            ));
+        }
+        state.instantiations += 1;
+
+        Ok(Instantiation {
+            instance: Box::new(MockInstance {
+                invocations: 0,
+                trap_on_invocation: state.trap_on_invocation,
+            }),
+            compile_time: Duration::ZERO,
+            instantiate_time: Duration::ZERO,
+            logs: None,
+        })
+    }
+}
+
+struct MockInstance {
+    invocations: u32,
+    trap_on_invocation: Option<u32>,
+}
+
+impl HostedInstance for MockInstance {
+    fn invoke(&mut self, function: &str) -> Result<()> {
+        self.invocations += 1;
+        if self.trap_on_invocation == Some(self.invocations) {
+            return Err(CoreError::WasmRuntimeError(format!(
+                "Wasm trap: unreachable executed in '{}'",
+                function
+            )));
+        }
+        Ok(())
+    }
+
+    fn call_export(&mut self, function: &str, _args: &[Val]) -> Result<Vec<Val>> {
+        self.invoke(function).map(|()| Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasmtime_host_invokes_export() {
+        // (module (func (export "run")))
+        let module = vec![
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+            0x03, 0x02, 0x01, 0x00, 0x07, 0x07, 0x01, 0x03, 0x72, 0x75, 0x6e, 0x00, 0x00, 0x0a,
+            0x04, 0x01, 0x02, 0x00, 0x0b,
+        ];
+        let host = WasmtimeHost::new().unwrap();
+        let mut instance = host
+            .instantiate(&module, &WasiCapabilitySet::none(), &WasiConfig::default())
+            .unwrap()
+            .instance;
+
+        assert!(instance.invoke("run").is_ok());
+        assert!(instance.invoke("missing").is_err());
+    }
+
+    #[test]
+    fn test_cache_directory_config_still_compiles() {
+        let directory =
+            std::env::temp_dir().join(format!("wasmatrix-cache-{}", uuid::Uuid::new_v4()));
+        let host = WasmtimeHost::with_config(&HostConfig {
+            parallel_compilation: true,
+            cache: Some(CompileCache::Directory(directory.clone())),
+        })
+        .unwrap();
+        let module = br#"(module (func (export "run")))"#;
+
+        for _ in 0..2 {
+            let mut instance = host
+                .instantiate(module, &WasiCapabilitySet::none(), &WasiConfig::default())
+                .unwrap()
+                .instance;
+            assert!(instance.invoke("run").is_ok());
+        }
+        assert!(directory.join("wasmtime-cache.toml").exists());
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn test_invalid_cache_config_falls_back_to_uncached() {
+        let host = WasmtimeHost::with_config(&HostConfig {
+            parallel_compilation: false,
+            cache: Some(CompileCache::ConfigFile(PathBuf::from(
+                "/nonexistent/wasmatrix-cache.toml",
+            ))),
+        })
+        .unwrap();
+
+        assert!(host
+            .instantiate(
+                br#"(module)"#,
+                &WasiCapabilitySet::none(),
+                &WasiConfig::default()
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_unsatisfied_memory_import_is_named_in_error() {
+        let module = br#"(module (import "env" "memory" (memory 1)))"#;
+        let host = WasmtimeHost::new().unwrap();
+
+        let error = host
+            .instantiate(module, &WasiCapabilitySet::none(), &WasiConfig::default())
+            .err()
+            .unwrap();
+
+        assert!(matches!(error, CoreError::WasmRuntimeError(_)));
+        assert!(error.to_string().contains("env::memory (memory)"));
+    }
+
+    #[test]
+    fn test_mock_host_traps_on_configured_invocation() {
+        let host = MockHost::new().trap_on_invocation(2);
+        let mut instance = host
+            .instantiate(&[], &WasiCapabilitySet::none(), &WasiConfig::default())
+            .unwrap()
+            .instance;
+
+        assert!(instance.invoke("run").is_ok());
+        assert!(matches!(
+            instance.invoke("run"),
+            Err(CoreError::WasmRuntimeError(_))
+        ));
+        assert!(instance.invoke("run").is_ok());
+        assert_eq!(host.instantiation_count(), 1);
+    }
+}
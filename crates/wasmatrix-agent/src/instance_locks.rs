@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// One async lock per instance id, so lifecycle operations on the same
+/// instance run one at a time while different instances proceed concurrently.
+///
+/// Entries are dropped once nobody holds or waits for them.
+#[derive(Default)]
+pub struct InstanceLocks {
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl InstanceLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait for exclusive access to `instance_id`
+    pub async fn lock(&self, instance_id: &str) -> InstanceLockGuard<'_> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(instance_id.to_string())
+            .or_default()
+            .clone();
+        InstanceLockGuard {
+            locks: self,
+            instance_id: instance_id.to_string(),
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+
+    /// Number of instance ids with a held or awaited lock
+    pub fn len(&self) -> usize {
+        self.locks.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub struct InstanceLockGuard<'a> {
+    locks: &'a InstanceLocks,
+    instance_id: String,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for InstanceLockGuard<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.locks.lock().unwrap_or_else(|e| e.into_inner());
+        drop(self.guard.take());
+        // Waiters clone the entry under the map lock, so a count of one means
+        // only the map still refers to it
+        if locks
+            .get(&self.instance_id)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(&self.instance_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_same_id_serializes_and_entries_are_released() {
+        let locks = Arc::new(InstanceLocks::new());
+        let guard = locks.lock("instance-1").await;
+
+        // A different id is not blocked
+        drop(locks.lock("instance-2").await);
+
+        let waiter = {
+            let locks = locks.clone();
+            tokio::spawn(async move {
+                drop(locks.lock("instance-1").await);
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        drop(guard);
+        waiter.await.unwrap();
+        assert!(locks.is_empty());
+    }
+}
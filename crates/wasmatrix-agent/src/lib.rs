@@ -1,26 +1,61 @@
+pub mod admission;
 pub mod features;
+pub mod host;
+pub mod instance_locks;
+pub mod logs;
 pub mod server;
+pub mod warm_pool;
+pub mod wasi;
 
+use admission::{inspect_module_resources, AdmissionLimits};
+use features::observability::controller::{
+    global_observability_controller, ObservabilityController,
+};
+use host::{HostConfig, HostedInstance, Instantiation, ModuleHost, WasmtimeHost};
+use instance_locks::InstanceLocks;
+use logs::InstanceLogs;
+use warm_pool::WarmPool;
+use wasi::{WasiCapabilitySet, WasiConfig};
+
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
+use wasmatrix_core::module_format::{detect_module_format, ModuleFormat};
+use wasmatrix_core::statelessness::StatelessnessPolicy;
 use wasmatrix_core::{
-    CapabilityAssignment, CoreError, ExecutionEventRecorder, InstanceStatus, RestartPolicy,
-    RestartPolicyType, Result,
+    CapabilityAssignment, CoreError, ExecutionEventRecorder, InstanceMetadata, InstanceStatus,
+    RestartPolicy, RestartPolicyType, Result,
 };
-use wasmtime::{Config, Engine, Instance, Module, Store};
+use wasmatrix_providers::features::provider_lifecycle::repo::InMemoryProviderLifecycleRepository;
+use wasmatrix_providers::features::provider_lifecycle::service::ProviderLifecycleService;
+use wasmatrix_providers::ProviderLifecycleController;
 
 /// Handle to a running Wasm instance
 pub struct InstanceHandle {
     pub instance_id: String,
-    pub store: Store<()>,
-    pub instance: Instance,
+    pub instance: Box<dyn HostedInstance>,
     pub module_bytes: Vec<u8>,
     pub capabilities: Vec<CapabilityAssignment>,
     pub restart_policy: RestartPolicy,
+    /// WASI env vars and args, kept so restarts see the same configuration
+    pub config: WasiConfig,
+    /// Captured stdout/stderr when the instance was started with `wasi:stdio`
+    pub logs: Option<InstanceLogs>,
+    pub started_at: DateTime<Utc>,
 }
 
+/// Backoff cap used when a restart policy does not set `max_backoff_seconds`
+pub const DEFAULT_MAX_BACKOFF_SECONDS: u64 = 300;
+
+/// `(module (func (export "run")))`, used by [`NodeAgent::self_test`]
+const SELF_TEST_MODULE: [u8; 33] = [
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x03, 0x02,
+    0x01, 0x00, 0x07, 0x07, 0x01, 0x03, 0x72, 0x75, 0x6e, 0x00, 0x00, 0x0a, 0x04, 0x01, 0x02, 0x00,
+    0x0b,
+];
+
 /// Crash information for restart policy evaluation
 #[derive(Debug, Clone)]
 pub struct CrashInfo {
@@ -41,12 +76,17 @@ impl CrashInfo {
         self.last_crash_time = Some(std::time::Instant::now());
     }
 
-    /// Calculate backoff delay based on crash count
+    /// Calculate backoff delay based on crash count, capped at 5 minutes
     pub fn calculate_backoff(&self, base_seconds: u64) -> u64 {
-        // Exponential backoff: base * 2^(crash_count - 1), capped at 5 minutes
-        let exponent = self.crash_count.saturating_sub(1);
-        let delay = base_seconds * 2_u64.pow(exponent.min(8)); // Cap at 256x base
-        delay.min(300) // Cap at 5 minutes
+        self.calculate_backoff_capped(base_seconds, DEFAULT_MAX_BACKOFF_SECONDS)
+    }
+
+    /// Calculate backoff delay based on crash count, capped at `max_seconds`
+    pub fn calculate_backoff_capped(&self, base_seconds: u64, max_seconds: u64) -> u64 {
+        // Exponential backoff: base * 2^(crash_count - 1)
+        let exponent = self.crash_count.saturating_sub(1).min(63);
+        let delay = base_seconds.saturating_mul(1_u64 << exponent);
+        delay.min(max_seconds)
     }
 }
 
@@ -89,7 +129,11 @@ impl RestartPolicyEvaluator {
 
                 // Calculate backoff delay
                 let backoff_seconds = policy.backoff_seconds.unwrap_or(5);
-                let delay = crash_info.calculate_backoff(backoff_seconds);
+                let max_backoff_seconds = policy
+                    .max_backoff_seconds
+                    .unwrap_or(DEFAULT_MAX_BACKOFF_SECONDS);
+                let delay =
+                    crash_info.calculate_backoff_capped(backoff_seconds, max_backoff_seconds);
                 info!(delay_seconds = delay, "Restarting instance with backoff");
                 Some(std::time::Duration::from_secs(delay))
             }
@@ -99,37 +143,137 @@ impl RestartPolicyEvaluator {
 
 /// Node Agent manages local Wasm instance execution
 pub struct NodeAgent {
-    engine: Engine,
+    host: Arc<dyn ModuleHost>,
+    warm_pool: Arc<WarmPool>,
     instances: Arc<RwLock<HashMap<String, InstanceHandle>>>,
     crash_history: Arc<RwLock<HashMap<String, CrashInfo>>>,
     event_recorder: Arc<RwLock<ExecutionEventRecorder>>,
-    crashed_instances: Arc<RwLock<HashMap<String, std::time::Instant>>>,
+    /// Crash reason of each instance marked crashed; when both are held this
+    /// lock is always taken after `instances`
+    crashed_instances: Arc<RwLock<HashMap<String, String>>>,
+    /// Serializes start/stop/restart/crash handling per instance id
+    lifecycle_locks: InstanceLocks,
+    /// Policy-driven restarts waiting out their backoff; entries are only
+    /// added or removed under the instance's lifecycle lock
+    pending_restarts: Arc<std::sync::Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
+    /// Availability of the providers backing instance capabilities
+    provider_lifecycle: Arc<ProviderLifecycleController>,
+    observability: Arc<ObservabilityController>,
+    /// Memory and table ceilings checked before a module is instantiated
+    admission_limits: AdmissionLimits,
+    /// Provider types sent to the control plane with every heartbeat
+    advertised_capabilities: Arc<RwLock<Vec<String>>>,
     node_id: String,
 }
 
 impl NodeAgent {
     pub fn new(node_id: impl Into<String>) -> Result<Self> {
-        let mut config = Config::new();
-        config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
+        Self::with_host_config(node_id, &HostConfig::default())
+    }
 
-        let engine = Engine::new(&config).map_err(|e| {
-            CoreError::InvalidInstanceId(format!("Failed to create wasmtime engine: {}", e))
-        })?;
+    /// Create an agent whose wasmtime engine uses the given compilation settings
+    pub fn with_host_config(node_id: impl Into<String>, host_config: &HostConfig) -> Result<Self> {
+        Ok(Self::with_host(
+            node_id,
+            Box::new(WasmtimeHost::with_config(host_config)?),
+        ))
+    }
 
-        Ok(Self {
-            engine,
+    /// Create an agent that executes modules through the given host
+    pub fn with_host(node_id: impl Into<String>, host: Box<dyn ModuleHost>) -> Self {
+        Self {
+            host: Arc::from(host),
+            warm_pool: Arc::new(WarmPool::new()),
             instances: Arc::new(RwLock::new(HashMap::new())),
             crash_history: Arc::new(RwLock::new(HashMap::new())),
             event_recorder: Arc::new(RwLock::new(ExecutionEventRecorder::new())),
             crashed_instances: Arc::new(RwLock::new(HashMap::new())),
+            lifecycle_locks: InstanceLocks::new(),
+            pending_restarts: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            provider_lifecycle: Arc::new(ProviderLifecycleController::new(
+                ProviderLifecycleService::new(Arc::new(InMemoryProviderLifecycleRepository::new())),
+            )),
+            observability: global_observability_controller(),
+            admission_limits: AdmissionLimits::default(),
+            advertised_capabilities: Arc::new(RwLock::new(Vec::new())),
             node_id: node_id.into(),
-        })
+        }
+    }
+
+    /// Record startup metrics into `observability` instead of the global registry
+    pub fn with_observability(mut self, observability: Arc<ObservabilityController>) -> Self {
+        self.observability = observability;
+        self
+    }
+
+    /// Reject modules declaring more memory or table elements than the node allows
+    pub fn with_admission_limits(mut self, admission_limits: AdmissionLimits) -> Self {
+        self.admission_limits = admission_limits;
+        self
     }
 
     pub fn node_id(&self) -> &str {
         &self.node_id
     }
 
+    /// Change the provider types advertised in heartbeats, e.g. after loading
+    /// a provider at runtime; an empty set advertises nothing
+    pub async fn set_advertised_capabilities(&self, capabilities: Vec<String>) {
+        *self.advertised_capabilities.write().await = capabilities;
+    }
+
+    pub async fn advertised_capabilities(&self) -> Vec<String> {
+        self.advertised_capabilities.read().await.clone()
+    }
+
+    pub fn provider_lifecycle(&self) -> Arc<ProviderLifecycleController> {
+        self.provider_lifecycle.clone()
+    }
+
+    /// Compile, instantiate and run a trivial module to verify the host works
+    /// before the node advertises itself as available.
+    pub fn self_test(&self) -> Result<()> {
+        let mut started = self
+            .host
+            .instantiate(
+                &SELF_TEST_MODULE,
+                &WasiCapabilitySet::none(),
+                &WasiConfig::default(),
+            )
+            .map_err(|e| {
+                CoreError::WasmRuntimeError(format!(
+                    "Node agent self-test failed to instantiate module: {}",
+                    e
+                ))
+            })?;
+        started.instance.invoke("run").map_err(|e| {
+            CoreError::WasmRuntimeError(format!("Node agent self-test failed to run module: {}", e))
+        })
+    }
+
+    /// Keep `size` pre-instantiated copies of `module_bytes` ready for instances
+    /// started with `capabilities`' WASI permissions; `0` disables warming.
+    ///
+    /// The pool is filled before returning and topped up in the background
+    /// whenever a start draws from it.
+    pub fn warm_module(
+        &self,
+        module_bytes: Vec<u8>,
+        capabilities: &[CapabilityAssignment],
+        size: usize,
+    ) -> Result<()> {
+        let wasi = WasiCapabilitySet::from_assignments(capabilities);
+        match self.warm_pool.configure(module_bytes, wasi, size) {
+            Some(refill) => self.warm_pool.fill(self.host.as_ref(), refill),
+            None => Ok(()),
+        }
+    }
+
+    /// Warm instances ready for the module with this hash
+    pub fn warm_instances_available(&self, module_hash: &str) -> usize {
+        self.warm_pool.available(module_hash)
+    }
+
     /// Start a Wasm instance locally
     pub async fn start_instance_local(
         &self,
@@ -138,56 +282,216 @@ impl NodeAgent {
         capabilities: Vec<CapabilityAssignment>,
         restart_policy: RestartPolicy,
     ) -> Result<()> {
-        // Validate module bytes
-        if module_bytes.len() < 4 || &module_bytes[0..4] != &[0x00, 0x61, 0x73, 0x6d] {
-            return Err(CoreError::InvalidInstanceId(
-                "Invalid Wasm module format".to_string(),
-            ));
-        }
-
-        // Compile module
-        let module = Module::new(&self.engine, &module_bytes).map_err(|e| {
-            CoreError::InvalidInstanceId(format!("Failed to compile Wasm module: {}", e))
-        })?;
+        self.start_instance_with_config(
+            instance_id,
+            module_bytes,
+            capabilities,
+            restart_policy,
+            WasiConfig::default(),
+        )
+        .await
+    }
 
-        // Create store with WASI context
-        let mut store = Store::new(&self.engine, ());
+    /// Start a Wasm instance locally with WASI env vars and args
+    pub async fn start_instance_with_config(
+        &self,
+        instance_id: String,
+        module_bytes: Vec<u8>,
+        capabilities: Vec<CapabilityAssignment>,
+        restart_policy: RestartPolicy,
+        config: WasiConfig,
+    ) -> Result<()> {
+        self.start_instance_annotated(
+            instance_id,
+            module_bytes,
+            capabilities,
+            restart_policy,
+            config,
+            &HashMap::new(),
+        )
+        .await
+    }
 
-        // Instantiate the module
-        let instance = Instance::new(&mut store, &module, &[]).map_err(|e| {
-            CoreError::InvalidInstanceId(format!("Failed to instantiate Wasm module: {}", e))
-        })?;
+    /// Start a Wasm instance, copying the caller's annotations onto its
+    /// `instance_started` event
+    pub async fn start_instance_annotated(
+        &self,
+        instance_id: String,
+        module_bytes: Vec<u8>,
+        capabilities: Vec<CapabilityAssignment>,
+        restart_policy: RestartPolicy,
+        config: WasiConfig,
+        annotations: &HashMap<String, String>,
+    ) -> Result<()> {
+        let _lifecycle = self.lifecycle_locks.lock(&instance_id).await;
+        self.start_unlocked(
+            instance_id,
+            module_bytes,
+            capabilities,
+            restart_policy,
+            config,
+            annotations,
+        )
+        .await
+    }
 
-        info!(instance_id = %instance_id, "Wasm instance started successfully");
+    /// Start an instance; the caller holds its lifecycle lock
+    async fn start_unlocked(
+        &self,
+        instance_id: String,
+        module_bytes: Vec<u8>,
+        capabilities: Vec<CapabilityAssignment>,
+        restart_policy: RestartPolicy,
+        config: WasiConfig,
+        annotations: &HashMap<String, String>,
+    ) -> Result<()> {
+        StatelessnessPolicy::verify_event_annotations(annotations)?;
+        let handle = self.instantiate_handle(
+            instance_id,
+            module_bytes,
+            capabilities,
+            restart_policy,
+            config,
+        )?;
 
         // Record start event
         {
             let mut recorder = self.event_recorder.write().await;
-            recorder.record_start(&instance_id);
+            recorder.record_start_annotated(&handle.instance_id, annotations);
+        }
+
+        let mut instances = self.instances.write().await;
+        instances.insert(handle.instance_id.clone(), handle);
+        self.observability.set_active_instances(instances.len());
+
+        Ok(())
+    }
+
+    /// Compile and instantiate a module into a handle that is not yet
+    /// registered as running
+    fn instantiate_handle(
+        &self,
+        instance_id: String,
+        module_bytes: Vec<u8>,
+        capabilities: Vec<CapabilityAssignment>,
+        restart_policy: RestartPolicy,
+        config: WasiConfig,
+    ) -> Result<InstanceHandle> {
+        config.validate()?;
+
+        // Components instantiate through a different path that is not wired up yet
+        match detect_module_format(&module_bytes) {
+            ModuleFormat::CoreModule => {}
+            ModuleFormat::Component => {
+                return Err(CoreError::WasmRuntimeError(
+                    "WebAssembly components are not supported yet; provide a core module"
+                        .to_string(),
+                ));
+            }
+            ModuleFormat::Unknown => {
+                return Err(CoreError::WasmRuntimeError(
+                    "Invalid Wasm module format".to_string(),
+                ));
+            }
         }
+        // Refuse declared memory the instance or node could never provide;
+        // malformed modules are left for the compiler to report
+        if let Ok(resources) = inspect_module_resources(&module_bytes) {
+            self.admission_limits
+                .check(&resources, config.max_memory_bytes)?;
+        }
+
+        // Compile and instantiate the module with the WASI functions its capabilities allow,
+        // unless a warm instance linked the same way is already waiting
+        let wasi = WasiCapabilitySet::from_assignments(&capabilities);
+        let warm = if config.is_empty() {
+            self.warm_pool
+                .take(&warm_pool::module_hash(&module_bytes), &wasi)
+        } else {
+            None
+        };
+        let started = match warm {
+            Some((started, refill)) => {
+                self.observability.record_warm_pool_hit();
+                if let Some(refill) = refill {
+                    let host = self.host.clone();
+                    let pool = self.warm_pool.clone();
+                    tokio::task::spawn_blocking(move || {
+                        if let Err(error) = pool.fill(host.as_ref(), refill) {
+                            warn!(error = %error, "Failed to refill warm pool");
+                        }
+                    });
+                }
+                started
+            }
+            None => {
+                let started = self.host.instantiate(&module_bytes, &wasi, &config)?;
+                self.observability
+                    .record_module_compile(started.compile_time);
+                self.observability
+                    .record_instance_instantiate(started.instantiate_time);
+                started
+            }
+        };
+        let Instantiation { instance, logs, .. } = started;
+
+        info!(instance_id = %instance_id, "Wasm instance started successfully");
 
-        // Store the handle
-        let handle = InstanceHandle {
-            instance_id: instance_id.clone(),
-            store,
+        Ok(InstanceHandle {
+            instance_id,
             instance,
             module_bytes,
             capabilities,
             restart_policy,
-        };
-
-        let mut instances = self.instances.write().await;
-        instances.insert(instance_id, handle);
-
-        Ok(())
+            config,
+            logs,
+            started_at: Utc::now(),
+        })
     }
 
     /// Stop a running Wasm instance
     pub async fn stop_instance_local(&self, instance_id: &str) -> Result<()> {
+        self.stop_instance_with_reason(instance_id, None).await
+    }
+
+    /// Stop a running Wasm instance, recording why on its `instance_stopped` event
+    pub async fn stop_instance_with_reason(
+        &self,
+        instance_id: &str,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        self.stop_instance_annotated(instance_id, reason, &HashMap::new())
+            .await
+    }
+
+    /// Stop a running Wasm instance, recording the reason and the caller's
+    /// annotations on its `instance_stopped` event
+    pub async fn stop_instance_annotated(
+        &self,
+        instance_id: &str,
+        reason: Option<&str>,
+        annotations: &HashMap<String, String>,
+    ) -> Result<()> {
+        StatelessnessPolicy::verify_event_annotations(annotations)?;
+        let _lifecycle = self.lifecycle_locks.lock(instance_id).await;
+        self.stop_unlocked(instance_id, reason, annotations).await
+    }
+
+    /// Stop an instance; the caller holds its lifecycle lock
+    async fn stop_unlocked(
+        &self,
+        instance_id: &str,
+        reason: Option<&str>,
+        annotations: &HashMap<String, String>,
+    ) -> Result<()> {
+        // A stop wins over a restart still waiting out its backoff
+        self.cancel_pending_restart(instance_id);
+
         let mut instances = self.instances.write().await;
 
         if instances.remove(instance_id).is_some() {
-            info!(instance_id = %instance_id, "Wasm instance stopped");
+            self.observability.set_active_instances(instances.len());
+            info!(instance_id = %instance_id, reason = reason.unwrap_or(""), "Wasm instance stopped");
 
             // Remove from crashed instances (if present)
             {
@@ -198,7 +502,7 @@ impl NodeAgent {
             // Record stop event
             {
                 let mut recorder = self.event_recorder.write().await;
-                recorder.record_stop(instance_id);
+                recorder.record_stop_annotated(instance_id, reason, annotations);
             }
 
             Ok(())
@@ -210,12 +514,65 @@ impl NodeAgent {
         }
     }
 
+    /// Invoke an exported function of a running instance.
+    ///
+    /// A trap is treated as a crash: it is handled through
+    /// [`NodeAgent::handle_crash`], which schedules any restart the policy asks
+    /// for, and surfaced as `CrashDetected`.
+    pub async fn invoke_instance(
+        self: &Arc<Self>,
+        instance_id: &str,
+        function: &str,
+    ) -> Result<()> {
+        let result = {
+            let mut instances = self.instances.write().await;
+            let handle = instances.get_mut(instance_id).ok_or_else(|| {
+                CoreError::InvalidInstanceId(format!("Instance {} not found", instance_id))
+            })?;
+            handle.instance.invoke(function)
+        };
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                let reason = error.to_string();
+                self.handle_crash(instance_id, reason.clone()).await;
+                Err(CoreError::CrashDetected(format!(
+                    "Instance {} crashed: {}",
+                    instance_id, reason
+                )))
+            }
+        }
+    }
+
+    /// Call an export of a running instance for diagnostics, without touching
+    /// its lifecycle: a trap is returned as an error but not treated as a crash.
+    pub async fn call_export(
+        &self,
+        instance_id: &str,
+        function: &str,
+        args: &[wasmtime::Val],
+    ) -> Result<Vec<wasmtime::Val>> {
+        let mut instances = self.instances.write().await;
+        let handle = instances.get_mut(instance_id).ok_or_else(|| {
+            CoreError::InvalidInstanceId(format!("Instance {} not found", instance_id))
+        })?;
+        handle.instance.call_export(function, args)
+    }
+
     /// Handle instance crash detection
     pub async fn on_instance_crash(
         &self,
         instance_id: &str,
         error: String,
     ) -> Option<std::time::Duration> {
+        let _lifecycle = self.lifecycle_locks.lock(instance_id).await;
+        // A stop that won the race already removed the instance; marking it
+        // crashed now would leave a crashed entry for an instance that is gone
+        if !self.instances.read().await.contains_key(instance_id) {
+            warn!(instance_id = %instance_id, error = %error, "Ignoring crash of instance that is no longer running");
+            return None;
+        }
         error!(instance_id = %instance_id, error = %error, "Instance crashed");
 
         // Record crash event in execution event recorder
@@ -223,11 +580,12 @@ impl NodeAgent {
             let mut recorder = self.event_recorder.write().await;
             recorder.record_crash(instance_id, &error);
         }
+        self.observability.record_instance_crash();
 
         // Mark instance as crashed
         {
             let mut crashed = self.crashed_instances.write().await;
-            crashed.insert(instance_id.to_string(), std::time::Instant::now());
+            crashed.insert(instance_id.to_string(), error);
         }
 
         // Record crash in history
@@ -256,22 +614,92 @@ impl NodeAgent {
         }
     }
 
-    /// Get instance status
-    pub async fn get_instance_status(&self, instance_id: &str) -> InstanceStatus {
-        // Check if crashed first (highest priority status)
-        {
-            let crashed = self.crashed_instances.read().await;
-            if crashed.contains_key(instance_id) {
-                return InstanceStatus::Crashed;
+    /// Handle a crash and, when the restart policy asks for it, restart the
+    /// instance in the background once the backoff has elapsed.
+    ///
+    /// Stopping or manually restarting the instance before then cancels the
+    /// pending restart.
+    pub async fn handle_crash(
+        self: &Arc<Self>,
+        instance_id: &str,
+        error: String,
+    ) -> Option<std::time::Duration> {
+        let delay = self.on_instance_crash(instance_id, error).await?;
+
+        let _lifecycle = self.lifecycle_locks.lock(instance_id).await;
+        // Stopped while the crash was being recorded
+        if !self.instances.read().await.contains_key(instance_id) {
+            return None;
+        }
+        let agent = self.clone();
+        let id = instance_id.to_string();
+        let task = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _lifecycle = agent.lifecycle_locks.lock(&id).await;
+            // Claiming the entry under the lifecycle lock decides the race
+            // with a stop: whichever removes it first wins
+            if agent.take_pending_restart(&id).is_none() {
+                return;
+            }
+            if let Err(error) = agent.restart_unlocked(&id).await {
+                warn!(instance_id = %id, error = %error, "Scheduled restart failed");
             }
+        });
+        if let Some(previous) = self
+            .pending_restarts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(instance_id.to_string(), task)
+        {
+            previous.abort();
+        }
+        Some(delay)
+    }
+
+    /// Whether a policy-driven restart is waiting to run for the instance
+    pub fn has_pending_restart(&self, instance_id: &str) -> bool {
+        self.pending_restarts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(instance_id)
+    }
+
+    fn take_pending_restart(&self, instance_id: &str) -> Option<tokio::task::JoinHandle<()>> {
+        self.pending_restarts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(instance_id)
+    }
+
+    /// Abort a pending restart; the caller holds the instance's lifecycle lock
+    fn cancel_pending_restart(&self, instance_id: &str) {
+        if let Some(task) = self.take_pending_restart(instance_id) {
+            task.abort();
+            info!(instance_id = %instance_id, "Cancelled pending restart");
         }
+    }
+
+    /// Get instance status
+    pub async fn get_instance_status(&self, instance_id: &str) -> InstanceStatus {
+        self.status_with_detail(instance_id).await.0
+    }
 
-        // Check if running
+    /// Get instance status along with the crash reason when it is `Crashed`.
+    ///
+    /// Precedence is `Crashed` > `Running` > `Stopped`: a crashed instance stays
+    /// `Crashed` until a restart swaps in its replacement, which clears the
+    /// mark under the same locks read here, so a query never sees a restart
+    /// half-done.
+    pub async fn status_with_detail(&self, instance_id: &str) -> (InstanceStatus, Option<String>) {
         let instances = self.instances.read().await;
-        if instances.contains_key(instance_id) {
-            InstanceStatus::Running
+        let crashed = self.crashed_instances.read().await;
+
+        if let Some(reason) = crashed.get(instance_id) {
+            (InstanceStatus::Crashed, Some(reason.clone()))
+        } else if instances.contains_key(instance_id) {
+            (InstanceStatus::Running, None)
         } else {
-            InstanceStatus::Stopped
+            (InstanceStatus::Stopped, None)
         }
     }
 
@@ -286,37 +714,58 @@ impl NodeAgent {
 
     /// Restart an instance (internal use)
     pub async fn restart_instance(&self, instance_id: &str) -> Result<()> {
+        let _lifecycle = self.lifecycle_locks.lock(instance_id).await;
+        self.cancel_pending_restart(instance_id);
+        self.restart_unlocked(instance_id).await
+    }
+
+    /// Restart an instance; the caller holds its lifecycle lock.
+    ///
+    /// The replacement is instantiated before the old instance is touched, then
+    /// swapped in and the crash mark cleared in one step, so the instance never
+    /// reads as `Stopped` or as both crashed and running mid-restart.
+    async fn restart_unlocked(&self, instance_id: &str) -> Result<()> {
         let instances = self.instances.read().await;
 
         if let Some(handle) = instances.get(instance_id) {
             let module_bytes = handle.module_bytes.clone();
             let capabilities = handle.capabilities.clone();
             let restart_policy = handle.restart_policy.clone();
+            let config = handle.config.clone();
             drop(instances);
 
-            // Remove from crashed instances (if present)
-            {
-                let mut crashed = self.crashed_instances.write().await;
-                crashed.remove(instance_id);
-            }
-
-            // Stop the old instance
-            self.stop_instance_local(instance_id).await?;
-
             // Start a new instance with the same parameters
-            self.start_instance_local(
+            let replacement = match self.instantiate_handle(
                 instance_id.to_string(),
                 module_bytes,
                 capabilities,
                 restart_policy,
-            )
-            .await?;
+                config,
+            ) {
+                Ok(replacement) => replacement,
+                Err(e) => return Err(self.record_failed_restart(instance_id, e).await),
+            };
+
+            {
+                let mut recorder = self.event_recorder.write().await;
+                recorder.record_stop_annotated(instance_id, None, &HashMap::new());
+                recorder.record_start_annotated(instance_id, &HashMap::new());
+            }
+
+            // Replace the old instance and clear its crash mark atomically
+            {
+                let mut instances = self.instances.write().await;
+                let mut crashed = self.crashed_instances.write().await;
+                instances.insert(instance_id.to_string(), replacement);
+                crashed.remove(instance_id);
+            }
 
             // Record restart event
             {
                 let mut recorder = self.event_recorder.write().await;
                 recorder.record_restart(instance_id);
             }
+            self.observability.record_instance_restart();
 
             info!(instance_id = %instance_id, "Instance restarted successfully");
             Ok(())
@@ -328,6 +777,175 @@ impl NodeAgent {
         }
     }
 
+    /// Mark an instance crashed after its replacement failed to start during
+    /// restart.
+    ///
+    /// The old handle is kept so the instance stays visible and can still be
+    /// stopped or restarted again instead of being left as an orphaned mark.
+    async fn record_failed_restart(&self, instance_id: &str, cause: CoreError) -> CoreError {
+        let reason = format!("restart failed: {}", cause);
+        error!(instance_id = %instance_id, error = %cause, "Instance restart failed");
+
+        {
+            let mut recorder = self.event_recorder.write().await;
+            recorder.record_crash(instance_id, &reason);
+        }
+        self.observability.record_instance_crash();
+
+        {
+            let mut crashed = self.crashed_instances.write().await;
+            crashed.insert(instance_id.to_string(), reason.clone());
+        }
+
+        {
+            let mut crash_history = self.crash_history.write().await;
+            crash_history
+                .entry(instance_id.to_string())
+                .or_insert_with(CrashInfo::new)
+                .record_crash();
+        }
+
+        CoreError::CrashDetected(format!("Instance {} is down: {}", instance_id, reason))
+    }
+
+    /// Force restart an instance regardless of its restart policy.
+    ///
+    /// Unlike policy-driven restarts, this resets the crash history so the
+    /// backoff starts from zero after an operator-initiated restart.
+    pub async fn force_restart_instance(&self, instance_id: &str) -> Result<()> {
+        self.force_restart_instance_with_capabilities(instance_id, Vec::new())
+            .await
+    }
+
+    /// [`Self::force_restart_instance`], first replacing the instance's grants
+    /// with `capabilities` unless it is empty, so the restarted copy starts
+    /// out holding them
+    pub async fn force_restart_instance_with_capabilities(
+        &self,
+        instance_id: &str,
+        capabilities: Vec<CapabilityAssignment>,
+    ) -> Result<()> {
+        let _lifecycle = self.lifecycle_locks.lock(instance_id).await;
+        // A manual restart supersedes a policy-driven one still waiting
+        self.cancel_pending_restart(instance_id);
+        if !capabilities.is_empty() {
+            self.replace_capabilities_unlocked(instance_id, capabilities)
+                .await?;
+        }
+        self.restart_unlocked(instance_id).await?;
+
+        {
+            let mut crash_history = self.crash_history.write().await;
+            crash_history.remove(instance_id);
+        }
+
+        info!(instance_id = %instance_id, "Instance force restarted");
+        Ok(())
+    }
+
+    /// Whether the instance is `Running` and every provider backing its
+    /// capabilities is running; a running instance whose provider is stopped
+    /// or was never started is not ready. Checking registers no provider.
+    pub async fn instance_ready(&self, instance_id: &str) -> bool {
+        if self.get_instance_status(instance_id).await != InstanceStatus::Running {
+            return false;
+        }
+        self.get_instance_capabilities(instance_id)
+            .await
+            .iter()
+            .all(|capability| {
+                self.provider_lifecycle
+                    .is_provider_running(&capability.capability_id)
+                    .unwrap_or(false)
+            })
+    }
+
+    /// Capability assignments held by a running instance
+    pub async fn get_instance_capabilities(&self, instance_id: &str) -> Vec<CapabilityAssignment> {
+        let instances = self.instances.read().await;
+        instances
+            .get(instance_id)
+            .map(|handle| handle.capabilities.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace every grant a running instance holds. Invocations that start
+    /// afterwards are authorised against `capabilities` only, and restarts
+    /// keep the new set.
+    pub async fn set_instance_capabilities(
+        &self,
+        instance_id: &str,
+        capabilities: Vec<CapabilityAssignment>,
+    ) -> Result<()> {
+        let _lifecycle = self.lifecycle_locks.lock(instance_id).await;
+        self.replace_capabilities_unlocked(instance_id, capabilities)
+            .await
+    }
+
+    /// Replace an instance's grants; the caller holds its lifecycle lock
+    async fn replace_capabilities_unlocked(
+        &self,
+        instance_id: &str,
+        capabilities: Vec<CapabilityAssignment>,
+    ) -> Result<()> {
+        let mut instances = self.instances.write().await;
+        let handle = instances.get_mut(instance_id).ok_or_else(|| {
+            CoreError::InvalidInstanceId(format!("Instance {} not found", instance_id))
+        })?;
+        handle.capabilities = capabilities
+            .into_iter()
+            .map(|mut capability| {
+                capability.instance_id = instance_id.to_string();
+                capability
+            })
+            .collect();
+        info!(
+            instance_id = %instance_id,
+            count = handle.capabilities.len(),
+            "Instance capabilities replaced"
+        );
+        Ok(())
+    }
+
+    /// Metadata of a running instance as seen from this node
+    pub async fn instance_metadata(&self, instance_id: &str) -> Option<InstanceMetadata> {
+        let (module_hash, created_at) = {
+            let instances = self.instances.read().await;
+            let handle = instances.get(instance_id)?;
+            (
+                warm_pool::module_hash(&handle.module_bytes),
+                handle.started_at,
+            )
+        };
+        Some(InstanceMetadata {
+            instance_id: instance_id.to_string(),
+            node_id: self.node_id.clone(),
+            module_hash,
+            created_at,
+            status: self.get_instance_status(instance_id).await,
+            deployment_id: None,
+            labels: Default::default(),
+            updated_at: None,
+        })
+    }
+
+    /// The last `max_bytes` bytes an instance wrote to stdout/stderr (`0` for all).
+    ///
+    /// Only instances started with the `wasi:stdio` permission have captured output.
+    pub async fn read_instance_logs(&self, instance_id: &str, max_bytes: usize) -> Result<Vec<u8>> {
+        let instances = self.instances.read().await;
+        let handle = instances.get(instance_id).ok_or_else(|| {
+            CoreError::InvalidInstanceId(format!("Instance {} not found", instance_id))
+        })?;
+        let logs = handle.logs.as_ref().ok_or_else(|| {
+            CoreError::InvalidCapabilityAssignment(format!(
+                "Instance {} does not capture output: missing 'wasi:stdio' permission",
+                instance_id
+            ))
+        })?;
+        Ok(logs.tail(max_bytes))
+    }
+
     /// List all running instances
     pub async fn list_instances(&self) -> Vec<String> {
         let instances = self.instances.read().await;
@@ -349,7 +967,7 @@ impl NodeAgent {
         recorder
             .get_events_for_instance(instance_id)
             .into_iter()
-            .map(|e| e.clone())
+            .cloned()
             .collect()
     }
 }
@@ -357,6 +975,7 @@ impl NodeAgent {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::host::MockHost;
 
     fn create_valid_wasm_module() -> Vec<u8> {
         // Minimal valid Wasm module (magic bytes + version)
@@ -403,21 +1022,115 @@ mod tests {
             )
             .await;
 
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_restart_policy_never() {
-        let policy = RestartPolicy::never();
-        let crash_info = CrashInfo::new();
-
-        let result = RestartPolicyEvaluator::should_restart(&policy, &crash_info);
-        assert!(result.is_none());
+        assert!(matches!(result, Err(CoreError::WasmRuntimeError(_))));
     }
 
-    #[test]
-    fn test_restart_policy_always() {
-        let policy = RestartPolicy::always();
+    #[tokio::test]
+    async fn test_component_is_rejected_as_unsupported() {
+        let agent = NodeAgent::new("test-node").unwrap();
+        let result = agent
+            .start_instance_local(
+                "test".to_string(),
+                vec![0x00, 0x61, 0x73, 0x6d, 0x0d, 0x00, 0x01, 0x00],
+                vec![],
+                RestartPolicy::default(),
+            )
+            .await;
+
+        match result {
+            Err(CoreError::WasmRuntimeError(message)) => {
+                assert!(message.contains("components are not supported"))
+            }
+            other => panic!("expected unsupported component error, got {other:?}"),
+        }
+        assert!(agent.list_instances().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_call_export_returns_typed_results() {
+        let agent = NodeAgent::new("test-node").unwrap();
+        let module = wat::parse_str(
+            r#"(module
+                 (func (export "add") (param i32 i32) (result i32)
+                   (i32.add (local.get 0) (local.get 1))))"#,
+        )
+        .unwrap();
+        agent
+            .start_instance_local("calc".to_string(), module, vec![], RestartPolicy::default())
+            .await
+            .unwrap();
+
+        let results = agent
+            .call_export(
+                "calc",
+                "add",
+                &[wasmtime::Val::I32(2), wasmtime::Val::I32(40)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].i32(), Some(42));
+
+        let mismatch = agent
+            .call_export(
+                "calc",
+                "add",
+                &[wasmtime::Val::I32(2), wasmtime::Val::I64(40)],
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            mismatch
+                .to_string()
+                .contains("argument 1 must be i32, got i64"),
+            "{}",
+            mismatch
+        );
+        let missing = agent.call_export("calc", "sub", &[]).await.unwrap_err();
+        assert!(missing.to_string().contains("'sub'"));
+        assert_eq!(
+            agent.get_instance_status("calc").await,
+            InstanceStatus::Running
+        );
+    }
+
+    #[tokio::test]
+    async fn test_uncompilable_module_is_a_runtime_error() {
+        let agent = NodeAgent::new("test-node").unwrap();
+        // Valid header followed by a truncated type section
+        let module = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05];
+
+        let error = agent
+            .start_instance_local("bad".to_string(), module, vec![], RestartPolicy::default())
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(error, CoreError::WasmRuntimeError(_)),
+            "{:?}",
+            error
+        );
+        let message = error.to_string();
+        assert!(
+            message.contains("Failed to compile Wasm module"),
+            "{}",
+            message
+        );
+        assert!(message.contains("unexpected end-of-file"), "{}", message);
+    }
+
+    #[test]
+    fn test_restart_policy_never() {
+        let policy = RestartPolicy::never();
+        let crash_info = CrashInfo::new();
+
+        let result = RestartPolicyEvaluator::should_restart(&policy, &crash_info);
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_restart_policy_always() {
+        let policy = RestartPolicy::always();
         let crash_info = CrashInfo::new();
 
         let result = RestartPolicyEvaluator::should_restart(&policy, &crash_info);
@@ -524,6 +1237,44 @@ mod tests {
         assert_eq!(crash_info.calculate_backoff(5), 300); // capped at 300
     }
 
+    #[test]
+    fn test_crash_info_backoff_custom_cap() {
+        let mut crash_info = CrashInfo::new();
+        for _ in 0..12 {
+            crash_info.record_crash();
+        }
+
+        assert_eq!(crash_info.calculate_backoff_capped(5, 60), 60);
+        assert_eq!(crash_info.calculate_backoff_capped(5, 3600), 3600);
+        assert_eq!(crash_info.calculate_backoff_capped(1, 3600), 2048); // 1 * 2^11
+    }
+
+    #[test]
+    fn test_restart_policy_respects_max_backoff() {
+        let mut crash_info = CrashInfo::new();
+        for _ in 0..10 {
+            crash_info.record_crash();
+        }
+
+        let mut policy = RestartPolicy::on_failure(20, 5);
+        assert_eq!(
+            RestartPolicyEvaluator::should_restart(&policy, &crash_info),
+            Some(std::time::Duration::from_secs(300))
+        );
+
+        policy.max_backoff_seconds = Some(30);
+        assert_eq!(
+            RestartPolicyEvaluator::should_restart(&policy, &crash_info),
+            Some(std::time::Duration::from_secs(30))
+        );
+
+        policy.max_backoff_seconds = Some(1800);
+        assert_eq!(
+            RestartPolicyEvaluator::should_restart(&policy, &crash_info),
+            Some(std::time::Duration::from_secs(1800))
+        );
+    }
+
     #[tokio::test]
     async fn test_crash_recovery_event_recording() {
         let agent = NodeAgent::new("test-node").unwrap();
@@ -608,6 +1359,468 @@ mod tests {
         assert_eq!(crash_count_after_restart, 1);
     }
 
+    #[tokio::test]
+    async fn test_restart_failure_reports_crashed() {
+        let agent = NodeAgent::new("test-node").unwrap();
+        let instance_id = "test-instance-1".to_string();
+
+        agent
+            .start_instance_local(
+                instance_id.clone(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::always(),
+            )
+            .await
+            .unwrap();
+
+        // Corrupt the stored module so the re-start step fails
+        agent
+            .instances
+            .write()
+            .await
+            .get_mut(&instance_id)
+            .unwrap()
+            .module_bytes = vec![0x00, 0x00, 0x00, 0x00];
+
+        let error = agent.restart_instance(&instance_id).await.unwrap_err();
+        assert!(matches!(error, CoreError::CrashDetected(_)));
+        assert!(error.to_string().contains("is down"));
+        assert!(error.to_string().contains("restart failed"));
+
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Crashed
+        );
+        assert_eq!(agent.get_crash_count(&instance_id).await, 1);
+        let events = agent.get_execution_events_for_instance(&instance_id).await;
+        let last = events.last().unwrap();
+        assert_eq!(last.event_type, "instance_crashed");
+        assert!(last.details.as_ref().unwrap()["error"].contains("restart failed"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_host_trap_on_second_invocation_is_crash() {
+        let host = MockHost::new().trap_on_invocation(2);
+        let agent = Arc::new(NodeAgent::with_host("test-node", Box::new(host.clone())));
+        let instance_id = "mock-instance".to_string();
+
+        agent
+            .start_instance_local(
+                instance_id.clone(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::on_failure(3, 1),
+            )
+            .await
+            .unwrap();
+
+        agent.invoke_instance(&instance_id, "run").await.unwrap();
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Running
+        );
+
+        let error = agent
+            .invoke_instance(&instance_id, "run")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, CoreError::CrashDetected(_)));
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Crashed
+        );
+        assert_eq!(agent.get_crash_count(&instance_id).await, 1);
+
+        agent.restart_instance(&instance_id).await.unwrap();
+        assert_eq!(host.instantiation_count(), 2);
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Running
+        );
+        // The fresh instance has its own invocation counter
+        agent.invoke_instance(&instance_id, "run").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mock_host_instantiation_failure_during_restart() {
+        let host = MockHost::new();
+        let agent = NodeAgent::with_host("test-node", Box::new(host.clone()));
+        let instance_id = "mock-instance".to_string();
+
+        agent
+            .start_instance_local(
+                instance_id.clone(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::always(),
+            )
+            .await
+            .unwrap();
+
+        host.set_fail_instantiation(true);
+        let error = agent.restart_instance(&instance_id).await.unwrap_err();
+
+        assert!(matches!(error, CoreError::CrashDetected(_)));
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Crashed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_restart_can_be_stopped_and_started_again() {
+        let host = MockHost::new();
+        let agent = NodeAgent::with_host("test-node", Box::new(host.clone()));
+        let instance_id = "mock-instance".to_string();
+
+        agent
+            .start_instance_local(
+                instance_id.clone(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::always(),
+            )
+            .await
+            .unwrap();
+
+        host.set_fail_instantiation(true);
+        agent.restart_instance(&instance_id).await.unwrap_err();
+        host.set_fail_instantiation(false);
+
+        agent.stop_instance_local(&instance_id).await.unwrap();
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Stopped
+        );
+        assert!(!agent
+            .crashed_instances
+            .read()
+            .await
+            .contains_key(&instance_id));
+
+        agent
+            .start_instance_local(
+                instance_id.clone(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::always(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Running
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_restart_can_be_retried() {
+        let host = MockHost::new();
+        let agent = NodeAgent::with_host("test-node", Box::new(host.clone()));
+        let instance_id = "mock-instance".to_string();
+
+        agent
+            .start_instance_local(
+                instance_id.clone(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::always(),
+            )
+            .await
+            .unwrap();
+
+        host.set_fail_instantiation(true);
+        agent.restart_instance(&instance_id).await.unwrap_err();
+        host.set_fail_instantiation(false);
+
+        agent.force_restart_instance(&instance_id).await.unwrap();
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Running
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_lifecycle_on_one_instance_stays_consistent() {
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let instance_id = "contended-instance";
+
+        let tasks: Vec<_> = (0..64)
+            .map(|i| {
+                let agent = agent.clone();
+                tokio::spawn(async move {
+                    match i % 4 {
+                        0 => {
+                            let _ = agent
+                                .start_instance_local(
+                                    instance_id.to_string(),
+                                    create_valid_wasm_module(),
+                                    vec![],
+                                    RestartPolicy::never(),
+                                )
+                                .await;
+                        }
+                        1 => {
+                            let _ = agent.stop_instance_local(instance_id).await;
+                        }
+                        2 => {
+                            let _ = agent.restart_instance(instance_id).await;
+                        }
+                        _ => {
+                            agent
+                                .on_instance_crash(instance_id, "trap".to_string())
+                                .await;
+                        }
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let running = agent.instances.read().await.contains_key(instance_id);
+        let crashed = agent
+            .crashed_instances
+            .read()
+            .await
+            .contains_key(instance_id);
+        assert!(
+            !crashed || running,
+            "crashed entry left for a stopped instance"
+        );
+        let expected = match (running, crashed) {
+            (_, true) => InstanceStatus::Crashed,
+            (true, false) => InstanceStatus::Running,
+            (false, false) => InstanceStatus::Stopped,
+        };
+        assert_eq!(agent.get_instance_status(instance_id).await, expected);
+        assert!(agent.lifecycle_locks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_force_restart_ignores_never_policy() {
+        let agent = NodeAgent::new("test-node").unwrap();
+        let instance_id = "test-instance-1".to_string();
+
+        agent
+            .start_instance_local(
+                instance_id.clone(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::never(),
+            )
+            .await
+            .unwrap();
+
+        let delay = agent
+            .on_instance_crash(&instance_id, "trap".to_string())
+            .await;
+        assert!(delay.is_none());
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Crashed
+        );
+
+        agent.force_restart_instance(&instance_id).await.unwrap();
+
+        assert_eq!(
+            agent.get_instance_status(&instance_id).await,
+            InstanceStatus::Running
+        );
+        assert_eq!(agent.get_crash_count(&instance_id).await, 0);
+        let events = agent.get_execution_events_for_instance(&instance_id).await;
+        assert_eq!(events.last().unwrap().event_type, "instance_restarted");
+    }
+
+    #[tokio::test]
+    async fn test_force_restart_unknown_instance_fails() {
+        let agent = NodeAgent::new("test-node").unwrap();
+        assert!(agent.force_restart_instance("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stop_cancels_pending_restart() {
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let instance_id = "backoff-instance";
+        agent
+            .start_instance_local(
+                instance_id.to_string(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::on_failure(3, 1),
+            )
+            .await
+            .unwrap();
+
+        let delay = agent.handle_crash(instance_id, "trap".to_string()).await;
+        assert_eq!(delay, Some(std::time::Duration::from_secs(1)));
+        assert!(agent.has_pending_restart(instance_id));
+
+        agent.stop_instance_local(instance_id).await.unwrap();
+        assert!(!agent.has_pending_restart(instance_id));
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert_eq!(
+            agent.get_instance_status(instance_id).await,
+            InstanceStatus::Stopped
+        );
+        let events = agent.get_execution_events_for_instance(instance_id).await;
+        assert!(events.iter().all(|e| e.event_type != "instance_restarted"));
+    }
+
+    #[tokio::test]
+    async fn test_pending_restart_runs_after_backoff() {
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let instance_id = "backoff-instance";
+        agent
+            .start_instance_local(
+                instance_id.to_string(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::always(),
+            )
+            .await
+            .unwrap();
+
+        agent.handle_crash(instance_id, "trap".to_string()).await;
+        for _ in 0..100 {
+            if !agent.has_pending_restart(instance_id) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert_eq!(
+            agent.get_instance_status(instance_id).await,
+            InstanceStatus::Running
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trap_during_invoke_schedules_restart() {
+        let host = MockHost::new().trap_on_invocation(1);
+        let agent = Arc::new(NodeAgent::with_host("test-node", Box::new(host.clone())));
+        let instance_id = "trapping-instance";
+        agent
+            .start_instance_local(
+                instance_id.to_string(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::always(),
+            )
+            .await
+            .unwrap();
+
+        let error = agent.invoke_instance(instance_id, "run").await.unwrap_err();
+        assert!(matches!(error, CoreError::CrashDetected(_)));
+        assert!(agent.has_pending_restart(instance_id));
+
+        for _ in 0..100 {
+            if !agent.has_pending_restart(instance_id) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert_eq!(
+            agent.get_instance_status(instance_id).await,
+            InstanceStatus::Running
+        );
+        assert_eq!(host.instantiation_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_force_restart_cancels_pending_restart() {
+        let host = MockHost::new().trap_on_invocation(1);
+        let agent = Arc::new(NodeAgent::with_host("test-node", Box::new(host.clone())));
+        let instance_id = "backoff-instance";
+        agent
+            .start_instance_local(
+                instance_id.to_string(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::on_failure(3, 1),
+            )
+            .await
+            .unwrap();
+
+        agent.invoke_instance(instance_id, "run").await.unwrap_err();
+        assert!(agent.has_pending_restart(instance_id));
+
+        agent.force_restart_instance(instance_id).await.unwrap();
+        assert!(!agent.has_pending_restart(instance_id));
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert_eq!(host.instantiation_count(), 2);
+        let restarts = agent
+            .get_execution_events_for_instance(instance_id)
+            .await
+            .iter()
+            .filter(|e| e.event_type == "instance_restarted")
+            .count();
+        assert_eq!(restarts, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_status_during_restart_has_no_intermediate_state() {
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let instance_id = "restarting-instance";
+        agent
+            .start_instance_local(
+                instance_id.to_string(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::never(),
+            )
+            .await
+            .unwrap();
+        agent
+            .on_instance_crash(instance_id, "unreachable executed".to_string())
+            .await;
+        assert_eq!(
+            agent.status_with_detail(instance_id).await,
+            (
+                InstanceStatus::Crashed,
+                Some("unreachable executed".to_string())
+            )
+        );
+
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let poller = {
+            let agent = agent.clone();
+            let done = done.clone();
+            tokio::spawn(async move {
+                let mut observed = Vec::new();
+                while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                    observed.push(agent.status_with_detail(instance_id).await);
+                    tokio::task::yield_now().await;
+                }
+                observed
+            })
+        };
+
+        agent.restart_instance(instance_id).await.unwrap();
+        done.store(true, std::sync::atomic::Ordering::SeqCst);
+        let observed = poller.await.unwrap();
+
+        // Crashed until the replacement is swapped in, then Running for good
+        let first_running = observed
+            .iter()
+            .position(|(status, _)| *status == InstanceStatus::Running)
+            .unwrap_or(observed.len());
+        for (status, reason) in &observed[..first_running] {
+            assert_eq!(*status, InstanceStatus::Crashed);
+            assert_eq!(reason.as_deref(), Some("unreachable executed"));
+        }
+        for detail in &observed[first_running..] {
+            assert_eq!(*detail, (InstanceStatus::Running, None));
+        }
+        assert_eq!(
+            agent.status_with_detail(instance_id).await,
+            (InstanceStatus::Running, None)
+        );
+    }
+
     #[tokio::test]
     async fn test_multiple_instance_crashes() {
         let agent = NodeAgent::new("test-node").unwrap();
@@ -813,4 +2026,282 @@ mod tests {
         let status = agent.get_instance_status(&instance_id).await;
         assert_eq!(status, InstanceStatus::Running);
     }
+
+    #[tokio::test]
+    async fn test_start_instance_records_compile_and_instantiate_metrics() {
+        let observability = Arc::new(ObservabilityController::standalone().unwrap());
+        let agent = NodeAgent::new("test-node")
+            .unwrap()
+            .with_observability(observability.clone());
+
+        agent
+            .start_instance_local(
+                "metrics-instance".to_string(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::default(),
+            )
+            .await
+            .unwrap();
+
+        let rendered = observability.render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_module_compile_seconds_count 1"));
+        assert!(rendered.contains("wasmatrix_instance_instantiate_seconds_count 1"));
+        assert!(rendered.contains("wasmatrix_agent_active_instances 1"));
+
+        agent.stop_instance_local("metrics-instance").await.unwrap();
+        let rendered = observability.render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_agent_active_instances 0"));
+    }
+
+    #[test]
+    fn test_self_test_succeeds_on_default_agent() {
+        let agent = NodeAgent::new("test-node").unwrap();
+        assert!(agent.self_test().is_ok());
+    }
+
+    #[test]
+    fn test_self_test_reports_instantiation_failure() {
+        let host = MockHost::new();
+        host.set_fail_instantiation(true);
+        let agent = NodeAgent::with_host("test-node", Box::new(host));
+
+        let error = agent.self_test().unwrap_err();
+        assert!(error.to_string().contains("self-test"));
+    }
+
+    const HELLO_MODULE: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "\10\00\00\00\06\00\00\00")
+          (data (i32.const 16) "hello\n")
+          (func (export "run")
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+    "#;
+
+    fn stdio_capability(instance_id: &str) -> CapabilityAssignment {
+        CapabilityAssignment::new(
+            instance_id.to_string(),
+            "stdio".to_string(),
+            wasmatrix_core::ProviderType::Kv,
+            vec!["wasi:stdio".to_string()],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_start_rejects_module_declaring_too_much_memory() {
+        let agent = NodeAgent::new("test-node").unwrap().with_admission_limits(
+            admission::AdmissionLimits {
+                max_memory_bytes: Some(16 * admission::WASM_PAGE_SIZE),
+                max_table_elements: None,
+            },
+        );
+        let oversized =
+            wat::parse_str(r#"(module (memory (export "memory") 65536) (func (export "run")))"#)
+                .unwrap();
+        let error = agent
+            .start_instance_local(
+                "oversized".to_string(),
+                oversized,
+                vec![],
+                RestartPolicy::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, CoreError::ResourceExhausted(_)));
+        assert!(error.to_string().contains("initial memory"));
+        assert!(agent.list_instances().await.is_empty());
+
+        let bounded =
+            wat::parse_str(r#"(module (memory (export "memory") 1 4) (func (export "run")))"#)
+                .unwrap();
+        agent
+            .start_instance_with_config(
+                "bounded".to_string(),
+                bounded.clone(),
+                vec![],
+                RestartPolicy::default(),
+                WasiConfig::default().with_max_memory_bytes(4 * admission::WASM_PAGE_SIZE),
+            )
+            .await
+            .unwrap();
+        let error = agent
+            .start_instance_with_config(
+                "capped".to_string(),
+                bounded,
+                vec![],
+                RestartPolicy::default(),
+                WasiConfig::default().with_max_memory_bytes(2 * admission::WASM_PAGE_SIZE),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("maximum"));
+    }
+
+    #[tokio::test]
+    async fn test_stdio_instance_output_is_captured() {
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let module = wat::parse_str(HELLO_MODULE).unwrap();
+        agent
+            .start_instance_local(
+                "printer".to_string(),
+                module,
+                vec![stdio_capability("printer")],
+                RestartPolicy::default(),
+            )
+            .await
+            .unwrap();
+
+        agent.invoke_instance("printer", "run").await.unwrap();
+        agent.invoke_instance("printer", "run").await.unwrap();
+
+        let logs = agent.read_instance_logs("printer", 0).await.unwrap();
+        assert_eq!(logs, b"hello\nhello\n");
+        let tail = agent.read_instance_logs("printer", 6).await.unwrap();
+        assert_eq!(tail, b"hello\n");
+    }
+
+    const ENV_ECHO_MODULE: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "environ_sizes_get"
+            (func $environ_sizes_get (param i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "environ_get"
+            (func $environ_get (param i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "run")
+            (drop (call $environ_sizes_get (i32.const 0) (i32.const 4)))
+            (drop (call $environ_get (i32.const 64) (i32.const 256)))
+            (i32.store (i32.const 16) (i32.const 256))
+            (i32.store (i32.const 20) (i32.sub (i32.load (i32.const 4)) (i32.const 1)))
+            (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))))
+    "#;
+
+    #[tokio::test]
+    async fn test_env_vars_are_visible_to_the_module() {
+        let agent = Arc::new(NodeAgent::new("test-node").unwrap());
+        let mut capability = stdio_capability("env-echo");
+        capability.add_permission("wasi:env".to_string());
+
+        agent
+            .start_instance_with_config(
+                "env-echo".to_string(),
+                wat::parse_str(ENV_ECHO_MODULE).unwrap(),
+                vec![capability],
+                RestartPolicy::default(),
+                WasiConfig::new(
+                    vec![("GREETING".to_string(), "hi".to_string())],
+                    vec!["env-echo".to_string()],
+                ),
+            )
+            .await
+            .unwrap();
+        agent.invoke_instance("env-echo", "run").await.unwrap();
+
+        let logs = agent.read_instance_logs("env-echo", 0).await.unwrap();
+        assert_eq!(logs, b"GREETING=hi");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_wasi_config_is_rejected() {
+        let agent = NodeAgent::new("test-node").unwrap();
+
+        let result = agent
+            .start_instance_with_config(
+                "bad-env".to_string(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::default(),
+                WasiConfig::new(vec![("KEY".to_string(), "a\0b".to_string())], vec![]),
+            )
+            .await;
+
+        assert!(result.unwrap_err().to_string().contains("NUL"));
+        assert!(agent.list_instances().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_logs_unavailable_without_stdio_permission() {
+        let agent = NodeAgent::new("test-node").unwrap();
+        agent
+            .start_instance_local(
+                "quiet".to_string(),
+                create_valid_wasm_module(),
+                vec![],
+                RestartPolicy::default(),
+            )
+            .await
+            .unwrap();
+
+        let error = agent.read_instance_logs("quiet", 0).await.unwrap_err();
+        assert!(error.to_string().contains("wasi:stdio"));
+        assert!(agent.read_instance_logs("missing", 0).await.is_err());
+
+        let module = wat::parse_str(HELLO_MODULE).unwrap();
+        let error = agent
+            .start_instance_local(
+                "printer".to_string(),
+                module,
+                vec![],
+                RestartPolicy::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("wasi:stdio"));
+    }
+
+    #[tokio::test]
+    async fn test_start_draws_from_warm_pool_without_recompiling() {
+        let host = MockHost::new();
+        let observability = Arc::new(ObservabilityController::standalone().unwrap());
+        let agent = NodeAgent::with_host("test-node", Box::new(host.clone()))
+            .with_observability(observability.clone());
+        let module = create_valid_wasm_module();
+        let hash = warm_pool::module_hash(&module);
+
+        agent.warm_module(module.clone(), &[], 2).unwrap();
+        assert_eq!(host.instantiation_count(), 2);
+        assert_eq!(agent.warm_instances_available(&hash), 2);
+
+        agent
+            .start_instance_local(
+                "warm-1".to_string(),
+                module.clone(),
+                vec![],
+                RestartPolicy::default(),
+            )
+            .await
+            .unwrap();
+
+        let rendered = observability.render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_warm_pool_hits_total 1"));
+        assert!(rendered.contains("wasmatrix_module_compile_seconds_count 0"));
+
+        // The slot is topped back up in the background with a fresh instance
+        for _ in 0..100 {
+            if agent.warm_instances_available(&hash) == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(agent.warm_instances_available(&hash), 2);
+        assert_eq!(host.instantiation_count(), 3);
+
+        // A different WASI set cannot reuse instances linked for the warmed one
+        agent
+            .start_instance_local(
+                "cold-1".to_string(),
+                module,
+                vec![stdio_capability("cold-1")],
+                RestartPolicy::default(),
+            )
+            .await
+            .unwrap();
+        let rendered = observability.render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_warm_pool_hits_total 1"));
+        assert!(rendered.contains("wasmatrix_module_compile_seconds_count 1"));
+    }
 }
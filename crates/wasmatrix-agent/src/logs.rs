@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Arc, RwLock};
+use wasi_common::pipe::WritePipe;
+use wasi_common::WasiFile;
+
+/// Bytes of stdout/stderr kept per instance before the oldest output is dropped
+pub const DEFAULT_LOG_CAPACITY: usize = 64 * 1024;
+
+/// Fixed-size byte buffer that keeps only the most recent `capacity` bytes
+#[derive(Debug)]
+pub struct LogRing {
+    buf: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl LogRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buf: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+}
+
+impl Write for LogRing {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let kept = &data[data.len().saturating_sub(self.capacity)..];
+        let overflow = (self.buf.len() + kept.len()).saturating_sub(self.capacity);
+        self.buf.drain(..overflow);
+        self.buf.extend(kept);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Captured stdout/stderr of one instance, shared with its WASI context
+#[derive(Debug, Clone)]
+pub struct InstanceLogs {
+    ring: Arc<RwLock<LogRing>>,
+}
+
+impl InstanceLogs {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ring: Arc::new(RwLock::new(LogRing::new(capacity))),
+        }
+    }
+
+    /// A WASI file that appends everything written to it into this buffer
+    pub fn pipe(&self) -> Box<dyn WasiFile> {
+        Box::new(WritePipe::from_shared(self.ring.clone()))
+    }
+
+    /// The last `max_bytes` bytes of output; `0` returns everything buffered
+    pub fn tail(&self, max_bytes: usize) -> Vec<u8> {
+        let ring = self.ring.read().unwrap_or_else(|e| e.into_inner());
+        let skip = match max_bytes {
+            0 => 0,
+            max => ring.buf.len().saturating_sub(max),
+        };
+        ring.buf.iter().skip(skip).copied().collect()
+    }
+}
+
+impl Default for InstanceLogs {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_drops_oldest_bytes_when_full() {
+        let logs = InstanceLogs::new(8);
+        {
+            let mut ring = logs.ring.write().unwrap();
+            ring.write_all(b"hello ").unwrap();
+            ring.write_all(b"world").unwrap();
+        }
+
+        assert_eq!(logs.tail(0), b"lo world");
+        assert_eq!(logs.tail(5), b"world");
+    }
+
+    #[test]
+    fn test_oversized_write_keeps_its_tail() {
+        let logs = InstanceLogs::new(4);
+        logs.ring.write().unwrap().write_all(b"abcdefgh").unwrap();
+
+        assert_eq!(logs.tail(100), b"efgh");
+    }
+}
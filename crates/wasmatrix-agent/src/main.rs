@@ -2,13 +2,17 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 use tonic::transport::Server;
-use tracing::{info, warn, Level};
+use tracing::{error, info, warn, Level};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
+use wasmatrix_agent::admission::AdmissionLimits;
+use wasmatrix_agent::features::observability::controller::metrics_router;
 use wasmatrix_agent::features::status_reporting::controller::StatusReportController;
 use wasmatrix_agent::features::status_reporting::repo::StatusReportRepo;
 use wasmatrix_agent::features::status_reporting::service::StatusReportService;
+use wasmatrix_agent::host::HostConfig;
 use wasmatrix_agent::server::NodeAgentServer;
 use wasmatrix_agent::NodeAgent;
+use wasmatrix_proto::tls::TlsConfig;
 use wasmatrix_proto::v1::node_agent_service_server::NodeAgentServiceServer;
 
 #[tokio::main]
@@ -28,6 +32,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .parse::<SocketAddr>()?;
     let control_plane_addr = std::env::var("CONTROL_PLANE_ADDR")
         .unwrap_or_else(|_| "http://127.0.0.1:50051".to_string());
+    let metrics_addr = std::env::var("AGENT_METRICS_ADDR")
+        .unwrap_or_else(|_| "127.0.0.1:9101".to_string())
+        .parse::<SocketAddr>()?;
     let report_interval_secs = std::env::var("STATUS_REPORT_INTERVAL_SECS")
         .ok()
         .and_then(|value| value.parse::<u64>().ok())
@@ -35,19 +42,76 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!(%node_id, %node_agent_addr, %control_plane_addr, "Starting Wasmatrix Node Agent");
 
-    let agent = Arc::new(NodeAgent::new(node_id.clone())?);
+    let tls = match TlsConfig::from_env() {
+        Ok(Some(tls)) => {
+            info!("gRPC TLS enabled");
+            Some(tls)
+        }
+        Ok(None) => {
+            warn!("GRPC_TLS_* not set; gRPC traffic is plaintext");
+            None
+        }
+        Err(error) => {
+            error!(error = %error, "Failed to load gRPC TLS configuration");
+            return Err(error.into());
+        }
+    };
+    let mut server_builder = Server::builder();
+    if let Some(tls) = &tls {
+        // Reporting to the control plane needs the CA; fail now rather than on first connect
+        tls.client_config()?;
+        server_builder = server_builder.tls_config(tls.server_config()?)?;
+    }
+
+    let admission_limits = AdmissionLimits {
+        max_memory_bytes: std::env::var("MAX_INSTANCE_MEMORY_BYTES")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok()),
+        max_table_elements: std::env::var("MAX_INSTANCE_TABLE_ELEMENTS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok()),
+    };
+    info!(
+        max_memory_bytes = ?admission_limits.max_memory_bytes,
+        max_table_elements = ?admission_limits.max_table_elements,
+        "Configured module admission limits"
+    );
+    let agent = Arc::new(
+        NodeAgent::with_host_config(node_id.clone(), &HostConfig::from_env())?
+            .with_admission_limits(admission_limits),
+    );
+    if let Err(error) = agent.self_test() {
+        error!(error = %error, "Startup self-test failed; refusing to register node");
+        return Err(error.into());
+    }
+    info!("Startup self-test passed");
+    if let Ok(capabilities) = std::env::var("NODE_CAPABILITIES") {
+        let capabilities: Vec<String> = capabilities
+            .split(',')
+            .map(str::trim)
+            .filter(|capability| !capability.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+        info!(?capabilities, "Advertising node capabilities in heartbeats");
+        agent.set_advertised_capabilities(capabilities).await;
+    }
 
-    let status_report_controller = match StatusReportRepo::connect(&control_plane_addr).await {
+    let status_report_controller = match StatusReportRepo::connect(
+        &control_plane_addr,
+        tls.as_ref(),
+    )
+    .await
+    {
         Ok(repo) => {
             let service = Arc::new(StatusReportService::new(
                 node_id.clone(),
                 agent.clone(),
-                repo,
-            ));
-            let controller = Arc::new(StatusReportController::new(
-                service,
-                Duration::from_secs(report_interval_secs),
+                Arc::new(repo),
             ));
+            let controller = Arc::new(
+                StatusReportController::new(service, Duration::from_secs(report_interval_secs))
+                    .with_final_heartbeat(true),
+            );
 
             if let Err(error) = controller.report_heartbeat().await {
                 warn!(error = %error, "Initial heartbeat report failed");
@@ -62,11 +126,62 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let server = NodeAgentServer::new(agent, status_report_controller);
-    Server::builder()
+    tokio::spawn(async move {
+        let listener = match tokio::net::TcpListener::bind(metrics_addr).await {
+            Ok(listener) => listener,
+            Err(error) => {
+                warn!(error = %error, %metrics_addr, "Failed to bind metrics endpoint");
+                return;
+            }
+        };
+        info!(%metrics_addr, "Metrics endpoint listening");
+        if let Err(error) = axum::serve(listener, metrics_router()).await {
+            warn!(error = %error, "Metrics endpoint exited with error");
+        }
+    });
+
+    let reflection = if wasmatrix_proto::reflection::reflection_enabled_from_env() {
+        info!("gRPC server reflection enabled");
+        Some(wasmatrix_proto::reflection::reflection_service()?)
+    } else {
+        None
+    };
+
+    let server = NodeAgentServer::new(agent, status_report_controller.clone());
+    for (env_var, provider_type) in [
+        ("INVOCATION_QUOTA_KV", wasmatrix_core::ProviderType::Kv),
+        ("INVOCATION_QUOTA_HTTP", wasmatrix_core::ProviderType::Http),
+        (
+            "INVOCATION_QUOTA_MESSAGING",
+            wasmatrix_core::ProviderType::Messaging,
+        ),
+    ] {
+        if let Some(limit) = std::env::var(env_var)
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+        {
+            server.set_invocation_quota(provider_type, limit)?;
+            info!(
+                ?provider_type,
+                limit, "Per-instance invocation quota configured"
+            );
+        }
+    }
+    server_builder
         .add_service(NodeAgentServiceServer::new(server))
-        .serve(node_agent_addr)
+        .add_optional_service(reflection)
+        .serve_with_shutdown(node_agent_addr, async {
+            if let Err(error) = tokio::signal::ctrl_c().await {
+                warn!(error = %error, "Failed to listen for shutdown signal");
+                std::future::pending::<()>().await;
+            }
+            info!("Shutdown signal received");
+        })
         .await?;
 
+    if let Some(controller) = status_report_controller {
+        controller.shutdown().await;
+    }
+
     Ok(())
 }
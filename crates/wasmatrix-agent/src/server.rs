@@ -1,26 +1,76 @@
 use crate::features::status_reporting::controller::StatusReportController;
+use crate::wasi::WasiConfig;
 use crate::NodeAgent;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tonic::{Request, Response, Status};
-use wasmatrix_core::CapabilityAssignment;
+use wasmatrix_core::{CapabilityAssignment, InstanceMetadata};
 use wasmatrix_proto::protocol;
 use wasmatrix_proto::v1::node_agent_service_server::NodeAgentService;
 use wasmatrix_proto::v1::{
     InvokeCapabilityRequest, InvokeCapabilityResponse, ListInstancesRequest, ListInstancesResponse,
-    QueryInstanceRequest, QueryInstanceResponse, StartInstanceRequest, StartInstanceResponse,
+    QueryInstanceRequest, QueryInstanceResponse, ReadInstanceLogsRequest, ReadInstanceLogsResponse,
+    RestartInstanceRequest, RestartInstanceResponse, SetInstanceCapabilitiesRequest,
+    SetInstanceCapabilitiesResponse, StartInstanceRequest, StartInstanceResponse,
     StopInstanceRequest, StopInstanceResponse,
 };
-use wasmatrix_providers::features::provider_lifecycle::repo::InMemoryProviderLifecycleRepository;
-use wasmatrix_providers::features::provider_lifecycle::service::ProviderLifecycleService;
+use wasmatrix_providers::features::async_invocation::service::AsyncInvocationService;
+use wasmatrix_providers::features::invocation_quota::repo::InMemoryInvocationQuotaRepository;
+use wasmatrix_providers::features::invocation_quota::service::InvocationQuotaService;
+use wasmatrix_providers::features::messaging_provider::repo::{
+    InMemoryMessagingProviderRepository, MessagingProviderRepository,
+};
 use wasmatrix_providers::{
-    kv_provider::KvProvider, CapabilityProvider, HttpCapabilityProvider,
-    MessagingCapabilityProvider, ProviderLifecycleController,
+    kv_provider::KvProvider, AsyncInvocationController, CapabilityProvider, HttpCapabilityProvider,
+    IntrospectionProvider, InvocationQuotaController, MessagingCapabilityProvider,
+    ProviderLifecycleController,
 };
 
 pub struct NodeAgentServer {
     agent: Arc<NodeAgent>,
     status_report_controller: Option<Arc<StatusReportController>>,
     provider_lifecycle_controller: Arc<ProviderLifecycleController>,
+    invocation_quota_controller: Arc<InvocationQuotaController>,
+    /// Shared so instance inboxes survive across invocations
+    messaging_repo: Arc<dyn MessagingProviderRepository>,
+    async_invocation_controller: Arc<AsyncInvocationController>,
+    providers: Arc<NodeProviders>,
+}
+
+/// Providers serving invocations on this node, one per capability, kept for
+/// the life of the server so stored data and reconfiguration carry across
+/// invocations
+#[derive(Default)]
+struct NodeProviders {
+    kv: Mutex<HashMap<String, Arc<KvProvider>>>,
+    http: Mutex<HashMap<String, Arc<HttpCapabilityProvider>>>,
+}
+
+impl NodeProviders {
+    fn kv(&self, capability_id: &str) -> Arc<KvProvider> {
+        self.kv
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(capability_id.to_string())
+            .or_insert_with(|| Arc::new(KvProvider::new(capability_id.to_string())))
+            .clone()
+    }
+
+    fn http(&self, capability_id: &str) -> wasmatrix_core::Result<Arc<HttpCapabilityProvider>> {
+        let mut providers = self.http.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(provider) = providers.get(capability_id) {
+            return Ok(provider.clone());
+        }
+        let provider = Arc::new(
+            HttpCapabilityProvider::new(capability_id.to_string()).map_err(|e| {
+                wasmatrix_core::CoreError::WasmRuntimeError(format!(
+                    "Failed to initialize HTTP provider: {e}"
+                ))
+            })?,
+        );
+        providers.insert(capability_id.to_string(), provider.clone());
+        Ok(provider)
+    }
 }
 
 impl NodeAgentServer {
@@ -28,37 +78,112 @@ impl NodeAgentServer {
         agent: Arc<NodeAgent>,
         status_report_controller: Option<Arc<StatusReportController>>,
     ) -> Self {
-        let lifecycle_controller = Arc::new(ProviderLifecycleController::new(
-            ProviderLifecycleService::new(Arc::new(InMemoryProviderLifecycleRepository::new())),
+        let lifecycle_controller = agent.provider_lifecycle();
+        let quota_controller = Arc::new(InvocationQuotaController::new(
+            InvocationQuotaService::new(Arc::new(InMemoryInvocationQuotaRepository::new())),
+        ));
+        let messaging_repo: Arc<dyn MessagingProviderRepository> =
+            Arc::new(InMemoryMessagingProviderRepository::new());
+        let async_invocation_controller = Arc::new(AsyncInvocationController::new(
+            AsyncInvocationService::new(messaging_repo.clone()),
         ));
         Self {
             agent,
             status_report_controller,
             provider_lifecycle_controller: lifecycle_controller,
+            invocation_quota_controller: quota_controller,
+            messaging_repo,
+            async_invocation_controller,
+            providers: Arc::new(NodeProviders::default()),
+        }
+    }
+
+    /// Replace the configuration of the provider serving `capability_id`;
+    /// invocations that start afterwards use the new settings
+    pub fn reconfigure_provider(
+        &self,
+        provider_type: wasmatrix_core::ProviderType,
+        capability_id: &str,
+        config: serde_json::Value,
+    ) -> wasmatrix_core::Result<()> {
+        match provider_type {
+            wasmatrix_core::ProviderType::Kv => {
+                self.providers.kv(capability_id).reconfigure(config)
+            }
+            wasmatrix_core::ProviderType::Http => {
+                self.providers.http(capability_id)?.reconfigure(config)
+            }
+            other => Err(wasmatrix_core::CoreError::InvalidCapabilityAssignment(
+                format!("{other:?} providers do not support reconfiguration"),
+            )),
         }
     }
 
-    pub fn start_provider(&self, provider_id: &str) -> Result<(), Status> {
+    pub fn start_provider(&self, provider_id: &str) -> wasmatrix_core::Result<()> {
         self.provider_lifecycle_controller
             .start_provider(provider_id)
-            .map_err(|e| Status::internal(e.to_string()))
     }
 
-    pub fn stop_provider(&self, provider_id: &str) -> Result<(), Status> {
+    /// Gracefully stop a provider once its in-flight invocations finish
+    pub async fn stop_provider(&self, provider_id: &str) -> wasmatrix_core::Result<()> {
         self.provider_lifecycle_controller
             .stop_provider(provider_id)
-            .map_err(|e| Status::internal(e.to_string()))
+            .await
+    }
+
+    /// Limit how many invocations of `provider_type` each instance may make
+    pub fn set_invocation_quota(
+        &self,
+        provider_type: wasmatrix_core::ProviderType,
+        max_invocations: u64,
+    ) -> wasmatrix_core::Result<()> {
+        self.invocation_quota_controller
+            .set_quota(provider_type, max_invocations)
+    }
+
+    /// Reset all invocation counters for an instance
+    pub fn reset_quota(&self, instance_id: &str) -> wasmatrix_core::Result<()> {
+        self.invocation_quota_controller.reset_quota(instance_id)
+    }
+
+    /// Metadata for an instance on this node, with its module hash and start
+    /// time when it is running
+    async fn local_metadata(&self, instance_id: &str) -> protocol::InstanceMetadata {
+        let capabilities = self
+            .agent
+            .get_instance_capabilities(instance_id)
+            .await
+            .into_iter()
+            .map(to_protocol_capability)
+            .collect();
+        match self.agent.instance_metadata(instance_id).await {
+            Some(metadata) => protocol::InstanceMetadata {
+                instance_id: metadata.instance_id,
+                node_id: metadata.node_id,
+                module_hash: metadata.module_hash,
+                created_at: metadata.created_at.timestamp(),
+                status: metadata.status.into(),
+                capabilities,
+            },
+            None => protocol::InstanceMetadata {
+                instance_id: instance_id.to_string(),
+                node_id: self.agent.node_id().to_string(),
+                module_hash: "unknown".to_string(),
+                created_at: 0,
+                status: self.agent.get_instance_status(instance_id).await.into(),
+                capabilities,
+            },
+        }
     }
 }
 
 // Helpers for conversion
 fn convert_capability(cap: protocol::CapabilityAssignment) -> CapabilityAssignment {
-    CapabilityAssignment {
-        instance_id: cap.instance_id,
-        capability_id: cap.capability_id,
-        provider_type: cap.provider_type.into(),
-        permissions: cap.permissions,
-    }
+    cap.into()
+}
+
+fn to_protocol_capability(cap: CapabilityAssignment) -> protocol::CapabilityAssignment {
+    cap.into()
 }
 
 #[tonic::async_trait]
@@ -96,11 +221,13 @@ impl NodeAgentService for NodeAgentServer {
         let instance_id = req.instance_id;
         match self
             .agent
-            .start_instance_local(
+            .start_instance_annotated(
                 instance_id.clone(),
                 req.module_bytes,
                 capabilities,
                 restart_policy,
+                WasiConfig::new(req.env, req.args),
+                &req.annotations,
             )
             .await
         {
@@ -141,9 +268,25 @@ impl NodeAgentService for NodeAgentServer {
         let req_proto = request.into_inner();
         let req: protocol::StopInstanceRequest = req_proto.into();
 
-        match self.agent.stop_instance_local(&req.instance_id).await {
+        match self
+            .agent
+            .stop_instance_annotated(&req.instance_id, req.reason.as_deref(), &req.annotations)
+            .await
+        {
             Ok(_) => {
                 tracing::info!(%correlation_id, instance_id = %req.instance_id, "Instance stopped");
+                if let Err(error) = self
+                    .invocation_quota_controller
+                    .reset_quota(&req.instance_id)
+                {
+                    tracing::warn!(error = %error, "Failed to reset invocation quota");
+                }
+                if let Err(error) = self
+                    .async_invocation_controller
+                    .cancel_instance(&req.instance_id)
+                {
+                    tracing::warn!(error = %error, "Failed to clear async invocation state");
+                }
                 if let Some(controller) = &self.status_report_controller {
                     if let Err(error) = controller
                         .report_status_change(
@@ -171,54 +314,170 @@ impl NodeAgentService for NodeAgentServer {
         }
     }
 
-    async fn query_instance(
+    async fn restart_instance(
         &self,
-        request: Request<QueryInstanceRequest>,
-    ) -> Result<Response<QueryInstanceResponse>, Status> {
+        request: Request<RestartInstanceRequest>,
+    ) -> Result<Response<RestartInstanceResponse>, Status> {
         let correlation_id = correlation_id_from_request(&request);
-        let req_proto = request.into_inner();
-        let instance_id = req_proto.instance_id;
-        tracing::debug!(%correlation_id, %instance_id, "Querying instance");
+        let req: protocol::RestartInstanceRequest = match request.into_inner().try_into() {
+            Ok(req) => req,
+            Err(e) => {
+                return Ok(Response::new(RestartInstanceResponse {
+                    success: false,
+                    message: format!("Invalid request: {}", e),
+                    error_code: Some("INVALID_REQUEST".to_string()),
+                }))
+            }
+        };
 
-        // This is a simplified implementation. Real query would return metadata.
-        // But NodeAgent mainly manages local execution. Metadata is in ControlPlane.
-        // However, NodeAgent can report status.
+        let capabilities = req
+            .capabilities
+            .into_iter()
+            .map(convert_capability)
+            .collect();
+        match self
+            .agent
+            .force_restart_instance_with_capabilities(&req.instance_id, capabilities)
+            .await
+        {
+            Ok(_) => {
+                tracing::info!(%correlation_id, instance_id = %req.instance_id, "Instance restarted");
+                if let Some(controller) = &self.status_report_controller {
+                    if let Err(error) = controller
+                        .report_status_change(
+                            req.instance_id,
+                            wasmatrix_core::InstanceStatus::Running,
+                            None,
+                        )
+                        .await
+                    {
+                        tracing::warn!(error = %error, "Failed to report restart status change");
+                    }
+                }
 
-        let status = self.agent.get_instance_status(&instance_id).await;
+                Ok(Response::new(RestartInstanceResponse {
+                    success: true,
+                    message: "Instance restarted successfully".to_string(),
+                    error_code: None,
+                }))
+            }
+            Err(e) => {
+                if let Some(controller) = &self.status_report_controller {
+                    if self.agent.get_instance_status(&req.instance_id).await
+                        == wasmatrix_core::InstanceStatus::Crashed
+                    {
+                        if let Err(error) = controller
+                            .report_status_change(
+                                req.instance_id,
+                                wasmatrix_core::InstanceStatus::Crashed,
+                                Some(e.to_string()),
+                            )
+                            .await
+                        {
+                            tracing::warn!(error = %error, "Failed to report restart failure");
+                        }
+                    }
+                }
 
-        // TODO: Populate full metadata if needed. For now just returning status via error/message or specific fields?
-        // The QueryInstanceResponse expects InstanceMetadata.
-        // NodeAgent might not store all metadata (like creation time, module hash) locally in a way that matches InstanceMetadata fully?
-        // Actually NodeAgent has InstanceHandle which has module_bytes, restart_policy.
-        // But InstanceMetadata has created_at, node_id, module_hash.
+                let error_code = match &e {
+                    wasmatrix_core::CoreError::InvalidInstanceId(_) => "INSTANCE_NOT_FOUND",
+                    _ => "INTERNAL_ERROR",
+                };
+                Ok(Response::new(RestartInstanceResponse {
+                    success: false,
+                    message: e.to_string(),
+                    error_code: Some(error_code.to_string()),
+                }))
+            }
+        }
+    }
 
-        // For now, let's return "Not Implemented" or partial data.
-        // But wait, QueryInstance is usually a ControlPlane operation.
-        // Why is it in NodeAgentService?
-        // Ah, Protocol definition puts QueryInstance in NodeAgentService too?
+    async fn read_instance_logs(
+        &self,
+        request: Request<ReadInstanceLogsRequest>,
+    ) -> Result<Response<ReadInstanceLogsResponse>, Status> {
+        let req: protocol::ReadInstanceLogsRequest = request.into_inner().into();
 
-        // Let's check wasmatrix.proto
-        // service NodeAgentService { rpc QueryInstance ... }
+        let response = match self
+            .agent
+            .read_instance_logs(&req.instance_id, req.max_bytes as usize)
+            .await
+        {
+            Ok(data) => protocol::ReadInstanceLogsResponse {
+                success: true,
+                data,
+                message: "Instance logs read successfully".to_string(),
+                error_code: None,
+            },
+            Err(e) => protocol::ReadInstanceLogsResponse {
+                success: false,
+                data: Vec::new(),
+                message: e.to_string(),
+                error_code: Some("LOGS_UNAVAILABLE".to_string()),
+            },
+        };
+        Ok(Response::new(response.into()))
+    }
 
-        // Okay, so Node Agent *should* answer this.
+    async fn set_instance_capabilities(
+        &self,
+        request: Request<SetInstanceCapabilitiesRequest>,
+    ) -> Result<Response<SetInstanceCapabilitiesResponse>, Status> {
+        let correlation_id = correlation_id_from_request(&request);
+        let req: protocol::SetInstanceCapabilitiesRequest = match request.into_inner().try_into() {
+            Ok(req) => req,
+            Err(e) => {
+                return Ok(Response::new(SetInstanceCapabilitiesResponse {
+                    success: false,
+                    message: format!("Invalid request: {}", e),
+                    error_code: Some("INVALID_REQUEST".to_string()),
+                }))
+            }
+        };
 
-        // I'll leave it as "not found" or basic impl for now.
+        let capabilities = req
+            .capabilities
+            .into_iter()
+            .map(convert_capability)
+            .collect();
+        let response = match self
+            .agent
+            .set_instance_capabilities(&req.instance_id, capabilities)
+            .await
+        {
+            Ok(()) => {
+                tracing::info!(%correlation_id, instance_id = %req.instance_id, "Instance capabilities updated");
+                protocol::SetInstanceCapabilitiesResponse {
+                    success: true,
+                    message: "Instance capabilities updated".to_string(),
+                    error_code: None,
+                }
+            }
+            Err(e) => protocol::SetInstanceCapabilitiesResponse {
+                success: false,
+                message: e.to_string(),
+                error_code: Some("INSTANCE_NOT_FOUND".to_string()),
+            },
+        };
+        Ok(Response::new(response.into()))
+    }
 
-        let status_proto: protocol::InstanceStatus = status.into();
+    async fn query_instance(
+        &self,
+        request: Request<QueryInstanceRequest>,
+    ) -> Result<Response<QueryInstanceResponse>, Status> {
+        let correlation_id = correlation_id_from_request(&request);
+        let req_proto = request.into_inner();
+        let instance_id = req_proto.instance_id;
+        tracing::debug!(%correlation_id, %instance_id, "Querying instance");
 
-        // Construct minimal metadata
-        let metadata = protocol::InstanceMetadata {
-            instance_id: instance_id.clone(),
-            node_id: "local".to_string(), // self.agent.node_id?
-            module_hash: "unknown".to_string(),
-            created_at: 0,
-            status: status_proto,
-        };
+        let metadata = self.local_metadata(&instance_id).await;
 
         Ok(Response::new(QueryInstanceResponse {
             success: true,
             instance: Some(metadata.into()),
             error_code: None,
+            ready: self.agent.instance_ready(&instance_id).await,
         }))
     }
 
@@ -230,20 +489,11 @@ impl NodeAgentService for NodeAgentServer {
         let instance_ids = self.agent.list_instances().await;
         tracing::debug!(%correlation_id, count = instance_ids.len(), "Listing instances");
 
-        let instances: Vec<wasmatrix_proto::v1::InstanceMetadata> = instance_ids
-            .into_iter()
-            .map(|id| {
-                // Basic metadata
-                protocol::InstanceMetadata {
-                    instance_id: id,
-                    node_id: "local".to_string(),
-                    module_hash: "unknown".to_string(),
-                    created_at: 0,
-                    status: protocol::InstanceStatus::Running, // If it's in list, it's running (mostly)
-                }
-                .into()
-            })
-            .collect();
+        let mut instances: Vec<wasmatrix_proto::v1::InstanceMetadata> =
+            Vec::with_capacity(instance_ids.len());
+        for id in instance_ids {
+            instances.push(self.local_metadata(&id).await.into());
+        }
 
         Ok(Response::new(ListInstancesResponse {
             success: true,
@@ -264,8 +514,9 @@ impl NodeAgentService for NodeAgentServer {
             operation = %req.operation,
             "Invoking capability"
         );
-        self.provider_lifecycle_controller
-            .ensure_provider_available(&req.capability_id)
+        let in_flight = self
+            .provider_lifecycle_controller
+            .begin_invocation(&req.capability_id)
             .map_err(|e| Status::failed_precondition(e.to_string()))?;
 
         let provider_type = match wasmatrix_proto::v1::ProviderType::try_from(req.provider_type) {
@@ -278,6 +529,41 @@ impl NodeAgentService for NodeAgentServer {
             }
         };
 
+        // Only the grant this node holds for the instance authorises the call;
+        // the request's own `permissions` carry no authority
+        let Some(assignment) = self
+            .agent
+            .get_instance_capabilities(&req.instance_id)
+            .await
+            .into_iter()
+            .find(|cap| {
+                cap.capability_id == req.capability_id
+                    && cap.provider_type == wasmatrix_core::ProviderType::from(provider_type)
+            })
+        else {
+            return Ok(Response::new(InvokeCapabilityResponse {
+                success: false,
+                message: format!(
+                    "Permission denied: instance {} holds no grant for capability {}",
+                    req.instance_id, req.capability_id
+                ),
+                result_json: None,
+                error_code: Some("PERMISSION_DENIED".to_string()),
+            }));
+        };
+
+        if let Err(error) = self
+            .invocation_quota_controller
+            .record_invocation(&req.instance_id, provider_type.into())
+        {
+            return Ok(Response::new(InvokeCapabilityResponse {
+                success: false,
+                message: error.to_string(),
+                result_json: None,
+                error_code: Some("QUOTA_EXCEEDED".to_string()),
+            }));
+        }
+
         let mut params: serde_json::Value = if req.params_json.trim().is_empty() {
             serde_json::json!({})
         } else {
@@ -286,43 +572,43 @@ impl NodeAgentService for NodeAgentServer {
             })?
         };
 
-        if let Some(map) = params.as_object_mut() {
-            map.insert(
-                "permissions".to_string(),
-                serde_json::Value::Array(
-                    req.permissions
-                        .iter()
-                        .cloned()
-                        .map(serde_json::Value::String)
-                        .collect(),
-                ),
-            );
-        }
+        // `"async": true` returns a correlation id now and delivers the result
+        // to the instance's inbox under `_invoke_result.<correlation_id>`.
+        let run_async = params
+            .as_object_mut()
+            .and_then(|map| map.remove("async"))
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
 
-        let result = match provider_type {
-            protocol::ProviderType::Kv => {
-                let provider = KvProvider::new(req.capability_id.clone());
-                provider.invoke(&req.instance_id, &req.operation, params)
-            }
-            protocol::ProviderType::Http => {
-                let capability_id = req.capability_id.clone();
-                let instance_id = req.instance_id.clone();
-                let operation = req.operation.clone();
-                tokio::task::spawn_blocking(move || {
-                    let provider = HttpCapabilityProvider::new(capability_id).map_err(|e| {
-                        wasmatrix_core::CoreError::WasmRuntimeError(format!(
-                            "Failed to initialize HTTP provider: {e}"
-                        ))
-                    })?;
-                    provider.invoke(&instance_id, &operation, params)
-                })
-                .await
-                .map_err(|e| Status::internal(format!("HTTP invocation join error: {e}")))?
-            }
-            protocol::ProviderType::Messaging => {
-                let provider = MessagingCapabilityProvider::new(req.capability_id.clone());
-                provider.invoke(&req.instance_id, &req.operation, params)
+        // Only the agent knows the running instance, so it supplies the metadata
+        let own_metadata = match provider_type {
+            protocol::ProviderType::Introspection => {
+                self.agent.instance_metadata(&req.instance_id).await
             }
+            _ => None,
+        };
+
+        let invocation = invoke_provider(
+            provider_type,
+            assignment,
+            req.operation,
+            params,
+            self.providers.clone(),
+            self.messaging_repo.clone(),
+            own_metadata,
+        );
+        // The guard travels with the invocation so provider shutdown waits for it
+        let invocation = async move {
+            let _in_flight = in_flight;
+            invocation.await
+        };
+        let result = if run_async {
+            let correlation_id = self
+                .async_invocation_controller
+                .dispatch(&req.instance_id, invocation);
+            Ok(serde_json::json!({ "correlation_id": correlation_id }))
+        } else {
+            invocation.await
         };
 
         match result {
@@ -332,16 +618,64 @@ impl NodeAgentService for NodeAgentServer {
                 result_json: Some(value.to_string()),
                 error_code: None,
             })),
-            Err(error) => Ok(Response::new(InvokeCapabilityResponse {
-                success: false,
-                message: error.to_string(),
-                result_json: None,
-                error_code: Some("INVOKE_FAILED".to_string()),
-            })),
+            Err(error) => {
+                let error_code = match &error {
+                    wasmatrix_core::CoreError::PermissionDenied(_) => "PERMISSION_DENIED",
+                    _ => "INVOKE_FAILED",
+                };
+                Ok(Response::new(InvokeCapabilityResponse {
+                    success: false,
+                    message: error.to_string(),
+                    result_json: None,
+                    error_code: Some(error_code.to_string()),
+                }))
+            }
         }
     }
 }
 
+async fn invoke_provider(
+    provider_type: protocol::ProviderType,
+    assignment: CapabilityAssignment,
+    operation: String,
+    params: serde_json::Value,
+    providers: Arc<NodeProviders>,
+    messaging_repo: Arc<dyn MessagingProviderRepository>,
+    own_metadata: Option<InstanceMetadata>,
+) -> wasmatrix_core::Result<serde_json::Value> {
+    match provider_type {
+        protocol::ProviderType::Kv => {
+            providers
+                .kv(&assignment.capability_id)
+                .invoke(&assignment, &operation, params)
+        }
+        protocol::ProviderType::Http => {
+            providers
+                .http(&assignment.capability_id)?
+                .invoke_async(&assignment, &operation, params)
+                .await
+        }
+        protocol::ProviderType::Messaging => {
+            let provider = MessagingCapabilityProvider::with_repository(
+                assignment.capability_id.clone(),
+                messaging_repo,
+            );
+            provider.invoke(&assignment, &operation, params)
+        }
+        protocol::ProviderType::Introspection => match own_metadata {
+            Some(metadata) => IntrospectionProvider::new(
+                assignment.capability_id.clone(),
+                metadata,
+            )
+            .invoke(&assignment, &operation, params),
+            None => Err(wasmatrix_core::CoreError::InvalidInstanceId(format!(
+                "Instance {} is not running on this node",
+                assignment.instance_id
+            ))),
+        },
+    }
+}
+
 fn correlation_id_from_request<T>(request: &Request<T>) -> String {
     request
         .metadata()
@@ -355,10 +689,12 @@ fn correlation_id_from_request<T>(request: &Request<T>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     use tonic::Request;
     use wasmatrix_proto::v1::node_agent_service_server::NodeAgentService;
     use wasmatrix_proto::v1::{
-        CapabilityAssignment as ProtoCapabilityAssignment, InstanceStatus as ProtoInstanceStatus,
+        CapabilityAssignment as ProtoCapabilityAssignment, CapabilityState as ProtoCapabilityState,
+        InstanceStatus as ProtoInstanceStatus,
         InvokeCapabilityRequest as ProtoInvokeCapabilityRequest, ProviderType as ProtoProviderType,
         RestartPolicy as ProtoRestartPolicy, RestartPolicyType as ProtoRestartPolicyType,
     };
@@ -372,6 +708,42 @@ mod tests {
         NodeAgentServer::new(agent, None)
     }
 
+    /// Start `instance_id` holding one active grant for `capability_id`
+    async fn start_with_grant(
+        server: &NodeAgentServer,
+        instance_id: &str,
+        capability_id: &str,
+        provider_type: ProtoProviderType,
+        permissions: &[&str],
+    ) {
+        let started = server
+            .start_instance(Request::new(StartInstanceRequest {
+                instance_id: instance_id.to_string(),
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![ProtoCapabilityAssignment {
+                    instance_id: instance_id.to_string(),
+                    capability_id: capability_id.to_string(),
+                    provider_type: provider_type as i32,
+                    permissions: permissions.iter().map(|p| p.to_string()).collect(),
+                    expires_at: None,
+                    state: ProtoCapabilityState::Active as i32,
+                }],
+                restart_policy: Some(ProtoRestartPolicy {
+                    policy_type: ProtoRestartPolicyType::Never as i32,
+                    max_retries: None,
+                    backoff_seconds: None,
+                    max_backoff_seconds: None,
+                }),
+                env: vec![],
+                args: vec![],
+                annotations: Default::default(),
+            }))
+            .await
+            .expect("start rpc should respond")
+            .into_inner();
+        assert!(started.success, "{}", started.message);
+    }
+
     #[tokio::test]
     async fn test_start_instance_invalid_request_returns_error_response() {
         let server = create_server();
@@ -380,6 +752,9 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: None,
+            env: vec![],
+            args: vec![],
+            annotations: Default::default(),
         };
 
         let response = server
@@ -392,6 +767,56 @@ mod tests {
         assert_eq!(response.error_code.as_deref(), Some("INVALID_REQUEST"));
     }
 
+    #[tokio::test]
+    async fn test_pending_grant_stays_pending_on_node() {
+        let server = create_server();
+        let instance_id = "instance-pending".to_string();
+        let response = server
+            .start_instance(Request::new(StartInstanceRequest {
+                instance_id: instance_id.clone(),
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![ProtoCapabilityAssignment {
+                    instance_id: instance_id.clone(),
+                    capability_id: "kv-1".to_string(),
+                    provider_type: ProtoProviderType::Kv as i32,
+                    permissions: vec!["kv:read".to_string()],
+                    expires_at: None,
+                    state: ProtoCapabilityState::Pending as i32,
+                }],
+                restart_policy: Some(ProtoRestartPolicy {
+                    policy_type: ProtoRestartPolicyType::Never as i32,
+                    max_retries: None,
+                    backoff_seconds: None,
+                    max_backoff_seconds: None,
+                }),
+                env: vec![],
+                args: vec![],
+                annotations: Default::default(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.success, "{}", response.message);
+
+        let held = server.agent.get_instance_capabilities(&instance_id).await;
+        assert_eq!(held[0].state, wasmatrix_core::CapabilityState::Pending);
+        assert!(!held[0].has_permission("kv:read"));
+
+        let metadata = server
+            .query_instance(Request::new(QueryInstanceRequest {
+                instance_id: instance_id.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .instance
+            .unwrap();
+        assert_eq!(
+            metadata.capabilities[0].state,
+            ProtoCapabilityState::Pending as i32
+        );
+    }
+
     #[tokio::test]
     async fn test_start_query_list_stop_instance_flow() {
         let server = create_server();
@@ -404,12 +829,18 @@ mod tests {
                 capability_id: "kv-1".to_string(),
                 provider_type: ProtoProviderType::Kv as i32,
                 permissions: vec!["kv:read".to_string()],
+                expires_at: Some(4_102_444_800),
+                state: ProtoCapabilityState::Active as i32,
             }],
             restart_policy: Some(ProtoRestartPolicy {
                 policy_type: ProtoRestartPolicyType::Always as i32,
                 max_retries: None,
                 backoff_seconds: None,
+                max_backoff_seconds: None,
             }),
+            env: vec![],
+            args: vec![],
+            annotations: Default::default(),
         };
 
         let start_response = server
@@ -433,6 +864,8 @@ mod tests {
             .expect("query should include instance");
         assert_eq!(metadata.instance_id, instance_id);
         assert_eq!(metadata.status, ProtoInstanceStatus::Running as i32);
+        // The grant's expiry survives the hop to the node
+        assert_eq!(metadata.capabilities[0].expires_at, Some(4_102_444_800));
 
         let list_response = server
             .list_instances(Request::new(ListInstancesRequest {}))
@@ -442,21 +875,283 @@ mod tests {
         assert!(list_response.success);
         assert_eq!(list_response.instances.len(), 1);
         assert_eq!(list_response.instances[0].instance_id, "instance-1");
+        let listed_caps = &list_response.instances[0].capabilities;
+        assert_eq!(listed_caps.len(), 1);
+        assert_eq!(listed_caps[0].capability_id, "kv-1");
+        assert_eq!(listed_caps[0].permissions, vec!["kv:read".to_string()]);
+        assert_eq!(metadata.capabilities.len(), 1);
 
         let stop_response = server
             .stop_instance(Request::new(StopInstanceRequest {
                 instance_id: "instance-1".to_string(),
+                reason: Some("scale-down".to_string()),
+                annotations: Default::default(),
             }))
             .await
             .expect("stop rpc should respond")
             .into_inner();
         assert!(stop_response.success);
         assert!(stop_response.error_code.is_none());
+
+        let stopped = server
+            .agent
+            .get_execution_events_for_instance("instance-1")
+            .await
+            .into_iter()
+            .find(|event| event.event_type == "instance_stopped")
+            .expect("stop should be recorded");
+        assert_eq!(
+            stopped
+                .details
+                .as_ref()
+                .and_then(|details| details.get("reason"))
+                .map(String::as_str),
+            Some("scale-down")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_and_list_report_module_hash() {
+        let server = create_server();
+        let module = create_valid_wasm_module();
+        let started = server
+            .start_instance(Request::new(StartInstanceRequest {
+                instance_id: "instance-hashed".to_string(),
+                module_bytes: module.clone(),
+                capabilities: vec![],
+                restart_policy: Some(ProtoRestartPolicy {
+                    policy_type: ProtoRestartPolicyType::Never as i32,
+                    max_retries: None,
+                    backoff_seconds: None,
+                    max_backoff_seconds: None,
+                }),
+                env: vec![],
+                args: vec![],
+                annotations: Default::default(),
+            }))
+            .await
+            .expect("start rpc should respond")
+            .into_inner();
+        assert!(started.success, "{}", started.message);
+        let expected = crate::warm_pool::module_hash(&module);
+
+        let queried = server
+            .query_instance(Request::new(QueryInstanceRequest {
+                instance_id: "instance-hashed".to_string(),
+            }))
+            .await
+            .expect("query rpc should respond")
+            .into_inner()
+            .instance
+            .expect("query should include instance");
+        assert_eq!(queried.module_hash, expected);
+        assert!(queried.created_at > 0);
+
+        let listed = server
+            .list_instances(Request::new(ListInstancesRequest {}))
+            .await
+            .expect("list rpc should respond")
+            .into_inner();
+        assert_eq!(listed.instances.len(), 1);
+        assert_eq!(listed.instances[0].module_hash, expected);
+        assert_eq!(listed.instances[0].created_at, queried.created_at);
+    }
+
+    #[tokio::test]
+    async fn test_start_annotations_appear_on_started_event() {
+        let server = create_server();
+        let request = |instance_id: &str, annotations| StartInstanceRequest {
+            instance_id: instance_id.to_string(),
+            module_bytes: create_valid_wasm_module(),
+            capabilities: vec![],
+            restart_policy: Some(ProtoRestartPolicy {
+                policy_type: ProtoRestartPolicyType::Never as i32,
+                max_retries: None,
+                backoff_seconds: None,
+                max_backoff_seconds: None,
+            }),
+            env: vec![],
+            args: vec![],
+            annotations,
+        };
+
+        let started = server
+            .start_instance(Request::new(request(
+                "instance-traced",
+                HashMap::from([("trace_id".to_string(), "abc123".to_string())]),
+            )))
+            .await
+            .expect("start rpc should respond")
+            .into_inner();
+        assert!(started.success, "{}", started.message);
+
+        let event = server
+            .agent
+            .get_execution_events_for_instance("instance-traced")
+            .await
+            .into_iter()
+            .find(|event| event.event_type == "instance_started")
+            .expect("start should be recorded");
+        assert_eq!(
+            event
+                .details
+                .as_ref()
+                .and_then(|details| details.get("annotation.trace_id"))
+                .map(String::as_str),
+            Some("abc123")
+        );
+
+        let oversized = (0..=wasmatrix_core::statelessness::MAX_EVENT_ANNOTATIONS)
+            .map(|i| (format!("key-{i}"), "value".to_string()))
+            .collect();
+        let rejected = server
+            .start_instance(Request::new(request("instance-noisy", oversized)))
+            .await
+            .expect("start rpc should respond")
+            .into_inner();
+        assert!(!rejected.success);
+        assert!(server
+            .agent
+            .get_execution_events_for_instance("instance-noisy")
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_ready_tracks_backing_provider() {
+        let server = create_server();
+        let instance_id = "instance-ready".to_string();
+        let start_response = server
+            .start_instance(Request::new(StartInstanceRequest {
+                instance_id: instance_id.clone(),
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![ProtoCapabilityAssignment {
+                    instance_id: instance_id.clone(),
+                    capability_id: "messaging-1".to_string(),
+                    provider_type: ProtoProviderType::Messaging as i32,
+                    permissions: vec!["msg:publish".to_string()],
+                    expires_at: None,
+                    state: ProtoCapabilityState::Active as i32,
+                }],
+                restart_policy: Some(ProtoRestartPolicy {
+                    policy_type: ProtoRestartPolicyType::Never as i32,
+                    max_retries: None,
+                    backoff_seconds: None,
+                    max_backoff_seconds: None,
+                }),
+                env: vec![],
+                args: vec![],
+                annotations: Default::default(),
+            }))
+            .await
+            .expect("start rpc should respond")
+            .into_inner();
+        assert!(start_response.success);
+
+        let query = || async {
+            server
+                .query_instance(Request::new(QueryInstanceRequest {
+                    instance_id: instance_id.clone(),
+                }))
+                .await
+                .expect("query rpc should respond")
+                .into_inner()
+        };
+        // Querying never starts the provider, so an unknown one stays not ready
+        assert!(!query().await.ready);
+        assert!(!query().await.ready);
+
+        server.start_provider("messaging-1").unwrap();
+        assert!(query().await.ready);
+
+        server.stop_provider("messaging-1").await.unwrap();
+
+        let response = query().await;
+        assert!(!response.ready);
+        assert_eq!(
+            response.instance.unwrap().status,
+            ProtoInstanceStatus::Running as i32
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restart_instance_ignores_never_policy() {
+        let server = create_server();
+        let instance_id = "instance-never".to_string();
+        let start_response = server
+            .start_instance(Request::new(StartInstanceRequest {
+                instance_id: instance_id.clone(),
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: Some(ProtoRestartPolicy {
+                    policy_type: ProtoRestartPolicyType::Never as i32,
+                    max_retries: None,
+                    backoff_seconds: None,
+                    max_backoff_seconds: None,
+                }),
+                env: vec![],
+                args: vec![],
+                annotations: Default::default(),
+            }))
+            .await
+            .expect("start rpc should respond")
+            .into_inner();
+        assert!(start_response.success);
+
+        server
+            .agent
+            .on_instance_crash(&instance_id, "trap".to_string())
+            .await;
+
+        let restart_response = server
+            .restart_instance(Request::new(RestartInstanceRequest {
+                instance_id: instance_id.clone(),
+                capabilities: vec![],
+            }))
+            .await
+            .expect("restart rpc should respond")
+            .into_inner();
+        assert!(restart_response.success);
+        assert!(restart_response.error_code.is_none());
+
+        let query_response = server
+            .query_instance(Request::new(QueryInstanceRequest { instance_id }))
+            .await
+            .expect("query rpc should respond")
+            .into_inner();
+        let metadata = query_response
+            .instance
+            .expect("query should include instance");
+        assert_eq!(metadata.status, ProtoInstanceStatus::Running as i32);
+    }
+
+    #[tokio::test]
+    async fn test_restart_unknown_instance_returns_error_response() {
+        let server = create_server();
+        let response = server
+            .restart_instance(Request::new(RestartInstanceRequest {
+                instance_id: "missing".to_string(),
+                capabilities: vec![],
+            }))
+            .await
+            .expect("restart rpc should respond")
+            .into_inner();
+
+        assert!(!response.success);
+        assert_eq!(response.error_code.as_deref(), Some("INSTANCE_NOT_FOUND"));
     }
 
     #[tokio::test]
     async fn test_invoke_capability_http_permission_denied() {
         let server = create_server();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "http-provider",
+            ProtoProviderType::Http,
+            &[],
+        )
+        .await;
         let response = server
             .invoke_capability(Request::new(ProtoInvokeCapabilityRequest {
                 instance_id: "instance-1".to_string(),
@@ -471,12 +1166,276 @@ mod tests {
             .into_inner();
 
         assert!(!response.success);
-        assert_eq!(response.error_code.as_deref(), Some("INVOKE_FAILED"));
+        assert_eq!(response.error_code.as_deref(), Some("PERMISSION_DENIED"));
+    }
+
+    #[tokio::test]
+    async fn test_reconfigured_http_provider_serves_later_invocations() {
+        let server = create_server();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "http-provider",
+            ProtoProviderType::Http,
+            &["http:request"],
+        )
+        .await;
+        server
+            .reconfigure_provider(
+                wasmatrix_core::ProviderType::Http,
+                "http-provider",
+                serde_json::json!({ "allowed_domains": ["api.example.com"] }),
+            )
+            .expect("http provider should accept the config");
+
+        let response = server
+            .invoke_capability(Request::new(ProtoInvokeCapabilityRequest {
+                instance_id: "instance-1".to_string(),
+                capability_id: "http-provider".to_string(),
+                provider_type: ProtoProviderType::Http as i32,
+                operation: "request".to_string(),
+                params_json: r#"{"method":"GET","url":"https://other.example.com/"}"#.to_string(),
+                permissions: vec![],
+            }))
+            .await
+            .expect("invoke rpc should respond")
+            .into_inner();
+
+        assert!(!response.success);
+        assert!(response.message.contains("not an allowed domain"));
+    }
+
+    #[tokio::test]
+    async fn test_kv_values_persist_across_invocations() {
+        let server = create_server();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "kv-provider",
+            ProtoProviderType::Kv,
+            &["kv:read", "kv:write"],
+        )
+        .await;
+        let invoke = |operation: &str, params_json: &str| {
+            Request::new(ProtoInvokeCapabilityRequest {
+                instance_id: "instance-1".to_string(),
+                capability_id: "kv-provider".to_string(),
+                provider_type: ProtoProviderType::Kv as i32,
+                operation: operation.to_string(),
+                params_json: params_json.to_string(),
+                permissions: vec![],
+            })
+        };
+
+        let set = server
+            .invoke_capability(invoke("set", r#"{"key":"k","value":"v"}"#))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(set.success);
+        let get = server
+            .invoke_capability(invoke("get", r#"{"key":"k"}"#))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert!(get.success);
+        assert_eq!(get.result_json.as_deref(), Some("\"v\""));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_introspection_reads_own_metadata() {
+        let server = create_server();
+        let instance_id = "instance-self".to_string();
+        let started = server
+            .start_instance(Request::new(StartInstanceRequest {
+                instance_id: instance_id.clone(),
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![ProtoCapabilityAssignment {
+                    instance_id: instance_id.clone(),
+                    capability_id: "introspection".to_string(),
+                    provider_type: ProtoProviderType::Introspection as i32,
+                    permissions: vec!["self:read".to_string()],
+                    expires_at: None,
+                    state: ProtoCapabilityState::Active as i32,
+                }],
+                restart_policy: Some(ProtoRestartPolicy {
+                    policy_type: ProtoRestartPolicyType::Never as i32,
+                    max_retries: None,
+                    backoff_seconds: None,
+                    max_backoff_seconds: None,
+                }),
+                env: vec![],
+                args: vec![],
+                annotations: Default::default(),
+            }))
+            .await
+            .expect("start rpc should respond")
+            .into_inner();
+        assert!(started.success, "{}", started.message);
+
+        let response = server
+            .invoke_capability(Request::new(ProtoInvokeCapabilityRequest {
+                instance_id: instance_id.clone(),
+                capability_id: "introspection".to_string(),
+                provider_type: ProtoProviderType::Introspection as i32,
+                operation: "read".to_string(),
+                params_json: String::new(),
+                permissions: vec!["self:read".to_string()],
+            }))
+            .await
+            .expect("invoke rpc should respond")
+            .into_inner();
+
+        assert!(response.success, "{}", response.message);
+        let result: serde_json::Value =
+            serde_json::from_str(response.result_json.as_deref().unwrap()).unwrap();
+        assert_eq!(result["instance_id"], "instance-self");
+        assert_eq!(result["node_id"], "test-node");
+        assert_eq!(result["status"], "running");
+    }
+
+    #[tokio::test]
+    async fn test_invoke_capability_without_stored_grant_is_denied() {
+        let server = create_server();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "messaging-provider",
+            ProtoProviderType::Messaging,
+            &["msg:subscribe:orders"],
+        )
+        .await;
+        let invoke = |instance_id: &str, capability_id: &str| {
+            Request::new(ProtoInvokeCapabilityRequest {
+                instance_id: instance_id.to_string(),
+                capability_id: capability_id.to_string(),
+                provider_type: ProtoProviderType::Messaging as i32,
+                operation: "publish".to_string(),
+                params_json: "{\"topic\":\"orders\",\"payload\":\"created\"}".to_string(),
+                permissions: vec!["msg:publish".to_string()],
+            })
+        };
+
+        // Claimed permissions grant nothing: not for an unknown instance, not
+        // for a capability the instance was never granted, and not beyond
+        // what the stored grant allows
+        for (instance_id, capability_id, error_code) in [
+            (
+                "instance-unknown",
+                "messaging-provider",
+                "PERMISSION_DENIED",
+            ),
+            ("instance-1", "messaging-other", "PERMISSION_DENIED"),
+            ("instance-1", "messaging-provider", "INVOKE_FAILED"),
+        ] {
+            let response = server
+                .invoke_capability(invoke(instance_id, capability_id))
+                .await
+                .expect("invoke rpc should respond")
+                .into_inner();
+            assert!(!response.success);
+            assert_eq!(response.error_code.as_deref(), Some(error_code));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_instance_capabilities_changes_what_invocations_may_do() {
+        let server = create_server();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "messaging-provider",
+            ProtoProviderType::Messaging,
+            &["msg:publish:orders"],
+        )
+        .await;
+        let publish = || {
+            Request::new(ProtoInvokeCapabilityRequest {
+                instance_id: "instance-1".to_string(),
+                capability_id: "messaging-provider".to_string(),
+                provider_type: ProtoProviderType::Messaging as i32,
+                operation: "publish".to_string(),
+                params_json: "{\"topic\":\"orders\",\"payload\":\"created\"}".to_string(),
+                permissions: vec![],
+            })
+        };
+        let set = |capabilities| {
+            Request::new(SetInstanceCapabilitiesRequest {
+                instance_id: "instance-1".to_string(),
+                capabilities,
+            })
+        };
+        assert!(
+            server
+                .invoke_capability(publish())
+                .await
+                .unwrap()
+                .get_ref()
+                .success
+        );
+
+        // Revoking the grant takes effect on the running instance
+        let revoked = server
+            .set_instance_capabilities(set(vec![]))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(revoked.success, "{}", revoked.message);
+        let denied = server
+            .invoke_capability(publish())
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!denied.success);
+        assert_eq!(denied.error_code.as_deref(), Some("PERMISSION_DENIED"));
+
+        // And so does granting it again
+        let granted = server
+            .set_instance_capabilities(set(vec![ProtoCapabilityAssignment {
+                instance_id: "instance-1".to_string(),
+                capability_id: "messaging-provider".to_string(),
+                provider_type: ProtoProviderType::Messaging as i32,
+                permissions: vec!["msg:publish:orders".to_string()],
+                expires_at: None,
+                state: ProtoCapabilityState::Active as i32,
+            }]))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(granted.success, "{}", granted.message);
+        assert!(
+            server
+                .invoke_capability(publish())
+                .await
+                .unwrap()
+                .get_ref()
+                .success
+        );
+
+        let unknown = server
+            .set_instance_capabilities(Request::new(SetInstanceCapabilitiesRequest {
+                instance_id: "instance-unknown".to_string(),
+                capabilities: vec![],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!unknown.success);
+        assert_eq!(unknown.error_code.as_deref(), Some("INSTANCE_NOT_FOUND"));
     }
 
     #[tokio::test]
     async fn test_invoke_capability_messaging_publish_success() {
         let server = create_server();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "messaging-provider",
+            ProtoProviderType::Messaging,
+            &["msg:publish:orders"],
+        )
+        .await;
         let response = server
             .invoke_capability(Request::new(ProtoInvokeCapabilityRequest {
                 instance_id: "instance-1".to_string(),
@@ -494,10 +1453,105 @@ mod tests {
         assert!(response.result_json.is_some());
     }
 
+    #[tokio::test]
+    async fn test_invoke_capability_ignores_permissions_in_params() {
+        let server = create_server();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "messaging-provider",
+            ProtoProviderType::Messaging,
+            &["msg:subscribe:orders"],
+        )
+        .await;
+        let response = server
+            .invoke_capability(Request::new(ProtoInvokeCapabilityRequest {
+                instance_id: "instance-1".to_string(),
+                capability_id: "messaging-provider".to_string(),
+                provider_type: ProtoProviderType::Messaging as i32,
+                operation: "publish".to_string(),
+                params_json:
+                    r#"{"topic":"orders","payload":"created","permissions":["msg:publish:orders"]}"#
+                        .to_string(),
+                permissions: vec!["msg:publish:orders".to_string()],
+            }))
+            .await
+            .expect("invoke rpc should respond")
+            .into_inner();
+
+        assert!(!response.success);
+        assert_eq!(response.error_code.as_deref(), Some("INVOKE_FAILED"));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_capability_async_result_is_polled_from_inbox() {
+        let server = create_server();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "messaging-provider",
+            ProtoProviderType::Messaging,
+            &["msg:publish:orders"],
+        )
+        .await;
+        let invoke = |operation: &str, params_json: String| {
+            Request::new(ProtoInvokeCapabilityRequest {
+                instance_id: "instance-1".to_string(),
+                capability_id: "messaging-provider".to_string(),
+                provider_type: ProtoProviderType::Messaging as i32,
+                operation: operation.to_string(),
+                params_json,
+                permissions: vec!["msg:publish:orders".to_string()],
+            })
+        };
+
+        let accepted = server
+            .invoke_capability(invoke(
+                "publish",
+                r#"{"topic":"orders","payload":"created","async":true}"#.to_string(),
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(accepted.success);
+        let accepted: serde_json::Value =
+            serde_json::from_str(accepted.result_json.as_deref().unwrap()).unwrap();
+        let correlation_id = accepted["correlation_id"].as_str().unwrap().to_string();
+
+        let poll_params = serde_json::json!({
+            "topic": format!("_invoke_result.{correlation_id}")
+        })
+        .to_string();
+        let mut delivered = None;
+        for _ in 0..100 {
+            let polled = server
+                .invoke_capability(invoke("poll", poll_params.clone()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(polled.success);
+            let polled: serde_json::Value =
+                serde_json::from_str(polled.result_json.as_deref().unwrap()).unwrap();
+            if let Some(message) = polled["messages"].as_array().and_then(|m| m.first()) {
+                delivered = Some(message["payload"].as_str().unwrap().to_string());
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let payload: serde_json::Value = serde_json::from_str(&delivered.unwrap()).unwrap();
+        assert_eq!(
+            payload["correlation_id"].as_str(),
+            Some(correlation_id.as_str())
+        );
+        assert_eq!(payload["success"].as_bool(), Some(true));
+        assert_eq!(payload["result"]["published"].as_bool(), Some(true));
+    }
+
     #[tokio::test]
     async fn test_provider_stopped_returns_unavailable_error() {
         let server = create_server();
-        server.stop_provider("messaging-provider").unwrap();
+        server.stop_provider("messaging-provider").await.unwrap();
 
         let response = server
             .invoke_capability(Request::new(ProtoInvokeCapabilityRequest {
@@ -520,8 +1574,16 @@ mod tests {
     #[tokio::test]
     async fn test_provider_restart_allows_invocation_again() {
         let server = create_server();
-        server.stop_provider("messaging-provider").unwrap();
+        server.stop_provider("messaging-provider").await.unwrap();
         server.start_provider("messaging-provider").unwrap();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "messaging-provider",
+            ProtoProviderType::Messaging,
+            &["msg:publish:orders"],
+        )
+        .await;
 
         let response = server
             .invoke_capability(Request::new(ProtoInvokeCapabilityRequest {
@@ -538,4 +1600,45 @@ mod tests {
 
         assert!(response.success);
     }
+
+    #[tokio::test]
+    async fn test_kv_invocation_quota_rejects_third_call() {
+        let server = create_server();
+        server
+            .set_invocation_quota(wasmatrix_core::ProviderType::Kv, 2)
+            .unwrap();
+        start_with_grant(
+            &server,
+            "instance-1",
+            "kv-provider",
+            ProtoProviderType::Kv,
+            &["kv:read"],
+        )
+        .await;
+        let request = || {
+            Request::new(ProtoInvokeCapabilityRequest {
+                instance_id: "instance-1".to_string(),
+                capability_id: "kv-provider".to_string(),
+                provider_type: ProtoProviderType::Kv as i32,
+                operation: "get".to_string(),
+                params_json: r#"{"key":"k"}"#.to_string(),
+                permissions: vec![],
+            })
+        };
+
+        for _ in 0..2 {
+            let response = server.invoke_capability(request()).await.unwrap();
+            assert!(response.get_ref().success, "{}", response.get_ref().message);
+        }
+        let third = server.invoke_capability(request()).await.unwrap();
+        assert!(!third.get_ref().success);
+        assert_eq!(
+            third.get_ref().error_code.as_deref(),
+            Some("QUOTA_EXCEEDED")
+        );
+
+        server.reset_quota("instance-1").unwrap();
+        let after_reset = server.invoke_capability(request()).await.unwrap();
+        assert!(after_reset.get_ref().success);
+    }
 }
@@ -0,0 +1,158 @@
+use crate::host::{Instantiation, ModuleHost};
+use crate::wasi::{WasiCapabilitySet, WasiConfig};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use wasmatrix_core::module_hash::compute_module_hash;
+use wasmatrix_core::Result;
+
+/// Hash used to match a start request against warmed modules
+pub fn module_hash(module_bytes: &[u8]) -> String {
+    compute_module_hash(module_bytes)
+}
+
+/// Warm instances are only interchangeable if they link the same WASI functions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct WarmKey {
+    module_hash: String,
+    wasi: WasiCapabilitySet,
+}
+
+struct WarmSlot {
+    module_bytes: Arc<Vec<u8>>,
+    target: usize,
+    ready: Vec<Instantiation>,
+    /// Instantiations handed to a background refill but not yet returned
+    pending: usize,
+}
+
+/// Instantiations needed to bring a slot back to its target size
+pub struct Refill {
+    key: WarmKey,
+    module_bytes: Arc<Vec<u8>>,
+    count: usize,
+}
+
+/// Pre-instantiated instances per (module hash, WASI set), ready to hand out.
+///
+/// Every entry owns a fresh store and is handed out exactly once; stopped
+/// instances are dropped rather than returned, so no state leaks between uses.
+/// Entries carry no WASI env vars or args.
+#[derive(Default)]
+pub struct WarmPool {
+    slots: Mutex<HashMap<WarmKey, WarmSlot>>,
+}
+
+impl WarmPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `target` instances of `module_bytes` warm; `0` removes the slot.
+    ///
+    /// Returns the instantiations needed to reach the target, if any.
+    pub fn configure(
+        &self,
+        module_bytes: Vec<u8>,
+        wasi: WasiCapabilitySet,
+        target: usize,
+    ) -> Option<Refill> {
+        let key = WarmKey {
+            module_hash: module_hash(&module_bytes),
+            wasi,
+        };
+        let mut slots = self.lock();
+        if target == 0 {
+            slots.remove(&key);
+            return None;
+        }
+
+        let slot = slots.entry(key.clone()).or_insert_with(|| WarmSlot {
+            module_bytes: Arc::new(module_bytes),
+            target,
+            ready: Vec::new(),
+            pending: 0,
+        });
+        slot.target = target;
+        slot.ready.truncate(target);
+        Self::plan_refill(key, slot)
+    }
+
+    /// Take a warm instance for `module_hash` linked with exactly `wasi`.
+    ///
+    /// On a hit, also returns the refill needed to top the slot back up.
+    pub fn take(
+        &self,
+        module_hash: &str,
+        wasi: &WasiCapabilitySet,
+    ) -> Option<(Instantiation, Option<Refill>)> {
+        let key = WarmKey {
+            module_hash: module_hash.to_string(),
+            wasi: wasi.clone(),
+        };
+        let mut slots = self.lock();
+        let slot = slots.get_mut(&key)?;
+        let started = slot.ready.pop()?;
+        Some((started, Self::plan_refill(key, slot)))
+    }
+
+    /// Instantiate everything `refill` asks for through `host`.
+    ///
+    /// Results for slots that were removed or are already full are dropped.
+    /// Stops at the first failure, releasing the remaining reservations.
+    pub fn fill(&self, host: &dyn ModuleHost, refill: Refill) -> Result<()> {
+        for remaining in (0..refill.count).rev() {
+            match host.instantiate(
+                &refill.module_bytes,
+                &refill.key.wasi,
+                &WasiConfig::default(),
+            ) {
+                Ok(started) => self.complete(&refill.key, Some(started)),
+                Err(error) => {
+                    for _ in 0..=remaining {
+                        self.complete(&refill.key, None);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn complete(&self, key: &WarmKey, started: Option<Instantiation>) {
+        let mut slots = self.lock();
+        if let Some(slot) = slots.get_mut(key) {
+            slot.pending = slot.pending.saturating_sub(1);
+            if let Some(started) = started {
+                if slot.ready.len() < slot.target {
+                    slot.ready.push(started);
+                }
+            }
+        }
+    }
+
+    /// Number of warm instances ready for `module_hash`, across all WASI sets
+    pub fn available(&self, module_hash: &str) -> usize {
+        self.lock()
+            .iter()
+            .filter(|(key, _)| key.module_hash == module_hash)
+            .map(|(_, slot)| slot.ready.len())
+            .sum()
+    }
+
+    fn plan_refill(key: WarmKey, slot: &mut WarmSlot) -> Option<Refill> {
+        let count = slot.target.saturating_sub(slot.ready.len() + slot.pending);
+        if count == 0 {
+            return None;
+        }
+        slot.pending += count;
+        Some(Refill {
+            key,
+            module_bytes: slot.module_bytes.clone(),
+            count,
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<WarmKey, WarmSlot>> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
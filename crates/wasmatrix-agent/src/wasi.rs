@@ -0,0 +1,346 @@
+use std::collections::BTreeSet;
+use wasi_common::WasiCtx;
+use wasmatrix_core::statelessness::StatelessnessPolicy;
+use wasmatrix_core::{CapabilityAssignment, CoreError, Result};
+use wasmtime::{Engine, Linker, Module, Store};
+
+/// Import modules that carry WASI preview 0/1 functions
+const WASI_MODULES: [&str; 2] = ["wasi_snapshot_preview1", "wasi_unstable"];
+
+/// Group of WASI functions that can be granted to an instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WasiCapability {
+    Clock,
+    Random,
+    Environment,
+    Process,
+    Filesystem,
+    Network,
+    /// Writing to stdout/stderr, which the host captures into a log buffer
+    Stdio,
+}
+
+impl WasiCapability {
+    /// Permission string that grants this group, e.g. `wasi:clock`
+    pub fn permission(&self) -> &'static str {
+        match self {
+            WasiCapability::Clock => "wasi:clock",
+            WasiCapability::Random => "wasi:random",
+            WasiCapability::Environment => "wasi:env",
+            WasiCapability::Process => "wasi:proc",
+            WasiCapability::Filesystem => "wasi:filesystem",
+            WasiCapability::Network => "wasi:network",
+            WasiCapability::Stdio => "wasi:stdio",
+        }
+    }
+
+    const ALL: [WasiCapability; 7] = [
+        WasiCapability::Clock,
+        WasiCapability::Random,
+        WasiCapability::Environment,
+        WasiCapability::Process,
+        WasiCapability::Filesystem,
+        WasiCapability::Network,
+        WasiCapability::Stdio,
+    ];
+
+    /// Group a WASI function belongs to; `None` for functions we never link
+    fn for_function(name: &str) -> Option<Self> {
+        match name {
+            "clock_res_get" | "clock_time_get" | "poll_oneoff" => Some(WasiCapability::Clock),
+            "random_get" => Some(WasiCapability::Random),
+            "args_get" | "args_sizes_get" | "environ_get" | "environ_sizes_get" => {
+                Some(WasiCapability::Environment)
+            }
+            "proc_exit" | "proc_raise" | "sched_yield" => Some(WasiCapability::Process),
+            "fd_write" => Some(WasiCapability::Stdio),
+            name if name.starts_with("fd_") || name.starts_with("path_") => {
+                Some(WasiCapability::Filesystem)
+            }
+            name if name.starts_with("sock_") => Some(WasiCapability::Network),
+            _ => None,
+        }
+    }
+}
+
+/// Environment variables and argv handed to an instance's WASI context.
+///
+/// This is start-up configuration: it is bounded by
+/// [`StatelessnessPolicy::verify_wasi_config`] and never persisted as instance state.
+/// Modules can only read it when granted `wasi:env`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WasiConfig {
+    pub env: Vec<(String, String)>,
+    pub args: Vec<String>,
+    /// Upper bound on the memory the module may declare, checked before
+    /// instantiation; `None` leaves only the node limit
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl WasiConfig {
+    pub fn new(env: Vec<(String, String)>, args: Vec<String>) -> Self {
+        Self {
+            env,
+            args,
+            max_memory_bytes: None,
+        }
+    }
+
+    pub fn with_max_memory_bytes(mut self, max_memory_bytes: u64) -> Self {
+        self.max_memory_bytes = Some(max_memory_bytes);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty() && self.args.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        StatelessnessPolicy::verify_wasi_config(&self.env, &self.args)
+    }
+}
+
+/// WASI functions an instance may import, derived from its `wasi:*` permissions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WasiCapabilitySet {
+    allowed: BTreeSet<WasiCapability>,
+}
+
+impl WasiCapabilitySet {
+    /// A set that allows no WASI functions
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, capability: WasiCapability) -> Self {
+        self.allowed.insert(capability);
+        self
+    }
+
+    /// Collect `wasi:*` permissions from an instance's capability assignments.
+    ///
+    /// Each group is decided by [`CapabilityAssignment::has_permission`], so
+    /// wildcard grants count and pending, revoked or expired assignments link
+    /// nothing, the same as for provider invocations.
+    pub fn from_assignments(assignments: &[CapabilityAssignment]) -> Self {
+        let allowed = WasiCapability::ALL
+            .into_iter()
+            .filter(|capability| {
+                assignments
+                    .iter()
+                    .any(|assignment| assignment.has_permission(capability.permission()))
+            })
+            .collect();
+        Self { allowed }
+    }
+
+    pub fn allows(&self, capability: WasiCapability) -> bool {
+        self.allowed.contains(&capability)
+    }
+
+    fn allows_function(&self, name: &str) -> bool {
+        // `fd_write` is needed both for stdio and for writing opened files
+        if name == "fd_write" && self.allows(WasiCapability::Filesystem) {
+            return true;
+        }
+        WasiCapability::for_function(name).is_some_and(|cap| self.allows(cap))
+    }
+
+    /// Reject modules that import WASI functions outside this set
+    pub fn check_imports(&self, module: &Module) -> Result<()> {
+        for import in module.imports() {
+            if !WASI_MODULES.contains(&import.module()) || self.allows_function(import.name()) {
+                continue;
+            }
+
+            let reason = match WasiCapability::for_function(import.name()) {
+                Some(cap) => format!("requires the '{}' permission", cap.permission()),
+                None => "is not supported".to_string(),
+            };
+            return Err(CoreError::WasmRuntimeError(format!(
+                "Failed to instantiate Wasm module: WASI import {}::{} {}",
+                import.module(),
+                import.name(),
+                reason
+            )));
+        }
+        Ok(())
+    }
+
+    /// Build a linker that defines only the allowed WASI functions for `store`
+    pub fn build_linker(
+        &self,
+        engine: &Engine,
+        store: &mut Store<WasiCtx>,
+    ) -> Result<Linker<WasiCtx>> {
+        let mut full = Linker::new(engine);
+        wasi_common::sync::add_to_linker(&mut full, |ctx| ctx).map_err(|e| {
+            CoreError::WasmRuntimeError(format!("Failed to set up WASI linker: {}", e))
+        })?;
+
+        let names: Vec<(String, String)> = full
+            .iter(&mut *store)
+            .filter(|(module, name, _)| WASI_MODULES.contains(module) && self.allows_function(name))
+            .map(|(module, name, _)| (module.to_string(), name.to_string()))
+            .collect();
+
+        let mut linker = Linker::new(engine);
+        for (module, name) in names {
+            if let Some(item) = full.get(&mut *store, &module, &name) {
+                linker.define(&*store, &module, &name, item).map_err(|e| {
+                    CoreError::WasmRuntimeError(format!(
+                        "Failed to define WASI import {}::{}: {}",
+                        module, name, e
+                    ))
+                })?;
+            }
+        }
+        Ok(linker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmatrix_core::ProviderType;
+
+    const CLOCK_MODULE: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "clock_time_get"
+            (func $clock_time_get (param i32 i64 i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "run")
+            (drop (call $clock_time_get (i32.const 0) (i64.const 0) (i32.const 8)))))
+    "#;
+
+    const FILESYSTEM_MODULE: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (memory (export "memory") 1))
+    "#;
+
+    fn instantiate(wat: &str, wasi: &WasiCapabilitySet) -> Result<()> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, wat).unwrap();
+        wasi.check_imports(&module)?;
+
+        let mut store = Store::new(&engine, wasi_common::sync::WasiCtxBuilder::new().build());
+        let linker = wasi.build_linker(&engine, &mut store)?;
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .map_err(|e| CoreError::WasmRuntimeError(e.to_string()))?;
+        if let Ok(run) = instance.get_typed_func::<(), ()>(&mut store, "run") {
+            run.call(&mut store, ())
+                .map_err(|e| CoreError::WasmRuntimeError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_capability_set_from_assignments() {
+        let assignments = vec![CapabilityAssignment::new(
+            "instance-1".to_string(),
+            "wasi".to_string(),
+            ProviderType::Kv,
+            vec![
+                "wasi:clock".to_string(),
+                "kv:read".to_string(),
+                "wasi:unknown".to_string(),
+            ],
+        )];
+
+        let set = WasiCapabilitySet::from_assignments(&assignments);
+
+        assert!(set.allows(WasiCapability::Clock));
+        assert!(!set.allows(WasiCapability::Filesystem));
+        assert_eq!(set, WasiCapabilitySet::none().with(WasiCapability::Clock));
+    }
+
+    #[test]
+    fn test_wildcard_grant_allows_every_wasi_group() {
+        let assignments = vec![CapabilityAssignment::new(
+            "instance-1".to_string(),
+            "wasi".to_string(),
+            ProviderType::Kv,
+            vec!["wasi:*".to_string()],
+        )];
+
+        let set = WasiCapabilitySet::from_assignments(&assignments);
+
+        assert!(WasiCapability::ALL
+            .into_iter()
+            .all(|capability| set.allows(capability)));
+        assert!(instantiate(FILESYSTEM_MODULE, &set).is_ok());
+    }
+
+    #[test]
+    fn test_pending_assignment_links_no_wasi_functions() {
+        let assignments = vec![CapabilityAssignment::new(
+            "instance-1".to_string(),
+            "wasi".to_string(),
+            ProviderType::Kv,
+            vec!["wasi:filesystem".to_string()],
+        )
+        .pending()];
+
+        let set = WasiCapabilitySet::from_assignments(&assignments);
+
+        assert_eq!(set, WasiCapabilitySet::none());
+        assert!(instantiate(FILESYSTEM_MODULE, &set).is_err());
+    }
+
+    #[test]
+    fn test_expired_assignment_links_no_wasi_functions() {
+        let assignments = vec![CapabilityAssignment::new(
+            "instance-1".to_string(),
+            "wasi".to_string(),
+            ProviderType::Kv,
+            vec!["wasi:clock".to_string()],
+        )
+        .with_expiry(chrono::Utc::now() - chrono::Duration::seconds(1))];
+
+        let set = WasiCapabilitySet::from_assignments(&assignments);
+
+        assert_eq!(set, WasiCapabilitySet::none());
+        assert!(instantiate(CLOCK_MODULE, &set).is_err());
+    }
+
+    #[test]
+    fn test_clock_only_instance_can_use_clocks() {
+        let wasi = WasiCapabilitySet::none().with(WasiCapability::Clock);
+        assert!(instantiate(CLOCK_MODULE, &wasi).is_ok());
+    }
+
+    #[test]
+    fn test_clock_only_instance_cannot_import_filesystem() {
+        let wasi = WasiCapabilitySet::none().with(WasiCapability::Clock);
+
+        let error = instantiate(FILESYSTEM_MODULE, &wasi).unwrap_err();
+
+        assert!(error.to_string().contains("path_open"));
+        assert!(error.to_string().contains("wasi:filesystem"));
+    }
+
+    #[test]
+    fn test_no_wasi_permissions_rejects_clock_import() {
+        let error = instantiate(CLOCK_MODULE, &WasiCapabilitySet::none()).unwrap_err();
+        assert!(error.to_string().contains("wasi:clock"));
+    }
+
+    #[test]
+    fn test_restricted_linker_omits_disallowed_functions() {
+        let engine = Engine::default();
+        let mut store = Store::new(&engine, wasi_common::sync::WasiCtxBuilder::new().build());
+        let wasi = WasiCapabilitySet::none().with(WasiCapability::Clock);
+
+        let linker = wasi.build_linker(&engine, &mut store).unwrap();
+
+        assert!(linker
+            .get(&mut store, "wasi_snapshot_preview1", "clock_time_get")
+            .is_some());
+        assert!(linker
+            .get(&mut store, "wasi_snapshot_preview1", "path_open")
+            .is_none());
+    }
+}
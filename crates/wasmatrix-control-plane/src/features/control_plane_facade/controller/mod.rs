@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::features::instance_management::service::InstanceService;
+use crate::features::node_routing::controller::NodeRoutingController;
+use crate::shared::error::{ControlPlaneError, ControlPlaneResult};
+use crate::shared::types::{
+    CapabilityAssignment, InstanceMetadata, InstanceStatusResponse, QueryInstanceRequest,
+    StartInstanceRequest, StopInstanceRequest,
+};
+use crate::ControlPlane;
+
+/// Single async entry point over the instance service and node routing.
+///
+/// Callers use this instead of bridging between the legacy synchronous
+/// [`ControlPlane`] and the feature services themselves.
+pub struct AsyncControlPlane {
+    instance_service: Arc<InstanceService>,
+    routing_controller: Arc<NodeRoutingController>,
+    recovery_state: Arc<Mutex<ControlPlane>>,
+}
+
+impl AsyncControlPlane {
+    pub fn new(
+        instance_service: Arc<InstanceService>,
+        routing_controller: Arc<NodeRoutingController>,
+    ) -> Self {
+        Self {
+            instance_service,
+            routing_controller,
+            recovery_state: Arc::new(Mutex::new(ControlPlane::default())),
+        }
+    }
+
+    /// Recover into `state` as well, e.g. the control plane the gRPC server uses
+    pub fn with_recovery_state(mut self, state: Arc<Mutex<ControlPlane>>) -> Self {
+        self.recovery_state = state;
+        self
+    }
+
+    pub async fn start(&self, request: StartInstanceRequest) -> ControlPlaneResult<String> {
+        self.instance_service.start_instance(request).await
+    }
+
+    pub async fn stop(&self, instance_id: &str) -> ControlPlaneResult<()> {
+        self.instance_service
+            .stop_instance(StopInstanceRequest {
+                instance_id: instance_id.to_string(),
+            })
+            .await
+    }
+
+    pub async fn query(&self, instance_id: &str) -> ControlPlaneResult<InstanceStatusResponse> {
+        self.instance_service
+            .query_instance(QueryInstanceRequest {
+                instance_id: instance_id.to_string(),
+            })
+            .await
+    }
+
+    pub async fn list(&self) -> ControlPlaneResult<Vec<InstanceMetadata>> {
+        self.instance_service.list_instances().await
+    }
+
+    pub async fn assign_capability(
+        &self,
+        assignment: CapabilityAssignment,
+    ) -> ControlPlaneResult<()> {
+        self.instance_service.assign_capability(assignment).await
+    }
+
+    /// Recover instances reported by a node agent and make them visible through this façade.
+    ///
+    /// Safe to repeat: instances the façade already knows keep their current
+    /// state rather than being reset to what the node reported.
+    pub async fn recover(&self, node_id: &str) -> ControlPlaneResult<usize> {
+        let recovered = self
+            .routing_controller
+            .recover_node_state(node_id, &self.recovery_state)
+            .await?;
+        self.restore_recovered(node_id).await?;
+        Ok(recovered)
+    }
+
+    /// Recover every registered node; one node failing does not stop the others
+    pub async fn recover_all(
+        &self,
+    ) -> ControlPlaneResult<Vec<(String, ControlPlaneResult<usize>)>> {
+        let mut results = self
+            .routing_controller
+            .recover_all_nodes(&self.recovery_state)
+            .await?;
+        for (node_id, result) in &mut results {
+            if result.is_ok() {
+                if let Err(error) = self.restore_recovered(node_id).await {
+                    *result = Err(error);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Copy instances recovered from `node_id` that the instance service does
+    /// not know yet
+    async fn restore_recovered(&self, node_id: &str) -> ControlPlaneResult<()> {
+        let known: HashSet<String> = self
+            .instance_service
+            .list_instances()
+            .await?
+            .into_iter()
+            .map(|metadata| metadata.instance_id)
+            .collect();
+
+        let instances: Vec<(InstanceMetadata, Vec<CapabilityAssignment>)> = {
+            let state = self.recovery_state.lock().map_err(|_| {
+                ControlPlaneError::StorageError("control plane lock poisoned".to_string())
+            })?;
+            state
+                .list_instances()
+                .into_iter()
+                .filter(|metadata| {
+                    metadata.node_id == node_id && !known.contains(&metadata.instance_id)
+                })
+                .map(|metadata| {
+                    let capabilities = state
+                        .get_capabilities(&metadata.instance_id)
+                        .cloned()
+                        .unwrap_or_default();
+                    (metadata.clone(), capabilities)
+                })
+                .collect()
+        };
+
+        for (metadata, capabilities) in instances {
+            self.instance_service
+                .restore_instance(metadata, capabilities)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::instance_management::repo::InMemoryInstanceRepository;
+    use crate::features::node_routing::repo::InMemoryNodeRoutingRepository;
+    use crate::features::node_routing::service::NodeRoutingService;
+    use crate::shared::types::{InstanceStatus, ProviderType};
+
+    fn create_facade() -> AsyncControlPlane {
+        let instance_service = Arc::new(InstanceService::new(
+            Arc::new(InMemoryInstanceRepository::new()),
+            "test-node",
+        ));
+        let routing_controller = Arc::new(NodeRoutingController::new(Arc::new(
+            NodeRoutingService::new(Arc::new(InMemoryNodeRoutingRepository::new())),
+        )));
+        AsyncControlPlane::new(instance_service, routing_controller)
+    }
+
+    fn create_valid_wasm_module() -> Vec<u8> {
+        vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]
+    }
+
+    #[tokio::test]
+    async fn test_facade_instance_lifecycle() {
+        let facade = create_facade();
+
+        let instance_id = facade
+            .start(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        facade
+            .assign_capability(CapabilityAssignment::new(
+                instance_id.clone(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            ))
+            .await
+            .unwrap();
+
+        let status = facade.query(&instance_id).await.unwrap();
+        assert_eq!(status.instance_id, instance_id);
+        assert_eq!(facade.list().await.unwrap().len(), 1);
+
+        facade.stop(&instance_id).await.unwrap();
+        let status = facade.query(&instance_id).await.unwrap();
+        assert_eq!(status.status, InstanceStatus::Stopped);
+    }
+
+    #[tokio::test]
+    async fn test_facade_errors_for_unknown_instance() {
+        let facade = create_facade();
+
+        assert!(matches!(
+            facade.query("missing").await,
+            Err(ControlPlaneError::InstanceNotFound(_))
+        ));
+        assert!(matches!(
+            facade.stop("missing").await,
+            Err(ControlPlaneError::InstanceNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_facade_recover_unknown_node() {
+        let facade = create_facade();
+
+        let result = facade.recover("node-missing").await;
+
+        assert!(matches!(
+            result,
+            Err(ControlPlaneError::InstanceNotFound(_))
+        ));
+        assert!(facade.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_facade_recover_is_idempotent() {
+        let cluster = crate::testing::TestCluster::start("node-1").await;
+        let instance_id = cluster
+            .routing
+            .start_instance(StartInstanceRequest {
+                module_bytes: crate::testing::EMPTY_MODULE.to_vec(),
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        let facade = AsyncControlPlane::new(
+            Arc::new(InstanceService::new(
+                Arc::new(InMemoryInstanceRepository::new()),
+                "test-node",
+            )),
+            cluster.routing.clone(),
+        );
+        assert_eq!(facade.recover("node-1").await.unwrap(), 1);
+        facade.stop(&instance_id).await.unwrap();
+
+        // A second recovery must not resurrect the locally stopped instance
+        assert_eq!(facade.recover("node-1").await.unwrap(), 1);
+        let instances = facade.list().await.unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(
+            facade.query(&instance_id).await.unwrap().status,
+            InstanceStatus::Stopped
+        );
+
+        let results = facade.recover_all().await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_ok());
+        assert_eq!(facade.list().await.unwrap().len(), 1);
+    }
+}
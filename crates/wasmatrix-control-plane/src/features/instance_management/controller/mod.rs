@@ -71,7 +71,6 @@ impl InstanceController {
 mod tests {
     use super::*;
     use crate::features::instance_management::repo::InMemoryInstanceRepository;
-    use crate::shared::types::RestartPolicy;
 
     fn create_test_controller() -> InstanceController {
         let repo = Arc::new(InMemoryInstanceRepository::new());
@@ -90,7 +89,13 @@ mod tests {
         let request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let result = controller.start_instance(request).await;
@@ -105,7 +110,13 @@ mod tests {
         let start_request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
         let instance_id = controller.start_instance(start_request).await.unwrap();
 
@@ -123,7 +134,13 @@ mod tests {
         let start_request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
         let instance_id = controller.start_instance(start_request).await.unwrap();
 
@@ -152,7 +169,13 @@ mod tests {
         let request = StartInstanceRequest {
             module_bytes: vec![0x00, 0x00, 0x00, 0x00],
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let result = controller.start_instance(request).await;
@@ -191,7 +214,13 @@ mod tests {
         let start_request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
         let instance_id = controller
             .start_instance(start_request.clone())
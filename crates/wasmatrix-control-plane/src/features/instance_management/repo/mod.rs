@@ -1,8 +1,9 @@
-use crate::shared::error::ControlPlaneResult;
-use crate::shared::types::{InstanceMetadata, InstanceStatus};
+use crate::shared::error::{ControlPlaneError, ControlPlaneResult};
+use crate::shared::types::{CapabilityAssignment, InstanceMetadata, InstanceStatus};
+use crate::ControlPlane;
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
 
 /// Repository trait for instance storage
 #[async_trait]
@@ -28,18 +29,29 @@ pub trait InstanceRepository: Send + Sync {
 
     /// Check if instance exists
     async fn exists(&self, instance_id: &str) -> ControlPlaneResult<bool>;
+
+    /// Store a capability assignment for an instance
+    async fn add_capability(&self, assignment: CapabilityAssignment) -> ControlPlaneResult<()>;
+
+    /// List capability assignments for an instance
+    async fn get_capabilities(
+        &self,
+        instance_id: &str,
+    ) -> ControlPlaneResult<Vec<CapabilityAssignment>>;
 }
 
 /// In-memory implementation of instance repository
 #[derive(Clone)]
 pub struct InMemoryInstanceRepository {
     storage: Arc<RwLock<HashMap<String, InstanceMetadata>>>,
+    capabilities: Arc<RwLock<HashMap<String, Vec<CapabilityAssignment>>>>,
 }
 
 impl InMemoryInstanceRepository {
     pub fn new() -> Self {
         Self {
             storage: Arc::new(RwLock::new(HashMap::new())),
+            capabilities: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
@@ -90,7 +102,14 @@ impl InstanceRepository for InMemoryInstanceRepository {
         let mut storage = self.storage.write().map_err(|_| {
             crate::shared::error::ControlPlaneError::StorageError("Lock poisoned".to_string())
         })?;
-        Ok(storage.remove(instance_id).is_some())
+        let removed = storage.remove(instance_id).is_some();
+        drop(storage);
+
+        let mut capabilities = self.capabilities.write().map_err(|_| {
+            crate::shared::error::ControlPlaneError::StorageError("Lock poisoned".to_string())
+        })?;
+        capabilities.remove(instance_id);
+        Ok(removed)
     }
 
     async fn list(&self) -> ControlPlaneResult<Vec<InstanceMetadata>> {
@@ -106,6 +125,108 @@ impl InstanceRepository for InMemoryInstanceRepository {
         })?;
         Ok(storage.contains_key(instance_id))
     }
+
+    async fn add_capability(&self, assignment: CapabilityAssignment) -> ControlPlaneResult<()> {
+        let mut capabilities = self.capabilities.write().map_err(|_| {
+            crate::shared::error::ControlPlaneError::StorageError("Lock poisoned".to_string())
+        })?;
+        capabilities
+            .entry(assignment.instance_id.clone())
+            .or_default()
+            .push(assignment);
+        Ok(())
+    }
+
+    async fn get_capabilities(
+        &self,
+        instance_id: &str,
+    ) -> ControlPlaneResult<Vec<CapabilityAssignment>> {
+        let capabilities = self.capabilities.read().map_err(|_| {
+            crate::shared::error::ControlPlaneError::StorageError("Lock poisoned".to_string())
+        })?;
+        Ok(capabilities.get(instance_id).cloned().unwrap_or_default())
+    }
+}
+
+/// Instance repository over a [`ControlPlane`] shared with the gRPC server,
+/// so the instance service and the server read and write the same state
+#[derive(Clone)]
+pub struct ControlPlaneInstanceRepository {
+    state: Arc<Mutex<ControlPlane>>,
+}
+
+impl ControlPlaneInstanceRepository {
+    pub fn new(state: Arc<Mutex<ControlPlane>>) -> Self {
+        Self { state }
+    }
+
+    fn lock(&self) -> ControlPlaneResult<MutexGuard<'_, ControlPlane>> {
+        self.state
+            .lock()
+            .map_err(|_| ControlPlaneError::StorageError("Lock poisoned".to_string()))
+    }
+}
+
+#[async_trait]
+impl InstanceRepository for ControlPlaneInstanceRepository {
+    async fn create(&self, metadata: InstanceMetadata) -> ControlPlaneResult<()> {
+        let mut state = self.lock()?;
+        let capabilities = state
+            .get_capabilities(&metadata.instance_id)
+            .cloned()
+            .unwrap_or_default();
+        state.restore_instance_state(metadata, capabilities);
+        Ok(())
+    }
+
+    async fn get(&self, instance_id: &str) -> ControlPlaneResult<Option<InstanceMetadata>> {
+        Ok(self.lock()?.get_instance(instance_id).cloned())
+    }
+
+    async fn update_status(
+        &self,
+        instance_id: &str,
+        status: InstanceStatus,
+    ) -> ControlPlaneResult<()> {
+        self.lock()?
+            .update_instance_status(instance_id, status)
+            .map_err(|_| ControlPlaneError::InstanceNotFound(instance_id.to_string()))
+    }
+
+    async fn delete(&self, instance_id: &str) -> ControlPlaneResult<bool> {
+        let mut state = self.lock()?;
+        let existed = state.get_instance(instance_id).is_some();
+        state.forget_instance(instance_id);
+        Ok(existed)
+    }
+
+    async fn list(&self) -> ControlPlaneResult<Vec<InstanceMetadata>> {
+        Ok(self.lock()?.list_instances().into_iter().cloned().collect())
+    }
+
+    async fn exists(&self, instance_id: &str) -> ControlPlaneResult<bool> {
+        Ok(self.lock()?.get_instance(instance_id).is_some())
+    }
+
+    async fn add_capability(&self, assignment: CapabilityAssignment) -> ControlPlaneResult<()> {
+        self.lock()?.assign_capability(assignment).map_err(|error| {
+            match error.error_code.as_str() {
+                "INSTANCE_NOT_FOUND" => ControlPlaneError::InstanceNotFound(error.message),
+                _ => ControlPlaneError::ValidationError(error.message),
+            }
+        })
+    }
+
+    async fn get_capabilities(
+        &self,
+        instance_id: &str,
+    ) -> ControlPlaneResult<Vec<CapabilityAssignment>> {
+        Ok(self
+            .lock()?
+            .get_capabilities(instance_id)
+            .cloned()
+            .unwrap_or_default())
+    }
 }
 
 #[cfg(test)]
@@ -189,6 +310,22 @@ mod tests {
         assert!(!result);
     }
 
+    #[tokio::test]
+    async fn test_add_and_get_capabilities() {
+        let repo = InMemoryInstanceRepository::new();
+        let assignment = CapabilityAssignment::new(
+            "test-1".to_string(),
+            "kv-1".to_string(),
+            crate::shared::types::ProviderType::Kv,
+            vec!["kv:read".to_string()],
+        );
+
+        repo.add_capability(assignment).await.unwrap();
+
+        assert_eq!(repo.get_capabilities("test-1").await.unwrap().len(), 1);
+        assert!(repo.get_capabilities("test-2").await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_default() {
         let repo = InMemoryInstanceRepository::default();
@@ -286,4 +423,41 @@ mod tests {
             assert_eq!(metadata.status, original_status);
         }
     }
+
+    #[tokio::test]
+    async fn test_control_plane_repository_shares_state_with_control_plane() {
+        let state = Arc::new(Mutex::new(ControlPlane::new("test-node")));
+        let repo = ControlPlaneInstanceRepository::new(state.clone());
+        let metadata = create_test_metadata("test-1");
+        let id = metadata.instance_id.clone();
+
+        repo.create(metadata).await.unwrap();
+        repo.add_capability(CapabilityAssignment::new(
+            id.clone(),
+            "kv-1".to_string(),
+            crate::shared::types::ProviderType::Kv,
+            vec!["kv:read".to_string()],
+        ))
+        .await
+        .unwrap();
+        assert!(state.lock().unwrap().get_instance(&id).is_some());
+        assert_eq!(
+            state.lock().unwrap().get_capabilities(&id).unwrap().len(),
+            1
+        );
+
+        state
+            .lock()
+            .unwrap()
+            .update_instance_status(&id, InstanceStatus::Running)
+            .unwrap();
+        assert_eq!(
+            repo.get(&id).await.unwrap().unwrap().status,
+            InstanceStatus::Running
+        );
+
+        assert!(repo.delete(&id).await.unwrap());
+        assert!(state.lock().unwrap().get_instance(&id).is_none());
+        assert!(repo.get_capabilities(&id).await.unwrap().is_empty());
+    }
 }
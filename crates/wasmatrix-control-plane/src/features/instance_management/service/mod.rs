@@ -1,16 +1,41 @@
 use crate::features::instance_management::repo::InstanceRepository;
+use crate::features::module_verification::service::{ModuleVerifier, NoopModuleVerifier};
 use crate::shared::error::{ControlPlaneError, ControlPlaneResult};
 use crate::shared::types::{
     CapabilityAssignment, InstanceMetadata, InstanceStatus, InstanceStatusResponse, ProviderType,
-    QueryInstanceRequest, StartInstanceRequest, StopInstanceRequest,
+    QueryInstanceRequest, StartInstanceRequest, StopBehavior, StopInstanceRequest,
 };
 use std::sync::Arc;
 use tracing::info;
+use wasmatrix_core::module_hash::compute_module_hash;
+use wasmatrix_core::statelessness::StatelessnessPolicy;
+use wasmparser::{Parser, Payload};
+
+/// Upper bounds on a module's declared imports, exports and functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleLimits {
+    pub max_imports: u32,
+    pub max_exports: u32,
+    pub max_functions: u32,
+}
+
+impl Default for ModuleLimits {
+    fn default() -> Self {
+        Self {
+            max_imports: 10_000,
+            max_exports: 10_000,
+            max_functions: 100_000,
+        }
+    }
+}
 
 /// Service for managing Wasm instances
 pub struct InstanceService {
     repo: Arc<dyn InstanceRepository>,
     node_id: String,
+    module_verifier: Arc<dyn ModuleVerifier>,
+    stop_behavior: StopBehavior,
+    module_limits: ModuleLimits,
 }
 
 impl InstanceService {
@@ -18,9 +43,30 @@ impl InstanceService {
         Self {
             repo,
             node_id: node_id.into(),
+            module_verifier: Arc::new(NoopModuleVerifier),
+            stop_behavior: StopBehavior::default(),
+            module_limits: ModuleLimits::default(),
         }
     }
 
+    /// Enable module signature verification with the given verifier
+    pub fn with_module_verifier(mut self, module_verifier: Arc<dyn ModuleVerifier>) -> Self {
+        self.module_verifier = module_verifier;
+        self
+    }
+
+    /// Choose whether stopping an instance retains or removes its metadata
+    pub fn with_stop_behavior(mut self, stop_behavior: StopBehavior) -> Self {
+        self.stop_behavior = stop_behavior;
+        self
+    }
+
+    /// Reject modules declaring more imports, exports or functions than allowed
+    pub fn with_module_limits(mut self, module_limits: ModuleLimits) -> Self {
+        self.module_limits = module_limits;
+        self
+    }
+
     /// Validate Wasm module format
     fn validate_wasm_module(module_bytes: &[u8]) -> ControlPlaneResult<()> {
         if module_bytes.is_empty() {
@@ -29,7 +75,7 @@ impl InstanceService {
             ));
         }
 
-        if module_bytes.len() < 4 || &module_bytes[0..4] != &[0x00, 0x61, 0x73, 0x6d] {
+        if module_bytes.len() < 4 || module_bytes[0..4] != [0x00, 0x61, 0x73, 0x6d] {
             return Err(ControlPlaneError::ValidationError(
                 "Invalid Wasm module format".to_string(),
             ));
@@ -45,6 +91,45 @@ impl InstanceService {
         Ok(())
     }
 
+    /// Check the module's import, export and function counts against the limits
+    fn check_module_limits(&self, module_bytes: &[u8]) -> ControlPlaneResult<()> {
+        let limits = &self.module_limits;
+        let mut functions = 0u32;
+
+        for payload in Parser::new(0).parse_all(module_bytes) {
+            let payload = payload.map_err(|e| {
+                ControlPlaneError::ValidationError(format!("Invalid Wasm module: {}", e))
+            })?;
+            match payload {
+                Payload::ImportSection(imports) if imports.count() > limits.max_imports => {
+                    return Err(ControlPlaneError::ResourceExhausted(format!(
+                        "Module declares {} imports, limit is {}",
+                        imports.count(),
+                        limits.max_imports
+                    )));
+                }
+                Payload::ExportSection(exports) if exports.count() > limits.max_exports => {
+                    return Err(ControlPlaneError::ResourceExhausted(format!(
+                        "Module declares {} exports, limit is {}",
+                        exports.count(),
+                        limits.max_exports
+                    )));
+                }
+                Payload::FunctionSection(section) => functions = section.count(),
+                _ => {}
+            }
+        }
+
+        if functions > limits.max_functions {
+            return Err(ControlPlaneError::ResourceExhausted(format!(
+                "Module declares {} functions, limit is {}",
+                functions, limits.max_functions
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Start a new instance
     pub async fn start_instance(
         &self,
@@ -52,11 +137,16 @@ impl InstanceService {
     ) -> ControlPlaneResult<String> {
         // Validation
         Self::validate_wasm_module(&request.module_bytes)?;
+        self.module_verifier
+            .verify(&request.module_bytes, request.module_signature.as_deref())?;
+        self.check_module_limits(&request.module_bytes)?;
+        StatelessnessPolicy::verify_wasi_config(&request.env, &request.args)
+            .map_err(|e| ControlPlaneError::ValidationError(e.to_string()))?;
 
-        // Create metadata
+        // Create metadata; env and args are configuration and are not stored
         let metadata = InstanceMetadata::new(
             self.node_id.clone(),
-            format!("{:x}", md5::compute(&request.module_bytes)),
+            compute_module_hash(&request.module_bytes),
         );
 
         let instance_id = metadata.instance_id.clone();
@@ -85,10 +175,16 @@ impl InstanceService {
             ));
         }
 
-        // Update status
-        self.repo
-            .update_status(&request.instance_id, InstanceStatus::Stopped)
-            .await?;
+        match self.stop_behavior {
+            StopBehavior::RetainMetadata => {
+                self.repo
+                    .update_status(&request.instance_id, InstanceStatus::Stopped)
+                    .await?;
+            }
+            StopBehavior::RemoveMetadata => {
+                self.repo.delete(&request.instance_id).await?;
+            }
+        }
 
         info!(instance_id = %request.instance_id, "Instance stopped successfully");
 
@@ -129,6 +225,61 @@ impl InstanceService {
         self.repo.list().await
     }
 
+    /// Assign a capability to an existing instance
+    pub async fn assign_capability(
+        &self,
+        assignment: CapabilityAssignment,
+    ) -> ControlPlaneResult<()> {
+        if !self.repo.exists(&assignment.instance_id).await? {
+            return Err(ControlPlaneError::InstanceNotFound(
+                assignment.instance_id.clone(),
+            ));
+        }
+
+        if assignment.capability_id.is_empty() {
+            return Err(ControlPlaneError::ValidationError(
+                "Capability ID cannot be empty".to_string(),
+            ));
+        }
+
+        if assignment.permissions().is_empty() {
+            return Err(ControlPlaneError::ValidationError(
+                "At least one permission must be specified".to_string(),
+            ));
+        }
+
+        self.repo.add_capability(assignment).await
+    }
+
+    /// Get capability assignments for an instance
+    pub async fn get_capabilities(
+        &self,
+        instance_id: &str,
+    ) -> ControlPlaneResult<Vec<CapabilityAssignment>> {
+        self.repo.get_capabilities(instance_id).await
+    }
+
+    /// Restore instance metadata and capability assignments recovered from a node agent
+    pub async fn restore_instance(
+        &self,
+        metadata: InstanceMetadata,
+        capabilities: Vec<CapabilityAssignment>,
+    ) -> ControlPlaneResult<()> {
+        let existing = self.repo.get_capabilities(&metadata.instance_id).await?;
+        self.repo.create(metadata).await?;
+        for assignment in capabilities {
+            // Recovery may run repeatedly; don't duplicate known grants
+            if existing
+                .iter()
+                .any(|known| known.capability_id == assignment.capability_id)
+            {
+                continue;
+            }
+            self.repo.add_capability(assignment).await?;
+        }
+        Ok(())
+    }
+
     /// Update instance status (called by node agent)
     pub async fn update_status(
         &self,
@@ -147,7 +298,6 @@ impl InstanceService {
 mod tests {
     use super::*;
     use crate::features::instance_management::repo::InMemoryInstanceRepository;
-    use crate::shared::types::RestartPolicy;
 
     fn create_test_service() -> InstanceService {
         let repo = Arc::new(InMemoryInstanceRepository::new());
@@ -165,13 +315,212 @@ mod tests {
         let request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let instance_id = service.start_instance(request).await.unwrap();
         assert!(!instance_id.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_start_instance_rejects_too_many_exports() {
+        let service = create_test_service().with_module_limits(ModuleLimits {
+            max_exports: 2,
+            ..ModuleLimits::default()
+        });
+        let module_bytes = wat::parse_str(
+            r#"(module
+                (func $f)
+                (export "a" (func $f))
+                (export "b" (func $f))
+                (export "c" (func $f)))"#,
+        )
+        .unwrap();
+
+        let request = StartInstanceRequest {
+            module_bytes,
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+
+        match service.start_instance(request).await {
+            Err(ControlPlaneError::ResourceExhausted(message)) => {
+                assert!(message.contains("3 exports"), "{}", message);
+            }
+            other => panic!("expected ResourceExhausted, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_instance_within_module_limits() {
+        let service = create_test_service().with_module_limits(ModuleLimits {
+            max_imports: 1,
+            max_exports: 1,
+            max_functions: 2,
+        });
+        let module_bytes = wat::parse_str(
+            r#"(module
+                (import "env" "log" (func $log))
+                (func $a)
+                (func $b call $a)
+                (export "b" (func $b)))"#,
+        )
+        .unwrap();
+
+        let request = StartInstanceRequest {
+            module_bytes,
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+
+        assert!(service.start_instance(request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_start_instance_validates_wasi_config() {
+        let service = create_test_service();
+        let request = |env: Vec<(String, String)>| StartInstanceRequest {
+            module_bytes: create_valid_wasm_module(),
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env,
+            args: vec!["app".to_string()],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+
+        assert!(service
+            .start_instance(request(vec![("MODE".to_string(), "prod".to_string())]))
+            .await
+            .is_ok());
+        let result = service
+            .start_instance(request(vec![("MODE".to_string(), "a\0b".to_string())]))
+            .await;
+        assert!(matches!(result, Err(ControlPlaneError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_start_instance_signature_verification() {
+        use crate::features::module_verification::service::Ed25519ModuleVerifier;
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let service =
+            create_test_service().with_module_verifier(Arc::new(Ed25519ModuleVerifier::new(vec![
+                key.verifying_key(),
+            ])));
+        let module = create_valid_wasm_module();
+        let signature = key.sign(&module).to_bytes().to_vec();
+
+        let signed = StartInstanceRequest {
+            module_bytes: module.clone(),
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: Some(signature.clone()),
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        assert!(service.start_instance(signed).await.is_ok());
+
+        let mut tampered_bytes = module;
+        tampered_bytes.extend_from_slice(&[0x00, 0x00]);
+        let tampered = StartInstanceRequest {
+            module_bytes: tampered_bytes,
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: Some(signature),
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let error = service.start_instance(tampered).await.unwrap_err();
+        let response: wasmatrix_core::ErrorResponse = error.into();
+        assert_eq!(response.error_code, "PERMISSION_DENIED");
+        assert!(service.list_instances().await.unwrap().len() == 1);
+    }
+
+    #[tokio::test]
+    async fn test_assign_capability() {
+        let service = create_test_service();
+        let instance_id = service
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        service
+            .assign_capability(CapabilityAssignment::new(
+                instance_id.clone(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            ))
+            .await
+            .unwrap();
+        let capabilities = service.get_capabilities(&instance_id).await.unwrap();
+        assert_eq!(capabilities.len(), 1);
+
+        let missing = service
+            .assign_capability(CapabilityAssignment::new(
+                "missing".to_string(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            ))
+            .await;
+        assert!(matches!(
+            missing,
+            Err(ControlPlaneError::InstanceNotFound(_))
+        ));
+
+        let no_permissions = service
+            .assign_capability(CapabilityAssignment::new(
+                instance_id,
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec![],
+            ))
+            .await;
+        assert!(matches!(
+            no_permissions,
+            Err(ControlPlaneError::ValidationError(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_start_instance_invalid_wasm() {
         let service = create_test_service();
@@ -179,7 +528,13 @@ mod tests {
         let request = StartInstanceRequest {
             module_bytes: vec![0x00, 0x00, 0x00, 0x00],
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let result = service.start_instance(request).await;
@@ -194,7 +549,13 @@ mod tests {
         let start_request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
         let instance_id = service.start_instance(start_request).await.unwrap();
 
@@ -203,6 +564,116 @@ mod tests {
         service.stop_instance(stop_request).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_stop_retains_metadata_by_default() {
+        let service = create_test_service();
+        let instance_id = service
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        service
+            .stop_instance(StopInstanceRequest {
+                instance_id: instance_id.clone(),
+            })
+            .await
+            .unwrap();
+
+        let status = service
+            .query_instance(QueryInstanceRequest { instance_id })
+            .await
+            .unwrap();
+        assert_eq!(status.status, InstanceStatus::Stopped);
+    }
+
+    #[tokio::test]
+    async fn test_stop_removes_metadata_when_configured() {
+        let repo = Arc::new(InMemoryInstanceRepository::new());
+        let service = InstanceService::new(repo.clone(), "test-node")
+            .with_stop_behavior(StopBehavior::RemoveMetadata);
+        let instance_id = service
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await
+            .unwrap();
+        service
+            .assign_capability(CapabilityAssignment::new(
+                instance_id.clone(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            ))
+            .await
+            .unwrap();
+
+        service
+            .stop_instance(StopInstanceRequest {
+                instance_id: instance_id.clone(),
+            })
+            .await
+            .unwrap();
+
+        let result = service
+            .query_instance(QueryInstanceRequest {
+                instance_id: instance_id.clone(),
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(ControlPlaneError::InstanceNotFound(_))
+        ));
+        assert!(service
+            .get_capabilities(&instance_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restore_instance_restores_capabilities_once() {
+        let service = create_test_service();
+        let metadata = InstanceMetadata::new("node-1".to_string(), "hash".to_string());
+        let instance_id = metadata.instance_id.clone();
+        let capabilities = vec![CapabilityAssignment::new(
+            instance_id.clone(),
+            "kv-1".to_string(),
+            ProviderType::Kv,
+            vec!["kv:read".to_string()],
+        )];
+
+        service
+            .restore_instance(metadata.clone(), capabilities.clone())
+            .await
+            .unwrap();
+        service
+            .restore_instance(metadata, capabilities)
+            .await
+            .unwrap();
+
+        let restored = service.get_capabilities(&instance_id).await.unwrap();
+        assert_eq!(restored.len(), 1);
+        assert!(restored[0].has_permission("kv:read"));
+    }
+
     #[tokio::test]
     async fn test_stop_instance_not_found() {
         let service = create_test_service();
@@ -223,7 +694,13 @@ mod tests {
         let start_request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
         let instance_id = service.start_instance(start_request).await.unwrap();
 
@@ -250,7 +727,13 @@ mod tests {
             let request = StartInstanceRequest {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
-                restart_policy: RestartPolicy::default(),
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
             };
             service.start_instance(request).await.unwrap();
         }
@@ -267,7 +750,13 @@ mod tests {
         let start_request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
         let instance_id = service.start_instance(start_request).await.unwrap();
 
@@ -295,7 +784,13 @@ mod tests {
         let request = StartInstanceRequest {
             module_bytes: vec![],
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let result = service.start_instance(request).await;
@@ -358,7 +853,13 @@ mod tests {
                 ProviderType::Kv,
                 vec!["kv:read".to_string()],
             )],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let instance_id = service.start_instance(request).await.unwrap();
@@ -373,7 +874,13 @@ mod tests {
         let start_request = StartInstanceRequest {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
         let instance_id = service.start_instance(start_request).await.unwrap();
 
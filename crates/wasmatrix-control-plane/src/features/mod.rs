@@ -1,3 +1,5 @@
+pub mod control_plane_facade;
 pub mod instance_management;
+pub mod module_verification;
 pub mod node_routing;
 pub mod observability;
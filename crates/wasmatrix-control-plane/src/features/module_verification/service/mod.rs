@@ -0,0 +1,162 @@
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+use crate::shared::error::{ControlPlaneError, ControlPlaneResult};
+
+/// Verifies a detached signature over Wasm module bytes before instantiation
+pub trait ModuleVerifier: Send + Sync {
+    fn verify(&self, module_bytes: &[u8], signature: Option<&[u8]>) -> ControlPlaneResult<()>;
+}
+
+/// Default verifier that accepts every module (verification disabled)
+#[derive(Debug, Default, Clone)]
+pub struct NoopModuleVerifier;
+
+impl ModuleVerifier for NoopModuleVerifier {
+    fn verify(&self, _module_bytes: &[u8], _signature: Option<&[u8]>) -> ControlPlaneResult<()> {
+        Ok(())
+    }
+}
+
+/// Verifier accepting modules signed by any of the trusted ed25519 public keys
+#[derive(Debug, Clone)]
+pub struct Ed25519ModuleVerifier {
+    trusted_keys: Vec<VerifyingKey>,
+}
+
+impl Ed25519ModuleVerifier {
+    pub fn new(trusted_keys: Vec<VerifyingKey>) -> Self {
+        Self { trusted_keys }
+    }
+
+    /// Build a verifier from comma-separated hex-encoded public keys
+    pub fn from_hex_keys(keys: &str) -> ControlPlaneResult<Self> {
+        let trusted_keys = keys
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(parse_hex_key)
+            .collect::<ControlPlaneResult<Vec<_>>>()?;
+
+        if trusted_keys.is_empty() {
+            return Err(ControlPlaneError::ValidationError(
+                "At least one trusted module key is required".to_string(),
+            ));
+        }
+
+        Ok(Self::new(trusted_keys))
+    }
+}
+
+impl ModuleVerifier for Ed25519ModuleVerifier {
+    fn verify(&self, module_bytes: &[u8], signature: Option<&[u8]>) -> ControlPlaneResult<()> {
+        let signature = signature.ok_or_else(|| {
+            ControlPlaneError::PermissionDenied("Module signature is required".to_string())
+        })?;
+        let signature = Signature::from_slice(signature).map_err(|_| {
+            ControlPlaneError::PermissionDenied("Malformed module signature".to_string())
+        })?;
+
+        if self
+            .trusted_keys
+            .iter()
+            .any(|key| key.verify(module_bytes, &signature).is_ok())
+        {
+            Ok(())
+        } else {
+            Err(ControlPlaneError::PermissionDenied(
+                "Module signature does not match any trusted key".to_string(),
+            ))
+        }
+    }
+}
+
+fn parse_hex_key(key: &str) -> ControlPlaneResult<VerifyingKey> {
+    let invalid = || ControlPlaneError::ValidationError(format!("Invalid module key: {}", key));
+
+    if key.len() != 64 || !key.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut bytes = [0u8; 32];
+    for (idx, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&key[idx * 2..idx * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+
+    VerifyingKey::from_bytes(&bytes).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn create_valid_wasm_module() -> Vec<u8> {
+        vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]
+    }
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_valid_signature_accepted() {
+        let key = signing_key();
+        let module = create_valid_wasm_module();
+        let signature = key.sign(&module).to_bytes();
+        let verifier = Ed25519ModuleVerifier::new(vec![key.verifying_key()]);
+
+        assert!(verifier.verify(&module, Some(&signature)).is_ok());
+    }
+
+    #[test]
+    fn test_tampered_module_rejected() {
+        let key = signing_key();
+        let module = create_valid_wasm_module();
+        let signature = key.sign(&module).to_bytes();
+        let verifier = Ed25519ModuleVerifier::new(vec![key.verifying_key()]);
+
+        let mut tampered = module.clone();
+        tampered.push(0x00);
+        let result = verifier.verify(&tampered, Some(&signature));
+
+        assert!(matches!(
+            result,
+            Err(ControlPlaneError::PermissionDenied(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsigned_module_rejected() {
+        let verifier = Ed25519ModuleVerifier::new(vec![signing_key().verifying_key()]);
+        let result = verifier.verify(&create_valid_wasm_module(), None);
+
+        assert!(matches!(
+            result,
+            Err(ControlPlaneError::PermissionDenied(_))
+        ));
+    }
+
+    #[test]
+    fn test_noop_verifier_bypasses_check() {
+        let verifier = NoopModuleVerifier;
+
+        assert!(verifier.verify(&create_valid_wasm_module(), None).is_ok());
+        assert!(verifier
+            .verify(&create_valid_wasm_module(), Some(&[0u8; 64]))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_from_hex_keys() {
+        let public_key = hex(signing_key().verifying_key().as_bytes());
+        let verifier = Ed25519ModuleVerifier::from_hex_keys(&format!(" {} ,", public_key)).unwrap();
+        assert_eq!(verifier.trusted_keys.len(), 1);
+
+        assert!(Ed25519ModuleVerifier::from_hex_keys("").is_err());
+        assert!(Ed25519ModuleVerifier::from_hex_keys("zz").is_err());
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
 
-use crate::features::node_routing::service::NodeRoutingService;
+use crate::features::node_routing::repo::ProviderMetadata;
+use crate::features::node_routing::service::{
+    ClusterCapacity, InstanceMove, NodeRoutingService, PersistenceStatus,
+};
 use crate::shared::error::ControlPlaneResult;
 use crate::shared::types::{
     InstanceMetadata, InstanceStatusResponse, QueryInstanceRequest, StartInstanceRequest,
@@ -23,10 +27,34 @@ impl NodeRoutingController {
         node_id: String,
         node_address: String,
         capabilities: Vec<String>,
-        max_instances: u32,
+        max_instances: Option<u32>,
+        weight: Option<u32>,
+    ) -> ControlPlaneResult<()> {
+        self.service
+            .register_node(node_id, node_address, capabilities, max_instances, weight)
+            .await
+    }
+
+    /// Replace or extend the provider types a node offers
+    pub async fn update_node_capabilities(
+        &self,
+        node_id: &str,
+        capabilities: Vec<String>,
+        replace: bool,
     ) -> ControlPlaneResult<()> {
         self.service
-            .register_node(node_id, node_address, capabilities, max_instances)
+            .update_node_capabilities(node_id, capabilities, replace)
+            .await
+    }
+
+    /// Registered providers, optionally narrowed by type and/or node
+    pub async fn list_provider_metadata_filtered(
+        &self,
+        provider_type: Option<&str>,
+        node_id: Option<&str>,
+    ) -> ControlPlaneResult<Vec<ProviderMetadata>> {
+        self.service
+            .list_provider_metadata_filtered(provider_type, node_id)
             .await
     }
 
@@ -45,8 +73,91 @@ impl NodeRoutingController {
         self.service.route_start_instance(request).await
     }
 
-    pub async fn stop_instance(&self, instance_id: &str) -> ControlPlaneResult<()> {
-        self.service.route_stop_instance(instance_id).await
+    /// Return the id of the node an instance is placed on
+    pub async fn locate_instance(&self, instance_id: &str) -> ControlPlaneResult<String> {
+        self.service.locate_instance(instance_id).await
+    }
+
+    /// Stop an instance on its node; `reason` and `annotations` are recorded on
+    /// the agent's stop event
+    pub async fn stop_instance(
+        &self,
+        instance_id: &str,
+        reason: Option<&str>,
+        annotations: &HashMap<String, String>,
+    ) -> ControlPlaneResult<()> {
+        self.service
+            .route_stop_instance(instance_id, reason, annotations)
+            .await
+    }
+
+    /// Drop routing assignments of reaped instances
+    pub async fn forget_instances(&self, instance_ids: &[String]) -> ControlPlaneResult<usize> {
+        self.service.forget_instances(instance_ids).await
+    }
+
+    /// Start an instance on `new_node_id` from `request`, with its stored
+    /// grants, and route it there
+    pub async fn reassign_instance(
+        &self,
+        instance_id: &str,
+        new_node_id: &str,
+        request: &StartInstanceRequest,
+    ) -> ControlPlaneResult<()> {
+        self.service
+            .reassign_instance(instance_id, new_node_id, request)
+            .await
+    }
+
+    /// Store a capability grant so it follows the instance across nodes
+    pub async fn record_capability_assignment(
+        &self,
+        assignment: CapabilityAssignment,
+    ) -> ControlPlaneResult<()> {
+        self.service.record_capability_assignment(assignment).await
+    }
+
+    /// Drop a stored capability grant; returns whether one was removed
+    pub async fn revoke_capability_assignment(
+        &self,
+        instance_id: &str,
+        capability_id: &str,
+    ) -> ControlPlaneResult<bool> {
+        self.service
+            .revoke_capability_assignment(instance_id, capability_id)
+            .await
+    }
+
+    /// Plan up to `max_moves` instance migrations that even out node load
+    pub async fn rebalance(&self, max_moves: usize) -> ControlPlaneResult<Vec<InstanceMove>> {
+        self.service.rebalance(max_moves).await
+    }
+
+    pub async fn execute_rebalance(
+        &self,
+        plan: &[InstanceMove],
+        requests: &HashMap<String, StartInstanceRequest>,
+    ) -> ControlPlaneResult<usize> {
+        self.service.execute_rebalance(plan, requests).await
+    }
+
+    /// Instance slot usage across all registered nodes
+    pub async fn cluster_capacity(&self) -> ControlPlaneResult<ClusterCapacity> {
+        self.service.cluster_capacity().await
+    }
+
+    /// Whether etcd persistence is configured and currently reachable
+    pub async fn persistence_status(&self) -> PersistenceStatus {
+        self.service.persistence_status().await
+    }
+
+    pub async fn sync_node_instance_metrics(&self) -> ControlPlaneResult<()> {
+        self.service.sync_node_instance_metrics().await
+    }
+
+    /// Force restart an instance on its node, ignoring its restart policy.
+    pub async fn restart_instance(&self, instance_id: &str) -> ControlPlaneResult<()> {
+        self.service.route_restart_instance(instance_id).await
     }
 
     pub async fn query_instance(
@@ -60,6 +171,13 @@ impl NodeRoutingController {
         self.service.route_list_instances().await
     }
 
+    pub async fn list_instances_by_module_hash(
+        &self,
+        hash: &str,
+    ) -> ControlPlaneResult<Vec<InstanceMetadata>> {
+        self.service.route_list_instances_by_module_hash(hash).await
+    }
+
     pub async fn invoke_capability(
         &self,
         instance_id: &str,
@@ -81,4 +199,11 @@ impl NodeRoutingController {
             .recover_node_state(node_id, control_plane)
             .await
     }
+
+    pub async fn recover_all_nodes(
+        &self,
+        control_plane: &Mutex<ControlPlane>,
+    ) -> ControlPlaneResult<Vec<(String, ControlPlaneResult<usize>)>> {
+        self.service.recover_all_nodes(control_plane).await
+    }
 }
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
+#[cfg(feature = "etcd")]
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -9,9 +11,15 @@ pub enum EtcdMetadataKind {
     Provider,
 }
 
+/// How long a health ping waits for etcd before reporting it unreachable
+#[cfg(feature = "etcd")]
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Clone, Default)]
 pub struct EtcdMetadataRepository {
     storage: Arc<RwLock<HashMap<String, String>>>,
+    /// Cluster checked by [`Self::ping`]; unset for purely in-memory use
+    config: Option<EtcdConfig>,
 }
 
 impl EtcdMetadataRepository {
@@ -19,6 +27,20 @@ impl EtcdMetadataRepository {
         Self::default()
     }
 
+    pub fn with_config(mut self, config: EtcdConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Check that the configured etcd cluster answers; always succeeds
+    /// when no cluster is configured
+    pub async fn ping(&self) -> Result<(), String> {
+        match &self.config {
+            Some(config) => ping_etcd(config).await,
+            None => Ok(()),
+        }
+    }
+
     pub async fn put_node_presence(
         &self,
         node_id: &str,
@@ -70,6 +92,7 @@ impl EtcdMetadataRepository {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct EtcdConfig {
     pub endpoints: Vec<String>,
     pub username: Option<String>,
@@ -109,6 +132,30 @@ pub async fn validate_etcd_config(_config: &EtcdConfig) -> Result<(), String> {
     Err("etcd feature is not enabled".to_string())
 }
 
+#[cfg(feature = "etcd")]
+async fn ping_etcd(config: &EtcdConfig) -> Result<(), String> {
+    let mut options = etcd_client::ConnectOptions::new()
+        .with_connect_timeout(PING_TIMEOUT)
+        .with_timeout(PING_TIMEOUT);
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+        options = options.with_user(username, password);
+    }
+    let ping = async {
+        let mut client = etcd_client::Client::connect(&config.endpoints, Some(options)).await?;
+        client.status().await
+    };
+    match tokio::time::timeout(PING_TIMEOUT, ping).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(error)) => Err(error.to_string()),
+        Err(_) => Err(format!("etcd did not answer within {PING_TIMEOUT:?}")),
+    }
+}
+
+#[cfg(not(feature = "etcd"))]
+async fn ping_etcd(_config: &EtcdConfig) -> Result<(), String> {
+    Err("etcd feature is not enabled".to_string())
+}
+
 pub fn classify_key(key: &str) -> Option<EtcdMetadataKind> {
     if key.starts_with("/wasmatrix/nodes/") {
         return Some(EtcdMetadataKind::Node);
@@ -202,4 +249,16 @@ mod tests {
             assert!(result.is_err());
         }
     }
+
+    #[tokio::test]
+    async fn test_ping_distinguishes_unreachable_cluster() {
+        assert!(EtcdMetadataRepository::new().ping().await.is_ok());
+
+        let unreachable = EtcdMetadataRepository::new().with_config(EtcdConfig {
+            endpoints: vec!["http://127.0.0.1:1".to_string()],
+            username: None,
+            password: None,
+        });
+        assert!(unreachable.ping().await.is_err());
+    }
 }
@@ -7,13 +7,17 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::shared::error::{ControlPlaneError, ControlPlaneResult};
+use wasmatrix_core::CapabilityAssignment;
 
 #[derive(Debug, Clone)]
 pub struct NodeAgentRecord {
     pub node_id: String,
     pub node_address: String,
     pub capabilities: Vec<String>,
-    pub max_instances: u32,
+    /// `None` means unlimited; `Some(0)` means the node accepts no instances
+    pub max_instances: Option<u32>,
+    /// Relative share of placements under weighted round-robin; 0 counts as 1
+    pub weight: u32,
     pub active_instances: u32,
     pub last_heartbeat: Option<DateTime<Utc>>,
     pub available: bool,
@@ -27,6 +31,14 @@ pub struct ProviderMetadata {
     pub last_updated: DateTime<Utc>,
 }
 
+/// Where an instance runs and which provider types its placement requires
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceAssignment {
+    pub instance_id: String,
+    pub node_id: String,
+    pub required_providers: Vec<String>,
+}
+
 #[async_trait]
 pub trait NodeRoutingRepository: Send + Sync {
     async fn upsert_node(&self, node: NodeAgentRecord) -> ControlPlaneResult<()>;
@@ -44,18 +56,53 @@ pub trait NodeRoutingRepository: Send + Sync {
     async fn assign_instance(&self, instance_id: String, node_id: String)
         -> ControlPlaneResult<()>;
     async fn lookup_instance_node(&self, instance_id: &str) -> ControlPlaneResult<Option<String>>;
+    /// The record of the node an instance is assigned to, read as one snapshot
+    /// so a concurrent reassignment cannot pair the instance with a stale node
+    async fn resolve_instance_target(
+        &self,
+        instance_id: &str,
+    ) -> ControlPlaneResult<Option<NodeAgentRecord>>;
+    /// Remember the provider types an instance needs wherever it is placed
+    async fn set_required_providers(
+        &self,
+        instance_id: &str,
+        providers: Vec<String>,
+    ) -> ControlPlaneResult<()>;
+    async fn list_instance_assignments(&self) -> ControlPlaneResult<Vec<InstanceAssignment>>;
+    /// Replace the capability grants stored for an instance
+    async fn set_instance_capabilities(
+        &self,
+        instance_id: &str,
+        capabilities: Vec<CapabilityAssignment>,
+    ) -> ControlPlaneResult<()>;
+    async fn get_instance_capabilities(
+        &self,
+        instance_id: &str,
+    ) -> ControlPlaneResult<Vec<CapabilityAssignment>>;
     async fn remove_instance_assignment(
         &self,
         instance_id: &str,
     ) -> ControlPlaneResult<Option<String>>;
     async fn upsert_provider_metadata(&self, provider: ProviderMetadata) -> ControlPlaneResult<()>;
     async fn list_provider_metadata(&self) -> ControlPlaneResult<Vec<ProviderMetadata>>;
+    async fn get_provider_metadata(
+        &self,
+        provider_id: &str,
+    ) -> ControlPlaneResult<Option<ProviderMetadata>>;
+    /// Providers matching every given filter; `None` matches anything
+    async fn list_provider_metadata_filtered(
+        &self,
+        provider_type: Option<&str>,
+        node_id: Option<&str>,
+    ) -> ControlPlaneResult<Vec<ProviderMetadata>>;
 }
 
 #[derive(Clone, Default)]
 pub struct InMemoryNodeRoutingRepository {
     nodes: Arc<RwLock<HashMap<String, NodeAgentRecord>>>,
     assignments: Arc<RwLock<HashMap<String, String>>>,
+    required_providers: Arc<RwLock<HashMap<String, Vec<String>>>>,
+    capabilities: Arc<RwLock<HashMap<String, Vec<CapabilityAssignment>>>>,
     providers: Arc<RwLock<HashMap<String, ProviderMetadata>>>,
 }
 
@@ -149,10 +196,74 @@ impl NodeRoutingRepository for InMemoryNodeRoutingRepository {
         Ok(assignments.get(instance_id).cloned())
     }
 
+    async fn resolve_instance_target(
+        &self,
+        instance_id: &str,
+    ) -> ControlPlaneResult<Option<NodeAgentRecord>> {
+        // Hold the assignment lock while reading the node so neither can change in between
+        let assignments = self.assignments.read().await;
+        let Some(node_id) = assignments.get(instance_id) else {
+            return Ok(None);
+        };
+        let nodes = self.nodes.read().await;
+        Ok(nodes.get(node_id).cloned())
+    }
+
+    async fn set_required_providers(
+        &self,
+        instance_id: &str,
+        providers: Vec<String>,
+    ) -> ControlPlaneResult<()> {
+        let mut required = self.required_providers.write().await;
+        if providers.is_empty() {
+            required.remove(instance_id);
+        } else {
+            required.insert(instance_id.to_string(), providers);
+        }
+        Ok(())
+    }
+
+    async fn list_instance_assignments(&self) -> ControlPlaneResult<Vec<InstanceAssignment>> {
+        let assignments = self.assignments.read().await;
+        let required = self.required_providers.read().await;
+        Ok(assignments
+            .iter()
+            .map(|(instance_id, node_id)| InstanceAssignment {
+                instance_id: instance_id.clone(),
+                node_id: node_id.clone(),
+                required_providers: required.get(instance_id).cloned().unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn set_instance_capabilities(
+        &self,
+        instance_id: &str,
+        capabilities: Vec<CapabilityAssignment>,
+    ) -> ControlPlaneResult<()> {
+        let mut stored = self.capabilities.write().await;
+        if capabilities.is_empty() {
+            stored.remove(instance_id);
+        } else {
+            stored.insert(instance_id.to_string(), capabilities);
+        }
+        Ok(())
+    }
+
+    async fn get_instance_capabilities(
+        &self,
+        instance_id: &str,
+    ) -> ControlPlaneResult<Vec<CapabilityAssignment>> {
+        let stored = self.capabilities.read().await;
+        Ok(stored.get(instance_id).cloned().unwrap_or_default())
+    }
+
     async fn remove_instance_assignment(
         &self,
         instance_id: &str,
     ) -> ControlPlaneResult<Option<String>> {
+        self.required_providers.write().await.remove(instance_id);
+        self.capabilities.write().await.remove(instance_id);
         let mut assignments = self.assignments.write().await;
         Ok(assignments.remove(instance_id))
     }
@@ -167,11 +278,34 @@ impl NodeRoutingRepository for InMemoryNodeRoutingRepository {
         let providers = self.providers.read().await;
         Ok(providers.values().cloned().collect())
     }
+
+    async fn get_provider_metadata(
+        &self,
+        provider_id: &str,
+    ) -> ControlPlaneResult<Option<ProviderMetadata>> {
+        let providers = self.providers.read().await;
+        Ok(providers.get(provider_id).cloned())
+    }
+
+    async fn list_provider_metadata_filtered(
+        &self,
+        provider_type: Option<&str>,
+        node_id: Option<&str>,
+    ) -> ControlPlaneResult<Vec<ProviderMetadata>> {
+        let providers = self.providers.read().await;
+        Ok(providers
+            .values()
+            .filter(|p| provider_type.is_none_or(|t| p.provider_type == t))
+            .filter(|p| node_id.is_none_or(|n| p.node_id == n))
+            .cloned()
+            .collect())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_register_and_lookup_node() {
@@ -180,7 +314,8 @@ mod tests {
             node_id: "node-1".to_string(),
             node_address: "http://127.0.0.1:50052".to_string(),
             capabilities: vec![],
-            max_instances: 10,
+            max_instances: Some(10),
+            weight: 1,
             active_instances: 0,
             last_heartbeat: None,
             available: true,
@@ -206,6 +341,69 @@ mod tests {
         assert_eq!(removed.as_deref(), Some("node-1"));
     }
 
+    #[tokio::test]
+    async fn test_resolve_instance_target_is_consistent_during_reassignment() {
+        let repo = InMemoryNodeRoutingRepository::new();
+        for node_id in ["node-a", "node-b"] {
+            repo.upsert_node(NodeAgentRecord {
+                node_id: node_id.to_string(),
+                node_address: format!("http://{node_id}:50052"),
+                capabilities: vec![],
+                max_instances: None,
+                weight: 1,
+                active_instances: 0,
+                last_heartbeat: None,
+                available: true,
+            })
+            .await
+            .unwrap();
+        }
+        repo.assign_instance("instance-1".to_string(), "node-a".to_string())
+            .await
+            .unwrap();
+
+        // Park a lookup on the node table, then move the instance to node-b and
+        // decommission node-a as soon as the move has gone through. Resolving
+        // in two reads would pair the instance with the removed node-a.
+        let mut nodes = repo.nodes.write().await;
+        let lookup = {
+            let repo = repo.clone();
+            tokio::spawn(async move { repo.resolve_instance_target("instance-1").await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let mover = {
+            let repo = repo.clone();
+            tokio::spawn(async move {
+                repo.assign_instance("instance-1".to_string(), "node-b".to_string())
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        if mover.is_finished() {
+            nodes.remove("node-a");
+        }
+        drop(nodes);
+
+        let node = lookup
+            .await
+            .unwrap()
+            .unwrap()
+            .expect("assigned instance always resolves to a node");
+        assert_eq!(node.node_id, "node-a");
+        mover.await.unwrap().unwrap();
+        let node = repo
+            .resolve_instance_target("instance-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(node.node_id, "node-b");
+        assert!(repo
+            .resolve_instance_target("missing")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
     #[tokio::test]
     async fn test_provider_metadata_stored_separately_from_instances() {
         let repo = InMemoryNodeRoutingRepository::new();
@@ -228,6 +426,65 @@ mod tests {
 
         assert_eq!(providers.len(), 1);
         assert_eq!(providers[0].provider_id, "kv-provider-1");
+        assert_eq!(
+            repo.get_provider_metadata("kv-provider-1")
+                .await
+                .unwrap()
+                .map(|provider| provider.node_id)
+                .as_deref(),
+            Some("node-1")
+        );
+        assert!(repo
+            .get_provider_metadata("missing")
+            .await
+            .unwrap()
+            .is_none());
         assert_eq!(assignment_node.as_deref(), Some("node-1"));
     }
+
+    #[tokio::test]
+    async fn test_list_provider_metadata_filtered_combines_filters() {
+        let repo = InMemoryNodeRoutingRepository::new();
+        for (provider_id, provider_type, node_id) in [
+            ("kv-1", "kv", "node-1"),
+            ("kv-3", "kv", "node-3"),
+            ("http-3", "http", "node-3"),
+            ("messaging-1", "messaging", "node-1"),
+        ] {
+            repo.upsert_provider_metadata(ProviderMetadata {
+                provider_id: provider_id.to_string(),
+                provider_type: provider_type.to_string(),
+                node_id: node_id.to_string(),
+                last_updated: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let ids = |providers: Vec<ProviderMetadata>| {
+            let mut ids: Vec<String> = providers.into_iter().map(|p| p.provider_id).collect();
+            ids.sort();
+            ids
+        };
+
+        let all = repo.list_provider_metadata_filtered(None, None).await;
+        assert_eq!(
+            ids(all.unwrap()),
+            vec!["http-3", "kv-1", "kv-3", "messaging-1"]
+        );
+        let kv = repo.list_provider_metadata_filtered(Some("kv"), None).await;
+        assert_eq!(ids(kv.unwrap()), vec!["kv-1", "kv-3"]);
+        let node_3 = repo
+            .list_provider_metadata_filtered(None, Some("node-3"))
+            .await;
+        assert_eq!(ids(node_3.unwrap()), vec!["http-3", "kv-3"]);
+        let kv_on_node_3 = repo
+            .list_provider_metadata_filtered(Some("kv"), Some("node-3"))
+            .await;
+        assert_eq!(ids(kv_on_node_3.unwrap()), vec!["kv-3"]);
+        let none = repo
+            .list_provider_metadata_filtered(Some("http"), Some("node-1"))
+            .await;
+        assert!(none.unwrap().is_empty());
+    }
 }
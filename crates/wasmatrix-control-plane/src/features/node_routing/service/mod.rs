@@ -1,33 +1,137 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use chrono::{DateTime, TimeZone, Utc};
 use tonic::transport::Channel;
 use tracing::warn;
 use wasmatrix_core::capability::PermissionEnforcer;
+use wasmatrix_core::module_hash::module_hashes_match;
+use wasmatrix_core::statelessness::StatelessnessPolicy;
 use wasmatrix_core::CapabilityAssignment;
+use wasmatrix_proto::tls::{self, TlsConfig};
 use wasmatrix_proto::v1::node_agent_service_client::NodeAgentServiceClient;
 use wasmatrix_proto::v1::{
-    InvokeCapabilityRequest, ListInstancesRequest, QueryInstanceRequest,
-    StartInstanceRequest as ProtoStartInstanceRequest, StopInstanceRequest,
+    CapabilityAssignment as ProtoCapabilityAssignment, InvokeCapabilityRequest,
+    ListInstancesRequest, QueryInstanceRequest, RestartInstanceRequest,
+    SetInstanceCapabilitiesRequest, StartInstanceRequest as ProtoStartInstanceRequest,
+    StopInstanceRequest,
 };
 
+use crate::features::module_verification::service::{ModuleVerifier, NoopModuleVerifier};
 use crate::features::node_routing::repo::etcd::EtcdMetadataRepository;
 use crate::features::node_routing::repo::{
-    NodeAgentRecord, NodeRoutingRepository, ProviderMetadata,
+    InstanceAssignment, NodeAgentRecord, NodeRoutingRepository, ProviderMetadata,
 };
 use crate::features::observability::controller::global_observability_controller;
 use crate::shared::error::{ControlPlaneError, ControlPlaneResult};
 use crate::shared::types::{
     InstanceMetadata, InstanceStatusResponse, QueryInstanceRequest as CoreQueryRequest,
-    StartInstanceRequest,
+    RestartPolicy, StartInstanceRequest,
 };
 use crate::ControlPlane;
 use std::sync::Mutex;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+/// Consecutive transport failures after which a node is marked unavailable
+const DEFAULT_NODE_FAILURE_THRESHOLD: u32 = 3;
+
+/// How long `recover_node_state` waits on a node before giving up on it
+const DEFAULT_RECOVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A planned migration: `(instance_id, from_node_id, to_node_id)`
+pub type InstanceMove = (String, String, String);
+
+/// Instance slot usage across registered nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClusterCapacity {
+    /// Slots offered by available nodes; `None` when any of them is unlimited
+    pub total_slots: Option<u64>,
+    /// Instances running on available nodes
+    pub used_slots: u64,
+    pub available_nodes: u32,
+    pub total_nodes: u32,
+}
+
+/// Whether etcd metadata persistence is configured and answering
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersistenceStatus {
+    pub enabled: bool,
+    /// Only meaningful when `enabled`
+    pub healthy: bool,
+    pub last_error: Option<String>,
+}
+
+/// How `route_start_instance` orders eligible nodes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlacementStrategy {
+    /// Fewest active instances first, rotating among equally-loaded nodes
+    #[default]
+    LeastLoaded,
+    /// Spread placements in proportion to each node's `weight`
+    WeightedRoundRobin,
+    /// Like `LeastLoaded`, but a node offering scarce providers the instance
+    /// does not need counts as busier, keeping specialized nodes free for
+    /// instances that require them
+    CapabilityAware,
+}
+
+/// Token bucket bounding how fast start requests are admitted cluster-wide
+#[derive(Debug)]
+struct StartRateLimiter {
+    burst: f64,
+    per_second: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl StartRateLimiter {
+    fn new(per_second: u32, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            burst,
+            per_second: f64::from(per_second),
+            tokens: burst,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn try_acquire(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.burst);
+        self.refilled_at = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
 
 pub struct NodeRoutingService {
     repo: Arc<dyn NodeRoutingRepository>,
     etcd_metadata_repo: Option<Arc<EtcdMetadataRepository>>,
+    module_verifier: Arc<dyn ModuleVerifier>,
+    /// Advances on every placement to rotate among equally-loaded nodes
+    placement_cursor: AtomicUsize,
+    placement_strategy: PlacementStrategy,
+    /// Running weights for weighted round-robin, keyed by node id
+    weighted_placement: Mutex<HashMap<String, i64>>,
+    /// Consecutive transport failures per node since its last successful RPC
+    node_failures: Mutex<HashMap<String, u32>>,
+    node_failure_threshold: u32,
+    /// Applied to start requests that do not set a restart policy
+    default_restart_policy: RestartPolicy,
+    /// Dial node agents over TLS when set
+    tls: Option<TlsConfig>,
+    /// Upper bound for placing a start request across all candidates
+    start_deadline: Option<Duration>,
+    start_rate_limiter: Option<Mutex<StartRateLimiter>>,
+    /// Upper bound for connecting to a node and listing its instances on recovery
+    recovery_timeout: Duration,
+    /// Reject start requests whose capabilities name another instance
+    validate_capability_instance_ids: bool,
 }
 
 impl NodeRoutingService {
@@ -35,6 +139,18 @@ impl NodeRoutingService {
         Self {
             repo,
             etcd_metadata_repo: None,
+            module_verifier: Arc::new(NoopModuleVerifier),
+            placement_cursor: AtomicUsize::new(0),
+            placement_strategy: PlacementStrategy::default(),
+            weighted_placement: Mutex::new(HashMap::new()),
+            node_failures: Mutex::new(HashMap::new()),
+            node_failure_threshold: DEFAULT_NODE_FAILURE_THRESHOLD,
+            default_restart_policy: RestartPolicy::default(),
+            tls: None,
+            start_deadline: None,
+            start_rate_limiter: None,
+            recovery_timeout: DEFAULT_RECOVERY_TIMEOUT,
+            validate_capability_instance_ids: false,
         }
     }
 
@@ -45,22 +161,100 @@ impl NodeRoutingService {
         Self {
             repo,
             etcd_metadata_repo: Some(etcd_metadata_repo),
+            module_verifier: Arc::new(NoopModuleVerifier),
+            placement_cursor: AtomicUsize::new(0),
+            placement_strategy: PlacementStrategy::default(),
+            weighted_placement: Mutex::new(HashMap::new()),
+            node_failures: Mutex::new(HashMap::new()),
+            node_failure_threshold: DEFAULT_NODE_FAILURE_THRESHOLD,
+            default_restart_policy: RestartPolicy::default(),
+            tls: None,
+            start_deadline: None,
+            start_rate_limiter: None,
+            recovery_timeout: DEFAULT_RECOVERY_TIMEOUT,
+            validate_capability_instance_ids: false,
         }
     }
 
+    /// Enable module signature verification with the given verifier
+    pub fn with_module_verifier(mut self, module_verifier: Arc<dyn ModuleVerifier>) -> Self {
+        self.module_verifier = module_verifier;
+        self
+    }
+
+    /// Mark a node unavailable only after this many consecutive transport failures
+    pub fn with_node_failure_threshold(mut self, threshold: u32) -> Self {
+        self.node_failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Connect to node agents over TLS, presenting the certificate for mTLS
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Fail `route_start_instance` with `Timeout` once placement has taken this
+    /// long, however many candidates remain; each connect and start RPC only
+    /// gets the time left
+    pub fn with_start_deadline(mut self, start_deadline: Duration) -> Self {
+        self.start_deadline = Some(start_deadline);
+        self
+    }
+
+    /// Admit at most `per_second` start requests per second across the
+    /// cluster, allowing bursts of up to `burst`; excess requests fail with
+    /// `ResourceExhausted` before any node is contacted
+    pub fn with_start_rate_limit(mut self, per_second: u32, burst: u32) -> Self {
+        self.start_rate_limiter = Some(Mutex::new(StartRateLimiter::new(per_second, burst)));
+        self
+    }
+
+    /// Give up recovering a node, and mark it unavailable, once connecting and
+    /// listing its instances has taken this long
+    pub fn with_recovery_timeout(mut self, recovery_timeout: Duration) -> Self {
+        self.recovery_timeout = recovery_timeout;
+        self
+    }
+
+    /// Reject start requests carrying a capability whose non-empty
+    /// `instance_id` differs from the instance being started, instead of
+    /// silently re-stamping it; empty ids are still filled in
+    pub fn with_capability_instance_id_validation(mut self, enabled: bool) -> Self {
+        self.validate_capability_instance_ids = enabled;
+        self
+    }
+
+    /// Restart policy for instances started without one (`Never` unless set)
+    pub fn with_default_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.default_restart_policy = restart_policy;
+        self
+    }
+
+    /// Choose how new instances are spread across nodes
+    pub fn with_placement_strategy(mut self, placement_strategy: PlacementStrategy) -> Self {
+        self.placement_strategy = placement_strategy;
+        self
+    }
+
     pub async fn register_node(
         &self,
         node_id: String,
         node_address: String,
         capabilities: Vec<String>,
-        max_instances: u32,
+        max_instances: Option<u32>,
+        weight: Option<u32>,
     ) -> ControlPlaneResult<()> {
+        if max_instances == Some(0) {
+            tracing::warn!(%node_id, "Node registered with max_instances=0 and will not accept instances");
+        }
         self.repo
             .upsert_node(NodeAgentRecord {
                 node_id: node_id.clone(),
                 node_address: normalize_endpoint(&node_address),
                 capabilities,
                 max_instances,
+                weight: weight.unwrap_or(1),
                 active_instances: 0,
                 last_heartbeat: Some(Utc::now()),
                 available: true,
@@ -77,6 +271,28 @@ impl NodeRoutingService {
         Ok(())
     }
 
+    /// Refresh the provider types a registered node offers, either replacing
+    /// the registered set or adding to it, so placement sees providers the
+    /// node loaded after registering
+    pub async fn update_node_capabilities(
+        &self,
+        node_id: &str,
+        capabilities: Vec<String>,
+        replace: bool,
+    ) -> ControlPlaneResult<()> {
+        let mut node = self.require_node(node_id).await?;
+        if replace {
+            node.capabilities = capabilities;
+        } else {
+            for capability in capabilities {
+                if !node.capabilities.contains(&capability) {
+                    node.capabilities.push(capability);
+                }
+            }
+        }
+        self.repo.upsert_node(node).await
+    }
+
     pub async fn register_provider_metadata(
         &self,
         provider_id: String,
@@ -102,6 +318,17 @@ impl NodeRoutingService {
         Ok(())
     }
 
+    /// Registered providers, optionally narrowed by type and/or node (AND semantics)
+    pub async fn list_provider_metadata_filtered(
+        &self,
+        provider_type: Option<&str>,
+        node_id: Option<&str>,
+    ) -> ControlPlaneResult<Vec<ProviderMetadata>> {
+        self.repo
+            .list_provider_metadata_filtered(provider_type, node_id)
+            .await
+    }
+
     pub async fn record_status_report(
         &self,
         node_id: &str,
@@ -117,65 +344,124 @@ impl NodeRoutingService {
         &self,
         request: StartInstanceRequest,
     ) -> ControlPlaneResult<String> {
+        if let Some(limiter) = &self.start_rate_limiter {
+            if !limiter
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .try_acquire()
+            {
+                return Err(ControlPlaneError::ResourceExhausted(
+                    "start rate exceeded".to_string(),
+                ));
+            }
+        }
+        self.module_verifier
+            .verify(&request.module_bytes, request.module_signature.as_deref())?;
+        StatelessnessPolicy::verify_wasi_config(&request.env, &request.args)
+            .map_err(|e| ControlPlaneError::ValidationError(e.to_string()))?;
+        StatelessnessPolicy::verify_event_annotations(&request.annotations)
+            .map_err(|e| ControlPlaneError::ValidationError(e.to_string()))?;
+        let instance_id = uuid::Uuid::new_v4().to_string();
+        if self.validate_capability_instance_ids {
+            check_capability_instance_ids(&instance_id, &request.capabilities)?;
+        }
+        let request = self.resolve_restart_policy(request);
+
         let nodes = self.repo.list_nodes().await?;
-        let candidates = select_candidate_nodes(nodes, &request);
+        let missing_providers = missing_provider_types(&nodes, &required_provider_types(&request));
+        let candidates = match self.placement_strategy {
+            PlacementStrategy::LeastLoaded | PlacementStrategy::CapabilityAware => {
+                let rotation = self.placement_cursor.fetch_add(1, Ordering::Relaxed);
+                let reserve_specialized =
+                    self.placement_strategy == PlacementStrategy::CapabilityAware;
+                select_candidate_nodes(nodes, &request, rotation, reserve_specialized)
+            }
+            PlacementStrategy::WeightedRoundRobin => {
+                let mut running = self
+                    .weighted_placement
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                select_weighted_candidate_nodes(nodes, &request, &mut running)
+            }
+        };
+        let candidates = apply_node_preference(
+            candidates,
+            request.preferred_node_id.as_deref(),
+            request.require_node,
+        )?;
 
         if candidates.is_empty() {
+            if !missing_providers.is_empty() {
+                return Err(ControlPlaneError::CapabilityNotFound(format!(
+                    "No registered node offers required provider type(s): {}",
+                    missing_providers.join(", ")
+                )));
+            }
             return Err(ControlPlaneError::ResourceExhausted(
                 "No registered node agents".to_string(),
             ));
         }
 
         let mut errors = Vec::new();
-        let instance_id = uuid::Uuid::new_v4().to_string();
+        let deadline = self
+            .start_deadline
+            .map(|budget| tokio::time::Instant::now() + budget);
 
         for node in candidates {
-            let mut client = match connect_client(&node.node_address).await {
-                Ok(client) => client,
-                Err(error) => {
+            let connected = within(
+                deadline,
+                connect_client(&node.node_address, self.tls.as_ref()),
+            )
+            .await;
+            let mut client = match connected {
+                None => return Err(self.start_deadline_exceeded(&node.node_id, errors).await),
+                Some(Ok(client)) => client,
+                Some(Err(error)) => {
                     errors.push(format!("{}: {}", node.node_id, error));
-                    let _ = self.repo.set_availability(&node.node_id, false).await;
+                    self.record_node_failure(&node.node_id).await;
                     continue;
                 }
             };
 
-            let req = ProtoStartInstanceRequest {
-                instance_id: instance_id.clone(),
-                module_bytes: request.module_bytes.clone(),
-                capabilities: request
-                    .capabilities
-                    .iter()
-                    .map(|cap| {
-                        wasmatrix_proto::protocol::CapabilityAssignment {
-                            instance_id: instance_id.clone(),
-                            capability_id: cap.capability_id.clone(),
-                            provider_type: cap.provider_type.into(),
-                            permissions: cap.permissions.clone(),
-                        }
-                        .into()
-                    })
-                    .collect(),
-                restart_policy: Some(
-                    wasmatrix_proto::protocol::RestartPolicy::from(request.restart_policy.clone())
-                        .into(),
-                ),
-            };
+            let req = proto_start_request(&instance_id, &request);
 
-            match client.start_instance(tonic::Request::new(req)).await {
+            let response = within(deadline, client.start_instance(tonic::Request::new(req))).await;
+            let Some(response) = response else {
+                return Err(self.start_deadline_exceeded(&node.node_id, errors).await);
+            };
+            match response {
                 Ok(response) if response.get_ref().success => {
                     self.repo
                         .assign_instance(instance_id.clone(), node.node_id.clone())
                         .await?;
+                    self.repo
+                        .set_required_providers(&instance_id, required_provider_types(&request))
+                        .await?;
+                    let capabilities = request
+                        .capabilities
+                        .iter()
+                        .map(|cap| {
+                            let mut cap = cap.clone();
+                            cap.instance_id = instance_id.clone();
+                            cap
+                        })
+                        .collect();
+                    self.repo
+                        .set_instance_capabilities(&instance_id, capabilities)
+                        .await?;
                     self.repo.increment_active_instances(&node.node_id).await?;
+                    self.publish_node_instance_count(&node.node_id).await?;
+                    self.reset_node_failures(&node.node_id);
                     self.repo.set_availability(&node.node_id, true).await?;
                     return Ok(instance_id);
                 }
                 Ok(response) => {
+                    self.reset_node_failures(&node.node_id);
                     errors.push(format!("{}: {}", node.node_id, response.get_ref().message));
                 }
                 Err(error) => {
                     errors.push(format!("{}: {}", node.node_id, error));
-                    let _ = self.repo.set_availability(&node.node_id, false).await;
+                    self.record_rpc_error(&node.node_id, &error).await;
                 }
             }
         }
@@ -186,38 +472,451 @@ impl NodeRoutingService {
         )))
     }
 
-    pub async fn route_stop_instance(&self, instance_id: &str) -> ControlPlaneResult<()> {
-        let node_id = self
+    /// Count the node that was still being tried as failed and build the error
+    async fn start_deadline_exceeded(
+        &self,
+        node_id: &str,
+        mut errors: Vec<String>,
+    ) -> ControlPlaneError {
+        self.record_node_failure(node_id).await;
+        errors.push(format!("{node_id}: start deadline exceeded"));
+        ControlPlaneError::Timeout(format!(
+            "Start request was not placed within {:?}: {}",
+            self.start_deadline.unwrap_or_default(),
+            errors.join(" | ")
+        ))
+    }
+
+    /// Report whether etcd persistence is configured and ping it if so
+    pub async fn persistence_status(&self) -> PersistenceStatus {
+        let Some(etcd_repo) = &self.etcd_metadata_repo else {
+            return PersistenceStatus {
+                enabled: false,
+                healthy: false,
+                last_error: None,
+            };
+        };
+        let last_error = etcd_repo.ping().await.err();
+        PersistenceStatus {
+            enabled: true,
+            healthy: last_error.is_none(),
+            last_error,
+        }
+    }
+
+    pub async fn locate_instance(&self, instance_id: &str) -> ControlPlaneResult<String> {
+        self.repo
+            .lookup_instance_node(instance_id)
+            .await?
+            .ok_or_else(|| ControlPlaneError::InstanceNotFound(instance_id.to_string()))
+    }
+
+    pub async fn route_stop_instance(
+        &self,
+        instance_id: &str,
+        reason: Option<&str>,
+        annotations: &HashMap<String, String>,
+    ) -> ControlPlaneResult<()> {
+        StatelessnessPolicy::verify_event_annotations(annotations)
+            .map_err(|e| ControlPlaneError::ValidationError(e.to_string()))?;
+        let node = self.resolve_target(instance_id).await?;
+
+        stop_on_node(&node, instance_id, reason, annotations, self.tls.as_ref()).await?;
+
+        self.repo.remove_instance_assignment(instance_id).await?;
+        self.repo.decrement_active_instances(&node.node_id).await?;
+        self.publish_node_instance_count(&node.node_id).await?;
+        Ok(())
+    }
+
+    /// Drop routing assignments of instances that no longer exist, e.g. after
+    /// [`ControlPlane::reap_stopped`]; returns how many were assigned
+    pub async fn forget_instances(&self, instance_ids: &[String]) -> ControlPlaneResult<usize> {
+        let mut forgotten = 0;
+        for instance_id in instance_ids {
+            if self
+                .repo
+                .remove_instance_assignment(instance_id)
+                .await?
+                .is_some()
+            {
+                forgotten += 1;
+            }
+        }
+        Ok(forgotten)
+    }
+
+    /// Move an instance to `new_node_id`, starting it there from `request`.
+    ///
+    /// The old (possibly dead) node is not contacted. The start request carries
+    /// the instance's stored capability grants, so the new copy holds exactly
+    /// the grants the instance had from its first moment; routing is left
+    /// untouched if the start fails.
+    pub async fn reassign_instance(
+        &self,
+        instance_id: &str,
+        new_node_id: &str,
+        request: &StartInstanceRequest,
+    ) -> ControlPlaneResult<()> {
+        let old_node_id = self
             .repo
             .lookup_instance_node(instance_id)
             .await?
             .ok_or_else(|| ControlPlaneError::InstanceNotFound(instance_id.to_string()))?;
+        if old_node_id == new_node_id {
+            return Ok(());
+        }
 
-        let node = self
-            .repo
-            .get_node(&node_id)
+        let new_node =
+            self.repo.get_node(new_node_id).await?.ok_or_else(|| {
+                ControlPlaneError::InstanceNotFound(format!("node {}", new_node_id))
+            })?;
+        if !can_accept_instance(&new_node) {
+            return Err(ControlPlaneError::ResourceExhausted(format!(
+                "node {} cannot accept instance {}",
+                new_node_id, instance_id
+            )));
+        }
+        if self.validate_capability_instance_ids {
+            check_capability_instance_ids(instance_id, &request.capabilities)?;
+        }
+        let request =
+            self.resolve_restart_policy(self.with_stored_capabilities(instance_id, request).await?);
+        start_on_node(&new_node, instance_id, &request, self.tls.as_ref()).await?;
+
+        self.record_reassignment(instance_id, &old_node_id, new_node_id)
+            .await
+    }
+
+    /// Route an instance that now runs on `new_node_id` there and move its
+    /// slot off `old_node_id`, if that node is still registered
+    async fn record_reassignment(
+        &self,
+        instance_id: &str,
+        old_node_id: &str,
+        new_node_id: &str,
+    ) -> ControlPlaneResult<()> {
+        self.repo
+            .assign_instance(instance_id.to_string(), new_node_id.to_string())
+            .await?;
+        self.repo.increment_active_instances(new_node_id).await?;
+        self.publish_node_instance_count(new_node_id).await?;
+        if self.repo.get_node(old_node_id).await?.is_some() {
+            self.repo.decrement_active_instances(old_node_id).await?;
+            self.publish_node_instance_count(old_node_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Grant a capability to a routed instance: the hosting node's running
+    /// copy gets it first, then it is stored so it follows the instance when
+    /// it is started on another node. Replaces any grant with the same
+    /// capability id; nothing is stored if the node cannot be updated.
+    pub async fn record_capability_assignment(
+        &self,
+        assignment: CapabilityAssignment,
+    ) -> ControlPlaneResult<()> {
+        let instance_id = assignment.instance_id.clone();
+        let mut capabilities = self.repo.get_instance_capabilities(&instance_id).await?;
+        capabilities.retain(|existing| existing.capability_id != assignment.capability_id);
+        capabilities.push(assignment);
+
+        self.push_instance_capabilities(&instance_id, &capabilities)
+            .await?;
+        self.repo
+            .set_instance_capabilities(&instance_id, capabilities)
+            .await
+    }
+
+    /// Take a capability away from a routed instance, on its hosting node and
+    /// in the stored grants. Returns whether a grant was removed; nothing
+    /// changes if the node cannot be updated.
+    pub async fn revoke_capability_assignment(
+        &self,
+        instance_id: &str,
+        capability_id: &str,
+    ) -> ControlPlaneResult<bool> {
+        self.repo
+            .lookup_instance_node(instance_id)
             .await?
-            .ok_or_else(|| ControlPlaneError::InstanceNotFound(format!("node {}", node_id)))?;
+            .ok_or_else(|| ControlPlaneError::InstanceNotFound(instance_id.to_string()))?;
+
+        let mut capabilities = self.repo.get_instance_capabilities(instance_id).await?;
+        let before = capabilities.len();
+        capabilities.retain(|existing| existing.capability_id != capability_id);
+        if capabilities.len() == before {
+            return Ok(false);
+        }
+
+        self.push_instance_capabilities(instance_id, &capabilities)
+            .await?;
+        self.repo
+            .set_instance_capabilities(instance_id, capabilities)
+            .await?;
+        Ok(true)
+    }
+
+    /// Replace the grants held by the instance's running copy on its node
+    async fn push_instance_capabilities(
+        &self,
+        instance_id: &str,
+        capabilities: &[CapabilityAssignment],
+    ) -> ControlPlaneResult<()> {
+        let node = self.resolve_target(instance_id).await?;
+        self.push_capabilities_to_node(&node, instance_id, capabilities)
+            .await
+    }
+
+    async fn push_capabilities_to_node(
+        &self,
+        node: &NodeAgentRecord,
+        instance_id: &str,
+        capabilities: &[CapabilityAssignment],
+    ) -> ControlPlaneResult<()> {
+        let mut client = connect_client(&node.node_address, self.tls.as_ref())
+            .await
+            .map_err(ControlPlaneError::Timeout)?;
+
+        let response = match client
+            .set_instance_capabilities(tonic::Request::new(SetInstanceCapabilitiesRequest {
+                instance_id: instance_id.to_string(),
+                capabilities: node_capabilities(instance_id, capabilities),
+            }))
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(error) => {
+                self.record_rpc_error(&node.node_id, &error).await;
+                return Err(ControlPlaneError::Timeout(error.to_string()));
+            }
+        };
+        if !response.success {
+            return Err(ControlPlaneError::WasmRuntimeError(format!(
+                "node {} did not update capabilities of {}: {}",
+                node.node_id, instance_id, response.message
+            )));
+        }
+        Ok(())
+    }
+
+    /// `request` with an unset restart policy replaced by the configured default
+    fn resolve_restart_policy(&self, request: StartInstanceRequest) -> StartInstanceRequest {
+        StartInstanceRequest {
+            restart_policy: Some(
+                request
+                    .restart_policy
+                    .unwrap_or_else(|| self.default_restart_policy.clone()),
+            ),
+            ..request
+        }
+    }
+
+    /// `request` with the instance's stored capability grants added, so a start
+    /// on another node keeps every grant the instance holds
+    async fn with_stored_capabilities(
+        &self,
+        instance_id: &str,
+        request: &StartInstanceRequest,
+    ) -> ControlPlaneResult<StartInstanceRequest> {
+        let mut capabilities = self.repo.get_instance_capabilities(instance_id).await?;
+        for assignment in &request.capabilities {
+            if !capabilities
+                .iter()
+                .any(|stored| stored.capability_id == assignment.capability_id)
+            {
+                capabilities.push(assignment.clone());
+            }
+        }
+        Ok(StartInstanceRequest {
+            capabilities,
+            ..request.clone()
+        })
+    }
+
+    /// Plan up to `max_moves` migrations that even out instance counts across
+    /// available nodes, without executing them.
+    ///
+    /// Moves only go to nodes with spare capacity that offer every provider
+    /// type the instance was started with.
+    pub async fn rebalance(&self, max_moves: usize) -> ControlPlaneResult<Vec<InstanceMove>> {
+        let nodes = self.repo.list_nodes().await?;
+        let assignments = self.repo.list_instance_assignments().await?;
+        Ok(plan_rebalance(nodes, assignments, max_moves))
+    }
+
+    /// Carry out a plan from [`Self::rebalance`], returning the number of moves made.
+    ///
+    /// The control plane does not keep module bytes, so `requests` must hold the
+    /// original start request for every instance in the plan; capability grants
+    /// stored for the instance are sent along with it. Each move starts the
+    /// instance on the new node before stopping it on the old one; if the old copy
+    /// cannot be stopped the new one is stopped again and the error is returned.
+    pub async fn execute_rebalance(
+        &self,
+        plan: &[InstanceMove],
+        requests: &HashMap<String, StartInstanceRequest>,
+    ) -> ControlPlaneResult<usize> {
+        if let Some((instance_id, _, _)) = plan.iter().find(|(id, _, _)| !requests.contains_key(id))
+        {
+            return Err(ControlPlaneError::InvalidRequest(format!(
+                "no start request for instance {}",
+                instance_id
+            )));
+        }
+
+        for (executed, (instance_id, from_node_id, to_node_id)) in plan.iter().enumerate() {
+            if self.validate_capability_instance_ids {
+                check_capability_instance_ids(instance_id, &requests[instance_id].capabilities)?;
+            }
+            let request = self.resolve_restart_policy(
+                self.with_stored_capabilities(instance_id, &requests[instance_id])
+                    .await?,
+            );
+            let current = self.repo.lookup_instance_node(instance_id).await?;
+            if current.as_deref() != Some(from_node_id.as_str()) {
+                return Err(ControlPlaneError::InvalidRequest(format!(
+                    "instance {} is no longer on node {} after {} moves",
+                    instance_id, from_node_id, executed
+                )));
+            }
+
+            let from_node = self.require_node(from_node_id).await?;
+            let to_node = self.require_node(to_node_id).await?;
+            if !can_accept_instance(&to_node)
+                || !node_supports_providers(&to_node, &required_provider_types(&request))
+            {
+                return Err(ControlPlaneError::ResourceExhausted(format!(
+                    "node {} cannot accept instance {}",
+                    to_node_id, instance_id
+                )));
+            }
 
-        let mut client = connect_client(&node.node_address)
+            start_on_node(&to_node, instance_id, &request, self.tls.as_ref()).await?;
+            if let Err(error) = stop_on_node(
+                &from_node,
+                instance_id,
+                Some("rebalance"),
+                &HashMap::new(),
+                self.tls.as_ref(),
+            )
+            .await
+            {
+                if let Err(rollback) = stop_on_node(
+                    &to_node,
+                    instance_id,
+                    Some("rebalance rollback"),
+                    &HashMap::new(),
+                    self.tls.as_ref(),
+                )
+                .await
+                {
+                    warn!(%instance_id, node_id = %to_node_id, error = %rollback, "Failed to roll back rebalance move");
+                }
+                return Err(error);
+            }
+            self.record_reassignment(instance_id, from_node_id, to_node_id)
+                .await?;
+        }
+
+        Ok(plan.len())
+    }
+
+    /// Count a transport failure talking to `node_id`; once
+    /// `node_failure_threshold` happen in a row the node is marked unavailable
+    async fn record_node_failure(&self, node_id: &str) {
+        let failures = {
+            let mut counts = self.node_failures.lock().unwrap_or_else(|e| e.into_inner());
+            let count = counts.entry(node_id.to_string()).or_insert(0);
+            *count += 1;
+            *count
+        };
+        if failures >= self.node_failure_threshold {
+            warn!(%node_id, failures, "Marking node unavailable after consecutive RPC failures");
+            self.reset_node_failures(node_id);
+            let _ = self.repo.set_availability(node_id, false).await;
+        }
+    }
+
+    /// Application errors mean the node answered, so only transport errors count
+    async fn record_rpc_error(&self, node_id: &str, error: &tonic::Status) {
+        if is_transport_error(error) {
+            self.record_node_failure(node_id).await;
+        } else {
+            self.reset_node_failures(node_id);
+        }
+    }
+
+    fn reset_node_failures(&self, node_id: &str) {
+        self.node_failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(node_id);
+    }
+
+    /// The node an instance is assigned to, resolved in one repository read
+    async fn resolve_target(&self, instance_id: &str) -> ControlPlaneResult<NodeAgentRecord> {
+        self.repo
+            .resolve_instance_target(instance_id)
+            .await?
+            .ok_or_else(|| ControlPlaneError::InstanceNotFound(instance_id.to_string()))
+    }
+
+    async fn require_node(&self, node_id: &str) -> ControlPlaneResult<NodeAgentRecord> {
+        self.repo
+            .get_node(node_id)
+            .await?
+            .ok_or_else(|| ControlPlaneError::InstanceNotFound(format!("node {}", node_id)))
+    }
+
+    /// Summarise instance slots across the cluster; unavailable nodes count
+    /// toward `total_nodes` only
+    pub async fn cluster_capacity(&self) -> ControlPlaneResult<ClusterCapacity> {
+        Ok(summarize_capacity(&self.repo.list_nodes().await?))
+    }
+
+    /// Push every node's active instance count to the per-node gauge
+    pub async fn sync_node_instance_metrics(&self) -> ControlPlaneResult<()> {
+        let observability = global_observability_controller();
+        for node in self.repo.list_nodes().await? {
+            observability.set_node_active_instances(&node.node_id, node.active_instances as usize);
+        }
+        Ok(())
+    }
+
+    async fn publish_node_instance_count(&self, node_id: &str) -> ControlPlaneResult<()> {
+        if let Some(node) = self.repo.get_node(node_id).await? {
+            global_observability_controller()
+                .set_node_active_instances(node_id, node.active_instances as usize);
+        }
+        Ok(())
+    }
+
+    /// Restart an instance on its node; the restart request carries its stored
+    /// capability grants so the restarted copy holds every grant the instance has
+    pub async fn route_restart_instance(&self, instance_id: &str) -> ControlPlaneResult<()> {
+        let node = self.resolve_target(instance_id).await?;
+        let capabilities = self.repo.get_instance_capabilities(instance_id).await?;
+
+        let mut client = connect_client(&node.node_address, self.tls.as_ref())
             .await
             .map_err(ControlPlaneError::Timeout)?;
 
         let response = client
-            .stop_instance(tonic::Request::new(StopInstanceRequest {
+            .restart_instance(tonic::Request::new(RestartInstanceRequest {
                 instance_id: instance_id.to_string(),
+                capabilities: node_capabilities(instance_id, &capabilities),
             }))
             .await
             .map_err(|e| ControlPlaneError::Timeout(e.to_string()))?;
 
-        if !response.get_ref().success {
-            return Err(ControlPlaneError::WasmRuntimeError(
-                response.get_ref().message.clone(),
-            ));
+        let response = response.into_inner();
+        if !response.success {
+            return Err(match response.error_code.as_deref() {
+                Some("INSTANCE_NOT_FOUND") => ControlPlaneError::InstanceNotFound(response.message),
+                _ => ControlPlaneError::WasmRuntimeError(response.message),
+            });
         }
 
-        self.repo.remove_instance_assignment(instance_id).await?;
-        self.repo.decrement_active_instances(&node_id).await?;
         Ok(())
     }
 
@@ -225,19 +924,9 @@ impl NodeRoutingService {
         &self,
         request: CoreQueryRequest,
     ) -> ControlPlaneResult<InstanceStatusResponse> {
-        let node_id = self
-            .repo
-            .lookup_instance_node(&request.instance_id)
-            .await?
-            .ok_or_else(|| ControlPlaneError::InstanceNotFound(request.instance_id.clone()))?;
-
-        let node = self
-            .repo
-            .get_node(&node_id)
-            .await?
-            .ok_or_else(|| ControlPlaneError::InstanceNotFound(format!("node {}", node_id)))?;
+        let node = self.resolve_target(&request.instance_id).await?;
 
-        let mut client = connect_client(&node.node_address)
+        let mut client = connect_client(&node.node_address, self.tls.as_ref())
             .await
             .map_err(ControlPlaneError::Timeout)?;
 
@@ -269,7 +958,7 @@ impl NodeRoutingService {
                 ControlPlaneError::ValidationError("invalid instance status".to_string())
             })?;
         let status = wasmatrix_proto::protocol::InstanceStatus::try_from(status_proto)
-            .map_err(ControlPlaneError::ValidationError)?
+            .map_err(ControlPlaneError::from)?
             .into();
         let created_at = unix_to_utc(meta.created_at).ok_or_else(|| {
             ControlPlaneError::ValidationError("invalid created_at timestamp".to_string())
@@ -283,16 +972,26 @@ impl NodeRoutingService {
         })
     }
 
+    /// Instances across all reachable nodes running the module with `hash`
+    pub async fn route_list_instances_by_module_hash(
+        &self,
+        hash: &str,
+    ) -> ControlPlaneResult<Vec<InstanceMetadata>> {
+        let mut instances = self.route_list_instances().await?;
+        instances.retain(|metadata| module_hashes_match(&metadata.module_hash, hash));
+        Ok(instances)
+    }
+
     pub async fn route_list_instances(&self) -> ControlPlaneResult<Vec<InstanceMetadata>> {
         let nodes = self.repo.list_nodes().await?;
         let mut all_instances = Vec::new();
 
         for node in nodes {
-            let mut client = match connect_client(&node.node_address).await {
+            let mut client = match connect_client(&node.node_address, self.tls.as_ref()).await {
                 Ok(client) => client,
                 Err(error) => {
                     warn!(node_id = %node.node_id, error = %error, "Skipping unavailable node during list");
-                    let _ = self.repo.set_availability(&node.node_id, false).await;
+                    self.record_node_failure(&node.node_id).await;
                     continue;
                 }
             };
@@ -301,10 +1000,13 @@ impl NodeRoutingService {
                 .list_instances(tonic::Request::new(ListInstancesRequest {}))
                 .await
             {
-                Ok(response) => response,
+                Ok(response) => {
+                    self.reset_node_failures(&node.node_id);
+                    response
+                }
                 Err(error) => {
                     warn!(node_id = %node.node_id, error = %error, "ListInstances failed for node");
-                    let _ = self.repo.set_availability(&node.node_id, false).await;
+                    self.record_rpc_error(&node.node_id, &error).await;
                     continue;
                 }
             };
@@ -335,6 +1037,9 @@ impl NodeRoutingService {
                     module_hash: meta.module_hash.clone(),
                     created_at,
                     status: status.into(),
+                    deployment_id: None,
+                    labels: Default::default(),
+                    updated_at: None,
                 });
             }
         }
@@ -371,6 +1076,13 @@ impl NodeRoutingService {
                     ))
                 })?;
 
+        if assignment.is_expired() {
+            return Err(ControlPlaneError::PermissionDenied(format!(
+                "capability assignment '{}' for instance '{}' has expired",
+                assignment.capability_id, instance_id
+            )));
+        }
+
         if !assignment.has_permission(required_permission) {
             return Err(ControlPlaneError::PermissionDenied(format!(
                 "instance '{}' lacks required permission '{}'",
@@ -378,10 +1090,10 @@ impl NodeRoutingService {
             )));
         }
 
-        let providers = self.repo.list_provider_metadata().await?;
-        let provider = providers
-            .into_iter()
-            .find(|p| p.provider_id == assignment.capability_id)
+        let provider = self
+            .repo
+            .get_provider_metadata(&assignment.capability_id)
+            .await?
             .ok_or_else(|| {
                 ControlPlaneError::CapabilityNotFound(format!(
                     "provider '{}' metadata not found",
@@ -405,7 +1117,7 @@ impl NodeRoutingService {
                 ControlPlaneError::InstanceNotFound(format!("node {}", provider.node_id))
             })?;
 
-        let mut client = connect_client(&node.node_address)
+        let mut client = connect_client(&node.node_address, self.tls.as_ref())
             .await
             .map_err(ControlPlaneError::Timeout)?;
 
@@ -418,7 +1130,7 @@ impl NodeRoutingService {
                 ) as i32,
                 operation: operation.to_string(),
                 params_json: params.to_string(),
-                permissions: assignment.permissions.clone(),
+                permissions: assignment.permissions().to_vec(),
             }))
             .await
             .map_err(|e| ControlPlaneError::Timeout(e.to_string()))?;
@@ -450,14 +1162,26 @@ impl NodeRoutingService {
             .await?
             .ok_or_else(|| ControlPlaneError::InstanceNotFound(format!("node {}", node_id)))?;
 
-        let mut client = connect_client(&node.node_address)
-            .await
-            .map_err(ControlPlaneError::Timeout)?;
+        let deadline = Some(tokio::time::Instant::now() + self.recovery_timeout);
+        let Some(connected) = within(
+            deadline,
+            connect_client(&node.node_address, self.tls.as_ref()),
+        )
+        .await
+        else {
+            return Err(self.recovery_timed_out(node_id).await);
+        };
+        let mut client = connected.map_err(ControlPlaneError::Timeout)?;
 
-        let response = client
-            .list_instances(tonic::Request::new(ListInstancesRequest {}))
-            .await
-            .map_err(|e| ControlPlaneError::Timeout(e.to_string()))?;
+        let Some(response) = within(
+            deadline,
+            client.list_instances(tonic::Request::new(ListInstancesRequest {})),
+        )
+        .await
+        else {
+            return Err(self.recovery_timed_out(node_id).await);
+        };
+        let response = response.map_err(|e| ControlPlaneError::Timeout(e.to_string()))?;
 
         if !response.get_ref().success {
             return Err(ControlPlaneError::WasmRuntimeError(
@@ -469,6 +1193,36 @@ impl NodeRoutingService {
             .await
     }
 
+    /// Recover every registered node concurrently, so a hung node only costs
+    /// the recovery timeout rather than stalling the others
+    pub async fn recover_all_nodes(
+        &self,
+        control_plane: &Mutex<ControlPlane>,
+    ) -> ControlPlaneResult<Vec<(String, ControlPlaneResult<usize>)>> {
+        let nodes = self.repo.list_nodes().await?;
+        let recoveries = nodes.into_iter().map(|node| async move {
+            let result = self.recover_node_state(&node.node_id, control_plane).await;
+            (node.node_id, result)
+        });
+        Ok(futures::future::join_all(recoveries).await)
+    }
+
+    /// Mark a node that did not answer in time unavailable and build the error
+    async fn recovery_timed_out(&self, node_id: &str) -> ControlPlaneError {
+        warn!(
+            %node_id,
+            timeout_ms = self.recovery_timeout.as_millis() as u64,
+            "Node did not answer recovery in time; skipping it"
+        );
+        if let Err(error) = self.repo.set_availability(node_id, false).await {
+            warn!(%node_id, %error, "Failed to mark node unavailable");
+        }
+        ControlPlaneError::Timeout(format!(
+            "recovery of node {node_id} timed out after {:?}",
+            self.recovery_timeout
+        ))
+    }
+
     async fn apply_recovered_instances(
         &self,
         node_id: &str,
@@ -485,7 +1239,7 @@ impl NodeRoutingService {
                 })?;
             let status: wasmatrix_core::InstanceStatus =
                 wasmatrix_proto::protocol::InstanceStatus::try_from(status_proto)
-                    .map_err(ControlPlaneError::ValidationError)?
+                    .map_err(ControlPlaneError::from)?
                     .into();
             let created_at = unix_to_utc(meta.created_at).ok_or_else(|| {
                 ControlPlaneError::ValidationError("invalid created_at timestamp".to_string())
@@ -498,19 +1252,38 @@ impl NodeRoutingService {
                 active_count = active_count.saturating_add(1);
             }
 
+            let capabilities = meta
+                .capabilities
+                .into_iter()
+                .map(|cap| {
+                    wasmatrix_proto::protocol::CapabilityAssignment::try_from(cap)
+                        .map(|cap| {
+                            // Keep expiry and grant state; only the owner is
+                            // taken from the recovered instance
+                            let mut cap: wasmatrix_core::CapabilityAssignment = cap.into();
+                            cap.instance_id = meta.instance_id.clone();
+                            cap
+                        })
+                        .map_err(ControlPlaneError::from)
+                })
+                .collect::<ControlPlaneResult<Vec<_>>>()?;
+
             let metadata = wasmatrix_core::InstanceMetadata {
                 instance_id: meta.instance_id.clone(),
                 node_id: meta.node_id,
                 module_hash: meta.module_hash,
                 created_at,
                 status,
+                deployment_id: None,
+                labels: Default::default(),
+                updated_at: None,
             };
 
             {
                 let mut cp = control_plane.lock().map_err(|_| {
                     ControlPlaneError::StorageError("control plane lock poisoned".to_string())
                 })?;
-                cp.restore_instance_state(metadata, vec![]);
+                cp.restore_instance_state(metadata, capabilities);
             }
 
             self.repo
@@ -522,37 +1295,249 @@ impl NodeRoutingService {
         self.repo
             .set_active_instances(node_id, active_count)
             .await?;
+        self.publish_node_instance_count(node_id).await?;
         Ok(recovered)
     }
 }
 
 fn can_accept_instance(node: &NodeAgentRecord) -> bool {
-    node.available && (node.max_instances == 0 || node.active_instances < node.max_instances)
+    node.available
+        && node
+            .max_instances
+            .is_none_or(|max| node.active_instances < max)
+}
+
+fn summarize_capacity(nodes: &[NodeAgentRecord]) -> ClusterCapacity {
+    let available: Vec<&NodeAgentRecord> = nodes.iter().filter(|node| node.available).collect();
+    ClusterCapacity {
+        total_slots: available
+            .iter()
+            .map(|node| node.max_instances.map(u64::from))
+            .sum(),
+        used_slots: available
+            .iter()
+            .map(|node| u64::from(node.active_instances))
+            .sum(),
+        available_nodes: available.len() as u32,
+        total_nodes: nodes.len() as u32,
+    }
 }
 
 fn node_supports_required_providers(
     node: &NodeAgentRecord,
     request: &StartInstanceRequest,
 ) -> bool {
-    let required = required_provider_types(request);
+    node_supports_providers(node, &required_provider_types(request))
+}
+
+fn node_supports_providers(node: &NodeAgentRecord, required: &[String]) -> bool {
     if required.is_empty() || node.capabilities.is_empty() {
         return true;
     }
 
     required
-        .into_iter()
-        .all(|provider| node.capabilities.iter().any(|cap| cap == &provider))
+        .iter()
+        .all(|provider| node.capabilities.iter().any(|cap| cap == provider))
+}
+
+/// Required provider types that no registered node offers; empty when there
+/// are no nodes at all, since then the problem is capacity, not providers
+fn missing_provider_types(nodes: &[NodeAgentRecord], required: &[String]) -> Vec<String> {
+    if nodes.is_empty() {
+        return Vec::new();
+    }
+    required
+        .iter()
+        .filter(|provider| {
+            !nodes
+                .iter()
+                .any(|node| node_supports_providers(node, std::slice::from_ref(provider)))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Greedily move one instance at a time from the busiest available node to the
+/// least busy one that can take it, until loads differ by at most one.
+fn plan_rebalance(
+    nodes: Vec<NodeAgentRecord>,
+    mut assignments: Vec<InstanceAssignment>,
+    max_moves: usize,
+) -> Vec<InstanceMove> {
+    let mut nodes: Vec<NodeAgentRecord> = nodes.into_iter().filter(|n| n.available).collect();
+    assignments.sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+    let mut moves = Vec::new();
+
+    while moves.len() < max_moves {
+        nodes.sort_by(|a, b| {
+            a.active_instances
+                .cmp(&b.active_instances)
+                .then_with(|| a.node_id.cmp(&b.node_id))
+        });
+
+        let next = nodes.iter().enumerate().rev().find_map(|(source, from)| {
+            nodes
+                .iter()
+                .enumerate()
+                .take_while(|(_, to)| to.active_instances + 1 < from.active_instances)
+                .filter(|(_, to)| can_accept_instance(to))
+                .find_map(|(target, to)| {
+                    assignments
+                        .iter()
+                        .position(|a| {
+                            a.node_id == from.node_id
+                                && node_supports_providers(to, &a.required_providers)
+                        })
+                        .map(|assignment| (assignment, source, target))
+                })
+        });
+        let Some((assignment, source, target)) = next else {
+            break;
+        };
+
+        let assignment = assignments.remove(assignment);
+        nodes[source].active_instances -= 1;
+        nodes[target].active_instances += 1;
+        moves.push((
+            assignment.instance_id,
+            nodes[source].node_id.clone(),
+            nodes[target].node_id.clone(),
+        ));
+    }
+
+    moves
 }
 
+/// Fail with `InvalidRequest` if a capability names an instance other than
+/// `instance_id`; an empty `instance_id` is left for the caller to stamp
+fn check_capability_instance_ids(
+    instance_id: &str,
+    capabilities: &[CapabilityAssignment],
+) -> ControlPlaneResult<()> {
+    match capabilities
+        .iter()
+        .find(|cap| !cap.instance_id.is_empty() && cap.instance_id != instance_id)
+    {
+        Some(cap) => Err(ControlPlaneError::InvalidRequest(format!(
+            "capability {} is assigned to instance {}, not {}",
+            cap.capability_id, cap.instance_id, instance_id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The grants a node should hold for `instance_id`. Pending and revoked
+/// grants stay on the control plane until approved.
+fn node_capabilities(
+    instance_id: &str,
+    capabilities: &[CapabilityAssignment],
+) -> Vec<ProtoCapabilityAssignment> {
+    capabilities
+        .iter()
+        .filter(|cap| cap.is_active())
+        .map(|cap| {
+            wasmatrix_proto::protocol::CapabilityAssignment {
+                instance_id: instance_id.to_string(),
+                capability_id: cap.capability_id.clone(),
+                provider_type: cap.provider_type.into(),
+                permissions: cap.permissions().to_vec(),
+                expires_at: cap.expires_at.map(|at| at.timestamp()),
+                state: cap.state.into(),
+            }
+            .into()
+        })
+        .collect()
+}
+
+fn proto_start_request(
+    instance_id: &str,
+    request: &StartInstanceRequest,
+) -> ProtoStartInstanceRequest {
+    ProtoStartInstanceRequest {
+        instance_id: instance_id.to_string(),
+        module_bytes: request.module_bytes.clone(),
+        capabilities: node_capabilities(instance_id, &request.capabilities),
+        restart_policy: Some(
+            wasmatrix_proto::protocol::RestartPolicy::from(
+                request.restart_policy.clone().unwrap_or_default(),
+            )
+            .into(),
+        ),
+        env: request
+            .env
+            .iter()
+            .map(|(key, value)| wasmatrix_proto::v1::EnvVar {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect(),
+        args: request.args.clone(),
+        annotations: request.annotations.clone(),
+    }
+}
+
+async fn start_on_node(
+    node: &NodeAgentRecord,
+    instance_id: &str,
+    request: &StartInstanceRequest,
+    tls: Option<&TlsConfig>,
+) -> ControlPlaneResult<()> {
+    let mut client = connect_client(&node.node_address, tls)
+        .await
+        .map_err(ControlPlaneError::Timeout)?;
+
+    let response = client
+        .start_instance(tonic::Request::new(proto_start_request(
+            instance_id,
+            request,
+        )))
+        .await
+        .map_err(|e| ControlPlaneError::Timeout(e.to_string()))?;
+
+    if !response.get_ref().success {
+        return Err(ControlPlaneError::WasmRuntimeError(
+            response.get_ref().message.clone(),
+        ));
+    }
+    Ok(())
+}
+
+async fn stop_on_node(
+    node: &NodeAgentRecord,
+    instance_id: &str,
+    reason: Option<&str>,
+    annotations: &HashMap<String, String>,
+    tls: Option<&TlsConfig>,
+) -> ControlPlaneResult<()> {
+    let mut client = connect_client(&node.node_address, tls)
+        .await
+        .map_err(ControlPlaneError::Timeout)?;
+
+    let response = client
+        .stop_instance(tonic::Request::new(StopInstanceRequest {
+            instance_id: instance_id.to_string(),
+            reason: reason.map(str::to_string),
+            annotations: annotations.clone(),
+        }))
+        .await
+        .map_err(|e| ControlPlaneError::Timeout(e.to_string()))?;
+
+    if !response.get_ref().success {
+        return Err(ControlPlaneError::WasmRuntimeError(
+            response.get_ref().message.clone(),
+        ));
+    }
+    Ok(())
+}
+
+/// Provider types a node must offer to host the instance; introspection is
+/// built into every agent and never narrows placement
 fn required_provider_types(request: &StartInstanceRequest) -> Vec<String> {
     let mut providers: Vec<String> = request
         .capabilities
         .iter()
-        .map(|cap| match cap.provider_type {
-            wasmatrix_core::ProviderType::Kv => "kv".to_string(),
-            wasmatrix_core::ProviderType::Http => "http".to_string(),
-            wasmatrix_core::ProviderType::Messaging => "messaging".to_string(),
-        })
+        .filter(|cap| cap.provider_type != wasmatrix_core::ProviderType::Introspection)
+        .map(|cap| provider_type_to_string(cap.provider_type).to_string())
         .collect();
     providers.sort();
     providers.dedup();
@@ -564,87 +1549,708 @@ fn provider_type_to_string(provider_type: wasmatrix_core::ProviderType) -> &'sta
         wasmatrix_core::ProviderType::Kv => "kv",
         wasmatrix_core::ProviderType::Http => "http",
         wasmatrix_core::ProviderType::Messaging => "messaging",
+        wasmatrix_core::ProviderType::Introspection => "introspection",
     }
 }
 
+/// Order eligible nodes for placement.
+///
+/// Nodes are sorted by score, then by `node_id`, so the result never depends
+/// on repository iteration order. The score is `active_instances`, plus, with
+/// `reserve_specialized`, one for every provider the node offers that the
+/// instance does not need and that some other eligible node lacks. Each group
+/// of equally-scored nodes is then rotated left by `rotation`, which spreads
+/// consecutive placements round-robin across nodes with the same score.
 fn select_candidate_nodes(
     mut nodes: Vec<NodeAgentRecord>,
     request: &StartInstanceRequest,
+    rotation: usize,
+    reserve_specialized: bool,
 ) -> Vec<NodeAgentRecord> {
     nodes.retain(|node| {
         can_accept_instance(node) && node_supports_required_providers(node, request)
     });
-    nodes.sort_by_key(|n| n.active_instances);
+    let penalties = if reserve_specialized {
+        specialization_penalties(&nodes, &required_provider_types(request))
+    } else {
+        HashMap::new()
+    };
+    let score = |node: &NodeAgentRecord| {
+        u64::from(node.active_instances) + penalties.get(&node.node_id).copied().unwrap_or(0)
+    };
+    nodes.sort_by(|a, b| {
+        score(a)
+            .cmp(&score(b))
+            .then_with(|| a.node_id.cmp(&b.node_id))
+    });
+
+    let mut start = 0;
+    while start < nodes.len() {
+        let group = score(&nodes[start]);
+        let len = nodes[start..]
+            .iter()
+            .take_while(|n| score(n) == group)
+            .count();
+        nodes[start..start + len].rotate_left(rotation % len);
+        start += len;
+    }
     nodes
 }
 
-fn normalize_endpoint(address: &str) -> String {
-    if address.starts_with("http://") || address.starts_with("https://") {
-        address.to_string()
-    } else {
-        format!("http://{}", address)
+/// Per node, how many providers it offers beyond `required` that not every
+/// eligible node offers; placing there uses up scarce capacity
+fn specialization_penalties(
+    nodes: &[NodeAgentRecord],
+    required: &[String],
+) -> HashMap<String, u64> {
+    let mut offered_by: HashMap<&str, usize> = HashMap::new();
+    for node in nodes {
+        for provider in &node.capabilities {
+            *offered_by.entry(provider.as_str()).or_default() += 1;
+        }
+    }
+    nodes
+        .iter()
+        .map(|node| {
+            let scarce = node
+                .capabilities
+                .iter()
+                .filter(|provider| !required.contains(provider))
+                .filter(|provider| offered_by[provider.as_str()] < nodes.len())
+                .count();
+            (node.node_id.clone(), scarce as u64)
+        })
+        .collect()
+}
+
+/// Order eligible nodes by smooth weighted round-robin.
+///
+/// Each eligible node's running weight grows by its `weight`; the node with
+/// the highest running weight (ties broken by `node_id`) is placed first and
+/// has the total eligible weight subtracted from its running weight. Over many
+/// calls each node comes first in proportion to its weight, interleaved rather
+/// than in bursts. The remaining nodes follow as fallbacks.
+fn select_weighted_candidate_nodes(
+    mut nodes: Vec<NodeAgentRecord>,
+    request: &StartInstanceRequest,
+    running: &mut HashMap<String, i64>,
+) -> Vec<NodeAgentRecord> {
+    nodes.retain(|node| {
+        can_accept_instance(node) && node_supports_required_providers(node, request)
+    });
+
+    let mut total = 0;
+    for node in &nodes {
+        let weight = i64::from(node.weight.max(1));
+        *running.entry(node.node_id.clone()).or_insert(0) += weight;
+        total += weight;
+    }
+    nodes.sort_by(|a, b| {
+        running[&b.node_id]
+            .cmp(&running[&a.node_id])
+            .then_with(|| a.node_id.cmp(&b.node_id))
+    });
+    if let Some(first) = nodes.first() {
+        if let Some(weight) = running.get_mut(&first.node_id) {
+            *weight -= total;
+        }
+    }
+    nodes
+}
+
+/// Move the preferred node to the front of `candidates`.
+///
+/// A preferred node missing from `candidates` is unavailable, full or lacks a
+/// required provider; that falls back to the normal order unless `require_node`
+/// is set. With `require_node` only the preferred node is ever tried.
+fn apply_node_preference(
+    mut candidates: Vec<NodeAgentRecord>,
+    preferred_node_id: Option<&str>,
+    require_node: bool,
+) -> ControlPlaneResult<Vec<NodeAgentRecord>> {
+    let Some(preferred_node_id) = preferred_node_id else {
+        if require_node {
+            return Err(ControlPlaneError::InvalidRequest(
+                "require_node is set without preferred_node_id".to_string(),
+            ));
+        }
+        return Ok(candidates);
+    };
+
+    match candidates
+        .iter()
+        .position(|node| node.node_id == preferred_node_id)
+    {
+        Some(index) if require_node => Ok(vec![candidates.swap_remove(index)]),
+        Some(index) => {
+            candidates[..=index].rotate_right(1);
+            Ok(candidates)
+        }
+        None if require_node => Err(ControlPlaneError::ResourceExhausted(format!(
+            "Required node {} cannot accept the instance",
+            preferred_node_id
+        ))),
+        None => Ok(candidates),
+    }
+}
+
+/// Whether an RPC failed to reach the node rather than being rejected by it
+fn is_transport_error(status: &tonic::Status) -> bool {
+    matches!(
+        status.code(),
+        tonic::Code::Unavailable | tonic::Code::DeadlineExceeded | tonic::Code::Cancelled
+    )
+}
+
+fn normalize_endpoint(address: &str) -> String {
+    if address.starts_with("http://") || address.starts_with("https://") {
+        address.to_string()
+    } else {
+        format!("http://{}", address)
+    }
+}
+
+fn unix_to_utc(ts: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(ts, 0).single()
+}
+
+/// Run `future` to completion, or until `deadline` if one is set
+async fn within<F: std::future::Future>(
+    deadline: Option<tokio::time::Instant>,
+    future: F,
+) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+async fn connect_client(
+    address: &str,
+    tls: Option<&TlsConfig>,
+) -> Result<NodeAgentServiceClient<Channel>, String> {
+    let channel = tls::endpoint(address, tls)
+        .map_err(|e| e.to_string())?
+        .connect()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(NodeAgentServiceClient::new(channel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::node_routing::repo::etcd::EtcdMetadataRepository;
+    use crate::features::node_routing::repo::InMemoryNodeRoutingRepository;
+    use crate::shared::types::RestartPolicy;
+    use wasmatrix_core::{CapabilityAssignment, ProviderType};
+
+    fn assignment(
+        instance_id: &str,
+        capability_id: &str,
+        provider_type: ProviderType,
+        permissions: Vec<&str>,
+    ) -> CapabilityAssignment {
+        CapabilityAssignment::new(
+            instance_id.to_string(),
+            capability_id.to_string(),
+            provider_type,
+            permissions.into_iter().map(|p| p.to_string()).collect(),
+        )
+    }
+
+    fn node_with_capacity(max_instances: Option<u32>, active_instances: u32) -> NodeAgentRecord {
+        NodeAgentRecord {
+            node_id: "node-cap".to_string(),
+            node_address: "http://127.0.0.1:50099".to_string(),
+            capabilities: vec![],
+            max_instances,
+            weight: 1,
+            active_instances,
+            last_heartbeat: Some(Utc::now()),
+            available: true,
+        }
+    }
+
+    #[test]
+    fn test_can_accept_instance_max_instances_semantics() {
+        assert!(can_accept_instance(&node_with_capacity(None, 0)));
+        assert!(can_accept_instance(&node_with_capacity(None, 10_000)));
+
+        assert!(!can_accept_instance(&node_with_capacity(Some(0), 0)));
+
+        assert!(can_accept_instance(&node_with_capacity(Some(2), 0)));
+        assert!(can_accept_instance(&node_with_capacity(Some(2), 1)));
+        assert!(!can_accept_instance(&node_with_capacity(Some(2), 2)));
+    }
+
+    #[tokio::test]
+    async fn test_record_reassignment_moves_assignment_and_counts() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone());
+        repo.upsert_node(NodeAgentRecord {
+            node_id: "reassign-dead".to_string(),
+            available: false,
+            ..node_with_capacity(Some(5), 1)
+        })
+        .await
+        .unwrap();
+        repo.upsert_node(NodeAgentRecord {
+            node_id: "reassign-healthy".to_string(),
+            ..node_with_capacity(Some(5), 0)
+        })
+        .await
+        .unwrap();
+        repo.assign_instance("instance-1".to_string(), "reassign-dead".to_string())
+            .await
+            .unwrap();
+
+        service
+            .record_reassignment("instance-1", "reassign-dead", "reassign-healthy")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            service.locate_instance("instance-1").await.unwrap(),
+            "reassign-healthy"
+        );
+        let active = |node: Option<NodeAgentRecord>| node.unwrap().active_instances;
+        assert_eq!(active(repo.get_node("reassign-dead").await.unwrap()), 0);
+        assert_eq!(active(repo.get_node("reassign-healthy").await.unwrap()), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reassign_instance_rejects_full_target() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone());
+        repo.upsert_node(NodeAgentRecord {
+            node_id: "reassign-source".to_string(),
+            ..node_with_capacity(Some(5), 1)
+        })
+        .await
+        .unwrap();
+        repo.upsert_node(NodeAgentRecord {
+            node_id: "reassign-full".to_string(),
+            ..node_with_capacity(Some(1), 1)
+        })
+        .await
+        .unwrap();
+        repo.assign_instance("instance-1".to_string(), "reassign-source".to_string())
+            .await
+            .unwrap();
+
+        let request = StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+
+        let result = service
+            .reassign_instance("instance-1", "reassign-full", &request)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(ControlPlaneError::ResourceExhausted(_))
+        ));
+        assert_eq!(
+            service.locate_instance("instance-1").await.unwrap(),
+            "reassign-source"
+        );
+        assert!(service
+            .reassign_instance("instance-1", "missing-node", &request)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_summarize_capacity_counts_available_nodes_only() {
+        let node = |node_id: &str, max_instances, active_instances, available| NodeAgentRecord {
+            node_id: node_id.to_string(),
+            available,
+            ..node_with_capacity(max_instances, active_instances)
+        };
+        let mut nodes = vec![
+            node("node-a", Some(10), 4, true),
+            node("node-b", Some(5), 5, true),
+            node("node-down", Some(8), 3, false),
+        ];
+
+        assert_eq!(
+            summarize_capacity(&nodes),
+            ClusterCapacity {
+                total_slots: Some(15),
+                used_slots: 9,
+                available_nodes: 2,
+                total_nodes: 3,
+            }
+        );
+
+        nodes.push(node("node-unlimited", None, 2, true));
+        nodes.push(node("node-unlimited-down", None, 0, false));
+        assert_eq!(
+            summarize_capacity(&nodes),
+            ClusterCapacity {
+                total_slots: None,
+                used_slots: 11,
+                available_nodes: 3,
+                total_nodes: 5,
+            }
+        );
+        assert_eq!(
+            summarize_capacity(&[]),
+            ClusterCapacity {
+                total_slots: Some(0),
+                used_slots: 0,
+                available_nodes: 0,
+                total_nodes: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rebalance_plans_moves_onto_empty_node() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone());
+        repo.upsert_node(NodeAgentRecord {
+            node_id: "rebalance-busy".to_string(),
+            ..node_with_capacity(None, 4)
+        })
+        .await
+        .unwrap();
+        repo.upsert_node(NodeAgentRecord {
+            node_id: "rebalance-empty".to_string(),
+            capabilities: vec!["http".to_string()],
+            ..node_with_capacity(Some(10), 0)
+        })
+        .await
+        .unwrap();
+        for instance_id in ["i-0", "i-1", "i-2", "i-3"] {
+            repo.assign_instance(instance_id.to_string(), "rebalance-busy".to_string())
+                .await
+                .unwrap();
+        }
+        // The empty node has no KV provider, so i-0 must stay put
+        repo.set_required_providers("i-0", vec!["kv".to_string()])
+            .await
+            .unwrap();
+
+        let plan = service.rebalance(10).await.unwrap();
+
+        let moved = |id: &str| {
+            (
+                id.to_string(),
+                "rebalance-busy".to_string(),
+                "rebalance-empty".to_string(),
+            )
+        };
+        assert_eq!(plan, vec![moved("i-1"), moved("i-2")]);
+        assert_eq!(service.rebalance(1).await.unwrap(), vec![moved("i-1")]);
+        // Planning does not move anything
+        assert_eq!(
+            service.locate_instance("i-1").await.unwrap(),
+            "rebalance-busy"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_node_instance_gauge_reflects_per_node_counts() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone());
+        for node_id in ["gauge-node-a", "gauge-node-b"] {
+            repo.upsert_node(NodeAgentRecord {
+                node_id: node_id.to_string(),
+                ..node_with_capacity(None, 0)
+            })
+            .await
+            .unwrap();
+        }
+
+        for (instance_id, node_id) in [
+            ("gauge-inst-1", "gauge-node-a"),
+            ("gauge-inst-2", "gauge-node-a"),
+            ("gauge-inst-3", "gauge-node-b"),
+        ] {
+            repo.assign_instance(instance_id.to_string(), node_id.to_string())
+                .await
+                .unwrap();
+            repo.increment_active_instances(node_id).await.unwrap();
+        }
+        service.sync_node_instance_metrics().await.unwrap();
+
+        let rendered = global_observability_controller().render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_node_active_instances{node_id=\"gauge-node-a\"} 2"));
+        assert!(rendered.contains("wasmatrix_node_active_instances{node_id=\"gauge-node-b\"} 1"));
+
+        repo.decrement_active_instances("gauge-node-a")
+            .await
+            .unwrap();
+        service
+            .publish_node_instance_count("gauge-node-a")
+            .await
+            .unwrap();
+        let rendered = global_observability_controller().render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_node_active_instances{node_id=\"gauge-node-a\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn test_persistence_status_reports_disabled_and_healthy_etcd() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let disabled = NodeRoutingService::new(repo.clone())
+            .persistence_status()
+            .await;
+        assert!(!disabled.enabled);
+        assert!(disabled.last_error.is_none());
+
+        let enabled =
+            NodeRoutingService::new_with_etcd(repo, Arc::new(EtcdMetadataRepository::new()))
+                .persistence_status()
+                .await;
+        assert!(enabled.enabled);
+        assert!(enabled.healthy);
+    }
+
+    #[tokio::test]
+    async fn test_start_route_without_nodes() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo);
+
+        let result = service
+            .route_start_instance(StartInstanceRequest {
+                module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_check_capability_instance_ids() {
+        let capability = |instance_id: &str| {
+            CapabilityAssignment::new(
+                instance_id.to_string(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )
+        };
+
+        assert!(check_capability_instance_ids("inst-1", &[capability("inst-1")]).is_ok());
+        assert!(check_capability_instance_ids("inst-1", &[capability("")]).is_ok());
+        let mismatch =
+            check_capability_instance_ids("inst-1", &[capability(""), capability("inst-2")]);
+        assert!(matches!(
+            mismatch,
+            Err(ControlPlaneError::InvalidRequest(message)) if message.contains("inst-2")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_start_route_rejects_mismatched_capability_instance_id() {
+        let request = |instance_id: &str| StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities: vec![CapabilityAssignment::new(
+                instance_id.to_string(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let service = NodeRoutingService::new(Arc::new(InMemoryNodeRoutingRepository::new()))
+            .with_capability_instance_id_validation(true);
+
+        let mismatched = service.route_start_instance(request("other")).await;
+        assert!(matches!(
+            mismatched,
+            Err(ControlPlaneError::InvalidRequest(_))
+        ));
+        // Empty ids pass validation and fail later only for want of a node
+        let empty = service.route_start_instance(request("")).await;
+        assert!(!matches!(empty, Err(ControlPlaneError::InvalidRequest(_))));
+
+        let unchecked = NodeRoutingService::new(Arc::new(InMemoryNodeRoutingRepository::new()))
+            .route_start_instance(request("other"))
+            .await;
+        assert!(!matches!(
+            unchecked,
+            Err(ControlPlaneError::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_start_route_names_provider_no_node_offers() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo);
+        for node_id in ["node-kv-1", "node-kv-2"] {
+            service
+                .register_node(
+                    node_id.to_string(),
+                    "127.0.0.1:65098".to_string(),
+                    vec!["kv".to_string()],
+                    Some(10),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let result = service
+            .route_start_instance(StartInstanceRequest {
+                module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+                capabilities: vec![wasmatrix_core::CapabilityAssignment::new(
+                    "i-1".to_string(),
+                    "http-1".to_string(),
+                    wasmatrix_core::ProviderType::Http,
+                    vec!["http:request".to_string()],
+                )],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await;
+
+        match result {
+            Err(ControlPlaneError::CapabilityNotFound(message)) => {
+                assert!(message.contains("http"), "{message}")
+            }
+            other => panic!("expected missing provider error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_rate_limit_rejects_overflow_until_refill() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo).with_start_rate_limit(20, 2);
+        let request = || StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let rate_limited = |result: &ControlPlaneResult<String>| matches!(result, Err(ControlPlaneError::ResourceExhausted(message)) if message == "start rate exceeded");
+
+        // No nodes are registered, so admitted starts fail later in placement
+        let mut results = Vec::new();
+        for _ in 0..5 {
+            results.push(service.route_start_instance(request()).await);
+        }
+        assert_eq!(results.iter().filter(|r| !rate_limited(r)).count(), 2);
+        assert!(results[2..].iter().all(rate_limited));
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert!(!rate_limited(
+            &service.route_start_instance(request()).await
+        ));
     }
-}
 
-fn unix_to_utc(ts: i64) -> Option<DateTime<Utc>> {
-    Utc.timestamp_opt(ts, 0).single()
-}
+    #[tokio::test]
+    async fn test_start_deadline_bounds_placement_across_hung_nodes() {
+        // Accepts TCP connections but never answers, so every RPC hangs
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
 
-async fn connect_client(address: &str) -> Result<NodeAgentServiceClient<Channel>, String> {
-    NodeAgentServiceClient::connect(address.to_string())
-        .await
-        .map_err(|e| e.to_string())
-}
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo).with_start_deadline(Duration::from_millis(300));
+        for node_id in ["hung-1", "hung-2", "hung-3"] {
+            service
+                .register_node(node_id.to_string(), address.clone(), vec![], Some(10), None)
+                .await
+                .unwrap();
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::features::node_routing::repo::etcd::EtcdMetadataRepository;
-    use crate::features::node_routing::repo::InMemoryNodeRoutingRepository;
-    use crate::shared::types::RestartPolicy;
-    use wasmatrix_core::{CapabilityAssignment, ProviderType};
+        let started = Instant::now();
+        let result = service
+            .route_start_instance(StartInstanceRequest {
+                module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await;
+        let elapsed = started.elapsed();
 
-    fn assignment(
-        instance_id: &str,
-        capability_id: &str,
-        provider_type: ProviderType,
-        permissions: Vec<&str>,
-    ) -> CapabilityAssignment {
-        CapabilityAssignment::new(
-            instance_id.to_string(),
-            capability_id.to_string(),
-            provider_type,
-            permissions.into_iter().map(|p| p.to_string()).collect(),
-        )
+        assert!(matches!(result, Err(ControlPlaneError::Timeout(_))));
+        assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
     }
 
     #[tokio::test]
-    async fn test_start_route_without_nodes() {
+    async fn test_start_route_rejects_unsigned_module_when_verification_enabled() {
+        use crate::features::module_verification::service::Ed25519ModuleVerifier;
+
         let repo = Arc::new(InMemoryNodeRoutingRepository::new());
-        let service = NodeRoutingService::new(repo);
+        let key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+        let service = NodeRoutingService::new(repo).with_module_verifier(Arc::new(
+            Ed25519ModuleVerifier::new(vec![key.verifying_key()]),
+        ));
 
         let result = service
             .route_start_instance(StartInstanceRequest {
                 module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
                 capabilities: vec![],
-                restart_policy: RestartPolicy::default(),
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
             })
             .await;
 
-        assert!(result.is_err());
+        assert!(matches!(
+            result,
+            Err(ControlPlaneError::PermissionDenied(_))
+        ));
     }
 
     #[tokio::test]
     async fn test_start_route_node_unavailable() {
         let repo = Arc::new(InMemoryNodeRoutingRepository::new());
-        let service = NodeRoutingService::new(repo.clone());
+        let service = NodeRoutingService::new(repo.clone()).with_node_failure_threshold(1);
 
         service
             .register_node(
                 "node-1".to_string(),
                 "127.0.0.1:65099".to_string(),
                 vec![],
-                10,
+                Some(10),
+                None,
             )
             .await
             .unwrap();
@@ -653,7 +2259,13 @@ mod tests {
             .route_start_instance(StartInstanceRequest {
                 module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
                 capabilities: vec![],
-                restart_policy: RestartPolicy::default(),
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
             })
             .await;
 
@@ -662,6 +2274,79 @@ mod tests {
         assert!(!node.available);
     }
 
+    #[tokio::test]
+    async fn test_node_marked_unavailable_only_after_repeated_failures() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone()).with_node_failure_threshold(3);
+        service
+            .register_node(
+                "node-flaky".to_string(),
+                "127.0.0.1:65099".to_string(),
+                vec![],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let available = || async {
+            repo.get_node("node-flaky")
+                .await
+                .unwrap()
+                .unwrap()
+                .available
+        };
+
+        service.route_list_instances().await.unwrap();
+        assert!(available().await);
+
+        service.route_list_instances().await.unwrap();
+        service.route_list_instances().await.unwrap();
+        assert!(!available().await);
+    }
+
+    #[test]
+    fn test_is_transport_error_classifies_status_codes() {
+        assert!(is_transport_error(&tonic::Status::unavailable(
+            "connection refused"
+        )));
+        assert!(is_transport_error(&tonic::Status::deadline_exceeded(
+            "timeout"
+        )));
+        assert!(!is_transport_error(&tonic::Status::invalid_argument(
+            "bad module"
+        )));
+        assert!(!is_transport_error(&tonic::Status::internal("trap")));
+    }
+
+    #[tokio::test]
+    async fn test_locate_instance_returns_assigned_node() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone());
+
+        repo.assign_instance("inst-1".to_string(), "node-2".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(service.locate_instance("inst-1").await.unwrap(), "node-2");
+        assert!(matches!(
+            service.locate_instance("missing").await,
+            Err(ControlPlaneError::InstanceNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_restart_route_unknown_instance() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo);
+
+        let result = service.route_restart_instance("missing").await;
+
+        assert!(matches!(
+            result,
+            Err(ControlPlaneError::InstanceNotFound(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_register_node_persists_etcd_metadata_when_enabled() {
         let repo = Arc::new(InMemoryNodeRoutingRepository::new());
@@ -673,7 +2358,8 @@ mod tests {
                 "node-1".to_string(),
                 "127.0.0.1:50052".to_string(),
                 vec![],
-                10,
+                Some(10),
+                None,
             )
             .await
             .unwrap();
@@ -703,6 +2389,152 @@ mod tests {
         assert!(!keys.iter().any(|k| k.contains("/instances/")));
     }
 
+    #[tokio::test]
+    async fn test_list_provider_metadata_filtered_by_type_and_node() {
+        let service = NodeRoutingService::new(Arc::new(InMemoryNodeRoutingRepository::new()));
+        for (provider_id, provider_type, node_id) in [
+            ("kv-1", "kv", "node-1"),
+            ("kv-3", "kv", "node-3"),
+            ("http-3", "http", "node-3"),
+        ] {
+            service
+                .register_provider_metadata(
+                    provider_id.to_string(),
+                    provider_type.to_string(),
+                    node_id.to_string(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let kv_on_node_3 = service
+            .list_provider_metadata_filtered(Some("kv"), Some("node-3"))
+            .await
+            .unwrap();
+        assert_eq!(kv_on_node_3.len(), 1);
+        assert_eq!(kv_on_node_3[0].provider_id, "kv-3");
+
+        let on_node_3 = service
+            .list_provider_metadata_filtered(None, Some("node-3"))
+            .await
+            .unwrap();
+        assert_eq!(on_node_3.len(), 2);
+        assert!(on_node_3.iter().all(|p| p.node_id == "node-3"));
+    }
+
+    #[tokio::test]
+    async fn test_updated_node_capabilities_are_used_for_placement() {
+        use wasmatrix_agent::server::NodeAgentServer;
+        use wasmatrix_proto::v1::node_agent_service_server::NodeAgentServiceServer;
+
+        let agent = Arc::new(wasmatrix_agent::NodeAgent::new("node-1".to_string()).unwrap());
+        let (agent_addr, _agent_server) = crate::testing::serve(NodeAgentServiceServer::new(
+            NodeAgentServer::new(agent, None),
+        ))
+        .await;
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone());
+        service
+            .register_node(
+                "node-1".to_string(),
+                format!("http://{agent_addr}"),
+                vec!["http".to_string()],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let request = || StartInstanceRequest {
+            module_bytes: crate::testing::EMPTY_MODULE.to_vec(),
+            capabilities: vec![CapabilityAssignment::new(
+                String::new(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+
+        assert!(matches!(
+            service.route_start_instance(request()).await,
+            Err(ControlPlaneError::CapabilityNotFound(_))
+        ));
+
+        service
+            .update_node_capabilities("node-1", vec!["kv".to_string()], false)
+            .await
+            .unwrap();
+        let node = repo.get_node("node-1").await.unwrap().unwrap();
+        assert_eq!(node.capabilities, vec!["http", "kv"]);
+        let instance_id = service.route_start_instance(request()).await.unwrap();
+        assert_eq!(
+            repo.lookup_instance_node(&instance_id).await.unwrap(),
+            Some("node-1".to_string())
+        );
+
+        service
+            .update_node_capabilities("node-1", vec!["http".to_string()], true)
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.route_start_instance(request()).await,
+            Err(ControlPlaneError::CapabilityNotFound(_))
+        ));
+        assert!(service
+            .update_node_capabilities("missing", vec![], true)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recover_all_nodes_skips_node_that_times_out() {
+        use wasmatrix_agent::server::NodeAgentServer;
+        use wasmatrix_proto::v1::node_agent_service_server::NodeAgentServiceServer;
+
+        let agent = Arc::new(wasmatrix_agent::NodeAgent::new("node-ok".to_string()).unwrap());
+        let (agent_addr, _agent_server) = crate::testing::serve(NodeAgentServiceServer::new(
+            NodeAgentServer::new(agent, None),
+        ))
+        .await;
+        // Accepts TCP connections but never answers, so recovery hangs
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hung_addr = listener.local_addr().unwrap().to_string();
+
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service =
+            NodeRoutingService::new(repo.clone()).with_recovery_timeout(Duration::from_millis(300));
+        for (node_id, address) in [
+            ("node-hung", hung_addr),
+            ("node-ok", format!("http://{agent_addr}")),
+        ] {
+            service
+                .register_node(node_id.to_string(), address, vec![], None, None)
+                .await
+                .unwrap();
+        }
+        let control_plane = Mutex::new(ControlPlane::new("cp-node"));
+
+        let started = Instant::now();
+        let mut results = service.recover_all_nodes(&control_plane).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "node-hung");
+        assert!(matches!(results[0].1, Err(ControlPlaneError::Timeout(_))));
+        assert_eq!(results[1].0, "node-ok");
+        assert_eq!(results[1].1.as_ref().unwrap(), &0);
+
+        assert!(!repo.get_node("node-hung").await.unwrap().unwrap().available);
+        assert!(repo.get_node("node-ok").await.unwrap().unwrap().available);
+    }
+
     #[tokio::test]
     async fn test_recover_node_state_applies_instance_statuses() {
         let repo = Arc::new(InMemoryNodeRoutingRepository::new());
@@ -714,7 +2546,8 @@ mod tests {
                 "node-1".to_string(),
                 "127.0.0.1:50052".to_string(),
                 vec![],
-                10,
+                Some(10),
+                None,
             )
             .await
             .unwrap();
@@ -726,6 +2559,24 @@ mod tests {
                 module_hash: "hash-a".to_string(),
                 created_at: 1_700_000_000,
                 status: wasmatrix_proto::v1::InstanceStatus::Running as i32,
+                capabilities: vec![
+                    wasmatrix_proto::v1::CapabilityAssignment {
+                        instance_id: "inst-a".to_string(),
+                        capability_id: "kv-1".to_string(),
+                        provider_type: wasmatrix_proto::v1::ProviderType::Kv as i32,
+                        permissions: vec!["kv:read".to_string(), "kv:write".to_string()],
+                        expires_at: Some(4_102_444_800),
+                        state: wasmatrix_proto::v1::CapabilityState::Active as i32,
+                    },
+                    wasmatrix_proto::v1::CapabilityAssignment {
+                        instance_id: "inst-a".to_string(),
+                        capability_id: "http-1".to_string(),
+                        provider_type: wasmatrix_proto::v1::ProviderType::Http as i32,
+                        permissions: vec!["http:request".to_string()],
+                        expires_at: None,
+                        state: wasmatrix_proto::v1::CapabilityState::Revoked as i32,
+                    },
+                ],
             },
             wasmatrix_proto::v1::InstanceMetadata {
                 instance_id: "inst-b".to_string(),
@@ -733,6 +2584,7 @@ mod tests {
                 module_hash: "hash-b".to_string(),
                 created_at: 1_700_000_001,
                 status: wasmatrix_proto::v1::InstanceStatus::Stopped as i32,
+                capabilities: vec![],
             },
         ];
 
@@ -755,6 +2607,20 @@ mod tests {
             .unwrap();
         assert_eq!(inst_a.status, wasmatrix_core::InstanceStatus::Running);
         assert_eq!(inst_b.status, wasmatrix_core::InstanceStatus::Stopped);
+
+        let caps_a = cp.get_capabilities("inst-a").unwrap();
+        assert_eq!(caps_a.len(), 2);
+        assert_eq!(caps_a[0].capability_id, "kv-1");
+        assert_eq!(caps_a[0].provider_type, ProviderType::Kv);
+        assert!(caps_a[0].has_permission("kv:write"));
+        assert_eq!(
+            caps_a[0].expires_at,
+            chrono::DateTime::from_timestamp(4_102_444_800, 0)
+        );
+        assert_eq!(caps_a[1].capability_id, "http-1");
+        assert_eq!(caps_a[1].state, wasmatrix_core::CapabilityState::Revoked);
+        assert!(!caps_a[1].has_permission("http:request"));
+        assert!(cp.get_capabilities("inst-b").is_none());
     }
 
     #[tokio::test]
@@ -766,7 +2632,8 @@ mod tests {
             node_id: "node-inst".to_string(),
             node_address: "http://127.0.0.1:50099".to_string(),
             capabilities: vec!["http".to_string()],
-            max_instances: 10,
+            max_instances: Some(10),
+            weight: 1,
             active_instances: 0,
             last_heartbeat: Some(Utc::now()),
             available: true,
@@ -793,6 +2660,38 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_route_capability_invocation_rejects_expired_assignment() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone());
+
+        repo.assign_instance("inst-1".to_string(), "node-inst".to_string())
+            .await
+            .unwrap();
+
+        let result = service
+            .route_capability_invocation(
+                "inst-1",
+                assignment(
+                    "inst-1",
+                    "http-provider-1",
+                    ProviderType::Http,
+                    vec!["http:request"],
+                )
+                .with_expiry(Utc::now() - chrono::Duration::seconds(1)),
+                "request",
+                serde_json::json!({"method":"GET","url":"https://example.com"}),
+            )
+            .await;
+
+        match result {
+            Err(ControlPlaneError::PermissionDenied(message)) => {
+                assert!(message.contains("expired"))
+            }
+            other => panic!("expected permission denied, got {:?}", other.map(|_| ())),
+        }
+    }
+
     #[tokio::test]
     async fn test_route_capability_invocation_requires_registered_provider() {
         let repo = Arc::new(InMemoryNodeRoutingRepository::new());
@@ -802,7 +2701,8 @@ mod tests {
             node_id: "node-inst".to_string(),
             node_address: "http://127.0.0.1:50099".to_string(),
             capabilities: vec!["http".to_string()],
-            max_instances: 10,
+            max_instances: Some(10),
+            weight: 1,
             active_instances: 0,
             last_heartbeat: Some(Utc::now()),
             available: true,
@@ -843,7 +2743,8 @@ mod tests {
             node_id: "node-inst".to_string(),
             node_address: "http://127.0.0.1:50099".to_string(),
             capabilities: vec!["http".to_string()],
-            max_instances: 10,
+            max_instances: Some(10),
+            weight: 1,
             active_instances: 0,
             last_heartbeat: Some(Utc::now()),
             available: true,
@@ -855,7 +2756,8 @@ mod tests {
             node_id: "provider-node".to_string(),
             node_address: "http://127.0.0.1:65098".to_string(),
             capabilities: vec!["http".to_string()],
-            max_instances: 10,
+            max_instances: Some(10),
+            weight: 1,
             active_instances: 0,
             last_heartbeat: Some(Utc::now()),
             available: true,
@@ -900,7 +2802,13 @@ mod tests {
             let request = StartInstanceRequest {
                 module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
                 capabilities: vec![],
-                restart_policy: RestartPolicy::default(),
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
             };
 
             let nodes = vec![
@@ -908,7 +2816,8 @@ mod tests {
                     node_id: format!("failed-{i}"),
                     node_address: "http://127.0.0.1:9".to_string(),
                     capabilities: vec![],
-                    max_instances: 10,
+                    max_instances: Some(10),
+                    weight: 1,
                     active_instances: 0,
                     last_heartbeat: Some(Utc::now()),
                     available: false,
@@ -917,14 +2826,15 @@ mod tests {
                     node_id: format!("healthy-{i}"),
                     node_address: "http://127.0.0.1:8".to_string(),
                     capabilities: vec![],
-                    max_instances: 10,
+                    max_instances: Some(10),
+                    weight: 1,
                     active_instances: 1,
                     last_heartbeat: Some(Utc::now()),
                     available: true,
                 },
             ];
 
-            let selected = select_candidate_nodes(nodes, &request);
+            let selected = select_candidate_nodes(nodes, &request, 0, false);
             assert_eq!(selected.len(), 1);
             assert!(selected[0].node_id.starts_with("healthy-"));
         }
@@ -935,7 +2845,13 @@ mod tests {
         let request = StartInstanceRequest {
             module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let nodes = vec![
@@ -943,7 +2859,8 @@ mod tests {
                 node_id: "node-2".to_string(),
                 node_address: "http://127.0.0.1:2".to_string(),
                 capabilities: vec![],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: 1,
                 active_instances: 5,
                 last_heartbeat: Some(Utc::now()),
                 available: true,
@@ -952,14 +2869,15 @@ mod tests {
                 node_id: "node-1".to_string(),
                 node_address: "http://127.0.0.1:1".to_string(),
                 capabilities: vec![],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: 1,
                 active_instances: 1,
                 last_heartbeat: Some(Utc::now()),
                 available: true,
             },
         ];
 
-        let selected = select_candidate_nodes(nodes, &request);
+        let selected = select_candidate_nodes(nodes, &request, 0, false);
         assert_eq!(selected.first().map(|n| n.node_id.as_str()), Some("node-1"));
     }
 
@@ -967,13 +2885,19 @@ mod tests {
     fn test_select_candidate_nodes_filters_by_provider_capability() {
         let request = StartInstanceRequest {
             module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
-            capabilities: vec![wasmatrix_core::CapabilityAssignment {
-                instance_id: "i-1".to_string(),
-                capability_id: "http-1".to_string(),
-                provider_type: wasmatrix_core::ProviderType::Http,
-                permissions: vec!["http:request".to_string()],
-            }],
-            restart_policy: RestartPolicy::default(),
+            capabilities: vec![wasmatrix_core::CapabilityAssignment::new(
+                "i-1".to_string(),
+                "http-1".to_string(),
+                wasmatrix_core::ProviderType::Http,
+                vec!["http:request".to_string()],
+            )],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let nodes = vec![
@@ -981,7 +2905,8 @@ mod tests {
                 node_id: "node-kv".to_string(),
                 node_address: "http://127.0.0.1:2".to_string(),
                 capabilities: vec!["kv".to_string()],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: 1,
                 active_instances: 0,
                 last_heartbeat: Some(Utc::now()),
                 available: true,
@@ -990,14 +2915,15 @@ mod tests {
                 node_id: "node-http".to_string(),
                 node_address: "http://127.0.0.1:1".to_string(),
                 capabilities: vec!["http".to_string()],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: 1,
                 active_instances: 0,
                 last_heartbeat: Some(Utc::now()),
                 available: true,
             },
         ];
 
-        let selected = select_candidate_nodes(nodes, &request);
+        let selected = select_candidate_nodes(nodes, &request, 0, false);
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0].node_id, "node-http");
     }
@@ -1007,7 +2933,13 @@ mod tests {
         let request = StartInstanceRequest {
             module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let nodes = vec![
@@ -1015,7 +2947,8 @@ mod tests {
                 node_id: "node-1".to_string(),
                 node_address: "http://127.0.0.1:50052".to_string(),
                 capabilities: vec![],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: 1,
                 active_instances: 2,
                 last_heartbeat: Some(Utc::now()),
                 available: true,
@@ -1024,14 +2957,15 @@ mod tests {
                 node_id: "node-2".to_string(),
                 node_address: "http://127.0.0.1:50053".to_string(),
                 capabilities: vec![],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: 1,
                 active_instances: 0,
                 last_heartbeat: Some(Utc::now()),
                 available: true,
             },
         ];
 
-        let selected = select_candidate_nodes(nodes, &request);
+        let selected = select_candidate_nodes(nodes, &request, 0, false);
         assert_eq!(selected.len(), 2);
         assert_eq!(selected[0].node_id, "node-2");
     }
@@ -1041,7 +2975,13 @@ mod tests {
         let request = StartInstanceRequest {
             module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
             capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
         };
 
         let nodes = vec![
@@ -1049,7 +2989,8 @@ mod tests {
                 node_id: "failing-node".to_string(),
                 node_address: "http://127.0.0.1:65098".to_string(),
                 capabilities: vec![],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: 1,
                 active_instances: 0,
                 last_heartbeat: Some(Utc::now()),
                 available: false,
@@ -1058,15 +2999,303 @@ mod tests {
                 node_id: "healthy-node".to_string(),
                 node_address: "http://127.0.0.1:50053".to_string(),
                 capabilities: vec![],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: 1,
                 active_instances: 1,
                 last_heartbeat: Some(Utc::now()),
                 available: true,
             },
         ];
 
-        let selected = select_candidate_nodes(nodes, &request);
+        let selected = select_candidate_nodes(nodes, &request, 0, false);
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0].node_id, "healthy-node");
     }
+
+    #[test]
+    fn test_preferred_node_is_tried_first_when_available() {
+        let request = StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: Some("node-busy".to_string()),
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let node = |node_id: &str, active_instances: u32| NodeAgentRecord {
+            node_id: node_id.to_string(),
+            ..node_with_capacity(Some(10), active_instances)
+        };
+        let candidates = select_candidate_nodes(
+            vec![
+                node("node-idle", 0),
+                node("node-busy", 5),
+                node("node-mid", 2),
+            ],
+            &request,
+            0,
+            false,
+        );
+
+        let ordered = apply_node_preference(candidates, Some("node-busy"), false).unwrap();
+        let order: Vec<&str> = ordered.iter().map(|n| n.node_id.as_str()).collect();
+        assert_eq!(order, vec!["node-busy", "node-idle", "node-mid"]);
+    }
+
+    #[test]
+    fn test_full_preferred_node_falls_back_to_normal_selection() {
+        let request = StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: Some("node-full".to_string()),
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let candidates = select_candidate_nodes(
+            vec![
+                NodeAgentRecord {
+                    node_id: "node-full".to_string(),
+                    ..node_with_capacity(Some(2), 2)
+                },
+                NodeAgentRecord {
+                    node_id: "node-free".to_string(),
+                    ..node_with_capacity(Some(2), 1)
+                },
+            ],
+            &request,
+            0,
+            false,
+        );
+
+        let ordered = apply_node_preference(candidates.clone(), Some("node-full"), false).unwrap();
+        assert_eq!(ordered.len(), 1);
+        assert_eq!(ordered[0].node_id, "node-free");
+        assert!(matches!(
+            apply_node_preference(candidates, Some("node-full"), true),
+            Err(ControlPlaneError::ResourceExhausted(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_start_route_fails_when_required_node_is_down() {
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone());
+        for node_id in ["node-down", "node-up"] {
+            service
+                .register_node(
+                    node_id.to_string(),
+                    "127.0.0.1:65099".to_string(),
+                    vec![],
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        repo.set_availability("node-down", false).await.unwrap();
+
+        let result = service
+            .route_start_instance(StartInstanceRequest {
+                module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: Some("node-down".to_string()),
+                require_node: true,
+                annotations: Default::default(),
+            })
+            .await;
+
+        match result {
+            Err(ControlPlaneError::ResourceExhausted(message)) => {
+                assert!(message.contains("node-down"), "{message}");
+            }
+            other => panic!("expected ResourceExhausted, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_unset_restart_policy_uses_configured_default() {
+        let request = StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let service = NodeRoutingService::new(repo.clone())
+            .with_default_restart_policy(RestartPolicy::on_failure(4, 2));
+
+        let proto = proto_start_request("inst-1", &service.resolve_restart_policy(request.clone()))
+            .restart_policy
+            .unwrap();
+        assert_eq!(
+            proto.policy_type,
+            wasmatrix_proto::v1::RestartPolicyType::OnFailure as i32
+        );
+        assert_eq!(proto.max_retries, Some(4));
+        assert_eq!(proto.backoff_seconds, Some(2));
+
+        // An explicit policy wins over the cluster default
+        let explicit = service.resolve_restart_policy(StartInstanceRequest {
+            restart_policy: Some(RestartPolicy::always()),
+            ..request.clone()
+        });
+        assert_eq!(
+            explicit.restart_policy.unwrap().policy_type,
+            wasmatrix_core::RestartPolicyType::Always
+        );
+
+        // Without a configured default the policy falls back to Never
+        let fallback = NodeRoutingService::new(repo).resolve_restart_policy(request);
+        assert_eq!(
+            fallback.restart_policy.unwrap().policy_type,
+            wasmatrix_core::RestartPolicyType::Never
+        );
+    }
+
+    #[test]
+    fn test_weighted_round_robin_follows_node_weights() {
+        let request = StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let node = |node_id: &str, weight: u32| NodeAgentRecord {
+            node_id: node_id.to_string(),
+            weight,
+            ..node_with_capacity(None, 0)
+        };
+        let nodes = vec![node("node-small", 1), node("node-big", 3)];
+
+        let mut running = HashMap::new();
+        let mut placements: HashMap<String, u32> = HashMap::new();
+        for _ in 0..400 {
+            let selected = select_weighted_candidate_nodes(nodes.clone(), &request, &mut running);
+            assert_eq!(selected.len(), 2);
+            *placements.entry(selected[0].node_id.clone()).or_insert(0) += 1;
+        }
+
+        assert_eq!(placements["node-big"], 300);
+        assert_eq!(placements["node-small"], 100);
+    }
+
+    #[test]
+    fn test_select_candidate_nodes_rotates_among_equally_loaded_nodes() {
+        let request = StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let node = |node_id: &str, active_instances: u32| NodeAgentRecord {
+            node_id: node_id.to_string(),
+            ..node_with_capacity(None, active_instances)
+        };
+        let nodes = vec![
+            node("node-c", 1),
+            node("node-busy", 5),
+            node("node-a", 1),
+            node("node-b", 1),
+        ];
+
+        let first_choices: Vec<String> = (0..6)
+            .map(|rotation| {
+                select_candidate_nodes(nodes.clone(), &request, rotation, false)[0]
+                    .node_id
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            first_choices,
+            vec!["node-a", "node-b", "node-c", "node-a", "node-b", "node-c"]
+        );
+
+        let mut reversed = nodes.clone();
+        reversed.reverse();
+        let order = |nodes: Vec<NodeAgentRecord>| -> Vec<String> {
+            select_candidate_nodes(nodes, &request, 1, false)
+                .into_iter()
+                .map(|n| n.node_id)
+                .collect()
+        };
+        assert_eq!(order(nodes), order(reversed));
+        assert_eq!(
+            order(vec![node("node-a", 1), node("node-busy", 5)])
+                .last()
+                .map(String::as_str),
+            Some("node-busy")
+        );
+    }
+
+    #[test]
+    fn test_capability_aware_selection_keeps_specialized_node_free() {
+        let request = |capabilities| StartInstanceRequest {
+            module_bytes: vec![0, 97, 115, 109, 1, 0, 0, 0],
+            capabilities,
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let nodes = vec![
+            NodeAgentRecord {
+                node_id: "node-a-http".to_string(),
+                capabilities: vec!["http".to_string()],
+                ..node_with_capacity(None, 2)
+            },
+            NodeAgentRecord {
+                node_id: "node-b-generic".to_string(),
+                ..node_with_capacity(None, 2)
+            },
+        ];
+        let first = |capabilities, reserve_specialized| {
+            select_candidate_nodes(
+                nodes.clone(),
+                &request(capabilities),
+                0,
+                reserve_specialized,
+            )[0]
+            .node_id
+            .clone()
+        };
+
+        assert_eq!(first(vec![], false), "node-a-http");
+        assert_eq!(first(vec![], true), "node-b-generic");
+        let http = vec![assignment(
+            "",
+            "http-1",
+            ProviderType::Http,
+            vec!["http:request"],
+        )];
+        assert_eq!(first(http, true), "node-a-http");
+    }
 }
@@ -1,4 +1,6 @@
-use crate::features::observability::repo::ObservabilityRepository;
+use crate::features::observability::repo::{
+    MetricsRepository, NoopMetricsRepository, ObservabilityRepository,
+};
 use crate::features::observability::service::ObservabilityService;
 use std::sync::{Arc, OnceLock};
 
@@ -31,6 +33,10 @@ impl ObservabilityController {
         self.service.set_node_health(node_id, healthy);
     }
 
+    pub fn set_node_active_instances(&self, node_id: &str, count: usize) {
+        self.service.set_node_active_instances(node_id, count);
+    }
+
     pub fn render_metrics(&self) -> Result<String, String> {
         self.service.render_metrics()
     }
@@ -40,15 +46,22 @@ static GLOBAL_OBSERVABILITY: OnceLock<Arc<ObservabilityController>> = OnceLock::
 
 pub fn global_observability_controller() -> Arc<ObservabilityController> {
     GLOBAL_OBSERVABILITY
-        .get_or_init(|| {
-            let repo = Arc::new(ObservabilityRepository::new().expect("metrics init"));
-            Arc::new(ObservabilityController::new(ObservabilityService::new(
-                repo,
-            )))
-        })
+        .get_or_init(|| Arc::new(controller_from_metrics_init(ObservabilityRepository::new())))
         .clone()
 }
 
+/// Build a controller from the result of metrics initialisation, falling back
+/// to no-op metrics so a registry error never takes the process down
+fn controller_from_metrics_init(
+    init: Result<ObservabilityRepository, String>,
+) -> ObservabilityController {
+    let repo = wasmatrix_observability::init_or_fallback(
+        init.map(|repo| Arc::new(repo) as Arc<dyn MetricsRepository>),
+        || Arc::new(NoopMetricsRepository),
+    );
+    ObservabilityController::new(ObservabilityService::new(repo))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,4 +73,15 @@ mod tests {
         let rendered = controller.render_metrics().unwrap();
         assert!(rendered.contains("wasmatrix_api_request_total"));
     }
+
+    #[test]
+    fn test_failed_metrics_init_falls_back_to_noop_controller() {
+        let controller =
+            controller_from_metrics_init(Err("duplicate metrics collector".to_string()));
+        controller.record_api_request("test", "ok", 0.01);
+        controller.record_crash();
+        controller.set_node_health("node-1", true);
+
+        assert_eq!(controller.render_metrics(), Ok(String::new()));
+    }
 }
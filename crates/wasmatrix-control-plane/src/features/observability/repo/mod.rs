@@ -1,78 +1,75 @@
-use prometheus::{
-    opts, Counter, CounterVec, Encoder, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec,
-    Registry, TextEncoder,
+use wasmatrix_observability::prometheus::{
+    opts, Counter, CounterVec, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec,
 };
+use wasmatrix_observability::MetricsRegistry;
+
+/// Sink for control plane metrics
+pub trait MetricsRepository: Send + Sync {
+    fn set_active_instance_count(&self, count: f64);
+    fn inc_instance_crash_total(&self);
+    fn observe_invocation_latency(&self, seconds: f64);
+    fn observe_api_request(&self, endpoint: &str, status: &str, seconds: f64);
+    fn set_node_agent_health(&self, node_id: &str, healthy: bool);
+    fn set_node_active_instances(&self, node_id: &str, count: f64);
+    fn render_metrics(&self) -> Result<String, String>;
+}
 
 pub struct ObservabilityRepository {
-    registry: Registry,
+    registry: MetricsRegistry,
     active_instance_count: Gauge,
     instance_crash_total: Counter,
     invocation_latency_seconds: Histogram,
     api_request_total: CounterVec,
     api_request_latency_seconds: HistogramVec,
     node_agent_health: GaugeVec,
+    node_active_instances: GaugeVec,
 }
 
 impl ObservabilityRepository {
     pub fn new() -> Result<Self, String> {
-        let registry = Registry::new();
+        let registry = MetricsRegistry::new();
 
-        let active_instance_count =
-            Gauge::with_opts(opts!("wasmatrix_active_instance_count", "Active instances"))
-                .map_err(|e| e.to_string())?;
-        let instance_crash_total = Counter::with_opts(opts!(
+        let active_instance_count = registry.register(Gauge::with_opts(opts!(
+            "wasmatrix_active_instance_count",
+            "Active instances"
+        )))?;
+        let instance_crash_total = registry.register(Counter::with_opts(opts!(
             "wasmatrix_instance_crash_total",
             "Crashed instances total"
-        ))
-        .map_err(|e| e.to_string())?;
-        let invocation_latency_seconds = Histogram::with_opts(HistogramOpts::new(
-            "wasmatrix_capability_invocation_latency_seconds",
-            "Capability invocation latency (seconds)",
-        ))
-        .map_err(|e| e.to_string())?;
-        let api_request_total = CounterVec::new(
+        )))?;
+        let invocation_latency_seconds =
+            registry.register(Histogram::with_opts(HistogramOpts::new(
+                "wasmatrix_capability_invocation_latency_seconds",
+                "Capability invocation latency (seconds)",
+            )))?;
+        let api_request_total = registry.register(CounterVec::new(
             opts!(
                 "wasmatrix_api_request_total",
                 "Control plane API request total"
             ),
             &["endpoint", "status"],
-        )
-        .map_err(|e| e.to_string())?;
-        let api_request_latency_seconds = HistogramVec::new(
+        ))?;
+        let api_request_latency_seconds = registry.register(HistogramVec::new(
             HistogramOpts::new(
                 "wasmatrix_api_request_latency_seconds",
                 "Control plane API request latency (seconds)",
             ),
             &["endpoint"],
-        )
-        .map_err(|e| e.to_string())?;
-        let node_agent_health = GaugeVec::new(
+        ))?;
+        let node_agent_health = registry.register(GaugeVec::new(
             opts!(
                 "wasmatrix_node_agent_health",
                 "Node Agent health status (1 healthy / 0 unhealthy)"
             ),
             &["node_id"],
-        )
-        .map_err(|e| e.to_string())?;
-
-        registry
-            .register(Box::new(active_instance_count.clone()))
-            .map_err(|e| e.to_string())?;
-        registry
-            .register(Box::new(instance_crash_total.clone()))
-            .map_err(|e| e.to_string())?;
-        registry
-            .register(Box::new(invocation_latency_seconds.clone()))
-            .map_err(|e| e.to_string())?;
-        registry
-            .register(Box::new(api_request_total.clone()))
-            .map_err(|e| e.to_string())?;
-        registry
-            .register(Box::new(api_request_latency_seconds.clone()))
-            .map_err(|e| e.to_string())?;
-        registry
-            .register(Box::new(node_agent_health.clone()))
-            .map_err(|e| e.to_string())?;
+        ))?;
+        let node_active_instances = registry.register(GaugeVec::new(
+            opts!(
+                "wasmatrix_node_active_instances",
+                "Active instances placed on each node"
+            ),
+            &["node_id"],
+        ))?;
 
         Ok(Self {
             registry,
@@ -82,22 +79,25 @@ impl ObservabilityRepository {
             api_request_total,
             api_request_latency_seconds,
             node_agent_health,
+            node_active_instances,
         })
     }
+}
 
-    pub fn set_active_instance_count(&self, count: f64) {
+impl MetricsRepository for ObservabilityRepository {
+    fn set_active_instance_count(&self, count: f64) {
         self.active_instance_count.set(count);
     }
 
-    pub fn inc_instance_crash_total(&self) {
+    fn inc_instance_crash_total(&self) {
         self.instance_crash_total.inc();
     }
 
-    pub fn observe_invocation_latency(&self, seconds: f64) {
+    fn observe_invocation_latency(&self, seconds: f64) {
         self.invocation_latency_seconds.observe(seconds);
     }
 
-    pub fn observe_api_request(&self, endpoint: &str, status: &str, seconds: f64) {
+    fn observe_api_request(&self, endpoint: &str, status: &str, seconds: f64) {
         self.api_request_total
             .with_label_values(&[endpoint, status])
             .inc();
@@ -106,19 +106,41 @@ impl ObservabilityRepository {
             .observe(seconds);
     }
 
-    pub fn set_node_agent_health(&self, node_id: &str, healthy: bool) {
+    fn set_node_agent_health(&self, node_id: &str, healthy: bool) {
         self.node_agent_health
             .with_label_values(&[node_id])
             .set(if healthy { 1.0 } else { 0.0 });
     }
 
-    pub fn render_metrics(&self) -> Result<String, String> {
-        let mut buffer = Vec::new();
-        let encoder = TextEncoder::new();
-        let families = self.registry.gather();
-        encoder
-            .encode(&families, &mut buffer)
-            .map_err(|e| e.to_string())?;
-        String::from_utf8(buffer).map_err(|e| e.to_string())
+    fn set_node_active_instances(&self, node_id: &str, count: f64) {
+        self.node_active_instances
+            .with_label_values(&[node_id])
+            .set(count);
+    }
+
+    fn render_metrics(&self) -> Result<String, String> {
+        self.registry.render()
+    }
+}
+
+/// Stand-in used when the Prometheus registry cannot be built; drops every
+/// observation and renders an empty exposition body
+pub struct NoopMetricsRepository;
+
+impl MetricsRepository for NoopMetricsRepository {
+    fn set_active_instance_count(&self, _count: f64) {}
+
+    fn inc_instance_crash_total(&self) {}
+
+    fn observe_invocation_latency(&self, _seconds: f64) {}
+
+    fn observe_api_request(&self, _endpoint: &str, _status: &str, _seconds: f64) {}
+
+    fn set_node_agent_health(&self, _node_id: &str, _healthy: bool) {}
+
+    fn set_node_active_instances(&self, _node_id: &str, _count: f64) {}
+
+    fn render_metrics(&self) -> Result<String, String> {
+        Ok(String::new())
     }
 }
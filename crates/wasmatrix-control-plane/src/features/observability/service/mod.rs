@@ -1,12 +1,12 @@
-use crate::features::observability::repo::ObservabilityRepository;
+use crate::features::observability::repo::MetricsRepository;
 use std::sync::Arc;
 
 pub struct ObservabilityService {
-    repo: Arc<ObservabilityRepository>,
+    repo: Arc<dyn MetricsRepository>,
 }
 
 impl ObservabilityService {
-    pub fn new(repo: Arc<ObservabilityRepository>) -> Self {
+    pub fn new(repo: Arc<dyn MetricsRepository>) -> Self {
         Self { repo }
     }
 
@@ -30,6 +30,10 @@ impl ObservabilityService {
         self.repo.set_node_agent_health(node_id, healthy);
     }
 
+    pub fn set_node_active_instances(&self, node_id: &str, count: usize) {
+        self.repo.set_node_active_instances(node_id, count as f64);
+    }
+
     pub fn render_metrics(&self) -> Result<String, String> {
         self.repo.render_metrics()
     }
@@ -1,21 +1,65 @@
 pub mod features;
 pub mod server;
 pub mod shared;
+#[cfg(test)]
+pub(crate) mod testing;
+
+pub use features::control_plane_facade::controller::AsyncControlPlane;
+pub use shared::types::StopBehavior;
 
 // Legacy ControlPlane implementation for backward compatibility
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::broadcast;
+use wasmatrix_core::capability::PermissionEnforcer;
+use wasmatrix_core::module_hash::{migrate_legacy_hash, module_hashes_match, HashScheme};
 use wasmatrix_core::{
-    CapabilityAssignment, CoreError, ErrorResponse, ExecutionEventRecorder, InstanceMetadata,
-    InstanceStatus, InstanceStatusResponse, QueryInstanceRequest, RestartPolicy, Result,
-    StartInstanceRequest, StopInstanceRequest,
+    CapabilityAssignment, CapabilityState, CoreError, ErrorResponse, ExecutionEvent,
+    ExecutionEventRecorder, InstanceMetadata, InstanceStatus, InstanceStatusResponse, ProviderType,
+    QueryInstanceRequest, RestartPolicy, Result, StartInstanceRequest, StopInstanceRequest,
 };
 
+/// Capabilities a single instance may hold unless configured otherwise
+pub const DEFAULT_MAX_CAPABILITIES_PER_INSTANCE: usize = 256;
+
+/// Status changes buffered per watched instance before slow watchers lag
+const STATUS_WATCH_CAPACITY: usize = 16;
+
+/// Serializable snapshot of the control plane's minimal state for backup and restore
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ControlPlaneSnapshot {
+    pub instances: Vec<InstanceMetadata>,
+    pub capabilities: Vec<CapabilityAssignment>,
+    pub crashed_instances: Vec<String>,
+    pub events: Vec<ExecutionEvent>,
+    /// Wall-clock time of every recorded crash per instance, oldest first
+    #[serde(default)]
+    pub crash_history: HashMap<String, Vec<chrono::DateTime<chrono::Utc>>>,
+}
+
+/// Legacy synchronous control plane.
+///
+/// Kept for internal use by the gRPC server and node recovery; new callers
+/// should use [`AsyncControlPlane`] instead.
 pub struct ControlPlane {
     instances: HashMap<String, InstanceMetadata>,
     crashed_instances: HashMap<String, std::time::Instant>,
+    /// Every recorded crash per instance, oldest first; survives recovery
+    crash_history: HashMap<String, Vec<std::time::Instant>>,
     capabilities: HashMap<String, Vec<CapabilityAssignment>>,
     event_recorder: ExecutionEventRecorder,
     node_id: String,
+    stop_behavior: StopBehavior,
+    /// Provider types each instance declared in its start request
+    declared_providers: HashMap<String, HashSet<ProviderType>>,
+    /// When set, later assignments are limited to declared provider types
+    enforce_declared_providers: bool,
+    /// Upper bound on capabilities assigned to one instance
+    max_capabilities_per_instance: usize,
+    /// Status change channels for instances that currently have watchers
+    status_watchers: HashMap<String, broadcast::Sender<InstanceStatus>>,
+    /// Digest used for the `module_hash` of newly started instances
+    module_hash_scheme: HashScheme,
 }
 
 impl ControlPlane {
@@ -23,12 +67,48 @@ impl ControlPlane {
         Self {
             instances: HashMap::new(),
             crashed_instances: HashMap::new(),
+            crash_history: HashMap::new(),
             capabilities: HashMap::new(),
             event_recorder: ExecutionEventRecorder::new(),
             node_id: node_id.into(),
+            stop_behavior: StopBehavior::default(),
+            declared_providers: HashMap::new(),
+            enforce_declared_providers: false,
+            max_capabilities_per_instance: DEFAULT_MAX_CAPABILITIES_PER_INSTANCE,
+            module_hash_scheme: HashScheme::default(),
+            status_watchers: HashMap::new(),
         }
     }
 
+    /// Limit how many capabilities `assign_capability` lets one instance hold
+    pub fn with_max_capabilities_per_instance(mut self, max: usize) -> Self {
+        self.max_capabilities_per_instance = max;
+        self
+    }
+
+    /// Hash newly started modules with `scheme`; existing hashes keep their own
+    pub fn with_module_hash_scheme(mut self, scheme: HashScheme) -> Self {
+        self.module_hash_scheme = scheme;
+        self
+    }
+
+    /// Restrict `assign_capability` to provider types declared at start
+    pub fn with_declared_provider_enforcement(mut self, enforce: bool) -> Self {
+        self.enforce_declared_providers = enforce;
+        self
+    }
+
+    /// Toggle declared-provider enforcement, e.g. for an admin override
+    pub fn set_declared_provider_enforcement(&mut self, enforce: bool) {
+        self.enforce_declared_providers = enforce;
+    }
+
+    /// Choose whether `stop_instance` retains or removes instance metadata
+    pub fn with_stop_behavior(mut self, stop_behavior: StopBehavior) -> Self {
+        self.stop_behavior = stop_behavior;
+        self
+    }
+
     /// Start a new Wasm instance
     /// Validates request and creates instance metadata
     pub fn start_instance(
@@ -53,21 +133,35 @@ impl ControlPlane {
             ));
         }
 
+        if request
+            .deployment_id
+            .as_deref()
+            .is_some_and(|deployment_id| deployment_id.trim().is_empty())
+        {
+            return Err(ErrorResponse::new(
+                "INVALID_REQUEST",
+                "Deployment ID cannot be empty",
+            ));
+        }
+
         // Create instance metadata
         let metadata = InstanceMetadata::new(
             self.node_id.clone(),
-            format!("{:x}", md5::compute(&request.module_bytes)),
-        );
+            self.module_hash_scheme.compute(&request.module_bytes),
+        )
+        .with_deployment(request.deployment_id);
 
         let instance_id = metadata.instance_id.clone();
 
         // Store instance
         self.instances.insert(instance_id.clone(), metadata);
+        self.declared_providers
+            .insert(instance_id.clone(), provider_types(&request.capabilities));
 
         // Store capability assignments
         if !request.capabilities.is_empty() {
             self.capabilities
-                .insert(instance_id.clone(), request.capabilities);
+                .insert(instance_id.clone(), compiled(request.capabilities));
         }
 
         Ok(instance_id)
@@ -87,15 +181,107 @@ impl ControlPlane {
         }
 
         // Find and update instance
-        if let Some(metadata) = self.instances.get_mut(&request.instance_id) {
-            metadata.status = InstanceStatus::Stopped;
-            Ok(())
-        } else {
-            Err(ErrorResponse::new(
+        if !self.instances.contains_key(&request.instance_id) {
+            return Err(ErrorResponse::new(
                 "INSTANCE_NOT_FOUND",
                 format!("Instance {} not found", request.instance_id),
-            ))
+            ));
+        }
+
+        match self.stop_behavior {
+            StopBehavior::RetainMetadata => {
+                self.set_status(&request.instance_id, InstanceStatus::Stopped);
+            }
+            StopBehavior::RemoveMetadata => {
+                self.set_status(&request.instance_id, InstanceStatus::Stopped);
+                self.forget_instance(&request.instance_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete stopped instances whose last status change is older than
+    /// `older_than`, returning their IDs so routing assignments can follow.
+    ///
+    /// Instances that never changed status are aged from `created_at`.
+    pub fn reap_stopped(&mut self, older_than: std::time::Duration) -> Vec<String> {
+        let Ok(older_than) = chrono::Duration::from_std(older_than) else {
+            return Vec::new();
+        };
+        let cutoff = chrono::Utc::now() - older_than;
+        let reaped: Vec<String> = self
+            .instances
+            .values()
+            .filter(|metadata| {
+                metadata.status == InstanceStatus::Stopped
+                    && metadata.updated_at.unwrap_or(metadata.created_at) < cutoff
+            })
+            .map(|metadata| metadata.instance_id.clone())
+            .collect();
+        for instance_id in &reaped {
+            self.forget_instance(instance_id);
+        }
+        reaped
+    }
+
+    /// Drop every piece of state kept for an instance
+    pub(crate) fn forget_instance(&mut self, instance_id: &str) {
+        // Revoked while the instance is still known, so this cannot fail
+        let _ = self.revoke_all_capabilities(instance_id);
+        // Dropping the sender ends every watch on the removed instance
+        self.status_watchers.remove(instance_id);
+        self.instances.remove(instance_id);
+        self.declared_providers.remove(instance_id);
+        self.crashed_instances.remove(instance_id);
+        self.crash_history.remove(instance_id);
+    }
+
+    /// Stop an instance, treating one that is already stopped or gone as success.
+    ///
+    /// Unlike [`ControlPlane::stop_instance`], retrying after a successful stop
+    /// does not fail with `INSTANCE_NOT_FOUND`, which keeps reconcile loops simple.
+    pub fn ensure_stopped(
+        &mut self,
+        request: StopInstanceRequest,
+    ) -> std::result::Result<(), ErrorResponse> {
+        let already_stopped = self
+            .instances
+            .get(&request.instance_id)
+            .is_none_or(|metadata| metadata.status == InstanceStatus::Stopped);
+        if !request.instance_id.is_empty() && already_stopped {
+            return Ok(());
+        }
+        self.stop_instance(request)
+    }
+
+    /// Member instances of a deployment, including stopped ones that are retained
+    pub fn list_deployment(&self, deployment_id: &str) -> Vec<&InstanceMetadata> {
+        self.instances
+            .values()
+            .filter(|metadata| metadata.deployment_id.as_deref() == Some(deployment_id))
+            .collect()
+    }
+
+    /// Stop every instance of a deployment, returning the IDs that were stopped.
+    ///
+    /// Members that are already stopped are skipped, so the call can be retried.
+    pub fn stop_deployment(
+        &mut self,
+        deployment_id: &str,
+    ) -> std::result::Result<Vec<String>, ErrorResponse> {
+        let members: Vec<String> = self
+            .list_deployment(deployment_id)
+            .into_iter()
+            .filter(|metadata| metadata.status != InstanceStatus::Stopped)
+            .map(|metadata| metadata.instance_id.clone())
+            .collect();
+
+        for instance_id in &members {
+            self.stop_instance(StopInstanceRequest {
+                instance_id: instance_id.clone(),
+            })?;
         }
+        Ok(members)
     }
 
     /// Query instance status
@@ -130,7 +316,83 @@ impl ControlPlane {
     /// Assign capabilities to an instance
     pub fn assign_capability(
         &mut self,
-        assignment: CapabilityAssignment,
+        mut assignment: CapabilityAssignment,
+    ) -> std::result::Result<(), ErrorResponse> {
+        self.validate_capability_assignment(&assignment)?;
+        self.check_capability_limit(&assignment.instance_id, 1)?;
+
+        // Add capability assignment
+        assignment.compile_permissions();
+        self.capabilities
+            .entry(assignment.instance_id.clone())
+            .or_default()
+            .push(assignment);
+
+        Ok(())
+    }
+
+    /// Assign several capabilities to one instance atomically.
+    ///
+    /// Every assignment is validated before any is applied, so a single
+    /// invalid entry leaves the instance's capabilities unchanged.
+    pub fn assign_capabilities(
+        &mut self,
+        instance_id: &str,
+        assignments: Vec<CapabilityAssignment>,
+    ) -> std::result::Result<(), ErrorResponse> {
+        let mut seen = std::collections::HashSet::new();
+        for assignment in &assignments {
+            if assignment.instance_id != instance_id {
+                return Err(ErrorResponse::new(
+                    "INVALID_REQUEST",
+                    format!(
+                        "Capability {} targets instance {} instead of {}",
+                        assignment.capability_id, assignment.instance_id, instance_id
+                    ),
+                ));
+            }
+            self.validate_capability_assignment(assignment)?;
+            if !seen.insert(assignment.capability_id.as_str()) {
+                return Err(ErrorResponse::new(
+                    "INVALID_REQUEST",
+                    format!(
+                        "Capability {} appears more than once in the batch",
+                        assignment.capability_id
+                    ),
+                ));
+            }
+        }
+        self.check_capability_limit(instance_id, assignments.len())?;
+
+        self.capabilities
+            .entry(instance_id.to_string())
+            .or_default()
+            .extend(compiled(assignments));
+
+        Ok(())
+    }
+
+    fn check_capability_limit(
+        &self,
+        instance_id: &str,
+        additional: usize,
+    ) -> std::result::Result<(), ErrorResponse> {
+        let assigned = self.capabilities.get(instance_id).map_or(0, Vec::len);
+        if assigned + additional > self.max_capabilities_per_instance {
+            return Err(ErrorResponse::new(
+                "RESOURCE_EXHAUSTED",
+                format!(
+                    "Instance {} would exceed the limit of {} capabilities",
+                    instance_id, self.max_capabilities_per_instance
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_capability_assignment(
+        &self,
+        assignment: &CapabilityAssignment,
     ) -> std::result::Result<(), ErrorResponse> {
         // Validate instance exists
         if !self.instances.contains_key(&assignment.instance_id) {
@@ -149,18 +411,27 @@ impl ControlPlane {
         }
 
         // Validate permissions
-        if assignment.permissions.is_empty() {
+        if assignment.permissions().is_empty() {
             return Err(ErrorResponse::new(
                 "INVALID_REQUEST",
                 "At least one permission must be specified",
             ));
         }
 
-        // Add capability assignment
-        self.capabilities
-            .entry(assignment.instance_id.clone())
-            .or_insert_with(Vec::new)
-            .push(assignment);
+        if self.enforce_declared_providers
+            && !self
+                .declared_providers
+                .get(&assignment.instance_id)
+                .is_some_and(|declared| declared.contains(&assignment.provider_type))
+        {
+            return Err(ErrorResponse::new(
+                "PERMISSION_DENIED",
+                format!(
+                    "Instance {} did not declare provider type {:?} at start",
+                    assignment.instance_id, assignment.provider_type
+                ),
+            ));
+        }
 
         Ok(())
     }
@@ -205,16 +476,151 @@ impl ControlPlane {
         }
     }
 
+    /// Activate a `Pending` capability so its permissions take effect
+    pub fn approve_capability(
+        &mut self,
+        instance_id: &str,
+        capability_id: &str,
+    ) -> std::result::Result<(), ErrorResponse> {
+        let assignment = self.capability_mut(instance_id, capability_id)?;
+        if assignment.state == CapabilityState::Revoked {
+            return Err(ErrorResponse::new(
+                "INVALID_REQUEST",
+                format!(
+                    "Capability {} for instance {} was revoked and cannot be approved",
+                    capability_id, instance_id
+                ),
+            ));
+        }
+        assignment.state = CapabilityState::Active;
+        Ok(())
+    }
+
+    /// Mark a capability `Revoked`; it stays assigned but grants nothing
+    pub fn deny_capability(
+        &mut self,
+        instance_id: &str,
+        capability_id: &str,
+    ) -> std::result::Result<(), ErrorResponse> {
+        self.capability_mut(instance_id, capability_id)?.state = CapabilityState::Revoked;
+        Ok(())
+    }
+
+    fn capability_mut(
+        &mut self,
+        instance_id: &str,
+        capability_id: &str,
+    ) -> std::result::Result<&mut CapabilityAssignment, ErrorResponse> {
+        self.capabilities
+            .get_mut(instance_id)
+            .and_then(|assignments| {
+                assignments
+                    .iter_mut()
+                    .find(|a| a.capability_id == capability_id)
+            })
+            .ok_or_else(|| {
+                ErrorResponse::new(
+                    "CAPABILITY_NOT_FOUND",
+                    format!(
+                        "Capability {} not assigned to instance {}",
+                        capability_id, instance_id
+                    ),
+                )
+            })
+    }
+
+    /// Revoke every capability assigned to an instance, returning how many were removed
+    pub fn revoke_all_capabilities(
+        &mut self,
+        instance_id: &str,
+    ) -> std::result::Result<usize, ErrorResponse> {
+        if !self.instances.contains_key(instance_id) {
+            return Err(ErrorResponse::new(
+                "INSTANCE_NOT_FOUND",
+                format!("Instance {} not found", instance_id),
+            ));
+        }
+
+        Ok(self
+            .capabilities
+            .remove(instance_id)
+            .map_or(0, |assignments| assignments.len()))
+    }
+
+    /// Remove expired capability assignments, returning how many were pruned
+    pub fn prune_expired_capabilities(&mut self) -> usize {
+        let now = chrono::Utc::now();
+        let mut pruned = 0;
+
+        self.capabilities.retain(|_, assignments| {
+            let before = assignments.len();
+            assignments.retain(|a| !a.is_expired_at(now));
+            pruned += before - assignments.len();
+            !assignments.is_empty()
+        });
+
+        pruned
+    }
+
     /// List all instances
     pub fn list_instances(&self) -> Vec<&InstanceMetadata> {
         self.instances.values().collect()
     }
 
+    /// List instances running the module with the given hash; only hashes of
+    /// the same scheme match
+    pub fn list_instances_by_module_hash(&self, hash: &str) -> Vec<&InstanceMetadata> {
+        self.instances
+            .values()
+            .filter(|metadata| module_hashes_match(&metadata.module_hash, hash))
+            .collect()
+    }
+
     /// Get capability assignments for an instance
     pub fn get_capabilities(&self, instance_id: &str) -> Option<&Vec<CapabilityAssignment>> {
         self.capabilities.get(instance_id)
     }
 
+    /// Put back a capability set captured with [`Self::get_capabilities`],
+    /// undoing a change the hosting node could not apply
+    pub(crate) fn restore_capabilities(
+        &mut self,
+        instance_id: &str,
+        capabilities: Option<Vec<CapabilityAssignment>>,
+    ) {
+        match capabilities {
+            Some(capabilities) => {
+                self.capabilities
+                    .insert(instance_id.to_string(), capabilities);
+            }
+            None => {
+                self.capabilities.remove(instance_id);
+            }
+        }
+    }
+
+    /// Every permission granted by the instance's active, unexpired capabilities,
+    /// deduplicated and sorted
+    pub fn effective_permissions(&self, instance_id: &str) -> Vec<String> {
+        let permissions: std::collections::BTreeSet<&String> = self
+            .capabilities
+            .get(instance_id)
+            .into_iter()
+            .flatten()
+            .filter(|assignment| assignment.is_active() && !assignment.is_expired())
+            .flat_map(|assignment| assignment.permissions())
+            .collect();
+        permissions.into_iter().cloned().collect()
+    }
+
+    /// Whether any effective permission of the instance covers `permission`,
+    /// honouring wildcard grants such as `kv:*`
+    pub fn can(&self, instance_id: &str, permission: &str) -> bool {
+        self.effective_permissions(instance_id)
+            .iter()
+            .any(|granted| PermissionEnforcer::permission_matches(granted, permission))
+    }
+
     /// Get instance metadata (internal use)
     pub fn get_instance(&self, instance_id: &str) -> Option<&InstanceMetadata> {
         self.instances.get(instance_id)
@@ -228,13 +634,124 @@ impl ControlPlane {
         capabilities: Vec<CapabilityAssignment>,
     ) {
         let instance_id = metadata.instance_id.clone();
+        let status = metadata.status;
         self.instances.insert(instance_id.clone(), metadata);
+        self.notify_watchers(&instance_id, status);
+        self.declared_providers
+            .insert(instance_id.clone(), provider_types(&capabilities));
 
         if capabilities.is_empty() {
             self.capabilities.remove(&instance_id);
         } else {
-            self.capabilities.insert(instance_id, capabilities);
+            self.capabilities
+                .insert(instance_id, compiled(capabilities));
+        }
+    }
+
+    /// Export instance metadata, capability assignments and crash history
+    pub fn export_state(&self) -> Result<ControlPlaneSnapshot> {
+        Ok(ControlPlaneSnapshot {
+            instances: self.instances.values().cloned().collect(),
+            capabilities: self.capabilities.values().flatten().cloned().collect(),
+            crashed_instances: self.crashed_instances.keys().cloned().collect(),
+            events: self.event_recorder.get_events().to_vec(),
+            crash_history: self
+                .crash_history
+                .iter()
+                .map(|(instance_id, crashes)| {
+                    let crashes = crashes.iter().map(|at| instant_to_utc(*at)).collect();
+                    (instance_id.clone(), crashes)
+                })
+                .collect(),
+        })
+    }
+
+    /// Replace the current state with a previously exported snapshot.
+    /// The snapshot is validated before any state is modified.
+    pub fn import_state(&mut self, snapshot: ControlPlaneSnapshot) -> Result<()> {
+        let mut instances = HashMap::new();
+        for mut metadata in snapshot.instances {
+            // Snapshots written before hashes were tagged hold bare md5 hex
+            metadata.module_hash = migrate_legacy_hash(&metadata.module_hash);
+            let instance_id = metadata.instance_id.clone();
+            if instances.insert(instance_id.clone(), metadata).is_some() {
+                return Err(CoreError::InvalidInstanceId(format!(
+                    "Duplicate instance {} in snapshot",
+                    instance_id
+                )));
+            }
+        }
+
+        let mut capabilities: HashMap<String, Vec<CapabilityAssignment>> = HashMap::new();
+        for mut assignment in snapshot.capabilities {
+            if !instances.contains_key(&assignment.instance_id) {
+                return Err(CoreError::InvalidCapabilityAssignment(format!(
+                    "Capability {} references missing instance {}",
+                    assignment.capability_id, assignment.instance_id
+                )));
+            }
+            assignment.compile_permissions();
+            capabilities
+                .entry(assignment.instance_id.clone())
+                .or_default()
+                .push(assignment);
+        }
+
+        let mut crash_history = HashMap::new();
+        for (instance_id, crashes) in snapshot.crash_history {
+            if !instances.contains_key(&instance_id) {
+                return Err(CoreError::InvalidInstanceId(format!(
+                    "Crash history for instance {} missing from snapshot",
+                    instance_id
+                )));
+            }
+            let mut crashes: Vec<std::time::Instant> =
+                crashes.into_iter().map(utc_to_instant).collect();
+            crashes.sort();
+            crash_history.insert(instance_id, crashes);
         }
+
+        let mut crashed_instances = HashMap::new();
+        for instance_id in snapshot.crashed_instances {
+            if !instances.contains_key(&instance_id) {
+                return Err(CoreError::InvalidInstanceId(format!(
+                    "Crashed instance {} missing from snapshot",
+                    instance_id
+                )));
+            }
+            let crashed_at = crash_history
+                .get(&instance_id)
+                .and_then(|crashes| crashes.last().copied())
+                .unwrap_or_else(std::time::Instant::now);
+            crashed_instances.insert(instance_id, crashed_at);
+        }
+
+        let mut event_recorder = ExecutionEventRecorder::new();
+        for event in snapshot.events {
+            event_recorder.record_event(event);
+        }
+
+        // The original start requests are not part of the snapshot, so treat
+        // the restored grants as what each instance declared.
+        self.declared_providers = instances
+            .keys()
+            .map(|instance_id| {
+                let declared = capabilities
+                    .get(instance_id)
+                    .map(|assignments| provider_types(assignments))
+                    .unwrap_or_default();
+                (instance_id.clone(), declared)
+            })
+            .collect();
+        self.instances = instances;
+        let instances = &self.instances;
+        self.status_watchers
+            .retain(|instance_id, _| instances.contains_key(instance_id));
+        self.capabilities = capabilities;
+        self.crashed_instances = crashed_instances;
+        self.crash_history = crash_history;
+        self.event_recorder = event_recorder;
+        Ok(())
     }
 
     /// Update instance status (called by Node Agent)
@@ -243,14 +760,83 @@ impl ControlPlane {
         instance_id: &str,
         status: InstanceStatus,
     ) -> Result<()> {
-        if let Some(metadata) = self.instances.get_mut(instance_id) {
-            metadata.status = status;
+        if self.set_status(instance_id, status) {
             Ok(())
         } else {
             Err(CoreError::InvalidInstanceId(instance_id.to_string()))
         }
     }
 
+    /// Current status of an instance plus a receiver for every later change.
+    ///
+    /// Dropping the receiver unsubscribes; the channel closes when the
+    /// instance's metadata is removed.
+    pub fn watch_instance(
+        &mut self,
+        instance_id: &str,
+    ) -> std::result::Result<(InstanceStatus, broadcast::Receiver<InstanceStatus>), ErrorResponse>
+    {
+        let Some(metadata) = self.instances.get(instance_id) else {
+            return Err(ErrorResponse::new(
+                "INSTANCE_NOT_FOUND",
+                format!("Instance {} not found", instance_id),
+            ));
+        };
+        let receiver = self
+            .status_watchers
+            .entry(instance_id.to_string())
+            .or_insert_with(|| broadcast::channel(STATUS_WATCH_CAPACITY).0)
+            .subscribe();
+        Ok((metadata.status, receiver))
+    }
+
+    /// Set an instance's status, stamping `updated_at` and notifying watchers
+    /// if it changed; false when the instance is unknown
+    fn set_status(&mut self, instance_id: &str, status: InstanceStatus) -> bool {
+        let Some(metadata) = self.instances.get_mut(instance_id) else {
+            return false;
+        };
+        if metadata.status != status {
+            metadata.status = status;
+            metadata.updated_at = Some(chrono::Utc::now());
+            self.notify_watchers(instance_id, status);
+        }
+        true
+    }
+
+    fn notify_watchers(&mut self, instance_id: &str, status: InstanceStatus) {
+        if let Some(sender) = self.status_watchers.get(instance_id) {
+            // Every receiver is gone, so nobody is watching any more
+            if sender.send(status).is_err() {
+                self.status_watchers.remove(instance_id);
+            }
+        }
+    }
+
+    /// Re-tag an instance without restarting it. With `merge` the given keys
+    /// are upserted into the existing labels; otherwise they replace them.
+    pub fn update_labels(
+        &mut self,
+        instance_id: &str,
+        labels: HashMap<String, String>,
+        merge: bool,
+    ) -> std::result::Result<(), ErrorResponse> {
+        let metadata = self.instances.get_mut(instance_id).ok_or_else(|| {
+            ErrorResponse::new(
+                "INSTANCE_NOT_FOUND",
+                format!("Instance {} not found", instance_id),
+            )
+        })?;
+
+        if merge {
+            metadata.labels.extend(labels);
+        } else {
+            metadata.labels = labels;
+        }
+        metadata.updated_at = Some(chrono::Utc::now());
+        Ok(())
+    }
+
     /// Record an instance crash and update system state
     /// Implements crash recovery logic that preserves system-level state
     pub fn record_instance_crash(
@@ -272,13 +858,16 @@ impl ControlPlane {
         self.event_recorder.record_crash(instance_id, &error_msg);
 
         // Mark instance as crashed
+        let crashed_at = std::time::Instant::now();
         self.crashed_instances
-            .insert(instance_id.to_string(), std::time::Instant::now());
+            .insert(instance_id.to_string(), crashed_at);
+        self.crash_history
+            .entry(instance_id.to_string())
+            .or_default()
+            .push(crashed_at);
 
         // Update instance status to Crashed
-        if let Some(metadata) = self.instances.get_mut(instance_id) {
-            metadata.status = InstanceStatus::Crashed;
-        }
+        self.set_status(instance_id, InstanceStatus::Crashed);
 
         Ok(())
     }
@@ -307,23 +896,52 @@ impl ControlPlane {
         self.event_recorder.record_restart(instance_id);
 
         // Reset instance status to Starting
-        if let Some(metadata) = self.instances.get_mut(instance_id) {
-            metadata.status = InstanceStatus::Starting;
-        }
+        self.set_status(instance_id, InstanceStatus::Starting);
 
         Ok(())
     }
 
     /// Get crash recovery information for an instance
     pub fn get_crash_info(&self, instance_id: &str) -> Option<CrashInfo> {
-        if self.crashed_instances.contains_key(instance_id) {
-            Some(CrashInfo {
-                crash_count: 1, // Simplified: actual implementation would track full history
-                last_crash_time: Some(std::time::Instant::now()),
+        let crashed_at = self.crashed_instances.get(instance_id)?;
+        let crash_count = self
+            .crash_history
+            .get(instance_id)
+            .map_or(0, |crashes| crashes.len() as u32);
+        Some(CrashInfo {
+            crash_count,
+            last_crash_time: Some(*crashed_at),
+        })
+    }
+
+    /// Instances that crashed more than `threshold` times within the last
+    /// `window`, paired with that crash count, most crashes first
+    pub fn list_crash_looping(
+        &self,
+        threshold: u32,
+        window: std::time::Duration,
+    ) -> Vec<(&InstanceMetadata, u32)> {
+        let mut looping: Vec<(&InstanceMetadata, u32)> = self
+            .crash_history
+            .iter()
+            .filter_map(|(instance_id, crashes)| {
+                let recent = crashes
+                    .iter()
+                    .filter(|crashed_at| crashed_at.elapsed() <= window)
+                    .count() as u32;
+                if recent <= threshold {
+                    return None;
+                }
+                self.instances
+                    .get(instance_id)
+                    .map(|metadata| (metadata, recent))
             })
-        } else {
-            None
-        }
+            .collect();
+        looping.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| a.0.instance_id.cmp(&b.0.instance_id))
+        });
+        looping
     }
 
     /// Check if an instance is currently in crashed state
@@ -336,6 +954,11 @@ impl ControlPlane {
         self.event_recorder.get_events()
     }
 
+    /// Recorded execution events per event type, e.g. `instance_crashed`
+    pub fn event_counts(&self) -> HashMap<String, usize> {
+        self.event_recorder.event_counts().clone()
+    }
+
     /// Get execution events for a specific instance
     pub fn get_execution_events_for_instance(
         &self,
@@ -343,6 +966,68 @@ impl ControlPlane {
     ) -> Vec<&wasmatrix_core::ExecutionEvent> {
         self.event_recorder.get_events_for_instance(instance_id)
     }
+
+    /// Lifecycle transitions of one instance, oldest first.
+    ///
+    /// Start events are recorded by the node agent, so the first entry is
+    /// derived from the instance's `created_at`. Entries carry only the event
+    /// type and timestamp; details such as crash messages are left out.
+    pub fn get_instance_history(&self, instance_id: &str) -> Vec<wasmatrix_core::ExecutionEvent> {
+        let Some(metadata) = self.instances.get(instance_id) else {
+            return Vec::new();
+        };
+
+        let mut history = vec![wasmatrix_core::ExecutionEvent {
+            event_type: "instance_started".to_string(),
+            instance_id: instance_id.to_string(),
+            timestamp: metadata.created_at,
+            details: None,
+            seq: 0,
+        }];
+        history.extend(
+            self.event_recorder
+                .get_events_for_instance(instance_id)
+                .into_iter()
+                .filter(|event| event.event_type != "instance_started")
+                .map(|event| wasmatrix_core::ExecutionEvent {
+                    details: None,
+                    ..event.clone()
+                }),
+        );
+        history
+    }
+}
+
+fn provider_types(assignments: &[CapabilityAssignment]) -> HashSet<ProviderType> {
+    assignments.iter().map(|a| a.provider_type).collect()
+}
+
+/// Assignments with their permissions compiled for the checks made once stored
+fn compiled(mut assignments: Vec<CapabilityAssignment>) -> Vec<CapabilityAssignment> {
+    assignments
+        .iter_mut()
+        .for_each(CapabilityAssignment::compile_permissions);
+    assignments
+}
+
+/// Wall-clock time of a monotonic instant, for snapshots
+fn instant_to_utc(at: std::time::Instant) -> chrono::DateTime<chrono::Utc> {
+    let age = chrono::Duration::from_std(at.elapsed()).unwrap_or_else(|_| chrono::Duration::zero());
+    chrono::Utc::now() - age
+}
+
+/// Monotonic instant of a wall-clock time restored from a snapshot; times
+/// older than the monotonic clock can represent are restored as far back as
+/// it allows
+fn utc_to_instant(at: chrono::DateTime<chrono::Utc>) -> std::time::Instant {
+    let now = std::time::Instant::now();
+    let mut age = (chrono::Utc::now() - at).to_std().unwrap_or_default();
+    loop {
+        match now.checked_sub(age) {
+            Some(instant) => return instant,
+            None => age /= 2,
+        }
+    }
 }
 
 impl Default for ControlPlane {
@@ -413,6 +1098,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -421,27 +1107,132 @@ mod tests {
     }
 
     #[test]
-    fn test_start_instance_empty_module() {
+    fn test_stop_deployment_stops_all_members() {
         let mut cp = ControlPlane::new("node-1");
-        let request = StartInstanceRequest {
-            module_bytes: vec![],
-            capabilities: vec![],
-            restart_policy: RestartPolicy::default(),
+        let mut start = |deployment_id: Option<&str>| {
+            cp.start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: deployment_id.map(str::to_string),
+            })
+            .unwrap()
         };
+        let mut replicas: Vec<String> = (0..3).map(|_| start(Some("web"))).collect();
+        let standalone = start(None);
 
-        let result = cp.start_instance(request);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().error_code, "INVALID_REQUEST");
+        let mut members: Vec<String> = cp
+            .list_deployment("web")
+            .into_iter()
+            .map(|metadata| metadata.instance_id.clone())
+            .collect();
+        members.sort();
+        replicas.sort();
+        assert_eq!(members, replicas);
+
+        let mut stopped = cp.stop_deployment("web").unwrap();
+        stopped.sort();
+        assert_eq!(stopped, replicas);
+        assert!(cp
+            .list_deployment("web")
+            .iter()
+            .all(|metadata| metadata.status == InstanceStatus::Stopped));
+        assert_eq!(
+            cp.get_instance(&standalone).unwrap().status,
+            InstanceStatus::Starting
+        );
+
+        assert!(cp.stop_deployment("web").unwrap().is_empty());
+        assert!(cp.stop_deployment("missing").unwrap().is_empty());
     }
 
     #[test]
-    fn test_start_instance_invalid_wasm() {
+    fn test_start_instance_rejects_empty_deployment_id() {
         let mut cp = ControlPlane::new("node-1");
-        let request = StartInstanceRequest {
-            module_bytes: vec![0x00, 0x00, 0x00, 0x00],
+        let result = cp.start_instance(StartInstanceRequest {
+            module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
-        };
+            deployment_id: Some(" ".to_string()),
+        });
+
+        assert_eq!(result.unwrap_err().error_code, "INVALID_REQUEST");
+    }
+
+    #[test]
+    fn test_list_instances_by_module_hash() {
+        let mut cp = ControlPlane::new("node-1");
+        let old_module = create_valid_wasm_module();
+        let mut new_module = create_valid_wasm_module();
+        new_module.extend_from_slice(&[0x00, 0x00]);
+
+        let mut start = |module_bytes: &Vec<u8>| {
+            cp.start_instance(StartInstanceRequest {
+                module_bytes: module_bytes.clone(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap()
+        };
+        let old_a = start(&old_module);
+        let old_b = start(&old_module);
+        start(&new_module);
+
+        let old_hash = HashScheme::Sha256.compute(&old_module);
+        let mut ids: Vec<_> = cp
+            .list_instances_by_module_hash(&old_hash)
+            .into_iter()
+            .map(|metadata| metadata.instance_id.clone())
+            .collect();
+        ids.sort();
+        let mut expected = vec![old_a, old_b];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        assert!(cp.list_instances_by_module_hash("unknown").is_empty());
+        // A legacy md5 hash of the same module names a different scheme
+        assert!(cp
+            .list_instances_by_module_hash(&HashScheme::Md5.compute(&old_module))
+            .is_empty());
+
+        let mut legacy = ControlPlane::new("node-1").with_module_hash_scheme(HashScheme::Md5);
+        legacy
+            .start_instance(StartInstanceRequest {
+                module_bytes: old_module.clone(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        let bare_md5 = format!("{:x}", md5::compute(&old_module));
+        assert_eq!(legacy.list_instances_by_module_hash(&bare_md5).len(), 1);
+    }
+
+    #[test]
+    fn test_start_instance_empty_module() {
+        let mut cp = ControlPlane::new("node-1");
+        let request = StartInstanceRequest {
+            module_bytes: vec![],
+            capabilities: vec![],
+            restart_policy: RestartPolicy::default(),
+            deployment_id: None,
+        };
+
+        let result = cp.start_instance(request);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().error_code, "INVALID_REQUEST");
+    }
+
+    #[test]
+    fn test_start_instance_invalid_wasm() {
+        let mut cp = ControlPlane::new("node-1");
+        let request = StartInstanceRequest {
+            module_bytes: vec![0x00, 0x00, 0x00, 0x00],
+            capabilities: vec![],
+            restart_policy: RestartPolicy::default(),
+            deployment_id: None,
+        };
 
         let result = cp.start_instance(request);
         assert!(result.is_err());
@@ -455,6 +1246,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -469,6 +1261,47 @@ mod tests {
         );
     }
 
+    fn start_and_stop(cp: &mut ControlPlane) -> String {
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        cp.stop_instance(StopInstanceRequest {
+            instance_id: instance_id.clone(),
+        })
+        .unwrap();
+        instance_id
+    }
+
+    #[test]
+    fn test_stop_behavior_retain_metadata() {
+        let mut cp = ControlPlane::new("node-1").with_stop_behavior(StopBehavior::RetainMetadata);
+        let instance_id = start_and_stop(&mut cp);
+
+        let response = cp
+            .query_instance(QueryInstanceRequest { instance_id })
+            .unwrap();
+        assert_eq!(response.status, InstanceStatus::Stopped);
+    }
+
+    #[test]
+    fn test_stop_behavior_remove_metadata() {
+        let mut cp = ControlPlane::new("node-1").with_stop_behavior(StopBehavior::RemoveMetadata);
+        let instance_id = start_and_stop(&mut cp);
+
+        let error = cp
+            .query_instance(QueryInstanceRequest {
+                instance_id: instance_id.clone(),
+            })
+            .unwrap_err();
+        assert_eq!(error.error_code, "INSTANCE_NOT_FOUND");
+        assert!(cp.get_capabilities(&instance_id).is_none());
+    }
+
     #[test]
     fn test_stop_instance_not_found() {
         let mut cp = ControlPlane::new("node-1");
@@ -481,6 +1314,62 @@ mod tests {
         assert_eq!(result.unwrap_err().error_code, "INSTANCE_NOT_FOUND");
     }
 
+    #[test]
+    fn test_ensure_stopped_is_idempotent() {
+        let mut cp = ControlPlane::new("node-1").with_stop_behavior(StopBehavior::RemoveMetadata);
+        let instance_id = start_and_stop(&mut cp);
+
+        let strict = cp.stop_instance(StopInstanceRequest {
+            instance_id: instance_id.clone(),
+        });
+        assert_eq!(strict.unwrap_err().error_code, "INSTANCE_NOT_FOUND");
+
+        assert!(cp
+            .ensure_stopped(StopInstanceRequest {
+                instance_id: instance_id.clone(),
+            })
+            .is_ok());
+        assert!(cp
+            .ensure_stopped(StopInstanceRequest {
+                instance_id: "never-existed".to_string(),
+            })
+            .is_ok());
+        assert_eq!(
+            cp.ensure_stopped(StopInstanceRequest {
+                instance_id: String::new(),
+            })
+            .unwrap_err()
+            .error_code,
+            "INVALID_REQUEST"
+        );
+    }
+
+    #[test]
+    fn test_ensure_stopped_stops_running_instance() {
+        let mut cp = ControlPlane::new("node-1").with_stop_behavior(StopBehavior::RetainMetadata);
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+
+        cp.ensure_stopped(StopInstanceRequest {
+            instance_id: instance_id.clone(),
+        })
+        .unwrap();
+        cp.ensure_stopped(StopInstanceRequest {
+            instance_id: instance_id.clone(),
+        })
+        .unwrap();
+        assert_eq!(
+            cp.get_instance(&instance_id).unwrap().status,
+            InstanceStatus::Stopped
+        );
+    }
+
     #[test]
     fn test_query_instance_success() {
         let mut cp = ControlPlane::new("node-1");
@@ -488,6 +1377,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -518,6 +1408,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -534,6 +1425,284 @@ mod tests {
         assert_eq!(capabilities[0].capability_id, "kv-1");
     }
 
+    #[test]
+    fn test_instance_history_is_ordered_and_payload_free() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        let other_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        cp.record_instance_crash(&instance_id, "trap").unwrap();
+
+        let history = cp.get_instance_history(&instance_id);
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].event_type, "instance_started");
+        assert_eq!(history[1].event_type, "instance_crashed");
+        assert!(history[0].timestamp <= history[1].timestamp);
+        assert!(history.iter().all(|e| e.details.is_none()));
+        assert_eq!(cp.get_instance_history(&other_id).len(), 1);
+    }
+
+    #[test]
+    fn test_update_labels_merges_or_replaces() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        let labels = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert!(cp.get_instance(&instance_id).unwrap().updated_at.is_none());
+
+        cp.update_labels(
+            &instance_id,
+            labels(&[("tier", "web"), ("track", "stable")]),
+            true,
+        )
+        .unwrap();
+        cp.update_labels(&instance_id, labels(&[("track", "canary")]), true)
+            .unwrap();
+        let merged = cp.get_instance(&instance_id).unwrap();
+        assert_eq!(
+            merged.labels,
+            labels(&[("tier", "web"), ("track", "canary")])
+        );
+        let merged_at = merged.updated_at.unwrap();
+
+        cp.update_labels(&instance_id, labels(&[("owner", "ops")]), false)
+            .unwrap();
+        let replaced = cp.get_instance(&instance_id).unwrap();
+        assert_eq!(replaced.labels, labels(&[("owner", "ops")]));
+        assert!(replaced.updated_at.unwrap() >= merged_at);
+        assert_eq!(replaced.status, InstanceStatus::Starting);
+    }
+
+    #[test]
+    fn test_update_labels_missing_instance() {
+        let mut cp = ControlPlane::new("node-1");
+
+        let err = cp
+            .update_labels("missing", HashMap::new(), true)
+            .unwrap_err();
+
+        assert_eq!(err.error_code, "INSTANCE_NOT_FOUND");
+    }
+
+    #[test]
+    fn test_reap_stopped_removes_only_old_stopped_instances() {
+        let mut cp = ControlPlane::new("node-1");
+        let start = |cp: &mut ControlPlane| {
+            let instance_id = cp
+                .start_instance(StartInstanceRequest {
+                    module_bytes: create_valid_wasm_module(),
+                    capabilities: vec![CapabilityAssignment::new(
+                        String::new(),
+                        "kv-1".to_string(),
+                        ProviderType::Kv,
+                        vec!["kv:read".to_string()],
+                    )],
+                    restart_policy: RestartPolicy::default(),
+                    deployment_id: None,
+                })
+                .unwrap();
+            cp.stop_instance(StopInstanceRequest {
+                instance_id: instance_id.clone(),
+            })
+            .unwrap();
+            instance_id
+        };
+        let old = start(&mut cp);
+        let recent = start(&mut cp);
+        let running = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        cp.instances.get_mut(&old).unwrap().updated_at =
+            Some(chrono::Utc::now() - chrono::Duration::hours(2));
+
+        let reaped = cp.reap_stopped(std::time::Duration::from_secs(3600));
+
+        assert_eq!(reaped, vec![old.clone()]);
+        assert!(cp.get_instance(&old).is_none());
+        assert!(cp.get_capabilities(&old).is_none());
+        assert!(cp.get_instance(&recent).is_some());
+        assert!(cp.get_capabilities(&recent).is_some());
+        assert!(cp.get_instance(&running).is_some());
+    }
+
+    #[test]
+    fn test_event_counts_group_by_kind() {
+        let mut cp = ControlPlane::new("node-1");
+        let mut start = || {
+            cp.start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap()
+        };
+        let first = start();
+        let second = start();
+        assert!(cp.event_counts().is_empty());
+
+        cp.record_instance_crash(&first, "trap").unwrap();
+        cp.handle_crash_recovery(&first).unwrap();
+        cp.record_instance_crash(&first, "trap").unwrap();
+        cp.record_instance_crash(&second, "oom").unwrap();
+        cp.handle_crash_recovery(&second).unwrap();
+
+        let counts = cp.event_counts();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["instance_crashed"], 3);
+        assert_eq!(counts["instance_restarted"], 2);
+    }
+
+    #[test]
+    fn test_assign_capabilities_applies_valid_batch() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+
+        cp.assign_capabilities(
+            &instance_id,
+            vec![
+                CapabilityAssignment::new(
+                    instance_id.clone(),
+                    "kv-1".to_string(),
+                    ProviderType::Kv,
+                    vec!["kv:read".to_string()],
+                ),
+                CapabilityAssignment::new(
+                    instance_id.clone(),
+                    "http-1".to_string(),
+                    ProviderType::Http,
+                    vec!["http:request".to_string()],
+                ),
+            ],
+        )
+        .unwrap();
+
+        let capabilities = cp.get_capabilities(&instance_id).unwrap();
+        assert_eq!(capabilities.len(), 2);
+        assert_eq!(capabilities[0].capability_id, "kv-1");
+        assert_eq!(capabilities[1].capability_id, "http-1");
+    }
+
+    #[test]
+    fn test_max_capabilities_per_instance() {
+        let mut cp = ControlPlane::new("node-1").with_max_capabilities_per_instance(3);
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        let kv = |capability_id: &str| {
+            CapabilityAssignment::new(
+                instance_id.clone(),
+                capability_id.to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )
+        };
+
+        cp.assign_capability(kv("kv-1")).unwrap();
+        cp.assign_capabilities(&instance_id, vec![kv("kv-2"), kv("kv-3")])
+            .unwrap();
+
+        let rejected = cp.assign_capability(kv("kv-4")).unwrap_err();
+        assert_eq!(rejected.error_code, "RESOURCE_EXHAUSTED");
+        let rejected_batch = cp
+            .assign_capabilities(&instance_id, vec![kv("kv-4")])
+            .unwrap_err();
+        assert_eq!(rejected_batch.error_code, "RESOURCE_EXHAUSTED");
+        assert_eq!(cp.get_capabilities(&instance_id).unwrap().len(), 3);
+
+        cp.revoke_capability(&instance_id, "kv-2").unwrap();
+        cp.assign_capability(kv("kv-4")).unwrap();
+        assert_eq!(cp.get_capabilities(&instance_id).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_assign_capabilities_rejects_whole_batch_on_invalid_entry() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+
+        let result = cp.assign_capabilities(
+            &instance_id,
+            vec![
+                CapabilityAssignment::new(
+                    instance_id.clone(),
+                    "kv-1".to_string(),
+                    ProviderType::Kv,
+                    vec!["kv:read".to_string()],
+                ),
+                CapabilityAssignment::new(
+                    instance_id.clone(),
+                    "http-1".to_string(),
+                    ProviderType::Http,
+                    vec![],
+                ),
+            ],
+        );
+
+        assert_eq!(result.unwrap_err().error_code, "INVALID_REQUEST");
+        assert!(cp.get_capabilities(&instance_id).is_none());
+
+        let mismatched = cp.assign_capabilities(
+            &instance_id,
+            vec![CapabilityAssignment::new(
+                "other-instance".to_string(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )],
+        );
+        assert_eq!(mismatched.unwrap_err().error_code, "INVALID_REQUEST");
+        assert!(cp.get_capabilities(&instance_id).is_none());
+    }
+
     #[test]
     fn test_revoke_capability_success() {
         let mut cp = ControlPlane::new("node-1");
@@ -541,6 +1710,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -557,6 +1727,178 @@ mod tests {
         assert!(cp.get_capabilities(&instance_id).is_none());
     }
 
+    #[test]
+    fn test_declared_provider_enforcement() {
+        let mut cp = ControlPlane::new("node-1").with_declared_provider_enforcement(true);
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![CapabilityAssignment::new(
+                    String::new(),
+                    "kv-1".to_string(),
+                    ProviderType::Kv,
+                    vec!["kv:read".to_string()],
+                )],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        let kv = CapabilityAssignment::new(
+            instance_id.clone(),
+            "kv-2".to_string(),
+            ProviderType::Kv,
+            vec!["kv:write".to_string()],
+        );
+        let http = CapabilityAssignment::new(
+            instance_id.clone(),
+            "http-1".to_string(),
+            ProviderType::Http,
+            vec!["http:request".to_string()],
+        );
+
+        assert!(cp.assign_capability(kv).is_ok());
+        assert_eq!(
+            cp.assign_capability(http.clone()).unwrap_err().error_code,
+            "PERMISSION_DENIED"
+        );
+        assert_eq!(cp.get_capabilities(&instance_id).unwrap().len(), 2);
+
+        cp.set_declared_provider_enforcement(false);
+        assert!(cp.assign_capability(http).is_ok());
+    }
+
+    #[test]
+    fn test_revoke_all_capabilities_removes_every_grant() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        for (capability_id, provider_type, permission) in [
+            ("kv-1", ProviderType::Kv, "kv:read"),
+            ("kv-2", ProviderType::Kv, "kv:write"),
+            ("http-1", ProviderType::Http, "http:request"),
+        ] {
+            cp.assign_capability(CapabilityAssignment::new(
+                instance_id.clone(),
+                capability_id.to_string(),
+                provider_type,
+                vec![permission.to_string()],
+            ))
+            .unwrap();
+        }
+
+        assert_eq!(cp.revoke_all_capabilities(&instance_id).unwrap(), 3);
+        assert!(cp.get_capabilities(&instance_id).is_none());
+        assert!(cp.get_instance(&instance_id).is_some());
+    }
+
+    #[test]
+    fn test_pending_capability_grants_nothing_until_approved() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        cp.assign_capability(
+            CapabilityAssignment::new(
+                instance_id.clone(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )
+            .pending(),
+        )
+        .unwrap();
+        let grants = |cp: &ControlPlane| {
+            cp.get_capabilities(&instance_id).unwrap()[0].has_permission("kv:read")
+        };
+
+        assert!(!grants(&cp));
+        cp.approve_capability(&instance_id, "kv-1").unwrap();
+        assert!(grants(&cp));
+
+        cp.deny_capability(&instance_id, "kv-1").unwrap();
+        assert!(!grants(&cp));
+        assert_eq!(
+            cp.approve_capability(&instance_id, "kv-1")
+                .unwrap_err()
+                .error_code,
+            "INVALID_REQUEST"
+        );
+        assert_eq!(
+            cp.deny_capability(&instance_id, "missing")
+                .unwrap_err()
+                .error_code,
+            "CAPABILITY_NOT_FOUND"
+        );
+    }
+
+    #[test]
+    fn test_effective_permissions_combine_assignments() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        cp.assign_capability(CapabilityAssignment::new(
+            instance_id.clone(),
+            "kv-1".to_string(),
+            ProviderType::Kv,
+            vec!["kv:*".to_string(), "kv:read".to_string()],
+        ))
+        .unwrap();
+        cp.assign_capability(CapabilityAssignment::new(
+            instance_id.clone(),
+            "http-1".to_string(),
+            ProviderType::Http,
+            vec!["http:request".to_string(), "kv:read".to_string()],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            cp.effective_permissions(&instance_id),
+            vec!["http:request", "kv:*", "kv:read"]
+        );
+        assert!(cp.can(&instance_id, "kv:write"));
+        assert!(cp.can(&instance_id, "http:request"));
+        assert!(!cp.can(&instance_id, "msg:publish"));
+        assert!(cp.effective_permissions("missing").is_empty());
+    }
+
+    #[test]
+    fn test_revoke_all_capabilities_without_grants_or_instance() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+
+        assert_eq!(cp.revoke_all_capabilities(&instance_id).unwrap(), 0);
+        assert_eq!(
+            cp.revoke_all_capabilities("non-existent")
+                .unwrap_err()
+                .error_code,
+            "INSTANCE_NOT_FOUND"
+        );
+    }
+
     #[test]
     fn test_list_instances() {
         let mut cp = ControlPlane::new("node-1");
@@ -567,6 +1909,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
             cp.start_instance(request).unwrap();
         }
@@ -584,6 +1927,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -605,6 +1949,30 @@ mod tests {
         assert_eq!(events[0].event_type, "instance_crashed");
     }
 
+    #[test]
+    fn test_list_crash_looping_returns_instances_over_threshold() {
+        let mut cp = ControlPlane::new("node-1");
+        let start = || StartInstanceRequest {
+            module_bytes: create_valid_wasm_module(),
+            capabilities: vec![],
+            restart_policy: RestartPolicy::default(),
+            deployment_id: None,
+        };
+        let looping_id = cp.start_instance(start()).unwrap();
+        let stable_id = cp.start_instance(start()).unwrap();
+
+        for _ in 0..4 {
+            cp.record_instance_crash(&looping_id, "trap").unwrap();
+            cp.handle_crash_recovery(&looping_id).unwrap();
+        }
+        cp.record_instance_crash(&stable_id, "trap").unwrap();
+
+        let looping = cp.list_crash_looping(2, std::time::Duration::from_secs(60));
+        assert_eq!(looping.len(), 1);
+        assert_eq!(looping[0].0.instance_id, looping_id);
+        assert_eq!(looping[0].1, 4);
+    }
+
     #[test]
     fn test_record_instance_crash_not_found() {
         let mut cp = ControlPlane::new("node-1");
@@ -621,6 +1989,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -664,6 +2033,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -703,6 +2073,7 @@ mod tests {
                     module_bytes: create_valid_wasm_module(),
                     capabilities: vec![],
                     restart_policy: RestartPolicy::default(),
+                    deployment_id: None,
                 };
                 cp.start_instance(request).unwrap()
             })
@@ -721,6 +2092,138 @@ mod tests {
         assert_eq!(cp.list_instances().len(), 3);
     }
 
+    #[test]
+    fn test_prune_expired_capabilities() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+
+        let grant = |capability_id: &str| {
+            CapabilityAssignment::new(
+                instance_id.clone(),
+                capability_id.to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )
+        };
+        cp.assign_capability(grant("kv-permanent")).unwrap();
+        cp.assign_capability(
+            grant("kv-active").with_expiry(chrono::Utc::now() + chrono::Duration::hours(1)),
+        )
+        .unwrap();
+        cp.assign_capability(
+            grant("kv-expired").with_expiry(chrono::Utc::now() - chrono::Duration::seconds(1)),
+        )
+        .unwrap();
+
+        assert_eq!(cp.prune_expired_capabilities(), 1);
+
+        let remaining: Vec<&str> = cp
+            .get_capabilities(&instance_id)
+            .unwrap()
+            .iter()
+            .map(|a| a.capability_id.as_str())
+            .collect();
+        assert_eq!(remaining, vec!["kv-permanent", "kv-active"]);
+        assert_eq!(cp.prune_expired_capabilities(), 0);
+    }
+
+    #[test]
+    fn test_export_import_state_round_trip() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_ids: Vec<String> = (0..2)
+            .map(|_| {
+                cp.start_instance(StartInstanceRequest {
+                    module_bytes: create_valid_wasm_module(),
+                    capabilities: vec![],
+                    restart_policy: RestartPolicy::default(),
+                    deployment_id: None,
+                })
+                .unwrap()
+            })
+            .collect();
+        cp.assign_capability(CapabilityAssignment::new(
+            instance_ids[0].clone(),
+            "kv-1".to_string(),
+            ProviderType::Kv,
+            vec!["kv:read".to_string()],
+        ))
+        .unwrap();
+        cp.record_instance_crash(&instance_ids[1], "test error")
+            .unwrap();
+
+        let snapshot = cp.export_state().unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let decoded: ControlPlaneSnapshot = serde_json::from_str(&json).unwrap();
+
+        let mut restored = ControlPlane::new("node-2");
+        restored.import_state(decoded).unwrap();
+
+        assert_eq!(restored.list_instances().len(), 2);
+        let capabilities = restored.get_capabilities(&instance_ids[0]).unwrap();
+        assert_eq!(capabilities.len(), 1);
+        assert!(capabilities[0].has_permission("kv:read"));
+        assert!(!restored.is_instance_crashed(&instance_ids[0]));
+        assert!(restored.is_instance_crashed(&instance_ids[1]));
+        assert_eq!(
+            restored.get_instance(&instance_ids[1]).unwrap().status,
+            InstanceStatus::Crashed
+        );
+        assert_eq!(
+            restored
+                .get_execution_events_for_instance(&instance_ids[1])
+                .len(),
+            1
+        );
+        assert_eq!(
+            restored
+                .get_crash_info(&instance_ids[1])
+                .unwrap()
+                .crash_count,
+            1
+        );
+        let looping = restored.list_crash_looping(0, std::time::Duration::from_secs(60));
+        assert_eq!(looping.len(), 1);
+        assert_eq!(looping[0].0.instance_id, instance_ids[1]);
+    }
+
+    #[test]
+    fn test_import_state_rejects_capabilities_for_missing_instance() {
+        let mut cp = ControlPlane::new("node-1");
+        let instance_id = cp
+            .start_instance(StartInstanceRequest {
+                module_bytes: create_valid_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+
+        let snapshot = ControlPlaneSnapshot {
+            capabilities: vec![CapabilityAssignment::new(
+                "missing".to_string(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )],
+            ..ControlPlaneSnapshot::default()
+        };
+
+        let result = cp.import_state(snapshot);
+        assert!(matches!(
+            result,
+            Err(CoreError::InvalidCapabilityAssignment(_))
+        ));
+        // Existing state is untouched on validation failure
+        assert!(cp.get_instance(&instance_id).is_some());
+    }
+
     // === Task 2.4: Unit Tests for Control Plane API Handlers ===
 
     #[test]
@@ -769,6 +2272,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -791,6 +2295,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -822,6 +2327,7 @@ mod tests {
             module_bytes: create_valid_wasm_module(),
             capabilities: vec![],
             restart_policy: RestartPolicy::default(),
+            deployment_id: None,
         };
 
         let instance_id = cp.start_instance(request).unwrap();
@@ -933,6 +2439,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
             let instance_id = cp.start_instance(request).unwrap();
 
@@ -968,6 +2475,7 @@ mod tests {
                     module_bytes: create_valid_wasm_module(),
                     capabilities: vec![],
                     restart_policy: RestartPolicy::default(),
+                    deployment_id: None,
                 };
                 let id = cp.start_instance(request).unwrap();
                 instance_ids.push(id);
@@ -1005,6 +2513,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
             let instance_id_1 = cp.start_instance(request1).unwrap();
 
@@ -1019,6 +2528,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
             let instance_id_2 = cp.start_instance(request2).unwrap();
 
@@ -1043,6 +2553,7 @@ mod tests {
                 module_bytes: vec![],
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
 
             let result = cp.start_instance(request);
@@ -1057,6 +2568,7 @@ mod tests {
                 module_bytes: vec![0x00, 0x00, 0x00, 0x00], // Invalid magic bytes
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
 
             let result = cp.start_instance(request);
@@ -1121,6 +2633,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
 
             let instance_id = cp.start_instance(request).unwrap();
@@ -1143,6 +2656,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
 
             let instance_id = cp.start_instance(request).unwrap();
@@ -1170,6 +2684,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
 
             let instance_id = cp.start_instance(request).unwrap();
@@ -1206,6 +2721,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
 
             let instance_id = cp.start_instance(request).unwrap();
@@ -1233,6 +2749,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
 
             let instance_id = cp.start_instance(request).unwrap();
@@ -1272,6 +2789,7 @@ mod tests {
                 module_bytes: create_valid_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             };
 
             let instance_id_1 = cp.start_instance(request.clone()).unwrap();
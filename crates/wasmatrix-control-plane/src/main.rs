@@ -3,18 +3,30 @@ use axum::Router;
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use tonic::transport::Server;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
+use wasmatrix_control_plane::features::control_plane_facade::controller::AsyncControlPlane;
+use wasmatrix_control_plane::features::instance_management::repo::ControlPlaneInstanceRepository;
+use wasmatrix_control_plane::features::instance_management::service::InstanceService;
+use wasmatrix_control_plane::features::module_verification::service::Ed25519ModuleVerifier;
 use wasmatrix_control_plane::features::node_routing::controller::NodeRoutingController;
 use wasmatrix_control_plane::features::node_routing::repo::etcd::{
     validate_etcd_config, EtcdConfig, EtcdMetadataRepository,
 };
 use wasmatrix_control_plane::features::node_routing::repo::InMemoryNodeRoutingRepository;
-use wasmatrix_control_plane::features::node_routing::service::NodeRoutingService;
+use wasmatrix_control_plane::features::node_routing::service::{
+    NodeRoutingService, PlacementStrategy,
+};
 use wasmatrix_control_plane::features::observability::controller::global_observability_controller;
 use wasmatrix_control_plane::server::ControlPlaneServer;
+use wasmatrix_core::module_hash::HashScheme;
+use wasmatrix_core::RestartPolicy;
+use wasmatrix_proto::tls::TlsConfig;
 use wasmatrix_proto::v1::control_plane_service_server::ControlPlaneServiceServer;
 
+/// How often stopped instances past their retention are reaped
+const STOPPED_INSTANCE_REAP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt()
@@ -33,9 +45,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("Starting Wasmatrix Control Plane");
 
-    let control_plane = Arc::new(Mutex::new(wasmatrix_control_plane::ControlPlane::new(
-        "node-1",
-    )));
+    let tls = match TlsConfig::from_env() {
+        Ok(Some(tls)) => {
+            info!("gRPC TLS enabled");
+            Some(tls)
+        }
+        Ok(None) => {
+            warn!("GRPC_TLS_* not set; gRPC traffic is plaintext");
+            None
+        }
+        Err(error) => {
+            error!(error = %error, "Failed to load gRPC TLS configuration");
+            return Err(error.into());
+        }
+    };
+    let mut server_builder = Server::builder();
+    if let Some(tls) = &tls {
+        // Dialing agents needs the CA; fail now rather than on the first placement
+        tls.client_config()?;
+        server_builder = server_builder.tls_config(tls.server_config()?)?;
+    }
+
+    let module_hash_scheme = match std::env::var("MODULE_HASH_SCHEME") {
+        Ok(value) => value.parse::<HashScheme>().unwrap_or_else(|error| {
+            warn!(error = %error, "Invalid MODULE_HASH_SCHEME, using the default");
+            HashScheme::default()
+        }),
+        Err(_) => HashScheme::default(),
+    };
+    info!(scheme = %module_hash_scheme, "Module hash scheme configured");
+    let control_plane = Arc::new(Mutex::new(
+        wasmatrix_control_plane::ControlPlane::new("node-1")
+            .with_module_hash_scheme(module_hash_scheme),
+    ));
 
     let mut etcd_metadata_repo: Option<Arc<EtcdMetadataRepository>> = None;
     if std::env::var("USE_ETCD").ok().as_deref() == Some("true") {
@@ -44,7 +86,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 warn!(error = %error, "Failed to validate etcd configuration");
             } else {
                 info!(endpoints = ?config.endpoints, "etcd configuration loaded");
-                etcd_metadata_repo = Some(Arc::new(EtcdMetadataRepository::new()));
+                etcd_metadata_repo =
+                    Some(Arc::new(EtcdMetadataRepository::new().with_config(config)));
             }
         } else {
             warn!("USE_ETCD is true but ETCD_ENDPOINTS is not configured");
@@ -52,11 +95,86 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let routing_repo = Arc::new(InMemoryNodeRoutingRepository::new());
-    let routing_service = if let Some(etcd_repo) = etcd_metadata_repo {
-        Arc::new(NodeRoutingService::new_with_etcd(routing_repo, etcd_repo))
+    let mut routing_service = if let Some(etcd_repo) = etcd_metadata_repo {
+        NodeRoutingService::new_with_etcd(routing_repo, etcd_repo)
     } else {
-        Arc::new(NodeRoutingService::new(routing_repo))
+        NodeRoutingService::new(routing_repo)
     };
+    if let Ok(trusted_keys) = std::env::var("MODULE_TRUSTED_KEYS") {
+        let verifier = Ed25519ModuleVerifier::from_hex_keys(&trusted_keys)?;
+        info!("Module signature verification enabled");
+        routing_service = routing_service.with_module_verifier(Arc::new(verifier));
+    }
+    match std::env::var("PLACEMENT_STRATEGY").ok().as_deref() {
+        None | Some("least_loaded") => {}
+        Some("weighted_round_robin") => {
+            info!("Weighted round-robin placement enabled");
+            routing_service =
+                routing_service.with_placement_strategy(PlacementStrategy::WeightedRoundRobin);
+        }
+        Some("capability_aware") => {
+            info!("Capability-aware placement enabled");
+            routing_service =
+                routing_service.with_placement_strategy(PlacementStrategy::CapabilityAware);
+        }
+        Some(other) => {
+            warn!(strategy = %other, "Unknown PLACEMENT_STRATEGY, using least_loaded");
+        }
+    }
+    if std::env::var("VALIDATE_CAPABILITY_INSTANCE_IDS")
+        .ok()
+        .as_deref()
+        == Some("true")
+    {
+        info!("Start requests with mismatched capability instance ids will be rejected");
+        routing_service = routing_service.with_capability_instance_id_validation(true);
+    }
+    // never, always or on_failure:<max_retries>:<backoff_seconds>
+    if let Ok(value) = std::env::var("DEFAULT_RESTART_POLICY") {
+        match value.parse::<RestartPolicy>() {
+            Ok(policy) => {
+                info!(policy = %value, "Instances without a restart policy use the configured default");
+                routing_service = routing_service.with_default_restart_policy(policy);
+            }
+            Err(error) => {
+                warn!(error = %error, "Invalid DEFAULT_RESTART_POLICY, using never");
+            }
+        }
+    }
+    if let Some(deadline_ms) = std::env::var("START_DEADLINE_MS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+    {
+        info!(
+            deadline_ms,
+            "Start requests must be placed within the deadline"
+        );
+        routing_service =
+            routing_service.with_start_deadline(std::time::Duration::from_millis(deadline_ms));
+    }
+    if let Some(per_second) = std::env::var("START_RATE_PER_SEC")
+        .ok()
+        .and_then(|value| value.parse::<u32>().ok())
+    {
+        let burst = std::env::var("START_RATE_BURST")
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(per_second);
+        info!(per_second, burst, "Start requests are rate limited");
+        routing_service = routing_service.with_start_rate_limit(per_second, burst);
+    }
+    if let Some(timeout_ms) = std::env::var("RECOVERY_TIMEOUT_MS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+    {
+        info!(timeout_ms, "Node recovery is bounded by the timeout");
+        routing_service =
+            routing_service.with_recovery_timeout(std::time::Duration::from_millis(timeout_ms));
+    }
+    if let Some(tls) = &tls {
+        routing_service = routing_service.with_tls(tls.clone());
+    }
+    let routing_service = Arc::new(routing_service);
     let routing_controller = Arc::new(NodeRoutingController::new(routing_service));
 
     if let Ok(static_nodes) = std::env::var("STATIC_NODE_AGENTS") {
@@ -68,7 +186,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             let node_id = format!("static-node-{}", idx + 1);
             if let Err(error) = routing_controller
-                .register_node(node_id.clone(), trimmed.to_string(), vec![], 0)
+                .register_node(node_id.clone(), trimmed.to_string(), vec![], None, None)
                 .await
             {
                 warn!(%node_id, endpoint = %trimmed, error = %error, "Failed to register static node");
@@ -78,11 +196,69 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // One state for the façade, the gRPC server and the reaper
+    let facade = AsyncControlPlane::new(
+        Arc::new(InstanceService::new(
+            Arc::new(ControlPlaneInstanceRepository::new(control_plane.clone())),
+            "node-1",
+        )),
+        routing_controller.clone(),
+    )
+    .with_recovery_state(control_plane.clone());
+    match facade.recover_all().await {
+        Ok(results) => {
+            for (node_id, result) in results {
+                match result {
+                    Ok(recovered) => info!(%node_id, recovered, "Recovered node state"),
+                    Err(error) => warn!(%node_id, error = %error, "Failed to recover node state"),
+                }
+            }
+        }
+        Err(error) => warn!(error = %error, "Failed to list nodes for recovery"),
+    }
+
+    if let Some(retention_secs) = std::env::var("STOPPED_INSTANCE_RETENTION_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+    {
+        info!(
+            retention_secs,
+            "Stopped instances are reaped after the retention period"
+        );
+        let retention = std::time::Duration::from_secs(retention_secs);
+        let reap_control_plane = control_plane.clone();
+        let reap_routing = routing_controller.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STOPPED_INSTANCE_REAP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let reaped = match reap_control_plane.lock() {
+                    Ok(mut control_plane) => control_plane.reap_stopped(retention),
+                    Err(_) => {
+                        warn!("Control plane lock poisoned; stopping the reaper");
+                        return;
+                    }
+                };
+                if reaped.is_empty() {
+                    continue;
+                }
+                if let Err(error) = reap_routing.forget_instances(&reaped).await {
+                    warn!(error = %error, "Failed to remove routing assignments of reaped instances");
+                }
+                info!(reaped = reaped.len(), "Reaped stopped instances");
+            }
+        });
+    }
+
+    let health_routing = routing_controller.clone();
     let server = ControlPlaneServer::new(control_plane, routing_controller);
 
     info!(%control_plane_addr, "Control Plane initialized successfully");
     tokio::spawn(async move {
-        let app = Router::new().route("/metrics", get(metrics_handler));
+        let app = Router::new().route("/metrics", get(metrics_handler)).route(
+            "/health",
+            get(move || health_handler(health_routing.clone())),
+        );
         let listener = match tokio::net::TcpListener::bind(metrics_addr).await {
             Ok(listener) => listener,
             Err(error) => {
@@ -96,14 +272,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    Server::builder()
+    let reflection = if wasmatrix_proto::reflection::reflection_enabled_from_env() {
+        info!("gRPC server reflection enabled");
+        Some(wasmatrix_proto::reflection::reflection_service()?)
+    } else {
+        None
+    };
+
+    server_builder
         .add_service(ControlPlaneServiceServer::new(server))
+        .add_optional_service(reflection)
         .serve(control_plane_addr)
         .await?;
 
     Ok(())
 }
 
+/// 503 only when etcd persistence is enabled but not answering
+async fn health_handler(
+    routing: Arc<NodeRoutingController>,
+) -> (axum::http::StatusCode, axum::Json<serde_json::Value>) {
+    let persistence = routing.persistence_status().await;
+    let code = if persistence.enabled && !persistence.healthy {
+        axum::http::StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        axum::http::StatusCode::OK
+    };
+    let body = serde_json::json!({
+        "persistence": {
+            "enabled": persistence.enabled,
+            "healthy": persistence.healthy,
+            "last_error": persistence.last_error,
+        }
+    });
+    (code, axum::Json(body))
+}
+
 async fn metrics_handler() -> String {
     global_observability_controller()
         .render_metrics()
@@ -1,14 +1,29 @@
 use crate::features::node_routing::controller::NodeRoutingController;
 use crate::features::observability::controller::global_observability_controller;
+use crate::shared::error::ControlPlaneError;
 use crate::ControlPlane;
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
+use tokio::sync::broadcast;
 use tonic::{Request, Response, Status};
 use wasmatrix_proto::v1::control_plane_service_server::ControlPlaneService;
 use wasmatrix_proto::v1::{
-    RegisterNodeRequest, RegisterNodeResponse, StatusReport, StatusReportResponse,
+    AssignCapabilityRequest, AssignCapabilityResponse, CrashLoopingInstance,
+    GetCapabilitiesRequest, GetCapabilitiesResponse, GetClusterCapacityRequest,
+    GetClusterCapacityResponse, GetEffectivePermissionsRequest, GetEffectivePermissionsResponse,
+    GetEventCountsRequest, GetEventCountsResponse, GetInstanceHistoryRequest,
+    GetInstanceHistoryResponse, GetPersistenceStatusRequest, GetPersistenceStatusResponse,
+    InstanceHistoryEntry, ListCrashLoopingRequest, ListCrashLoopingResponse, LocateInstanceRequest,
+    LocateInstanceResponse, RegisterNodeRequest, RegisterNodeResponse, RestartInstanceRequest,
+    RestartInstanceResponse, RevokeCapabilityRequest, RevokeCapabilityResponse, StatusReport,
+    StatusReportResponse, WatchInstanceRequest, WatchInstanceResponse,
 };
 
+type WatchInstanceStream =
+    Pin<Box<dyn Stream<Item = Result<WatchInstanceResponse, Status>> + Send + 'static>>;
+
 pub struct ControlPlaneServer {
     control_plane: Arc<Mutex<ControlPlane>>,
     node_routing_controller: Arc<NodeRoutingController>,
@@ -28,6 +43,8 @@ impl ControlPlaneServer {
 
 #[tonic::async_trait]
 impl ControlPlaneService for ControlPlaneServer {
+    type WatchInstanceStream = WatchInstanceStream;
+
     async fn register_node(
         &self,
         request: Request<RegisterNodeRequest>,
@@ -44,6 +61,7 @@ impl ControlPlaneService for ControlPlaneServer {
                 req.node_address,
                 req.capabilities,
                 req.max_instances,
+                req.weight,
             )
             .await;
         if let Err(error) = register_result {
@@ -108,6 +126,26 @@ impl ControlPlaneService for ControlPlaneServer {
             return Err(Status::internal(error.to_string()));
         }
         observability.set_node_health(&req.node_id, true);
+        if !req.capabilities.is_empty() {
+            if let Err(error) = self
+                .node_routing_controller
+                .update_node_capabilities(
+                    &req.node_id,
+                    req.capabilities.clone(),
+                    req.replace_capabilities,
+                )
+                .await
+            {
+                tracing::warn!(node_id = %req.node_id, error = %error, "Failed to refresh advertised node capabilities");
+            }
+        }
+        if let Err(error) = self
+            .node_routing_controller
+            .sync_node_instance_metrics()
+            .await
+        {
+            tracing::warn!(error = %error, "Failed to sync per-node instance metrics");
+        }
 
         let mut control_plane = self
             .control_plane
@@ -119,7 +157,7 @@ impl ControlPlaneService for ControlPlaneServer {
                 .map_err(|_| Status::invalid_argument("Invalid instance status"))?;
             let core_status: wasmatrix_core::InstanceStatus =
                 wasmatrix_proto::protocol::InstanceStatus::try_from(proto_status)
-                    .map_err(Status::invalid_argument)?
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?
                     .into();
             if matches!(core_status, wasmatrix_core::InstanceStatus::Crashed) {
                 observability.record_crash();
@@ -160,6 +198,590 @@ impl ControlPlaneService for ControlPlaneServer {
             message: "Status report received".to_string(),
         }))
     }
+
+    async fn locate_instance(
+        &self,
+        request: Request<LocateInstanceRequest>,
+    ) -> Result<Response<LocateInstanceResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let req = request.into_inner();
+        let observability = global_observability_controller();
+
+        match self
+            .node_routing_controller
+            .locate_instance(&req.instance_id)
+            .await
+        {
+            Ok(node_id) => {
+                observability.record_api_request(
+                    "locate_instance",
+                    "ok",
+                    started.elapsed().as_secs_f64(),
+                );
+                tracing::debug!(%correlation_id, instance_id = %req.instance_id, %node_id, "Located instance");
+                Ok(Response::new(LocateInstanceResponse {
+                    success: true,
+                    node_id: Some(node_id),
+                    message: "Instance located".to_string(),
+                    error_code: None,
+                }))
+            }
+            Err(error) => {
+                observability.record_api_request(
+                    "locate_instance",
+                    "error",
+                    started.elapsed().as_secs_f64(),
+                );
+                let error: wasmatrix_core::ErrorResponse = error.into();
+                Ok(Response::new(LocateInstanceResponse {
+                    success: false,
+                    node_id: None,
+                    message: error.message,
+                    error_code: Some(error.error_code),
+                }))
+            }
+        }
+    }
+
+    async fn get_instance_history(
+        &self,
+        request: Request<GetInstanceHistoryRequest>,
+    ) -> Result<Response<GetInstanceHistoryResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let req = request.into_inner();
+        let observability = global_observability_controller();
+
+        let control_plane = self
+            .control_plane
+            .lock()
+            .map_err(|_| Status::internal("control plane lock poisoned"))?;
+
+        if control_plane.get_instance(&req.instance_id).is_none() {
+            observability.record_api_request(
+                "get_instance_history",
+                "error",
+                started.elapsed().as_secs_f64(),
+            );
+            return Ok(Response::new(GetInstanceHistoryResponse {
+                success: false,
+                entries: vec![],
+                message: format!("Instance {} not found", req.instance_id),
+                error_code: Some("INSTANCE_NOT_FOUND".to_string()),
+            }));
+        }
+
+        let entries: Vec<InstanceHistoryEntry> = control_plane
+            .get_instance_history(&req.instance_id)
+            .into_iter()
+            .map(|event| InstanceHistoryEntry {
+                event_type: event.event_type,
+                timestamp: event.timestamp.timestamp(),
+            })
+            .collect();
+
+        observability.record_api_request(
+            "get_instance_history",
+            "ok",
+            started.elapsed().as_secs_f64(),
+        );
+        tracing::debug!(%correlation_id, instance_id = %req.instance_id, entries = entries.len(), "Returned instance history");
+
+        Ok(Response::new(GetInstanceHistoryResponse {
+            success: true,
+            entries,
+            message: "Instance history retrieved".to_string(),
+            error_code: None,
+        }))
+    }
+
+    async fn list_crash_looping(
+        &self,
+        request: Request<ListCrashLoopingRequest>,
+    ) -> Result<Response<ListCrashLoopingResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let req = request.into_inner();
+        let observability = global_observability_controller();
+
+        let control_plane = self
+            .control_plane
+            .lock()
+            .map_err(|_| Status::internal("control plane lock poisoned"))?;
+
+        let instances: Vec<CrashLoopingInstance> = control_plane
+            .list_crash_looping(
+                req.threshold,
+                std::time::Duration::from_secs(req.window_seconds),
+            )
+            .into_iter()
+            .map(|(metadata, crash_count)| CrashLoopingInstance {
+                instance_id: metadata.instance_id.clone(),
+                crash_count,
+            })
+            .collect();
+
+        observability.record_api_request(
+            "list_crash_looping",
+            "ok",
+            started.elapsed().as_secs_f64(),
+        );
+        tracing::debug!(%correlation_id, threshold = req.threshold, window_seconds = req.window_seconds, instances = instances.len(), "Listed crash-looping instances");
+
+        Ok(Response::new(ListCrashLoopingResponse {
+            success: true,
+            instances,
+            message: "Crash-looping instances listed".to_string(),
+            error_code: None,
+        }))
+    }
+
+    async fn get_cluster_capacity(
+        &self,
+        request: Request<GetClusterCapacityRequest>,
+    ) -> Result<Response<GetClusterCapacityResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let observability = global_observability_controller();
+
+        match self.node_routing_controller.cluster_capacity().await {
+            Ok(capacity) => {
+                observability.record_api_request(
+                    "get_cluster_capacity",
+                    "ok",
+                    started.elapsed().as_secs_f64(),
+                );
+                tracing::debug!(%correlation_id, ?capacity, "Computed cluster capacity");
+                Ok(Response::new(GetClusterCapacityResponse {
+                    success: true,
+                    total_slots: capacity.total_slots,
+                    used_slots: capacity.used_slots,
+                    available_nodes: capacity.available_nodes,
+                    total_nodes: capacity.total_nodes,
+                    message: "Cluster capacity computed".to_string(),
+                    error_code: None,
+                }))
+            }
+            Err(error) => {
+                observability.record_api_request(
+                    "get_cluster_capacity",
+                    "error",
+                    started.elapsed().as_secs_f64(),
+                );
+                let error: wasmatrix_core::ErrorResponse = error.into();
+                Ok(Response::new(GetClusterCapacityResponse {
+                    success: false,
+                    total_slots: None,
+                    used_slots: 0,
+                    available_nodes: 0,
+                    total_nodes: 0,
+                    message: error.message,
+                    error_code: Some(error.error_code),
+                }))
+            }
+        }
+    }
+
+    async fn get_effective_permissions(
+        &self,
+        request: Request<GetEffectivePermissionsRequest>,
+    ) -> Result<Response<GetEffectivePermissionsResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let req = request.into_inner();
+        let observability = global_observability_controller();
+
+        let control_plane = self
+            .control_plane
+            .lock()
+            .map_err(|_| Status::internal("control plane lock poisoned"))?;
+
+        if control_plane.get_instance(&req.instance_id).is_none() {
+            observability.record_api_request(
+                "get_effective_permissions",
+                "error",
+                started.elapsed().as_secs_f64(),
+            );
+            return Ok(Response::new(GetEffectivePermissionsResponse {
+                success: false,
+                permissions: vec![],
+                allowed: None,
+                message: format!("Instance {} not found", req.instance_id),
+                error_code: Some("INSTANCE_NOT_FOUND".to_string()),
+            }));
+        }
+
+        let permissions = control_plane.effective_permissions(&req.instance_id);
+        let allowed = req
+            .permission
+            .as_deref()
+            .map(|permission| control_plane.can(&req.instance_id, permission));
+
+        observability.record_api_request(
+            "get_effective_permissions",
+            "ok",
+            started.elapsed().as_secs_f64(),
+        );
+        tracing::debug!(%correlation_id, instance_id = %req.instance_id, permissions = permissions.len(), ?allowed, "Returned effective permissions");
+
+        Ok(Response::new(GetEffectivePermissionsResponse {
+            success: true,
+            permissions,
+            allowed,
+            message: "Effective permissions retrieved".to_string(),
+            error_code: None,
+        }))
+    }
+
+    async fn get_persistence_status(
+        &self,
+        request: Request<GetPersistenceStatusRequest>,
+    ) -> Result<Response<GetPersistenceStatusResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+
+        let status = self.node_routing_controller.persistence_status().await;
+
+        global_observability_controller().record_api_request(
+            "get_persistence_status",
+            "ok",
+            started.elapsed().as_secs_f64(),
+        );
+        tracing::debug!(%correlation_id, ?status, "Reported persistence status");
+
+        Ok(Response::new(GetPersistenceStatusResponse {
+            enabled: status.enabled,
+            healthy: status.healthy,
+            last_error: status.last_error,
+        }))
+    }
+
+    async fn get_event_counts(
+        &self,
+        request: Request<GetEventCountsRequest>,
+    ) -> Result<Response<GetEventCountsResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+
+        let counts = self
+            .control_plane
+            .lock()
+            .map_err(|_| Status::internal("control plane lock poisoned"))?
+            .event_counts()
+            .into_iter()
+            .map(|(kind, count)| (kind, count as u64))
+            .collect::<std::collections::HashMap<_, _>>();
+
+        global_observability_controller().record_api_request(
+            "get_event_counts",
+            "ok",
+            started.elapsed().as_secs_f64(),
+        );
+        tracing::debug!(%correlation_id, kinds = counts.len(), "Returned event counts");
+
+        Ok(Response::new(GetEventCountsResponse { counts }))
+    }
+
+    async fn watch_instance(
+        &self,
+        request: Request<WatchInstanceRequest>,
+    ) -> Result<Response<Self::WatchInstanceStream>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let instance_id = request.into_inner().instance_id;
+        let observability = global_observability_controller();
+
+        let watched = self
+            .control_plane
+            .lock()
+            .map_err(|_| Status::internal("control plane lock poisoned"))?
+            .watch_instance(&instance_id);
+        let (status, receiver) = match watched {
+            Ok(watched) => watched,
+            Err(error) => {
+                observability.record_api_request(
+                    "watch_instance",
+                    "error",
+                    started.elapsed().as_secs_f64(),
+                );
+                return Err(Status::not_found(error.message));
+            }
+        };
+        observability.record_api_request("watch_instance", "ok", started.elapsed().as_secs_f64());
+        tracing::debug!(%correlation_id, %instance_id, "Watching instance status");
+
+        let changes = futures::stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(status) => return Some((status, receiver)),
+                    // A slow watcher missed some changes; carry on with the oldest kept one
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        let updates = futures::stream::once(async move { status })
+            .chain(changes)
+            .map(move |status| WatchInstanceResponse {
+                instance_id: instance_id.clone(),
+                status: wasmatrix_proto::v1::InstanceStatus::from(
+                    wasmatrix_proto::protocol::InstanceStatus::from(status),
+                ) as i32,
+            })
+            .map(Ok);
+        Ok(Response::new(Box::pin(updates)))
+    }
+
+    async fn assign_capability(
+        &self,
+        request: Request<AssignCapabilityRequest>,
+    ) -> Result<Response<AssignCapabilityResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let req =
+            wasmatrix_proto::protocol::AssignCapabilityRequest::try_from(request.into_inner())
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let assignment = wasmatrix_core::CapabilityAssignment::from(req.assignment);
+        let observability = global_observability_controller();
+
+        let (previous, assigned) = {
+            let mut control_plane = self
+                .control_plane
+                .lock()
+                .map_err(|_| Status::internal("control plane lock poisoned"))?;
+            let previous = control_plane
+                .get_capabilities(&assignment.instance_id)
+                .cloned();
+            (
+                previous,
+                control_plane.assign_capability(assignment.clone()),
+            )
+        };
+        if let Err(error) = assigned {
+            observability.record_api_request(
+                "assign_capability",
+                "error",
+                started.elapsed().as_secs_f64(),
+            );
+            return Ok(Response::new(AssignCapabilityResponse {
+                success: false,
+                message: error.message,
+                error_code: Some(error.error_code),
+            }));
+        }
+
+        let instance_id = assignment.instance_id.clone();
+        let capability_id = assignment.capability_id.clone();
+        // Hand the grant to the hosting node and store it with the routed
+        // instance so it also holds on every later start
+        match self
+            .node_routing_controller
+            .record_capability_assignment(assignment)
+            .await
+        {
+            Ok(()) => {}
+            Err(ControlPlaneError::InstanceNotFound(_)) => {
+                tracing::debug!(%correlation_id, %instance_id, "Instance not routed to a node; grant kept locally");
+            }
+            Err(error) => {
+                tracing::warn!(%correlation_id, %instance_id, %capability_id, error = %error, "Failed to forward capability grant to hosting node");
+                self.control_plane
+                    .lock()
+                    .map_err(|_| Status::internal("control plane lock poisoned"))?
+                    .restore_capabilities(&instance_id, previous);
+                observability.record_api_request(
+                    "assign_capability",
+                    "error",
+                    started.elapsed().as_secs_f64(),
+                );
+                return Ok(Response::new(AssignCapabilityResponse {
+                    success: false,
+                    message: format!("Capability not granted on hosting node: {}", error),
+                    error_code: Some("NODE_UPDATE_FAILED".to_string()),
+                }));
+            }
+        }
+
+        observability.record_api_request(
+            "assign_capability",
+            "ok",
+            started.elapsed().as_secs_f64(),
+        );
+        tracing::debug!(%correlation_id, %instance_id, %capability_id, "Assigned capability");
+
+        Ok(Response::new(AssignCapabilityResponse {
+            success: true,
+            message: format!("Capability {} assigned to {}", capability_id, instance_id),
+            error_code: None,
+        }))
+    }
+
+    async fn revoke_capability(
+        &self,
+        request: Request<RevokeCapabilityRequest>,
+    ) -> Result<Response<RevokeCapabilityResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let req = request.into_inner();
+        let observability = global_observability_controller();
+
+        let (previous, revoked) = {
+            let mut control_plane = self
+                .control_plane
+                .lock()
+                .map_err(|_| Status::internal("control plane lock poisoned"))?;
+            let previous = control_plane.get_capabilities(&req.instance_id).cloned();
+            (
+                previous,
+                control_plane.revoke_capability(&req.instance_id, &req.capability_id),
+            )
+        };
+        if let Err(error) = revoked {
+            observability.record_api_request(
+                "revoke_capability",
+                "error",
+                started.elapsed().as_secs_f64(),
+            );
+            return Ok(Response::new(RevokeCapabilityResponse {
+                success: false,
+                message: error.message,
+                error_code: Some(error.error_code),
+            }));
+        }
+
+        match self
+            .node_routing_controller
+            .revoke_capability_assignment(&req.instance_id, &req.capability_id)
+            .await
+        {
+            Ok(_) => {}
+            Err(ControlPlaneError::InstanceNotFound(_)) => {
+                tracing::debug!(%correlation_id, instance_id = %req.instance_id, "Instance not routed to a node; revoked locally");
+            }
+            Err(error) => {
+                tracing::warn!(%correlation_id, instance_id = %req.instance_id, capability_id = %req.capability_id, error = %error, "Failed to forward capability revocation to hosting node");
+                self.control_plane
+                    .lock()
+                    .map_err(|_| Status::internal("control plane lock poisoned"))?
+                    .restore_capabilities(&req.instance_id, previous);
+                observability.record_api_request(
+                    "revoke_capability",
+                    "error",
+                    started.elapsed().as_secs_f64(),
+                );
+                return Ok(Response::new(RevokeCapabilityResponse {
+                    success: false,
+                    message: format!("Capability not revoked on hosting node: {}", error),
+                    error_code: Some("NODE_UPDATE_FAILED".to_string()),
+                }));
+            }
+        }
+
+        observability.record_api_request(
+            "revoke_capability",
+            "ok",
+            started.elapsed().as_secs_f64(),
+        );
+        tracing::debug!(%correlation_id, instance_id = %req.instance_id, capability_id = %req.capability_id, "Revoked capability");
+
+        Ok(Response::new(RevokeCapabilityResponse {
+            success: true,
+            message: format!(
+                "Capability {} revoked from {}",
+                req.capability_id, req.instance_id
+            ),
+            error_code: None,
+        }))
+    }
+
+    async fn get_capabilities(
+        &self,
+        request: Request<GetCapabilitiesRequest>,
+    ) -> Result<Response<GetCapabilitiesResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let req = request.into_inner();
+        let observability = global_observability_controller();
+
+        let control_plane = self
+            .control_plane
+            .lock()
+            .map_err(|_| Status::internal("control plane lock poisoned"))?;
+
+        if control_plane.get_instance(&req.instance_id).is_none() {
+            observability.record_api_request(
+                "get_capabilities",
+                "error",
+                started.elapsed().as_secs_f64(),
+            );
+            return Ok(Response::new(GetCapabilitiesResponse {
+                success: false,
+                capabilities: vec![],
+                message: format!("Instance {} not found", req.instance_id),
+                error_code: Some("INSTANCE_NOT_FOUND".to_string()),
+            }));
+        }
+
+        let capabilities: Vec<wasmatrix_proto::v1::CapabilityAssignment> = control_plane
+            .get_capabilities(&req.instance_id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .map(|assignment| {
+                wasmatrix_proto::protocol::CapabilityAssignment::from(assignment).into()
+            })
+            .collect();
+
+        observability.record_api_request("get_capabilities", "ok", started.elapsed().as_secs_f64());
+        tracing::debug!(%correlation_id, instance_id = %req.instance_id, capabilities = capabilities.len(), "Returned capabilities");
+
+        Ok(Response::new(GetCapabilitiesResponse {
+            success: true,
+            capabilities,
+            message: "Capabilities retrieved".to_string(),
+            error_code: None,
+        }))
+    }
+
+    async fn restart_instance(
+        &self,
+        request: Request<RestartInstanceRequest>,
+    ) -> Result<Response<RestartInstanceResponse>, Status> {
+        let started = Instant::now();
+        let correlation_id = correlation_id_from_request(&request);
+        let req = request.into_inner();
+        let observability = global_observability_controller();
+
+        // Forwarded to the hosting node, which restarts regardless of policy
+        if let Err(error) = self
+            .node_routing_controller
+            .restart_instance(&req.instance_id)
+            .await
+        {
+            tracing::warn!(%correlation_id, instance_id = %req.instance_id, error = %error, "Failed to restart instance");
+            observability.record_api_request(
+                "restart_instance",
+                "error",
+                started.elapsed().as_secs_f64(),
+            );
+            let error = wasmatrix_core::ErrorResponse::from(error);
+            return Ok(Response::new(RestartInstanceResponse {
+                success: false,
+                message: error.message,
+                error_code: Some(error.error_code),
+            }));
+        }
+
+        observability.record_api_request("restart_instance", "ok", started.elapsed().as_secs_f64());
+        tracing::info!(%correlation_id, instance_id = %req.instance_id, "Restarted instance");
+
+        Ok(Response::new(RestartInstanceResponse {
+            success: true,
+            message: format!("Instance {} restarted", req.instance_id),
+            error_code: None,
+        }))
+    }
 }
 
 fn correlation_id_from_request<T>(request: &Request<T>) -> String {
@@ -175,10 +797,15 @@ fn correlation_id_from_request<T>(request: &Request<T>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::features::node_routing::repo::InMemoryNodeRoutingRepository;
+    use crate::features::node_routing::repo::{
+        InMemoryNodeRoutingRepository, NodeRoutingRepository,
+    };
     use crate::features::node_routing::service::NodeRoutingService;
     use std::sync::Arc;
-    use wasmatrix_core::{QueryInstanceRequest, RestartPolicy, StartInstanceRequest};
+    use wasmatrix_core::{
+        CapabilityAssignment, ProviderType, QueryInstanceRequest, RestartPolicy,
+        StartInstanceRequest,
+    };
     use wasmatrix_proto::v1::InstanceStatusUpdate;
 
     fn create_server_with_state() -> (ControlPlaneServer, Arc<Mutex<ControlPlane>>) {
@@ -205,7 +832,8 @@ mod tests {
                 node_id: "node-1".to_string(),
                 node_address: "127.0.0.1:50052".to_string(),
                 capabilities: vec![],
-                max_instances: 100,
+                max_instances: Some(100),
+                weight: None,
             }))
             .await
             .unwrap();
@@ -216,6 +844,7 @@ mod tests {
                 module_bytes: minimal_wasm_module(),
                 capabilities: vec![],
                 restart_policy: RestartPolicy::default(),
+                deployment_id: None,
             })
             .unwrap()
         };
@@ -237,6 +866,8 @@ mod tests {
                         error_message: None,
                     }],
                     timestamp: 1_700_000_000 + i as i64,
+                    capabilities: vec![],
+                    replace_capabilities: false,
                 }))
                 .await
                 .unwrap();
@@ -265,7 +896,8 @@ mod tests {
                 node_id: "node-2".to_string(),
                 node_address: "127.0.0.1:51052".to_string(),
                 capabilities: vec!["kv".to_string()],
-                max_instances: 10,
+                max_instances: Some(10),
+                weight: None,
             }))
             .await
             .unwrap();
@@ -283,7 +915,8 @@ mod tests {
                 node_id: "node-1".to_string(),
                 node_address: "127.0.0.1:50052".to_string(),
                 capabilities: vec![],
-                max_instances: 100,
+                max_instances: Some(100),
+                weight: None,
             }))
             .await
             .unwrap();
@@ -297,10 +930,523 @@ mod tests {
                     error_message: Some("bad".to_string()),
                 }],
                 timestamp: 1_700_000_000,
+                capabilities: vec![],
+                replace_capabilities: false,
             }))
             .await;
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().code(), tonic::Code::InvalidArgument);
     }
+
+    #[tokio::test]
+    async fn test_grpc_locate_instance_returns_node() {
+        let control_plane = Arc::new(Mutex::new(ControlPlane::new("node-1")));
+        let routing_repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        routing_repo
+            .assign_instance("instance-1".to_string(), "node-7".to_string())
+            .await
+            .unwrap();
+        let routing_controller = Arc::new(NodeRoutingController::new(Arc::new(
+            NodeRoutingService::new(routing_repo),
+        )));
+        let server = ControlPlaneServer::new(control_plane, routing_controller);
+
+        let response = server
+            .locate_instance(Request::new(LocateInstanceRequest {
+                instance_id: "instance-1".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.success);
+        assert_eq!(response.node_id.as_deref(), Some("node-7"));
+
+        let missing = server
+            .locate_instance(Request::new(LocateInstanceRequest {
+                instance_id: "missing".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!missing.success);
+        assert!(missing.node_id.is_none());
+        assert_eq!(missing.error_code.as_deref(), Some("INSTANCE_NOT_FOUND"));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_get_instance_history_started_then_crashed() {
+        let (server, control_plane) = create_server_with_state();
+        let instance_id = {
+            let mut cp = control_plane.lock().unwrap();
+            let id = cp
+                .start_instance(StartInstanceRequest {
+                    module_bytes: minimal_wasm_module(),
+                    capabilities: vec![],
+                    restart_policy: RestartPolicy::default(),
+                    deployment_id: None,
+                })
+                .unwrap();
+            cp.record_instance_crash(&id, "trap").unwrap();
+            id
+        };
+
+        let response = server
+            .get_instance_history(Request::new(GetInstanceHistoryRequest {
+                instance_id: instance_id.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert!(response.success);
+        let kinds: Vec<&str> = response
+            .entries
+            .iter()
+            .map(|e| e.event_type.as_str())
+            .collect();
+        assert_eq!(kinds, vec!["instance_started", "instance_crashed"]);
+        assert!(response.entries[0].timestamp <= response.entries[1].timestamp);
+
+        let missing = server
+            .get_instance_history(Request::new(GetInstanceHistoryRequest {
+                instance_id: "missing".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!missing.success);
+        assert_eq!(missing.error_code.as_deref(), Some("INSTANCE_NOT_FOUND"));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_list_crash_looping() {
+        let (server, control_plane) = create_server_with_state();
+        let (looping_id, stable_id) = {
+            let mut cp = control_plane.lock().unwrap();
+            let mut start = || {
+                cp.start_instance(StartInstanceRequest {
+                    module_bytes: minimal_wasm_module(),
+                    capabilities: vec![],
+                    restart_policy: RestartPolicy::default(),
+                    deployment_id: None,
+                })
+                .unwrap()
+            };
+            let looping_id = start();
+            let stable_id = start();
+            for _ in 0..3 {
+                cp.record_instance_crash(&looping_id, "trap").unwrap();
+                cp.handle_crash_recovery(&looping_id).unwrap();
+            }
+            cp.record_instance_crash(&stable_id, "trap").unwrap();
+            (looping_id, stable_id)
+        };
+
+        let response = server
+            .list_crash_looping(Request::new(ListCrashLoopingRequest {
+                threshold: 1,
+                window_seconds: 300,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert!(response.success);
+        assert_eq!(
+            response.instances,
+            vec![CrashLoopingInstance {
+                instance_id: looping_id,
+                crash_count: 3,
+            }]
+        );
+        assert!(response
+            .instances
+            .iter()
+            .all(|instance| instance.instance_id != stable_id));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_get_event_counts() {
+        let (server, control_plane) = create_server_with_state();
+        {
+            let mut cp = control_plane.lock().unwrap();
+            let instance_id = cp
+                .start_instance(StartInstanceRequest {
+                    module_bytes: minimal_wasm_module(),
+                    capabilities: vec![],
+                    restart_policy: RestartPolicy::default(),
+                    deployment_id: None,
+                })
+                .unwrap();
+            for _ in 0..2 {
+                cp.record_instance_crash(&instance_id, "trap").unwrap();
+                cp.handle_crash_recovery(&instance_id).unwrap();
+            }
+            cp.record_instance_crash(&instance_id, "trap").unwrap();
+        }
+
+        let response = server
+            .get_event_counts(Request::new(GetEventCountsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.counts.len(), 2);
+        assert_eq!(response.counts["instance_crashed"], 3);
+        assert_eq!(response.counts["instance_restarted"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_watch_instance_streams_status_changes() {
+        let (server, control_plane) = create_server_with_state();
+        server
+            .register_node(Request::new(RegisterNodeRequest {
+                node_id: "node-1".to_string(),
+                node_address: "127.0.0.1:50052".to_string(),
+                capabilities: vec![],
+                max_instances: Some(100),
+                weight: None,
+            }))
+            .await
+            .unwrap();
+        let instance_id = control_plane
+            .lock()
+            .unwrap()
+            .start_instance(StartInstanceRequest {
+                module_bytes: minimal_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+
+        let mut updates = server
+            .watch_instance(Request::new(WatchInstanceRequest {
+                instance_id: instance_id.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let current = updates.next().await.unwrap().unwrap();
+        assert_eq!(current.instance_id, instance_id);
+        assert_eq!(
+            current.status,
+            wasmatrix_proto::v1::InstanceStatus::Starting as i32
+        );
+
+        server
+            .report_status(Request::new(StatusReport {
+                node_id: "node-1".to_string(),
+                instance_updates: vec![InstanceStatusUpdate {
+                    instance_id: instance_id.clone(),
+                    status: wasmatrix_proto::v1::InstanceStatus::Running as i32,
+                    error_message: None,
+                }],
+                timestamp: 1_700_000_000,
+                capabilities: vec![],
+                replace_capabilities: false,
+            }))
+            .await
+            .unwrap();
+        let changed = tokio::time::timeout(std::time::Duration::from_secs(1), updates.next())
+            .await
+            .expect("status change should be streamed")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            changed.status,
+            wasmatrix_proto::v1::InstanceStatus::Running as i32
+        );
+
+        let missing = server
+            .watch_instance(Request::new(WatchInstanceRequest {
+                instance_id: "missing".to_string(),
+            }))
+            .await;
+        assert_eq!(missing.err().unwrap().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_assign_and_revoke_capability() {
+        use wasmatrix_agent::server::NodeAgentServer;
+        use wasmatrix_agent::NodeAgent;
+        use wasmatrix_proto::v1::node_agent_service_client::NodeAgentServiceClient;
+        use wasmatrix_proto::v1::node_agent_service_server::NodeAgentServiceServer;
+
+        let agent = Arc::new(NodeAgent::new("node-1".to_string()).unwrap());
+        let (agent_addr, _agent_server) = crate::testing::serve(NodeAgentServiceServer::new(
+            NodeAgentServer::new(agent, None),
+        ))
+        .await;
+        let mut agent_client = NodeAgentServiceClient::connect(format!("http://{agent_addr}"))
+            .await
+            .unwrap();
+
+        let control_plane = Arc::new(Mutex::new(ControlPlane::new("node-1")));
+        let routing_repo = Arc::new(InMemoryNodeRoutingRepository::new());
+        let routing_service = Arc::new(NodeRoutingService::new(routing_repo.clone()));
+        let routing = Arc::new(NodeRoutingController::new(routing_service));
+        let server = ControlPlaneServer::new(control_plane.clone(), routing.clone());
+        routing
+            .register_node(
+                "node-1".to_string(),
+                format!("http://{agent_addr}"),
+                vec![],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let instance_id = control_plane
+            .lock()
+            .unwrap()
+            .start_instance(StartInstanceRequest {
+                module_bytes: minimal_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        let started = agent_client
+            .start_instance(wasmatrix_proto::v1::StartInstanceRequest {
+                instance_id: instance_id.clone(),
+                module_bytes: minimal_wasm_module(),
+                capabilities: vec![],
+                restart_policy: Some(wasmatrix_proto::v1::RestartPolicy {
+                    policy_type: wasmatrix_proto::v1::RestartPolicyType::Never as i32,
+                    max_retries: None,
+                    backoff_seconds: None,
+                    max_backoff_seconds: None,
+                }),
+                env: vec![],
+                args: vec![],
+                annotations: Default::default(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(started.success, "{}", started.message);
+        routing_repo
+            .assign_instance(instance_id.clone(), "node-1".to_string())
+            .await
+            .unwrap();
+
+        let grant = |instance_id: &str| {
+            Request::new(AssignCapabilityRequest {
+                assignment: Some(wasmatrix_proto::v1::CapabilityAssignment {
+                    instance_id: instance_id.to_string(),
+                    capability_id: "msg-1".to_string(),
+                    provider_type: wasmatrix_proto::v1::ProviderType::Messaging as i32,
+                    permissions: vec!["msg:publish:orders".to_string()],
+                    expires_at: None,
+                    state: wasmatrix_proto::v1::CapabilityState::Active as i32,
+                }),
+            })
+        };
+        let publish = || wasmatrix_proto::v1::InvokeCapabilityRequest {
+            instance_id: instance_id.clone(),
+            capability_id: "msg-1".to_string(),
+            provider_type: wasmatrix_proto::v1::ProviderType::Messaging as i32,
+            operation: "publish".to_string(),
+            params_json: "{\"topic\":\"orders\",\"payload\":\"created\"}".to_string(),
+            permissions: vec![],
+        };
+
+        let denied = agent_client
+            .invoke_capability(publish())
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(denied.error_code.as_deref(), Some("PERMISSION_DENIED"));
+
+        let assigned = server
+            .assign_capability(grant(&instance_id))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(assigned.success, "{}", assigned.message);
+
+        let capabilities = server
+            .get_capabilities(Request::new(GetCapabilitiesRequest {
+                instance_id: instance_id.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(capabilities.success);
+        assert_eq!(capabilities.capabilities.len(), 1);
+        assert_eq!(capabilities.capabilities[0].capability_id, "msg-1");
+        assert_eq!(
+            capabilities.capabilities[0].permissions,
+            vec!["msg:publish:orders"]
+        );
+
+        let allowed = server
+            .get_effective_permissions(Request::new(GetEffectivePermissionsRequest {
+                instance_id: instance_id.clone(),
+                permission: Some("msg:publish:orders".to_string()),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(allowed.allowed, Some(true));
+        let stored = routing_repo
+            .get_instance_capabilities(&instance_id)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].capability_id, "msg-1");
+        // The running instance may use the grant straight away
+        let invoked = agent_client
+            .invoke_capability(publish())
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(invoked.success, "{}", invoked.message);
+
+        let revoked = server
+            .revoke_capability(Request::new(RevokeCapabilityRequest {
+                instance_id: instance_id.clone(),
+                capability_id: "msg-1".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(revoked.success, "{}", revoked.message);
+
+        let denied = server
+            .get_effective_permissions(Request::new(GetEffectivePermissionsRequest {
+                instance_id: instance_id.clone(),
+                permission: Some("msg:publish:orders".to_string()),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(denied.allowed, Some(false));
+        assert!(routing_repo
+            .get_instance_capabilities(&instance_id)
+            .await
+            .unwrap()
+            .is_empty());
+        let denied = agent_client
+            .invoke_capability(publish())
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(denied.error_code.as_deref(), Some("PERMISSION_DENIED"));
+
+        // A grant the hosting node cannot apply is rolled back
+        routing
+            .register_node(
+                "node-down".to_string(),
+                "http://127.0.0.1:1".to_string(),
+                vec![],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let unreachable_id = control_plane
+            .lock()
+            .unwrap()
+            .start_instance(StartInstanceRequest {
+                module_bytes: minimal_wasm_module(),
+                capabilities: vec![],
+                restart_policy: RestartPolicy::default(),
+                deployment_id: None,
+            })
+            .unwrap();
+        routing_repo
+            .assign_instance(unreachable_id.clone(), "node-down".to_string())
+            .await
+            .unwrap();
+        let failed = server
+            .assign_capability(grant(&unreachable_id))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!failed.success);
+        assert_eq!(failed.error_code.as_deref(), Some("NODE_UPDATE_FAILED"));
+        assert!(control_plane
+            .lock()
+            .unwrap()
+            .get_capabilities(&unreachable_id)
+            .is_none_or(Vec::is_empty));
+        assert!(routing_repo
+            .get_instance_capabilities(&unreachable_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let missing = server
+            .get_capabilities(Request::new(GetCapabilitiesRequest {
+                instance_id: "missing".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!missing.success);
+        assert_eq!(missing.error_code.as_deref(), Some("INSTANCE_NOT_FOUND"));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_get_effective_permissions() {
+        let (server, control_plane) = create_server_with_state();
+        let instance_id = {
+            let mut cp = control_plane.lock().unwrap();
+            let instance_id = cp
+                .start_instance(StartInstanceRequest {
+                    module_bytes: minimal_wasm_module(),
+                    capabilities: vec![],
+                    restart_policy: RestartPolicy::default(),
+                    deployment_id: None,
+                })
+                .unwrap();
+            cp.assign_capability(CapabilityAssignment::new(
+                instance_id.clone(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:*".to_string()],
+            ))
+            .unwrap();
+            instance_id
+        };
+
+        let response = server
+            .get_effective_permissions(Request::new(GetEffectivePermissionsRequest {
+                instance_id: instance_id.clone(),
+                permission: Some("kv:delete".to_string()),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.success);
+        assert_eq!(response.permissions, vec!["kv:*"]);
+        assert_eq!(response.allowed, Some(true));
+
+        let missing = server
+            .get_effective_permissions(Request::new(GetEffectivePermissionsRequest {
+                instance_id: "missing".to_string(),
+                permission: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!missing.success);
+        assert_eq!(missing.error_code.as_deref(), Some("INSTANCE_NOT_FOUND"));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_get_persistence_status_without_etcd() {
+        let (server, _) = create_server_with_state();
+
+        let response = server
+            .get_persistence_status(Request::new(GetPersistenceStatusRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.enabled);
+        assert!(response.last_error.is_none());
+    }
 }
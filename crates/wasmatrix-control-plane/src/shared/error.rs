@@ -51,6 +51,12 @@ impl From<ControlPlaneError> for wasmatrix_core::ErrorResponse {
     }
 }
 
+impl From<wasmatrix_proto::conversion::ConversionError> for ControlPlaneError {
+    fn from(err: wasmatrix_proto::conversion::ConversionError) -> Self {
+        ControlPlaneError::ValidationError(err.to_string())
+    }
+}
+
 pub type ControlPlaneResult<T> = std::result::Result<T, ControlPlaneError>;
 
 #[cfg(test)]
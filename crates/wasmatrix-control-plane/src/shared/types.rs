@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 pub use wasmatrix_core::{
     CapabilityAssignment, InstanceMetadata, InstanceStatus, ProviderType, RestartPolicy,
 };
@@ -7,7 +9,21 @@ pub use wasmatrix_core::{
 pub struct StartInstanceRequest {
     pub module_bytes: Vec<u8>,
     pub capabilities: Vec<CapabilityAssignment>,
-    pub restart_policy: RestartPolicy,
+    /// `None` uses the control plane's default restart policy
+    pub restart_policy: Option<RestartPolicy>,
+    /// Detached signature over `module_bytes`, checked when verification is enabled
+    pub module_signature: Option<Vec<u8>>,
+    /// WASI environment variables; configuration only, never stored in instance metadata
+    pub env: Vec<(String, String)>,
+    /// WASI argv; configuration only, never stored in instance metadata
+    pub args: Vec<String>,
+    /// Node to try first when it is available and offers the required providers
+    pub preferred_node_id: Option<String>,
+    /// Fail instead of falling back when `preferred_node_id` cannot take the instance
+    pub require_node: bool,
+    /// Caller-supplied labels (trace id, deploy id) copied onto the agent's
+    /// `instance_started` event; never stored in instance metadata
+    pub annotations: HashMap<String, String>,
 }
 
 /// Request to stop an instance
@@ -16,6 +32,16 @@ pub struct StopInstanceRequest {
     pub instance_id: String,
 }
 
+/// What `stop_instance` does with the instance's metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StopBehavior {
+    /// Keep the metadata with `Stopped` status, e.g. for auditing
+    #[default]
+    RetainMetadata,
+    /// Remove the metadata and capability assignments entirely
+    RemoveMetadata,
+}
+
 /// Request to query an instance
 #[derive(Debug, Clone)]
 pub struct QueryInstanceRequest {
@@ -0,0 +1,498 @@
+//! In-process harness that runs a control plane and a node agent on
+//! ephemeral ports so tests can exercise the full routing path over gRPC.
+
+use crate::features::node_routing::controller::NodeRoutingController;
+use crate::features::node_routing::repo::InMemoryNodeRoutingRepository;
+use crate::features::node_routing::service::NodeRoutingService;
+use crate::server::ControlPlaneServer;
+use crate::ControlPlane;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+use wasmatrix_agent::server::NodeAgentServer;
+use wasmatrix_agent::NodeAgent;
+use wasmatrix_proto::v1::control_plane_service_client::ControlPlaneServiceClient;
+use wasmatrix_proto::v1::control_plane_service_server::ControlPlaneServiceServer;
+use wasmatrix_proto::v1::node_agent_service_client::NodeAgentServiceClient;
+use wasmatrix_proto::v1::node_agent_service_server::NodeAgentServiceServer;
+use wasmatrix_proto::v1::RegisterNodeRequest;
+
+/// Smallest valid Wasm module: `(module)`
+pub const EMPTY_MODULE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+/// A running control plane with one registered node agent.
+///
+/// Both servers are aborted when the harness is dropped.
+pub struct TestCluster {
+    pub node_id: String,
+    pub routing: Arc<NodeRoutingController>,
+    pub control_plane_client: ControlPlaneServiceClient<Channel>,
+    pub agent_client: NodeAgentServiceClient<Channel>,
+    servers: Vec<JoinHandle<()>>,
+}
+
+impl TestCluster {
+    /// Boot both servers and register the agent with the control plane
+    pub async fn start(node_id: &str) -> Self {
+        let agent = Arc::new(NodeAgent::new(node_id.to_string()).expect("create node agent"));
+        let (agent_addr, agent_server) = serve(NodeAgentServiceServer::new(NodeAgentServer::new(
+            agent, None,
+        )))
+        .await;
+
+        let control_plane = Arc::new(Mutex::new(ControlPlane::new(node_id)));
+        let routing = Arc::new(NodeRoutingController::new(Arc::new(
+            NodeRoutingService::new(Arc::new(InMemoryNodeRoutingRepository::new())),
+        )));
+        let (control_plane_addr, control_plane_server) = serve(ControlPlaneServiceServer::new(
+            ControlPlaneServer::new(control_plane.clone(), routing.clone()),
+        ))
+        .await;
+
+        let mut control_plane_client =
+            ControlPlaneServiceClient::connect(format!("http://{control_plane_addr}"))
+                .await
+                .expect("connect to control plane");
+        let agent_client = NodeAgentServiceClient::connect(format!("http://{agent_addr}"))
+            .await
+            .expect("connect to node agent");
+
+        let registered = control_plane_client
+            .register_node(RegisterNodeRequest {
+                node_id: node_id.to_string(),
+                node_address: format!("http://{agent_addr}"),
+                capabilities: vec![],
+                max_instances: None,
+                weight: None,
+            })
+            .await
+            .expect("register node")
+            .into_inner();
+        assert!(registered.success, "{}", registered.message);
+
+        Self {
+            node_id: node_id.to_string(),
+            routing,
+            control_plane_client,
+            agent_client,
+            servers: vec![agent_server, control_plane_server],
+        }
+    }
+}
+
+impl TestCluster {
+    /// Boot another node agent and register it with the routing controller
+    pub async fn add_node(&mut self, node_id: &str) -> NodeAgentServiceClient<Channel> {
+        let agent = Arc::new(NodeAgent::new(node_id.to_string()).expect("create node agent"));
+        let (agent_addr, agent_server) = serve(NodeAgentServiceServer::new(NodeAgentServer::new(
+            agent, None,
+        )))
+        .await;
+        self.servers.push(agent_server);
+
+        self.routing
+            .register_node(
+                node_id.to_string(),
+                format!("http://{agent_addr}"),
+                vec![],
+                None,
+                None,
+            )
+            .await
+            .expect("register node");
+        NodeAgentServiceClient::connect(format!("http://{agent_addr}"))
+            .await
+            .expect("connect to node agent")
+    }
+}
+
+impl Drop for TestCluster {
+    fn drop(&mut self) {
+        for server in &self.servers {
+            server.abort();
+        }
+    }
+}
+
+pub(crate) async fn serve<S>(service: S) -> (SocketAddr, JoinHandle<()>)
+where
+    S: tonic::codegen::Service<
+            tonic::codegen::http::Request<tonic::transport::Body>,
+            Response = tonic::codegen::http::Response<tonic::body::BoxBody>,
+            Error = std::convert::Infallible,
+        > + tonic::server::NamedService
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind ephemeral port");
+    let addr = listener.local_addr().expect("local addr");
+    let handle = tokio::spawn(async move {
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .expect("serve");
+    });
+    (addr, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::types::{QueryInstanceRequest, StartInstanceRequest};
+    use std::collections::HashMap;
+    use wasmatrix_core::{CapabilityAssignment, InstanceStatus, ProviderType};
+    use wasmatrix_proto::v1::{LocateInstanceRequest, RestartInstanceRequest};
+
+    #[tokio::test]
+    async fn test_full_instance_lifecycle_over_grpc() {
+        let mut cluster = TestCluster::start("harness-node").await;
+
+        let instance_id = cluster
+            .routing
+            .start_instance(StartInstanceRequest {
+                module_bytes: EMPTY_MODULE.to_vec(),
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        let located = cluster
+            .control_plane_client
+            .locate_instance(LocateInstanceRequest {
+                instance_id: instance_id.clone(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(located.node_id, Some(cluster.node_id.clone()));
+
+        let status = cluster
+            .routing
+            .query_instance(QueryInstanceRequest {
+                instance_id: instance_id.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(status.status, InstanceStatus::Running);
+        assert_eq!(status.node_id, cluster.node_id);
+
+        cluster
+            .routing
+            .stop_instance(&instance_id, None, &HashMap::new())
+            .await
+            .unwrap();
+
+        let listed = cluster
+            .agent_client
+            .list_instances(wasmatrix_proto::v1::ListInstancesRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(listed
+            .instances
+            .iter()
+            .all(|instance| instance.instance_id != instance_id));
+    }
+
+    #[tokio::test]
+    async fn test_restart_instance_over_grpc_reaches_the_agent() {
+        let mut cluster = TestCluster::start("harness-node").await;
+
+        let instance_id = cluster
+            .routing
+            .start_instance(StartInstanceRequest {
+                module_bytes: EMPTY_MODULE.to_vec(),
+                capabilities: vec![],
+                restart_policy: None,
+                module_signature: None,
+                env: vec![],
+                args: vec![],
+                preferred_node_id: None,
+                require_node: false,
+                annotations: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        let restarted = cluster
+            .control_plane_client
+            .restart_instance(RestartInstanceRequest {
+                instance_id: instance_id.clone(),
+                capabilities: vec![],
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(restarted.success, "{}", restarted.message);
+
+        // Gone from the agent while the control plane still routes to it
+        let stopped = cluster
+            .agent_client
+            .stop_instance(wasmatrix_proto::v1::StopInstanceRequest {
+                instance_id: instance_id.clone(),
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(stopped.success, "{}", stopped.message);
+        let lost = cluster
+            .control_plane_client
+            .restart_instance(RestartInstanceRequest {
+                instance_id: instance_id.clone(),
+                capabilities: vec![],
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!lost.success);
+        assert_eq!(lost.error_code.as_deref(), Some("INSTANCE_NOT_FOUND"));
+
+        let unknown = cluster
+            .control_plane_client
+            .restart_instance(RestartInstanceRequest {
+                instance_id: "missing".to_string(),
+                capabilities: vec![],
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!unknown.success);
+        assert_eq!(unknown.error_code.as_deref(), Some("INSTANCE_NOT_FOUND"));
+    }
+
+    #[tokio::test]
+    async fn test_migrated_instance_keeps_capability_grants() {
+        let mut cluster = TestCluster::start("node-a").await;
+        let request = || StartInstanceRequest {
+            module_bytes: EMPTY_MODULE.to_vec(),
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+
+        let instance_id = cluster
+            .routing
+            .start_instance(StartInstanceRequest {
+                capabilities: vec![CapabilityAssignment::new(
+                    String::new(),
+                    "kv-1".to_string(),
+                    ProviderType::Kv,
+                    vec!["kv:read".to_string()],
+                )],
+                ..request()
+            })
+            .await
+            .unwrap();
+        cluster
+            .routing
+            .record_capability_assignment(CapabilityAssignment::new(
+                instance_id.clone(),
+                "http-1".to_string(),
+                ProviderType::Http,
+                vec!["http:request".to_string()],
+            ))
+            .await
+            .unwrap();
+
+        let mut target = cluster.add_node("node-b").await;
+        let plan = vec![(
+            instance_id.clone(),
+            "node-a".to_string(),
+            "node-b".to_string(),
+        )];
+        let requests = HashMap::from([(instance_id.clone(), request())]);
+        assert_eq!(
+            cluster
+                .routing
+                .execute_rebalance(&plan, &requests)
+                .await
+                .unwrap(),
+            1
+        );
+
+        let listed = target
+            .list_instances(wasmatrix_proto::v1::ListInstancesRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        let migrated = listed
+            .instances
+            .iter()
+            .find(|instance| instance.instance_id == instance_id)
+            .expect("instance started on target node");
+        let mut granted: Vec<&str> = migrated
+            .capabilities
+            .iter()
+            .map(|cap| cap.capability_id.as_str())
+            .collect();
+        granted.sort();
+        assert_eq!(granted, vec!["http-1", "kv-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_reassigned_and_restarted_instance_gets_stored_grants() {
+        let mut cluster = TestCluster::start("node-a").await;
+        let request = || StartInstanceRequest {
+            module_bytes: EMPTY_MODULE.to_vec(),
+            capabilities: vec![CapabilityAssignment::new(
+                String::new(),
+                "kv-1".to_string(),
+                ProviderType::Kv,
+                vec!["kv:read".to_string()],
+            )],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+        let instance_id = cluster.routing.start_instance(request()).await.unwrap();
+        cluster
+            .routing
+            .record_capability_assignment(CapabilityAssignment::new(
+                instance_id.clone(),
+                "http-1".to_string(),
+                ProviderType::Http,
+                vec!["http:request".to_string()],
+            ))
+            .await
+            .unwrap();
+
+        // node-b does not run the instance until the reassignment starts it
+        let mut target = cluster.add_node("node-b").await;
+        let granted = |listed: wasmatrix_proto::v1::ListInstancesResponse| {
+            let instance = listed
+                .instances
+                .into_iter()
+                .find(|instance| instance.instance_id == instance_id)
+                .expect("instance listed on node-b");
+            let mut granted: Vec<String> = instance
+                .capabilities
+                .into_iter()
+                .map(|cap| cap.capability_id)
+                .collect();
+            granted.sort();
+            granted
+        };
+
+        cluster
+            .routing
+            .reassign_instance(&instance_id, "node-b", &request())
+            .await
+            .unwrap();
+        assert_eq!(
+            cluster.routing.locate_instance(&instance_id).await.unwrap(),
+            "node-b"
+        );
+        let listed = target
+            .list_instances(wasmatrix_proto::v1::ListInstancesRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(granted(listed), vec!["http-1", "kv-1"]);
+
+        // A restart resends the stored grants even if the node lost them
+        let cleared = target
+            .set_instance_capabilities(wasmatrix_proto::v1::SetInstanceCapabilitiesRequest {
+                instance_id: instance_id.clone(),
+                capabilities: vec![],
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(cleared.success, "{}", cleared.message);
+        cluster
+            .routing
+            .restart_instance(&instance_id)
+            .await
+            .unwrap();
+        let listed = target
+            .list_instances(wasmatrix_proto::v1::ListInstancesRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(granted(listed), vec!["http-1", "kv-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_rebalance_moves_planned_instance_between_agents() {
+        let mut cluster = TestCluster::start("node-a").await;
+        let request = || StartInstanceRequest {
+            module_bytes: EMPTY_MODULE.to_vec(),
+            capabilities: vec![],
+            restart_policy: None,
+            module_signature: None,
+            env: vec![],
+            args: vec![],
+            preferred_node_id: None,
+            require_node: false,
+            annotations: Default::default(),
+        };
+
+        let mut requests = HashMap::new();
+        for _ in 0..2 {
+            let instance_id = cluster.routing.start_instance(request()).await.unwrap();
+            requests.insert(instance_id, request());
+        }
+        let mut target = cluster.add_node("node-b").await;
+
+        let plan = cluster.routing.rebalance(10).await.unwrap();
+        assert_eq!(plan.len(), 1);
+        let (moved, from, to) = plan[0].clone();
+        assert_eq!((from.as_str(), to.as_str()), ("node-a", "node-b"));
+        assert_eq!(
+            cluster
+                .routing
+                .execute_rebalance(&plan, &requests)
+                .await
+                .unwrap(),
+            1
+        );
+
+        assert_eq!(
+            cluster.routing.locate_instance(&moved).await.unwrap(),
+            "node-b"
+        );
+        let on_target = target
+            .list_instances(wasmatrix_proto::v1::ListInstancesRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(on_target.instances.len(), 1);
+        assert_eq!(on_target.instances[0].instance_id, moved);
+        let on_source = cluster
+            .agent_client
+            .list_instances(wasmatrix_proto::v1::ListInstancesRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(on_source.instances.len(), 1);
+        assert_ne!(on_source.instances[0].instance_id, moved);
+
+        // The counts are now even, so nothing further is planned
+        assert!(cluster.routing.rebalance(10).await.unwrap().is_empty());
+    }
+}
@@ -1,5 +1,60 @@
-use crate::{CapabilityAssignment, CoreError, ProviderType, Result};
-use std::collections::HashMap;
+use crate::{CapabilityAssignment, CapabilityState, CoreError, ProviderType, Result};
+use std::collections::{HashMap, HashSet};
+
+#[cfg(test)]
+thread_local! {
+    static WILDCARD_PARSES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Permissions of one assignment compiled for repeated lookups.
+///
+/// Exact grants go into a set; wildcard grants are split into the segments
+/// before their `*` once, so matching never re-parses the granted side.
+/// Matches exactly what [`PermissionEnforcer::permission_matches`] would.
+#[derive(Debug, Clone, Default)]
+pub struct PermissionIndex {
+    exact: HashSet<String>,
+    wildcard_prefixes: Vec<Vec<String>>,
+}
+
+impl PermissionIndex {
+    pub fn compile(permissions: &[String]) -> Self {
+        let mut index = Self::default();
+        for permission in permissions {
+            let segments: Vec<&str> = permission.split(':').collect();
+            match segments.iter().position(|segment| *segment == "*") {
+                Some(star) => {
+                    #[cfg(test)]
+                    WILDCARD_PARSES.with(|count| count.set(count.get() + 1));
+                    index.wildcard_prefixes.push(
+                        segments[..star]
+                            .iter()
+                            .map(|segment| segment.to_string())
+                            .collect(),
+                    );
+                }
+                None => {
+                    index.exact.insert(permission.clone());
+                }
+            }
+        }
+        index
+    }
+
+    /// Whether any compiled grant covers `required`
+    pub fn matches(&self, required: &str) -> bool {
+        if self.exact.contains(required) {
+            return true;
+        }
+        if self.wildcard_prefixes.is_empty() {
+            return false;
+        }
+        let segments: Vec<&str> = required.split(':').collect();
+        self.wildcard_prefixes.iter().any(|prefix| {
+            segments.len() > prefix.len() && prefix.iter().zip(&segments).all(|(a, b)| a == b)
+        })
+    }
+}
 
 /// Registry for managing capability assignments
 #[derive(Debug, Default)]
@@ -61,10 +116,15 @@ impl CapabilityRegistry {
             ProviderType::Kv => vec!["kv:read", "kv:write", "kv:delete"],
             ProviderType::Http => vec!["http:request"],
             ProviderType::Messaging => vec!["msg:publish", "msg:subscribe"],
+            ProviderType::Introspection => vec!["self:read"],
         };
 
-        for permission in &assignment.permissions {
-            if !valid_permissions.contains(&permission.as_str()) {
+        // A wildcard grant is valid when it covers at least one known permission
+        for permission in assignment.permissions() {
+            if !valid_permissions
+                .iter()
+                .any(|valid| PermissionEnforcer::permission_matches(permission, valid))
+            {
                 return Err(CoreError::InvalidCapabilityAssignment(format!(
                     "Invalid permission '{}' for provider type {:?}",
                     permission, provider_type
@@ -76,11 +136,12 @@ impl CapabilityRegistry {
     }
 
     /// Store a capability assignment for an instance
-    pub fn assign_capability(&mut self, assignment: CapabilityAssignment) -> Result<()> {
+    pub fn assign_capability(&mut self, mut assignment: CapabilityAssignment) -> Result<()> {
         // Validate the assignment
         self.validate_assignment(&assignment)?;
 
-        // Store the assignment
+        // Store the assignment with its permissions compiled for later checks
+        assignment.compile_permissions();
         self.assignments
             .entry(assignment.instance_id.clone())
             .or_insert_with(Vec::new)
@@ -110,6 +171,47 @@ impl CapabilityRegistry {
         }
     }
 
+    /// Activate a pending capability; returns false if it is not assigned
+    /// or was already revoked
+    pub fn approve_capability(&mut self, instance_id: &str, capability_id: &str) -> bool {
+        self.set_state(instance_id, capability_id, |state| {
+            (state != CapabilityState::Revoked).then_some(CapabilityState::Active)
+        })
+    }
+
+    /// Mark a capability revoked; returns false if it is not assigned
+    pub fn deny_capability(&mut self, instance_id: &str, capability_id: &str) -> bool {
+        self.set_state(instance_id, capability_id, |_| {
+            Some(CapabilityState::Revoked)
+        })
+    }
+
+    fn set_state(
+        &mut self,
+        instance_id: &str,
+        capability_id: &str,
+        next: impl Fn(CapabilityState) -> Option<CapabilityState>,
+    ) -> bool {
+        let Some(assignment) = self
+            .assignments
+            .get_mut(instance_id)
+            .and_then(|assignments| {
+                assignments
+                    .iter_mut()
+                    .find(|a| a.capability_id == capability_id)
+            })
+        else {
+            return false;
+        };
+        match next(assignment.state) {
+            Some(state) => {
+                assignment.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get all capability assignments for an instance
     pub fn get_capabilities(&self, instance_id: &str) -> Option<&Vec<CapabilityAssignment>> {
         self.assignments.get(instance_id)
@@ -127,13 +229,10 @@ impl CapabilityRegistry {
     pub fn has_permission(&self, instance_id: &str, capability_id: &str, permission: &str) -> bool {
         self.assignments
             .get(instance_id)
-            .map(|assignments| {
-                assignments
-                    .iter()
-                    .filter(|a| a.capability_id == capability_id)
-                    .any(|a| a.has_permission(permission))
-            })
-            .unwrap_or(false)
+            .into_iter()
+            .flatten()
+            .filter(|a| a.capability_id == capability_id)
+            .any(|a| a.has_permission(permission))
     }
 
     /// Get all instance IDs with capabilities
@@ -178,11 +277,36 @@ impl PermissionEnforcer {
     pub fn messaging_permission(operation: &str) -> Option<&'static str> {
         match operation {
             "publish" => Some("msg:publish"),
-            "subscribe" => Some("msg:subscribe"),
+            "subscribe" | "list_subscriptions" => Some("msg:subscribe"),
             _ => None,
         }
     }
 
+    /// Required permission for an introspection operation
+    pub fn introspection_permission(operation: &str) -> Option<&'static str> {
+        match operation {
+            "read" => Some("self:read"),
+            _ => None,
+        }
+    }
+
+    /// Whether a granted permission covers `required`.
+    ///
+    /// Permissions are `:`-separated paths; a `*` segment in the grant matches
+    /// the rest of the path, so `kv:*` covers `kv:read` and `*` covers everything.
+    pub fn permission_matches(granted: &str, required: &str) -> bool {
+        let mut required_segments = required.split(':');
+        for segment in granted.split(':') {
+            if segment == "*" {
+                return required_segments.next().is_some();
+            }
+            if required_segments.next() != Some(segment) {
+                return false;
+            }
+        }
+        required_segments.next().is_none()
+    }
+
     /// Get required permission for any operation
     pub fn required_permission(
         provider_type: ProviderType,
@@ -192,6 +316,7 @@ impl PermissionEnforcer {
             ProviderType::Kv => Self::kv_permission(operation),
             ProviderType::Http => Self::http_permission(operation),
             ProviderType::Messaging => Self::messaging_permission(operation),
+            ProviderType::Introspection => Self::introspection_permission(operation),
         }
     }
 
@@ -249,6 +374,29 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_pending_capability_denies_until_approved() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register_provider("kv-store", ProviderType::Kv);
+        registry
+            .assign_capability(
+                create_test_assignment("instance-1", "kv-store", ProviderType::Kv, vec!["kv:read"])
+                    .pending(),
+            )
+            .unwrap();
+
+        assert!(registry.has_capability("instance-1", "kv-store"));
+        assert!(!registry.has_permission("instance-1", "kv-store", "kv:read"));
+
+        assert!(registry.approve_capability("instance-1", "kv-store"));
+        assert!(registry.has_permission("instance-1", "kv-store", "kv:read"));
+
+        assert!(registry.deny_capability("instance-1", "kv-store"));
+        assert!(!registry.has_permission("instance-1", "kv-store", "kv:read"));
+        assert!(!registry.approve_capability("instance-1", "kv-store"));
+        assert!(!registry.approve_capability("instance-1", "missing"));
+    }
+
     #[test]
     fn test_capability_registry_basic() {
         let mut registry = CapabilityRegistry::new();
@@ -449,6 +597,160 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_permission_matches_wildcards() {
+        assert!(PermissionEnforcer::permission_matches("kv:read", "kv:read"));
+        assert!(!PermissionEnforcer::permission_matches(
+            "kv:read", "kv:write"
+        ));
+        assert!(PermissionEnforcer::permission_matches("kv:*", "kv:write"));
+        assert!(PermissionEnforcer::permission_matches("*", "msg:publish"));
+        assert!(!PermissionEnforcer::permission_matches(
+            "kv:*",
+            "http:request"
+        ));
+        assert!(!PermissionEnforcer::permission_matches("kv:*", "kv"));
+        assert!(!PermissionEnforcer::permission_matches("kv", "kv:read"));
+    }
+
+    #[test]
+    fn test_permission_index_matches_naive_and_parses_wildcards_once() {
+        let granted: Vec<String> = ["kv:read", "msg:*", "http:api:*"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let parses_before = WILDCARD_PARSES.with(|count| count.get());
+        let index = PermissionIndex::compile(&granted);
+        assert_eq!(WILDCARD_PARSES.with(|count| count.get()) - parses_before, 2);
+
+        let required = [
+            "kv:read",
+            "kv:write",
+            "msg:publish",
+            "msg",
+            "http:api:get",
+            "http:api",
+            "http:other:get",
+            "self:read",
+        ];
+        for _ in 0..1000 {
+            for permission in required {
+                let naive = granted
+                    .iter()
+                    .any(|g| PermissionEnforcer::permission_matches(g, permission));
+                assert_eq!(index.matches(permission), naive, "{permission}");
+            }
+        }
+        assert_eq!(WILDCARD_PARSES.with(|count| count.get()) - parses_before, 2);
+    }
+
+    #[test]
+    fn test_assignment_compiles_wildcards_once_across_checks() {
+        let parses_before = WILDCARD_PARSES.with(|count| count.get());
+        let mut assignment = create_test_assignment(
+            "instance-1",
+            "kv-store",
+            ProviderType::Kv,
+            vec!["kv:*", "msg:publish:*"],
+        );
+        // Compiled by the constructor, before any check
+        assert_eq!(WILDCARD_PARSES.with(|count| count.get()) - parses_before, 2);
+        for _ in 0..1000 {
+            assert!(assignment.has_permission("kv:read"));
+            assert!(assignment.has_permission("msg:publish:orders"));
+            assert!(!assignment.has_permission("http:request"));
+        }
+        assert_eq!(WILDCARD_PARSES.with(|count| count.get()) - parses_before, 2);
+
+        // Clones carry the compiled index
+        let cloned = assignment.clone();
+        assert!(cloned.has_permission("kv:write"));
+        assert_eq!(WILDCARD_PARSES.with(|count| count.get()) - parses_before, 2);
+
+        // Narrowed grants take effect immediately
+        assignment.set_permissions(vec!["http:request".to_string()]);
+        assert!(assignment.has_permission("http:request"));
+        assert!(!assignment.has_permission("kv:read"));
+    }
+
+    #[test]
+    fn test_stored_assignment_is_compiled() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register_provider("kv-store", ProviderType::Kv);
+        let mut assignment =
+            create_test_assignment("instance-1", "kv-store", ProviderType::Kv, vec!["kv:*"]);
+        // As if deserialized, which skips the index
+        assignment.permission_index = None;
+
+        registry.assign_capability(assignment).unwrap();
+        let stored = &registry.get_capabilities("instance-1").unwrap()[0];
+        assert!(stored.permission_index.is_some());
+        assert!(stored.has_permission("kv:read"));
+    }
+
+    #[test]
+    fn test_revoke_keeps_other_grants_of_the_instance() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register_provider("kv-store", ProviderType::Kv);
+        registry.register_provider("bus", ProviderType::Messaging);
+
+        registry
+            .assign_capability(create_test_assignment(
+                "instance-1",
+                "kv-store",
+                ProviderType::Kv,
+                vec!["kv:read"],
+            ))
+            .unwrap();
+        registry
+            .assign_capability(create_test_assignment(
+                "instance-1",
+                "bus",
+                ProviderType::Messaging,
+                vec!["msg:publish"],
+            ))
+            .unwrap();
+        registry
+            .revoke_capability("instance-1", "kv-store")
+            .unwrap();
+
+        assert!(registry.has_permission("instance-1", "bus", "msg:publish"));
+        assert!(!registry.has_permission("instance-1", "kv-store", "kv:read"));
+    }
+
+    #[test]
+    fn test_registry_accepts_and_enforces_wildcard_grants() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register_provider("kv-store", ProviderType::Kv);
+
+        registry
+            .assign_capability(create_test_assignment(
+                "instance-1",
+                "kv-store",
+                ProviderType::Kv,
+                vec!["kv:*"],
+            ))
+            .unwrap();
+        assert!(registry.has_permission("instance-1", "kv-store", "kv:write"));
+        assert!(PermissionEnforcer::enforce(
+            &registry,
+            "instance-1",
+            "kv-store",
+            ProviderType::Kv,
+            "delete"
+        )
+        .is_ok());
+
+        assert!(registry
+            .assign_capability(create_test_assignment(
+                "instance-2",
+                "kv-store",
+                ProviderType::Kv,
+                vec!["msg:*"],
+            ))
+            .is_err());
+    }
+
     #[test]
     fn test_permission_enforcer_no_assignment() {
         let registry = CapabilityRegistry::new();
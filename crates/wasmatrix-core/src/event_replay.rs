@@ -0,0 +1,151 @@
+//! Replay of recorded execution events
+//!
+//! An event log taken from one recorder can be fed, in order, into a sink such
+//! as a fresh [`ExecutionEventRecorder`] to exercise downstream consumers or to
+//! simulate a past run, either as fast as possible or paced by the recorded
+//! timestamps.
+
+use crate::{ExecutionEvent, ExecutionEventRecorder};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Receiver of replayed events
+pub trait EventSink {
+    fn accept(&self, event: ExecutionEvent);
+}
+
+/// Replaying into a recorder records each event anew, so it gets the target
+/// recorder's next `seq` rather than the one it had in the source log
+impl EventSink for Mutex<ExecutionEventRecorder> {
+    fn accept(&self, event: ExecutionEvent) {
+        self.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record_event(event);
+    }
+}
+
+/// How fast events are replayed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ReplaySpeed {
+    /// Deliver every event immediately
+    #[default]
+    Unpaced,
+    /// Wait out the recorded gap between consecutive events
+    RealTime,
+    /// Wait out the recorded gap divided by the factor; `2.0` replays twice as fast
+    Scaled(f64),
+}
+
+impl ReplaySpeed {
+    /// Time to wait before delivering an event recorded `gap` after the previous one
+    pub fn delay(self, gap: chrono::Duration) -> Duration {
+        let gap = gap.to_std().unwrap_or_default();
+        match self {
+            ReplaySpeed::Unpaced => Duration::ZERO,
+            ReplaySpeed::RealTime => gap,
+            ReplaySpeed::Scaled(factor) if factor > 0.0 && factor.is_finite() => {
+                gap.div_f64(factor)
+            }
+            ReplaySpeed::Scaled(_) => Duration::ZERO,
+        }
+    }
+}
+
+impl ExecutionEventRecorder {
+    /// Feed every recorded event into `sink` in recording order, sleeping the
+    /// calling thread between events as `speed` asks. Returns the number of
+    /// events delivered.
+    pub fn replay_into(&self, sink: &dyn EventSink, speed: ReplaySpeed) -> usize {
+        replay_events(self.get_events(), sink, speed)
+    }
+}
+
+/// Feed an exported event log into `sink`; see [`ExecutionEventRecorder::replay_into`]
+pub fn replay_events(events: &[ExecutionEvent], sink: &dyn EventSink, speed: ReplaySpeed) -> usize {
+    let mut previous: Option<&ExecutionEvent> = None;
+    for event in events {
+        if let Some(previous) = previous {
+            let delay = speed.delay(event.timestamp - previous.timestamp);
+            if !delay.is_zero() {
+                std::thread::sleep(delay);
+            }
+        }
+        sink.accept(event.clone());
+        previous = Some(event);
+    }
+    events.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use chrono::{TimeZone, Utc};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct CollectingSink {
+        events: Mutex<Vec<ExecutionEvent>>,
+    }
+
+    impl EventSink for CollectingSink {
+        fn accept(&self, event: ExecutionEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn test_replay_three_event_log_in_order() {
+        let clock = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        ));
+        let mut recorder = ExecutionEventRecorder::with_clock(clock.clone());
+        recorder.record_start("instance-1");
+        clock.advance(chrono::Duration::milliseconds(20));
+        recorder.record_crash("instance-1", "trap");
+        clock.advance(chrono::Duration::milliseconds(20));
+        recorder.record_restart("instance-1");
+
+        let sink = CollectingSink::default();
+        let started = std::time::Instant::now();
+        // 40ms of recorded gaps replayed four times faster
+        assert_eq!(recorder.replay_into(&sink, ReplaySpeed::Scaled(4.0)), 3);
+        assert!(started.elapsed() >= Duration::from_millis(10));
+
+        let replayed = sink.events.into_inner().unwrap();
+        let kinds: Vec<_> = replayed.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(
+            kinds,
+            ["instance_started", "instance_crashed", "instance_restarted"]
+        );
+        assert_eq!(
+            replayed.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+
+        // A fresh recorder re-sequences what it is fed
+        let fresh = Mutex::new(ExecutionEventRecorder::new());
+        fresh.lock().unwrap().record_stop("other");
+        recorder.replay_into(&fresh, ReplaySpeed::Unpaced);
+        let fresh = fresh.into_inner().unwrap();
+        assert_eq!(fresh.get_events().len(), 4);
+        assert_eq!(fresh.get_events()[3].event_type, "instance_restarted");
+        assert_eq!(fresh.get_events()[3].seq, 4);
+    }
+
+    #[test]
+    fn test_replay_speed_delay() {
+        let gap = chrono::Duration::seconds(2);
+        assert_eq!(ReplaySpeed::Unpaced.delay(gap), Duration::ZERO);
+        assert_eq!(ReplaySpeed::RealTime.delay(gap), Duration::from_secs(2));
+        assert_eq!(
+            ReplaySpeed::Scaled(4.0).delay(gap),
+            Duration::from_millis(500)
+        );
+        assert_eq!(ReplaySpeed::Scaled(0.0).delay(gap), Duration::ZERO);
+        assert_eq!(
+            ReplaySpeed::RealTime.delay(chrono::Duration::seconds(-1)),
+            Duration::ZERO
+        );
+    }
+}
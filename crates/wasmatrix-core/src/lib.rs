@@ -1,10 +1,16 @@
 pub mod capability;
+pub mod clock;
+pub mod event_replay;
 pub mod isolation;
+pub mod module_format;
+pub mod module_hash;
 pub mod statelessness;
 
 use chrono::{DateTime, Utc};
+use clock::{Clock, SystemClock};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -14,6 +20,8 @@ pub enum CoreError {
     InvalidInstanceId(String),
     #[error("Invalid capability assignment: {0}")]
     InvalidCapabilityAssignment(String),
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
     #[error("Wasm runtime error: {0}")]
@@ -37,6 +45,42 @@ pub enum InstanceStatus {
     Crashed,
 }
 
+impl InstanceStatus {
+    /// The snake_case name, identical to the serde representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstanceStatus::Starting => "starting",
+            InstanceStatus::Running => "running",
+            InstanceStatus::Stopped => "stopped",
+            InstanceStatus::Crashed => "crashed",
+        }
+    }
+}
+
+impl std::fmt::Display for InstanceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("unknown instance status '{0}' (expected one of: starting, running, stopped, crashed)")]
+pub struct ParseInstanceStatusError(pub String);
+
+impl std::str::FromStr for InstanceStatus {
+    type Err = ParseInstanceStatusError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "starting" => Ok(InstanceStatus::Starting),
+            "running" => Ok(InstanceStatus::Running),
+            "stopped" => Ok(InstanceStatus::Stopped),
+            "crashed" => Ok(InstanceStatus::Crashed),
+            other => Err(ParseInstanceStatusError(other.to_string())),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstanceMetadata {
     pub instance_id: String,
@@ -44,6 +88,15 @@ pub struct InstanceMetadata {
     pub module_hash: String,
     pub created_at: DateTime<Utc>,
     pub status: InstanceStatus,
+    /// Logical deployment this instance is a replica of, if any
+    #[serde(default)]
+    pub deployment_id: Option<String>,
+    /// Operator tags such as `canary`; can change while the instance runs
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// When the labels or status last changed; `None` if never since creation
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 impl InstanceMetadata {
@@ -54,16 +107,38 @@ impl InstanceMetadata {
             module_hash,
             created_at: Utc::now(),
             status: InstanceStatus::Starting,
+            deployment_id: None,
+            labels: HashMap::new(),
+            updated_at: None,
         }
     }
+
+    pub fn with_deployment(mut self, deployment_id: Option<String>) -> Self {
+        self.deployment_id = deployment_id;
+        self
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ProviderType {
     Kv,
     Http,
     Messaging,
+    /// Reserved provider through which an instance reads its own metadata
+    Introspection,
+}
+
+/// Whether a capability assignment currently grants its permissions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityState {
+    /// Awaiting approval; grants nothing yet
+    Pending,
+    #[default]
+    Active,
+    /// Denied or withdrawn; grants nothing
+    Revoked,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,7 +146,19 @@ pub struct CapabilityAssignment {
     pub instance_id: String,
     pub capability_id: String,
     pub provider_type: ProviderType,
-    pub permissions: Vec<String>,
+    /// Private so every change goes through a setter that recompiles the index
+    permissions: Vec<String>,
+    /// Expired assignments are treated as absent; `None` never expires
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Only `Active` assignments grant permissions; older data defaults to `Active`
+    #[serde(default)]
+    pub state: CapabilityState,
+    /// `permissions` compiled whenever they are set; `None` after
+    /// deserializing until [`CapabilityAssignment::compile_permissions`] runs,
+    /// and matched against the list directly until then
+    #[serde(skip)]
+    permission_index: Option<capability::PermissionIndex>,
 }
 
 impl CapabilityAssignment {
@@ -86,11 +173,74 @@ impl CapabilityAssignment {
             capability_id,
             provider_type,
             permissions,
+            expires_at: None,
+            state: CapabilityState::Active,
+            permission_index: None,
         }
+        .compiled()
+    }
+
+    pub fn permissions(&self) -> &[String] {
+        &self.permissions
+    }
+
+    /// Replace the granted permissions
+    pub fn set_permissions(&mut self, permissions: Vec<String>) {
+        self.permissions = permissions;
+        self.compile_permissions();
+    }
+
+    /// Grant one more permission
+    pub fn add_permission(&mut self, permission: String) {
+        self.permissions.push(permission);
+        self.compile_permissions();
+    }
+
+    /// Build the permission index, e.g. for an assignment that was deserialized
+    pub fn compile_permissions(&mut self) {
+        self.permission_index = Some(capability::PermissionIndex::compile(&self.permissions));
     }
 
+    fn compiled(mut self) -> Self {
+        self.compile_permissions();
+        self
+    }
+
+    /// Start the assignment in `Pending`, granting nothing until approved
+    pub fn pending(mut self) -> Self {
+        self.state = CapabilityState::Pending;
+        self
+    }
+
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Utc::now())
+    }
+
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state == CapabilityState::Active
+    }
+
+    /// Whether this assignment grants `permission`, honouring wildcard grants
+    /// the same way [`capability::PermissionEnforcer::permission_matches`] does
     pub fn has_permission(&self, permission: &str) -> bool {
-        self.permissions.contains(&permission.to_string())
+        if !self.is_active() || self.is_expired() {
+            return false;
+        }
+        match &self.permission_index {
+            Some(index) => index.matches(permission),
+            None => self.permissions.iter().any(|granted| {
+                capability::PermissionEnforcer::permission_matches(granted, permission)
+            }),
+        }
     }
 }
 
@@ -107,6 +257,9 @@ pub struct RestartPolicy {
     pub policy_type: RestartPolicyType,
     pub max_retries: Option<u32>,
     pub backoff_seconds: Option<u64>,
+    /// Upper bound for the exponential restart backoff; defaults to 300 seconds
+    #[serde(default)]
+    pub max_backoff_seconds: Option<u64>,
 }
 
 impl Default for RestartPolicy {
@@ -115,6 +268,7 @@ impl Default for RestartPolicy {
             policy_type: RestartPolicyType::Never,
             max_retries: None,
             backoff_seconds: None,
+            max_backoff_seconds: None,
         }
     }
 }
@@ -125,6 +279,7 @@ impl RestartPolicy {
             policy_type: RestartPolicyType::Never,
             max_retries: None,
             backoff_seconds: None,
+            max_backoff_seconds: None,
         }
     }
 
@@ -133,6 +288,7 @@ impl RestartPolicy {
             policy_type: RestartPolicyType::Always,
             max_retries: None,
             backoff_seconds: None,
+            max_backoff_seconds: None,
         }
     }
 
@@ -141,6 +297,32 @@ impl RestartPolicy {
             policy_type: RestartPolicyType::OnFailure,
             max_retries: Some(max_retries),
             backoff_seconds: Some(backoff_seconds),
+            max_backoff_seconds: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("unknown restart policy '{0}' (expected never, always or on_failure:<max_retries>:<backoff_seconds>)")]
+pub struct ParseRestartPolicyError(pub String);
+
+impl std::str::FromStr for RestartPolicy {
+    type Err = ParseRestartPolicyError;
+
+    /// Parse `never`, `always` or `on_failure:<max_retries>:<backoff_seconds>`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split(':').collect::<Vec<_>>().as_slice() {
+            ["never"] => Ok(RestartPolicy::never()),
+            ["always"] => Ok(RestartPolicy::always()),
+            ["on_failure", max_retries, backoff_seconds] => {
+                match (max_retries.parse(), backoff_seconds.parse()) {
+                    (Ok(max_retries), Ok(backoff_seconds)) => {
+                        Ok(RestartPolicy::on_failure(max_retries, backoff_seconds))
+                    }
+                    _ => Err(ParseRestartPolicyError(s.to_string())),
+                }
+            }
+            _ => Err(ParseRestartPolicyError(s.to_string())),
         }
     }
 }
@@ -150,6 +332,9 @@ pub struct StartInstanceRequest {
     pub module_bytes: Vec<u8>,
     pub capabilities: Vec<CapabilityAssignment>,
     pub restart_policy: RestartPolicy,
+    /// Group the instance under this deployment for lifecycle operations
+    #[serde(default)]
+    pub deployment_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -180,11 +365,20 @@ pub struct ErrorResponse {
 
 impl ErrorResponse {
     pub fn new(error_code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new_at(error_code, message, &SystemClock)
+    }
+
+    /// Like [`Self::new`], but timestamped by `clock`
+    pub fn new_at(
+        error_code: impl Into<String>,
+        message: impl Into<String>,
+        clock: &dyn Clock,
+    ) -> Self {
         Self {
             error_code: error_code.into(),
             message: message.into(),
             details: None,
-            timestamp: Utc::now(),
+            timestamp: clock.now(),
         }
     }
 
@@ -200,15 +394,28 @@ pub struct ExecutionEvent {
     pub instance_id: String,
     pub timestamp: DateTime<Utc>,
     pub details: Option<HashMap<String, String>>,
+    /// Monotonic position assigned by the recorder; 0 means never recorded
+    #[serde(default)]
+    pub seq: u64,
 }
 
 impl ExecutionEvent {
     pub fn new(event_type: impl Into<String>, instance_id: impl Into<String>) -> Self {
+        Self::new_at(event_type, instance_id, &SystemClock)
+    }
+
+    /// Like [`Self::new`], but timestamped by `clock`
+    pub fn new_at(
+        event_type: impl Into<String>,
+        instance_id: impl Into<String>,
+        clock: &dyn Clock,
+    ) -> Self {
         Self {
             event_type: event_type.into(),
             instance_id: instance_id.into(),
-            timestamp: Utc::now(),
+            timestamp: clock.now(),
             details: None,
+            seq: 0,
         }
     }
 
@@ -216,14 +423,59 @@ impl ExecutionEvent {
         self.details = Some(details);
         self
     }
+
+    /// Add caller-supplied annotations to the details, each key prefixed with
+    /// [`ANNOTATION_KEY_PREFIX`] so it cannot clash with system-generated keys
+    pub fn with_annotations(mut self, annotations: &HashMap<String, String>) -> Self {
+        if annotations.is_empty() {
+            return self;
+        }
+        self.details.get_or_insert_with(HashMap::new).extend(
+            annotations
+                .iter()
+                .map(|(key, value)| (format!("{ANNOTATION_KEY_PREFIX}{key}"), value.clone())),
+        );
+        self
+    }
 }
 
+/// Prefix of event detail keys that hold caller-supplied annotations
+pub const ANNOTATION_KEY_PREFIX: &str = "annotation.";
+
 pub type Result<T> = std::result::Result<T, CoreError>;
 
-/// Execution event recorder for tracking instance lifecycle and crash events
-#[derive(Debug, Default)]
+/// Compact view of an instance's lifecycle derived from its recorded events
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstanceLifecycleSummary {
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_event: Option<ExecutionEvent>,
+    pub crash_count: u32,
+    pub restart_count: u32,
+    pub current_kind: Option<String>,
+}
+
+/// Execution event recorder for tracking instance lifecycle and crash events.
+///
+/// Every recorded event gets a `seq` one higher than the previous one, across
+/// all instances, so consumers can resume with [`Self::get_events_since`] and
+/// detect gaps. Sequence numbers start at 1 and are not reused after `clear`.
+///
+/// Timestamps never go backwards: an event stamped earlier than the previous
+/// one (e.g. after a wall-clock jump) is recorded with the previous timestamp.
+#[derive(Debug)]
 pub struct ExecutionEventRecorder {
     events: Vec<ExecutionEvent>,
+    /// Events recorded per `event_type`, maintained as events are recorded
+    counts: HashMap<String, usize>,
+    last_seq: u64,
+    last_timestamp: Option<DateTime<Utc>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for ExecutionEventRecorder {
+    fn default() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
 }
 
 impl ExecutionEventRecorder {
@@ -231,35 +483,102 @@ impl ExecutionEventRecorder {
         Self::default()
     }
 
-    pub fn record_event(&mut self, event: ExecutionEvent) {
+    /// Recorder whose `record_*` helpers take their timestamps from `clock`
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            events: Vec::new(),
+            counts: HashMap::new(),
+            last_seq: 0,
+            last_timestamp: None,
+            clock,
+        }
+    }
+
+    pub fn record_event(&mut self, mut event: ExecutionEvent) {
+        self.last_seq += 1;
+        event.seq = self.last_seq;
+        if let Some(last) = self.last_timestamp {
+            event.timestamp = event.timestamp.max(last);
+        }
+        self.last_timestamp = Some(event.timestamp);
+        *self.counts.entry(event.event_type.clone()).or_default() += 1;
         self.events.push(event);
     }
 
+    fn event(&self, event_type: &str, instance_id: &str) -> ExecutionEvent {
+        ExecutionEvent::new_at(event_type, instance_id, self.clock.as_ref())
+    }
+
     pub fn record_crash(&mut self, instance_id: &str, error: &str) {
         let mut details = std::collections::HashMap::new();
         details.insert("error".to_string(), error.to_string());
 
         self.record_event(
-            ExecutionEvent::new("instance_crashed", instance_id).with_details(details),
+            self.event("instance_crashed", instance_id)
+                .with_details(details),
         );
     }
 
     pub fn record_restart(&mut self, instance_id: &str) {
-        self.record_event(ExecutionEvent::new("instance_restarted", instance_id));
+        self.record_event(self.event("instance_restarted", instance_id));
     }
 
     pub fn record_start(&mut self, instance_id: &str) {
-        self.record_event(ExecutionEvent::new("instance_started", instance_id));
+        self.record_start_annotated(instance_id, &HashMap::new());
+    }
+
+    /// Record a start carrying the caller's annotations
+    pub fn record_start_annotated(
+        &mut self,
+        instance_id: &str,
+        annotations: &HashMap<String, String>,
+    ) {
+        self.record_event(
+            self.event("instance_started", instance_id)
+                .with_annotations(annotations),
+        );
     }
 
     pub fn record_stop(&mut self, instance_id: &str) {
-        self.record_event(ExecutionEvent::new("instance_stopped", instance_id));
+        self.record_stop_with_reason(instance_id, None);
+    }
+
+    /// Record a stop, keeping the operator's reason in the event details
+    pub fn record_stop_with_reason(&mut self, instance_id: &str, reason: Option<&str>) {
+        self.record_stop_annotated(instance_id, reason, &HashMap::new());
+    }
+
+    /// Record a stop carrying the operator's reason and the caller's annotations
+    pub fn record_stop_annotated(
+        &mut self,
+        instance_id: &str,
+        reason: Option<&str>,
+        annotations: &HashMap<String, String>,
+    ) {
+        let mut event = self.event("instance_stopped", instance_id);
+        if let Some(reason) = reason {
+            let mut details = std::collections::HashMap::new();
+            details.insert("reason".to_string(), reason.to_string());
+            event = event.with_details(details);
+        }
+        self.record_event(event.with_annotations(annotations));
     }
 
     pub fn get_events(&self) -> &[ExecutionEvent] {
         &self.events
     }
 
+    /// Number of recorded events per event type, without scanning the log
+    pub fn event_counts(&self) -> &HashMap<String, usize> {
+        &self.counts
+    }
+
+    /// Events recorded after `seq`, in order
+    pub fn get_events_since(&self, seq: u64) -> &[ExecutionEvent] {
+        let start = self.events.partition_point(|e| e.seq <= seq);
+        &self.events[start..]
+    }
+
     pub fn get_events_for_instance(&self, instance_id: &str) -> Vec<&ExecutionEvent> {
         self.events
             .iter()
@@ -267,8 +586,35 @@ impl ExecutionEventRecorder {
             .collect()
     }
 
+    pub fn latest_event_for_instance(&self, instance_id: &str) -> Option<&ExecutionEvent> {
+        self.events
+            .iter()
+            .rev()
+            .find(|e| e.instance_id == instance_id)
+    }
+
+    pub fn lifecycle_summary(&self, instance_id: &str) -> InstanceLifecycleSummary {
+        let mut summary = InstanceLifecycleSummary::default();
+
+        for event in self.events.iter().filter(|e| e.instance_id == instance_id) {
+            match event.event_type.as_str() {
+                "instance_started" if summary.started_at.is_none() => {
+                    summary.started_at = Some(event.timestamp);
+                }
+                "instance_crashed" => summary.crash_count += 1,
+                "instance_restarted" => summary.restart_count += 1,
+                _ => {}
+            }
+        }
+
+        summary.last_event = self.latest_event_for_instance(instance_id).cloned();
+        summary.current_kind = summary.last_event.as_ref().map(|e| e.event_type.clone());
+        summary
+    }
+
     pub fn clear(&mut self) {
         self.events.clear();
+        self.counts.clear();
     }
 }
 
@@ -333,6 +679,24 @@ impl MetadataRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::MockClock;
+
+    #[test]
+    fn test_event_counts_track_recorded_events() {
+        let mut recorder = ExecutionEventRecorder::new();
+        recorder.record_start("instance-1");
+        recorder.record_crash("instance-1", "trap");
+        recorder.record_restart("instance-1");
+        recorder.record_crash("instance-2", "trap");
+
+        let counts = recorder.event_counts();
+        assert_eq!(counts["instance_started"], 1);
+        assert_eq!(counts["instance_crashed"], 2);
+        assert_eq!(counts["instance_restarted"], 1);
+
+        recorder.clear();
+        assert!(recorder.event_counts().is_empty());
+    }
 
     #[test]
     fn test_instance_metadata_creation() {
@@ -343,6 +707,31 @@ mod tests {
         assert!(!metadata.instance_id.is_empty());
     }
 
+    #[test]
+    fn test_instance_status_display_and_parse_round_trip() {
+        for status in [
+            InstanceStatus::Starting,
+            InstanceStatus::Running,
+            InstanceStatus::Stopped,
+            InstanceStatus::Crashed,
+        ] {
+            let text = status.to_string();
+            assert_eq!(text.parse::<InstanceStatus>().unwrap(), status);
+            assert_eq!(
+                serde_json::to_string(&status).unwrap(),
+                format!("\"{}\"", text)
+            );
+        }
+    }
+
+    #[test]
+    fn test_instance_status_parse_rejects_unknown() {
+        let error = "Paused".parse::<InstanceStatus>().unwrap_err();
+        assert_eq!(error, ParseInstanceStatusError("Paused".to_string()));
+        assert!(error.to_string().contains("expected one of"));
+        assert!("Running".parse::<InstanceStatus>().is_err());
+    }
+
     #[test]
     fn test_capability_assignment_permissions() {
         let assignment = CapabilityAssignment::new(
@@ -357,6 +746,84 @@ mod tests {
         assert!(!assignment.has_permission("kv:delete"));
     }
 
+    #[test]
+    fn test_has_permission_agrees_with_permission_matches() {
+        let granted = ["kv:*", "msg:publish:orders", "http:request"];
+        let assignment = CapabilityAssignment::new(
+            "instance-1".to_string(),
+            "kv-1".to_string(),
+            ProviderType::Kv,
+            granted.iter().map(|p| p.to_string()).collect(),
+        );
+
+        for required in [
+            "kv:read",
+            "kv:delete",
+            "kv",
+            "msg:publish:orders",
+            "msg:publish",
+            "http:request",
+            "http:domain:example.com",
+        ] {
+            let matched = granted
+                .iter()
+                .any(|g| capability::PermissionEnforcer::permission_matches(g, required));
+            assert_eq!(assignment.has_permission(required), matched, "{required}");
+        }
+        assert!(assignment.has_permission("kv:read"));
+    }
+
+    #[test]
+    fn test_capability_state_gates_permissions() {
+        let mut assignment = CapabilityAssignment::new(
+            "instance-1".to_string(),
+            "kv-1".to_string(),
+            ProviderType::Kv,
+            vec!["kv:read".to_string()],
+        )
+        .pending();
+        assert!(!assignment.has_permission("kv:read"));
+
+        assignment.state = CapabilityState::Active;
+        assert!(assignment.has_permission("kv:read"));
+
+        assignment.state = CapabilityState::Revoked;
+        assert!(!assignment.has_permission("kv:read"));
+
+        let legacy: CapabilityAssignment = serde_json::from_str(
+            r#"{"instance_id":"i","capability_id":"kv-1","provider_type":"kv","permissions":["kv:read"]}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.state, CapabilityState::Active);
+    }
+
+    #[test]
+    fn test_capability_assignment_expiry() {
+        let assignment = CapabilityAssignment::new(
+            "instance-1".to_string(),
+            "kv-1".to_string(),
+            ProviderType::Kv,
+            vec!["kv:read".to_string()],
+        );
+        assert!(!assignment.is_expired());
+
+        let active = assignment
+            .clone()
+            .with_expiry(Utc::now() + chrono::Duration::hours(1));
+        assert!(active.has_permission("kv:read"));
+
+        let expired = assignment.with_expiry(Utc::now() - chrono::Duration::seconds(1));
+        assert!(expired.is_expired());
+        assert!(!expired.has_permission("kv:read"));
+    }
+
+    #[test]
+    fn test_capability_assignment_deserializes_without_expiry() {
+        let json = r#"{"instance_id":"i","capability_id":"kv-1","provider_type":"kv","permissions":["kv:read"]}"#;
+        let assignment: CapabilityAssignment = serde_json::from_str(json).unwrap();
+        assert!(assignment.expires_at.is_none());
+    }
+
     #[test]
     fn test_restart_policy_default() {
         let policy = RestartPolicy::default();
@@ -373,6 +840,28 @@ mod tests {
         assert_eq!(policy.backoff_seconds, Some(5));
     }
 
+    #[test]
+    fn test_restart_policy_from_str() {
+        let policy: RestartPolicy = "on_failure:3:5".parse().unwrap();
+        assert_eq!(policy.policy_type, RestartPolicyType::OnFailure);
+        assert_eq!(policy.max_retries, Some(3));
+        assert_eq!(policy.backoff_seconds, Some(5));
+        assert_eq!(
+            "always".parse::<RestartPolicy>().unwrap().policy_type,
+            RestartPolicyType::Always
+        );
+        assert_eq!(
+            "never".parse::<RestartPolicy>().unwrap().policy_type,
+            RestartPolicyType::Never
+        );
+        for invalid in ["on_failure", "on_failure:3", "on_failure:x:5", "sometimes"] {
+            assert_eq!(
+                invalid.parse::<RestartPolicy>().unwrap_err(),
+                ParseRestartPolicyError(invalid.to_string())
+            );
+        }
+    }
+
     #[test]
     fn test_serialization_roundtrip() {
         let metadata = InstanceMetadata::new("node-1".to_string(), "abc123".to_string());
@@ -416,6 +905,12 @@ mod tests {
         assert!(error.to_string().contains("Resource exhaustion"));
     }
 
+    #[test]
+    fn test_core_error_permission_denied() {
+        let error = CoreError::PermissionDenied("no assigned permissions".to_string());
+        assert!(error.to_string().contains("Permission denied"));
+    }
+
     #[test]
     fn test_core_error_timeout() {
         let error = CoreError::Timeout("Operation timed out".to_string());
@@ -504,6 +999,29 @@ mod tests {
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].event_type, "instance_stopped");
         assert_eq!(events[0].instance_id, "instance-1");
+        assert!(events[0].details.is_none());
+    }
+
+    #[test]
+    fn test_execution_event_recorder_record_stop_with_reason() {
+        let mut recorder = ExecutionEventRecorder::new();
+        recorder.record_stop_with_reason("instance-1", Some("incident"));
+        let details = recorder.get_events()[0].details.as_ref().unwrap();
+        assert_eq!(details.get("reason").map(String::as_str), Some("incident"));
+    }
+
+    #[test]
+    fn test_execution_event_recorder_keeps_annotations_apart_from_system_keys() {
+        let mut recorder = ExecutionEventRecorder::new();
+        let annotations = HashMap::from([("reason".to_string(), "spoofed".to_string())]);
+        recorder.record_stop_annotated("instance-1", Some("incident"), &annotations);
+
+        let details = recorder.get_events()[0].details.as_ref().unwrap();
+        assert_eq!(details.get("reason").map(String::as_str), Some("incident"));
+        assert_eq!(
+            details.get("annotation.reason").map(String::as_str),
+            Some("spoofed")
+        );
     }
 
     #[test]
@@ -524,6 +1042,110 @@ mod tests {
         assert_eq!(events[3].event_type, "instance_stopped");
     }
 
+    #[test]
+    fn test_execution_event_recorder_lifecycle_summary() {
+        let mut recorder = ExecutionEventRecorder::new();
+        recorder.record_start("instance-1");
+        recorder.record_start("instance-2");
+        recorder.record_crash("instance-1", "trap");
+        recorder.record_restart("instance-1");
+        recorder.record_stop("instance-1");
+
+        let latest = recorder.latest_event_for_instance("instance-1").unwrap();
+        assert_eq!(latest.event_type, "instance_stopped");
+
+        let summary = recorder.lifecycle_summary("instance-1");
+        assert_eq!(
+            summary.started_at,
+            Some(recorder.get_events_for_instance("instance-1")[0].timestamp)
+        );
+        assert_eq!(summary.crash_count, 1);
+        assert_eq!(summary.restart_count, 1);
+        assert_eq!(summary.current_kind.as_deref(), Some("instance_stopped"));
+        assert_eq!(
+            summary.last_event.map(|e| e.event_type).as_deref(),
+            Some("instance_stopped")
+        );
+    }
+
+    #[test]
+    fn test_execution_event_recorder_sequence_numbers() {
+        let mut recorder = ExecutionEventRecorder::new();
+        recorder.record_start("instance-1");
+        recorder.record_start("instance-2");
+        recorder.record_crash("instance-1", "trap");
+        recorder.record_restart("instance-1");
+        recorder.record_stop("instance-2");
+
+        let seqs: Vec<u64> = recorder.get_events().iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, vec![1, 2, 3, 4, 5]);
+
+        let tail = recorder.get_events_since(3);
+        assert_eq!(tail.len(), 2);
+        assert_eq!(tail[0].event_type, "instance_restarted");
+        assert_eq!(tail[1].instance_id, "instance-2");
+        assert!(recorder.get_events_since(5).is_empty());
+        assert_eq!(recorder.get_events_since(0).len(), 5);
+
+        recorder.clear();
+        recorder.record_start("instance-3");
+        assert_eq!(recorder.get_events()[0].seq, 6);
+    }
+
+    #[test]
+    fn test_execution_event_recorder_uses_injected_clock() {
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(MockClock::new(start));
+        let mut recorder = ExecutionEventRecorder::with_clock(clock.clone());
+
+        recorder.record_start("instance-1");
+        clock.advance(chrono::Duration::seconds(1));
+        recorder.record_crash("instance-1", "trap");
+        clock.advance(chrono::Duration::seconds(1));
+        recorder.record_restart("instance-1");
+
+        let timestamps: Vec<_> = recorder.get_events().iter().map(|e| e.timestamp).collect();
+        assert_eq!(
+            timestamps,
+            vec![
+                start,
+                start + chrono::Duration::seconds(1),
+                start + chrono::Duration::seconds(2),
+            ]
+        );
+
+        // A backwards jump is clamped to the previous timestamp
+        clock.set(start - chrono::Duration::hours(1));
+        recorder.record_stop("instance-1");
+        assert_eq!(
+            recorder.get_events()[3].timestamp,
+            start + chrono::Duration::seconds(2)
+        );
+
+        let error = ErrorResponse::new_at("TIMEOUT", "took too long", clock.as_ref());
+        assert_eq!(error.timestamp, start - chrono::Duration::hours(1));
+    }
+
+    #[test]
+    fn test_execution_event_deserializes_without_seq() {
+        let json = r#"{"event_type":"instance_started","instance_id":"i-1","timestamp":"2024-01-01T00:00:00Z","details":null}"#;
+        let event: ExecutionEvent = serde_json::from_str(json).unwrap();
+        assert_eq!(event.seq, 0);
+    }
+
+    #[test]
+    fn test_execution_event_recorder_lifecycle_summary_unknown_instance() {
+        let recorder = ExecutionEventRecorder::new();
+
+        assert!(recorder.latest_event_for_instance("missing").is_none());
+        let summary = recorder.lifecycle_summary("missing");
+        assert!(summary.started_at.is_none());
+        assert!(summary.last_event.is_none());
+        assert_eq!(summary.crash_count, 0);
+        assert_eq!(summary.restart_count, 0);
+        assert!(summary.current_kind.is_none());
+    }
+
     #[test]
     fn test_execution_event_timestamps() {
         let mut recorder = ExecutionEventRecorder::new();
@@ -723,6 +1345,7 @@ mod tests {
                 Just(ProviderType::Kv),
                 Just(ProviderType::Http),
                 Just(ProviderType::Messaging),
+                Just(ProviderType::Introspection),
             ]
         }
 
@@ -0,0 +1,68 @@
+//! Detection of the WebAssembly binary flavour
+//!
+//! Core modules and Component Model components share the `\0asm` magic but
+//! differ in the version and layer fields of the 8-byte preamble, and they are
+//! instantiated differently.
+
+/// The `\0asm` magic every WebAssembly binary starts with
+pub const WASM_MAGIC: [u8; 4] = [0x00, 0x61, 0x73, 0x6d];
+
+const CORE_MODULE_VERSION: u16 = 1;
+const CORE_MODULE_LAYER: u16 = 0;
+const COMPONENT_LAYER: u16 = 1;
+
+/// What kind of WebAssembly binary a byte buffer holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleFormat {
+    /// A core module: version 1, layer 0
+    CoreModule,
+    /// A Component Model component: layer 1, any version
+    Component,
+    /// Missing magic, truncated preamble, or an unknown version/layer
+    Unknown,
+}
+
+/// Classify `bytes` by their 8-byte preamble
+pub fn detect_module_format(bytes: &[u8]) -> ModuleFormat {
+    if bytes.len() < 8 || bytes[0..4] != WASM_MAGIC {
+        return ModuleFormat::Unknown;
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    let layer = u16::from_le_bytes([bytes[6], bytes[7]]);
+    match (version, layer) {
+        (CORE_MODULE_VERSION, CORE_MODULE_LAYER) => ModuleFormat::CoreModule,
+        (_, COMPONENT_LAYER) => ModuleFormat::Component,
+        _ => ModuleFormat::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_core_module_and_component() {
+        let core = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        let component = [0x00, 0x61, 0x73, 0x6d, 0x0d, 0x00, 0x01, 0x00];
+
+        assert_eq!(detect_module_format(&core), ModuleFormat::CoreModule);
+        assert_eq!(detect_module_format(&component), ModuleFormat::Component);
+    }
+
+    #[test]
+    fn test_unrecognized_preambles_are_unknown() {
+        assert_eq!(detect_module_format(&[]), ModuleFormat::Unknown);
+        assert_eq!(
+            detect_module_format(&[0x00, 0x61, 0x73, 0x6d]),
+            ModuleFormat::Unknown
+        );
+        assert_eq!(
+            detect_module_format(b"\x7fELF\x02\x01\x01\x00"),
+            ModuleFormat::Unknown
+        );
+        assert_eq!(
+            detect_module_format(&[0x00, 0x61, 0x73, 0x6d, 0x02, 0x00, 0x00, 0x00]),
+            ModuleFormat::Unknown
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Scheme-tagged module hashes
+//!
+//! `module_hash` values are stored as `<scheme>:<hex>` (`sha256:…`, `md5:…`) so
+//! hashes written before the move to sha256 keep working next to new ones.
+//! Metadata persisted earlier holds bare md5 hex, which is read as `md5`.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Digest used to identify a module's bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HashScheme {
+    /// Legacy scheme; bare hex values without a tag are md5
+    Md5,
+    #[default]
+    Sha256,
+}
+
+impl HashScheme {
+    pub fn tag(self) -> &'static str {
+        match self {
+            HashScheme::Md5 => "md5",
+            HashScheme::Sha256 => "sha256",
+        }
+    }
+
+    /// Length of the hex digest this scheme produces
+    pub fn hex_len(self) -> usize {
+        match self {
+            HashScheme::Md5 => 32,
+            HashScheme::Sha256 => 64,
+        }
+    }
+
+    /// Tagged hash of `module_bytes`, e.g. `sha256:<hex>`
+    pub fn compute(self, module_bytes: &[u8]) -> String {
+        let hex = match self {
+            HashScheme::Md5 => format!("{:x}", md5::compute(module_bytes)),
+            HashScheme::Sha256 => {
+                use sha2::Digest;
+                format!("{:x}", sha2::Sha256::digest(module_bytes))
+            }
+        };
+        format!("{}:{}", self.tag(), hex)
+    }
+}
+
+impl fmt::Display for HashScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl FromStr for HashScheme {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "md5" => Ok(HashScheme::Md5),
+            "sha256" => Ok(HashScheme::Sha256),
+            other => Err(format!("unknown module hash scheme '{}'", other)),
+        }
+    }
+}
+
+/// A module hash split into its scheme and hex digest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleHash<'a> {
+    pub scheme: HashScheme,
+    pub hex: &'a str,
+}
+
+impl<'a> ModuleHash<'a> {
+    /// Parse `<scheme>:<hex>`, or bare md5 hex from before hashes were tagged.
+    /// Returns `None` for anything else, including a digest of the wrong length.
+    pub fn parse(value: &'a str) -> Option<Self> {
+        let (scheme, hex) = match value.split_once(':') {
+            Some((tag, hex)) => (tag.parse().ok()?, hex),
+            None => (HashScheme::Md5, value),
+        };
+        let well_formed =
+            hex.len() == scheme.hex_len() && hex.bytes().all(|b| b.is_ascii_hexdigit());
+        well_formed.then_some(Self { scheme, hex })
+    }
+}
+
+/// Tagged hash of `module_bytes` with the default scheme
+pub fn compute_module_hash(module_bytes: &[u8]) -> String {
+    HashScheme::default().compute(module_bytes)
+}
+
+/// Whether two stored hashes name the same module. Hashes only match within
+/// one scheme, so an md5 hash never equals a sha256 hash of the same bytes;
+/// values that are not hashes at all are compared as plain strings.
+pub fn module_hashes_match(a: &str, b: &str) -> bool {
+    match (ModuleHash::parse(a), ModuleHash::parse(b)) {
+        (Some(a), Some(b)) => a.scheme == b.scheme && a.hex.eq_ignore_ascii_case(b.hex),
+        _ => a == b,
+    }
+}
+
+/// Whether `module_bytes` hash to `hash` under the scheme `hash` was written with
+pub fn module_matches_hash(module_bytes: &[u8], hash: &str) -> bool {
+    ModuleHash::parse(hash)
+        .is_some_and(|parsed| module_hashes_match(&parsed.scheme.compute(module_bytes), hash))
+}
+
+/// Tag a legacy bare md5 hex value as `md5:`; anything else is returned unchanged
+pub fn migrate_legacy_hash(value: &str) -> String {
+    match ModuleHash::parse(value) {
+        Some(parsed) if !value.contains(':') => format!("{}:{}", parsed.scheme.tag(), parsed.hex),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE: &[u8] = &[0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+    #[test]
+    fn test_parse_tagged_and_legacy_hashes() {
+        let legacy = format!("{:x}", md5::compute(MODULE));
+        assert_eq!(
+            ModuleHash::parse(&legacy),
+            Some(ModuleHash {
+                scheme: HashScheme::Md5,
+                hex: &legacy,
+            })
+        );
+
+        let sha = HashScheme::Sha256.compute(MODULE);
+        let parsed = ModuleHash::parse(&sha).unwrap();
+        assert_eq!(parsed.scheme, HashScheme::Sha256);
+        assert_eq!(parsed.hex.len(), 64);
+
+        assert_eq!(ModuleHash::parse("blake3:abcd"), None);
+        assert_eq!(ModuleHash::parse("sha256:abcd"), None);
+        assert_eq!(ModuleHash::parse("unknown"), None);
+        assert_eq!("SHA256".parse::<HashScheme>(), Ok(HashScheme::Sha256));
+    }
+
+    #[test]
+    fn test_legacy_md5_does_not_match_sha256_of_same_module() {
+        let legacy = migrate_legacy_hash(&format!("{:x}", md5::compute(MODULE)));
+        assert!(legacy.starts_with("md5:"));
+        let fresh = compute_module_hash(MODULE);
+        assert!(fresh.starts_with("sha256:"));
+
+        assert!(!module_hashes_match(&legacy, &fresh));
+        // Each still identifies the module under its own scheme
+        assert!(module_matches_hash(MODULE, &legacy));
+        assert!(module_matches_hash(MODULE, &fresh));
+        assert!(module_hashes_match(
+            &legacy,
+            &format!("{:x}", md5::compute(MODULE))
+        ));
+
+        assert_eq!(migrate_legacy_hash(&fresh), fresh);
+        assert_eq!(migrate_legacy_hash("unknown"), "unknown");
+    }
+}
@@ -4,6 +4,18 @@
 //! and restart-assumed. No instance memory state is persisted.
 
 use crate::{CapabilityAssignment, CoreError, InstanceMetadata, InstanceStatus, Result};
+use std::collections::HashMap;
+
+/// Maximum number of environment variables passed to a WASI instance
+pub const MAX_WASI_ENV_VARS: usize = 64;
+/// Maximum number of argv entries passed to a WASI instance
+pub const MAX_WASI_ARGS: usize = 64;
+/// Maximum combined size in bytes of all env keys, values and args
+pub const MAX_WASI_CONFIG_BYTES: usize = 16 * 1024;
+/// Maximum number of caller-supplied annotations on one execution event
+pub const MAX_EVENT_ANNOTATIONS: usize = 16;
+/// Maximum combined size in bytes of all annotation keys and values
+pub const MAX_EVENT_ANNOTATION_BYTES: usize = 1024;
 
 /// Audit record for verifying statelessness
 #[derive(Debug, Clone)]
@@ -126,6 +138,82 @@ impl StatelessnessPolicy {
         Ok(())
     }
 
+    /// Verify WASI env vars and args are small configuration, not smuggled
+    /// application data, and can be handed to a WASI context as C strings.
+    pub fn verify_wasi_config(env: &[(String, String)], args: &[String]) -> Result<()> {
+        if env.len() > MAX_WASI_ENV_VARS {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Too many environment variables: {} (max {})",
+                env.len(),
+                MAX_WASI_ENV_VARS
+            )));
+        }
+        if args.len() > MAX_WASI_ARGS {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Too many args: {} (max {})",
+                args.len(),
+                MAX_WASI_ARGS
+            )));
+        }
+
+        for (key, value) in env {
+            if key.is_empty() || key.contains('=') {
+                return Err(CoreError::InvalidCapabilityAssignment(format!(
+                    "Invalid environment variable name '{}'",
+                    key
+                )));
+            }
+            if key.contains('\0') || value.contains('\0') {
+                return Err(CoreError::InvalidCapabilityAssignment(format!(
+                    "Environment variable '{}' contains a NUL byte",
+                    key
+                )));
+            }
+        }
+        if args.iter().any(|arg| arg.contains('\0')) {
+            return Err(CoreError::InvalidCapabilityAssignment(
+                "Args must not contain NUL bytes".to_string(),
+            ));
+        }
+
+        let total: usize = env.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
+            + args.iter().map(String::len).sum::<usize>();
+        if total > MAX_WASI_CONFIG_BYTES {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "WASI env and args total {} bytes (max {})",
+                total, MAX_WASI_CONFIG_BYTES
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Bound caller-supplied event annotations so the event log stays small
+    pub fn verify_event_annotations(annotations: &HashMap<String, String>) -> Result<()> {
+        if annotations.len() > MAX_EVENT_ANNOTATIONS {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Too many event annotations: {} (max {})",
+                annotations.len(),
+                MAX_EVENT_ANNOTATIONS
+            )));
+        }
+        if annotations.keys().any(String::is_empty) {
+            return Err(CoreError::InvalidCapabilityAssignment(
+                "Event annotation keys must not be empty".to_string(),
+            ));
+        }
+
+        let total: usize = annotations.iter().map(|(k, v)| k.len() + v.len()).sum();
+        if total > MAX_EVENT_ANNOTATION_BYTES {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Event annotations total {} bytes (max {})",
+                total, MAX_EVENT_ANNOTATION_BYTES
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Check that no logs are persisted as state
     pub fn verify_no_log_state(logs: &[String]) -> Result<()> {
         // Logs should be ephemeral, not stored as state
@@ -265,6 +353,9 @@ mod tests {
             module_hash: "hash123".to_string(),
             created_at: chrono::Utc::now(),
             status: InstanceStatus::Running,
+            deployment_id: None,
+            labels: Default::default(),
+            updated_at: None,
         };
 
         // Create invalid metadata (but can't directly change status to invalid enum)
@@ -318,6 +409,9 @@ mod tests {
             module_hash: "hash123".to_string(),
             created_at: chrono::Utc::now(),
             status: InstanceStatus::Running,
+            deployment_id: None,
+            labels: Default::default(),
+            updated_at: None,
         };
 
         // Wait a moment
@@ -330,6 +424,9 @@ mod tests {
             module_hash: "hash123".to_string(),
             created_at: chrono::Utc::now(),
             status: InstanceStatus::Starting,
+            deployment_id: None,
+            labels: Default::default(),
+            updated_at: None,
         };
 
         // Should fail because instance_id is the same after restart
@@ -352,6 +449,9 @@ mod tests {
             module_hash: "hash123".to_string(),
             created_at: chrono::Utc::now(),
             status: InstanceStatus::Running,
+            deployment_id: None,
+            labels: Default::default(),
+            updated_at: None,
         };
 
         // Wait a moment to ensure different timestamp
@@ -364,6 +464,9 @@ mod tests {
             module_hash: "hash123".to_string(),
             created_at: chrono::Utc::now(),
             status: InstanceStatus::Starting,
+            deployment_id: None,
+            labels: Default::default(),
+            updated_at: None,
         };
 
         let result = StatelessnessPolicy::verify_restart_state_cleared(
@@ -384,6 +487,9 @@ mod tests {
             module_hash: "hash123".to_string(),
             created_at: now,
             status: InstanceStatus::Running,
+            deployment_id: None,
+            labels: Default::default(),
+            updated_at: None,
         };
 
         // Wait to ensure different timestamp
@@ -396,6 +502,9 @@ mod tests {
             module_hash: "hash123".to_string(),
             created_at: now, // OLD timestamp - should fail!
             status: InstanceStatus::Starting,
+            deployment_id: None,
+            labels: Default::default(),
+            updated_at: None,
         };
 
         let result = StatelessnessPolicy::verify_restart_state_cleared(
@@ -443,4 +552,45 @@ mod tests {
 
         assert!(all_passed, "All statelessness validations should pass");
     }
+
+    #[test]
+    fn test_verify_wasi_config_accepts_small_configuration() {
+        let env = vec![("GREETING".to_string(), "hi".to_string())];
+        let args = vec!["app".to_string(), "--verbose".to_string()];
+        assert!(StatelessnessPolicy::verify_wasi_config(&env, &args).is_ok());
+    }
+
+    #[test]
+    fn test_verify_wasi_config_rejects_nul_bytes_and_oversized_input() {
+        let nul_value = vec![("KEY".to_string(), "a\0b".to_string())];
+        assert!(StatelessnessPolicy::verify_wasi_config(&nul_value, &[]).is_err());
+
+        let bad_key = vec![("A=B".to_string(), "x".to_string())];
+        assert!(StatelessnessPolicy::verify_wasi_config(&bad_key, &[]).is_err());
+
+        assert!(StatelessnessPolicy::verify_wasi_config(&[], &["x\0".to_string()]).is_err());
+
+        let too_many: Vec<String> = (0..=MAX_WASI_ARGS).map(|i| i.to_string()).collect();
+        assert!(StatelessnessPolicy::verify_wasi_config(&[], &too_many).is_err());
+
+        let blob = vec![("DATA".to_string(), "x".repeat(MAX_WASI_CONFIG_BYTES))];
+        assert!(StatelessnessPolicy::verify_wasi_config(&blob, &[]).is_err());
+    }
+
+    #[test]
+    fn test_verify_event_annotations_bounds_count_and_size() {
+        let small = HashMap::from([("trace_id".to_string(), "abc123".to_string())]);
+        assert!(StatelessnessPolicy::verify_event_annotations(&small).is_ok());
+
+        let too_many: HashMap<String, String> = (0..=MAX_EVENT_ANNOTATIONS)
+            .map(|i| (format!("k{i}"), String::new()))
+            .collect();
+        assert!(StatelessnessPolicy::verify_event_annotations(&too_many).is_err());
+
+        let blob = HashMap::from([("data".to_string(), "x".repeat(MAX_EVENT_ANNOTATION_BYTES))]);
+        assert!(StatelessnessPolicy::verify_event_annotations(&blob).is_err());
+
+        let empty_key = HashMap::from([(String::new(), "x".to_string())]);
+        assert!(StatelessnessPolicy::verify_event_annotations(&empty_key).is_err());
+    }
 }
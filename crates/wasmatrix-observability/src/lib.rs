@@ -0,0 +1,93 @@
+//! Prometheus plumbing shared by the control plane, node agent and providers.
+//!
+//! Each crate still defines its own metrics in `features::observability`;
+//! this crate owns the registry, the text rendering and the start-up fallback
+//! they have in common.
+
+pub use prometheus;
+
+use prometheus::core::Collector;
+use prometheus::{Encoder, Registry, TextEncoder};
+
+/// A Prometheus registry whose errors are reported as strings
+#[derive(Default)]
+pub struct MetricsRegistry {
+    registry: Registry,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a freshly built collector and hand it back for recording
+    pub fn register<C>(&self, collector: prometheus::Result<C>) -> Result<C, String>
+    where
+        C: Collector + Clone + 'static,
+    {
+        let collector = collector.map_err(|e| e.to_string())?;
+        self.registry
+            .register(Box::new(collector.clone()))
+            .map_err(|e| e.to_string())?;
+        Ok(collector)
+    }
+
+    /// Every registered metric in the text exposition format
+    pub fn render(&self) -> Result<String, String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| e.to_string())?;
+        String::from_utf8(buffer).map_err(|e| e.to_string())
+    }
+}
+
+/// Unwrap the result of building a crate's metrics, logging the error and
+/// using `fallback` instead so a registry error never takes the process down
+pub fn init_or_fallback<T>(init: Result<T, String>, fallback: impl FnOnce() -> T) -> T {
+    init.unwrap_or_else(|error| {
+        tracing::error!(%error, "Metrics initialisation failed; metrics are disabled");
+        fallback()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::IntCounter;
+
+    #[test]
+    fn test_registered_metrics_are_rendered() {
+        let registry = MetricsRegistry::new();
+        let counter = registry
+            .register(IntCounter::new("wasmatrix_test_total", "Test counter"))
+            .unwrap();
+        counter.inc();
+
+        assert!(registry
+            .render()
+            .unwrap()
+            .contains("wasmatrix_test_total 1"));
+    }
+
+    #[test]
+    fn test_duplicate_registration_is_an_error() {
+        let registry = MetricsRegistry::new();
+        registry
+            .register(IntCounter::new("wasmatrix_test_total", "Test counter"))
+            .unwrap();
+
+        assert!(registry
+            .register(IntCounter::new("wasmatrix_test_total", "Test counter"))
+            .is_err());
+        assert!(registry
+            .register(IntCounter::new("bad name", "Invalid"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_init_or_fallback_uses_fallback_on_error() {
+        assert_eq!(init_or_fallback(Ok(1), || 0), 1);
+        assert_eq!(init_or_fallback(Err("boom".to_string()), || 0), 0);
+    }
+}
@@ -1,5 +1,8 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
-    tonic_build::compile_protos("proto/wasmatrix.proto")?;
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("wasmatrix_descriptor.bin"))
+        .compile(&["proto/wasmatrix.proto"], &["proto"])?;
     Ok(())
 }
@@ -2,6 +2,15 @@ use crate::protocol;
 use crate::v1;
 use std::convert::TryFrom;
 
+/// Why a wire message could not be converted into its protocol type
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConversionError {
+    #[error("{0} is missing")]
+    MissingField(&'static str),
+    #[error("invalid {field} value {value}")]
+    InvalidEnum { field: &'static str, value: i32 },
+}
+
 // StartInstanceRequest
 impl From<protocol::StartInstanceRequest> for v1::StartInstanceRequest {
     fn from(req: protocol::StartInstanceRequest) -> Self {
@@ -10,12 +19,19 @@ impl From<protocol::StartInstanceRequest> for v1::StartInstanceRequest {
             module_bytes: req.module_bytes,
             capabilities: req.capabilities.into_iter().map(Into::into).collect(),
             restart_policy: Some(req.restart_policy.into()),
+            env: req
+                .env
+                .into_iter()
+                .map(|(key, value)| v1::EnvVar { key, value })
+                .collect(),
+            args: req.args,
+            annotations: req.annotations,
         }
     }
 }
 
 impl TryFrom<v1::StartInstanceRequest> for protocol::StartInstanceRequest {
-    type Error = String;
+    type Error = ConversionError;
 
     fn try_from(req: v1::StartInstanceRequest) -> Result<Self, Self::Error> {
         Ok(Self {
@@ -28,8 +44,15 @@ impl TryFrom<v1::StartInstanceRequest> for protocol::StartInstanceRequest {
                 .collect::<Result<_, _>>()?,
             restart_policy: req
                 .restart_policy
-                .ok_or("restart_policy is missing")?
+                .ok_or(ConversionError::MissingField("restart_policy"))?
                 .try_into()?,
+            env: req
+                .env
+                .into_iter()
+                .map(|var| (var.key, var.value))
+                .collect(),
+            args: req.args,
+            annotations: req.annotations,
         })
     }
 }
@@ -60,6 +83,8 @@ impl From<protocol::StopInstanceRequest> for v1::StopInstanceRequest {
     fn from(req: protocol::StopInstanceRequest) -> Self {
         Self {
             instance_id: req.instance_id,
+            reason: req.reason,
+            annotations: req.annotations,
         }
     }
 }
@@ -68,6 +93,8 @@ impl From<v1::StopInstanceRequest> for protocol::StopInstanceRequest {
     fn from(req: v1::StopInstanceRequest) -> Self {
         Self {
             instance_id: req.instance_id,
+            reason: req.reason,
+            annotations: req.annotations,
         }
     }
 }
@@ -93,164 +120,791 @@ impl From<v1::StopInstanceResponse> for protocol::StopInstanceResponse {
     }
 }
 
-// QueryInstanceRequest
-impl From<protocol::QueryInstanceRequest> for v1::QueryInstanceRequest {
-    fn from(req: protocol::QueryInstanceRequest) -> Self {
+// QueryInstanceRequest
+impl From<protocol::QueryInstanceRequest> for v1::QueryInstanceRequest {
+    fn from(req: protocol::QueryInstanceRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
+    }
+}
+
+impl From<v1::QueryInstanceRequest> for protocol::QueryInstanceRequest {
+    fn from(req: v1::QueryInstanceRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
+    }
+}
+
+// QueryInstanceResponse
+impl From<protocol::QueryInstanceResponse> for v1::QueryInstanceResponse {
+    fn from(res: protocol::QueryInstanceResponse) -> Self {
+        Self {
+            success: res.success,
+            instance: res.instance.map(Into::into),
+            error_code: res.error_code,
+            ready: res.ready,
+        }
+    }
+}
+
+impl TryFrom<v1::QueryInstanceResponse> for protocol::QueryInstanceResponse {
+    type Error = ConversionError;
+
+    fn try_from(res: v1::QueryInstanceResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            success: res.success,
+            instance: res.instance.map(TryInto::try_into).transpose()?,
+            error_code: res.error_code,
+            ready: res.ready,
+        })
+    }
+}
+
+// ListInstancesRequest
+impl From<protocol::ListInstancesRequest> for v1::ListInstancesRequest {
+    fn from(_req: protocol::ListInstancesRequest) -> Self {
+        Self {}
+    }
+}
+
+impl From<v1::ListInstancesRequest> for protocol::ListInstancesRequest {
+    fn from(_req: v1::ListInstancesRequest) -> Self {
+        Self {}
+    }
+}
+
+// ListInstancesResponse
+impl From<protocol::ListInstancesResponse> for v1::ListInstancesResponse {
+    fn from(res: protocol::ListInstancesResponse) -> Self {
+        Self {
+            success: res.success,
+            instances: res.instances.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<v1::ListInstancesResponse> for protocol::ListInstancesResponse {
+    type Error = ConversionError;
+
+    fn try_from(res: v1::ListInstancesResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            success: res.success,
+            instances: res
+                .instances
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+// InvokeCapabilityRequest
+impl From<protocol::InvokeCapabilityRequest> for v1::InvokeCapabilityRequest {
+    fn from(req: protocol::InvokeCapabilityRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+            capability_id: req.capability_id,
+            provider_type: v1::ProviderType::from(req.provider_type).into(),
+            operation: req.operation,
+            params_json: req.params_json,
+            permissions: req.permissions,
+        }
+    }
+}
+
+impl TryFrom<v1::InvokeCapabilityRequest> for protocol::InvokeCapabilityRequest {
+    type Error = ConversionError;
+
+    fn try_from(req: v1::InvokeCapabilityRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            instance_id: req.instance_id,
+            capability_id: req.capability_id,
+            provider_type: v1::ProviderType::try_from(req.provider_type)
+                .map_err(|_| ConversionError::InvalidEnum {
+                    field: "provider_type",
+                    value: req.provider_type,
+                })?
+                .try_into()?,
+            operation: req.operation,
+            params_json: req.params_json,
+            permissions: req.permissions,
+        })
+    }
+}
+
+// InvokeCapabilityResponse
+impl From<protocol::InvokeCapabilityResponse> for v1::InvokeCapabilityResponse {
+    fn from(res: protocol::InvokeCapabilityResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+            result_json: res.result_json,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::InvokeCapabilityResponse> for protocol::InvokeCapabilityResponse {
+    fn from(res: v1::InvokeCapabilityResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+            result_json: res.result_json,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// RestartInstanceRequest
+impl From<protocol::RestartInstanceRequest> for v1::RestartInstanceRequest {
+    fn from(req: protocol::RestartInstanceRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+            capabilities: req.capabilities.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<v1::RestartInstanceRequest> for protocol::RestartInstanceRequest {
+    type Error = ConversionError;
+
+    fn try_from(req: v1::RestartInstanceRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            instance_id: req.instance_id,
+            capabilities: req
+                .capabilities
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+// RestartInstanceResponse
+impl From<protocol::RestartInstanceResponse> for v1::RestartInstanceResponse {
+    fn from(res: protocol::RestartInstanceResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::RestartInstanceResponse> for protocol::RestartInstanceResponse {
+    fn from(res: v1::RestartInstanceResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// RegisterNodeRequest
+impl From<protocol::RegisterNodeRequest> for v1::RegisterNodeRequest {
+    fn from(req: protocol::RegisterNodeRequest) -> Self {
+        Self {
+            node_id: req.node_id,
+            node_address: req.node_address,
+            capabilities: req.capabilities,
+            max_instances: req.max_instances,
+            weight: req.weight,
+        }
+    }
+}
+
+impl From<v1::RegisterNodeRequest> for protocol::RegisterNodeRequest {
+    fn from(req: v1::RegisterNodeRequest) -> Self {
+        Self {
+            node_id: req.node_id,
+            node_address: req.node_address,
+            capabilities: req.capabilities,
+            max_instances: req.max_instances,
+            weight: req.weight,
+        }
+    }
+}
+
+// RegisterNodeResponse
+impl From<protocol::RegisterNodeResponse> for v1::RegisterNodeResponse {
+    fn from(res: protocol::RegisterNodeResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::RegisterNodeResponse> for protocol::RegisterNodeResponse {
+    fn from(res: v1::RegisterNodeResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// StatusReport
+impl From<protocol::StatusReport> for v1::StatusReport {
+    fn from(report: protocol::StatusReport) -> Self {
+        Self {
+            node_id: report.node_id,
+            instance_updates: report
+                .instance_updates
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            timestamp: report.timestamp,
+            capabilities: report.capabilities,
+            replace_capabilities: report.replace_capabilities,
+        }
+    }
+}
+
+impl TryFrom<v1::StatusReport> for protocol::StatusReport {
+    type Error = ConversionError;
+
+    fn try_from(report: v1::StatusReport) -> Result<Self, Self::Error> {
+        Ok(Self {
+            node_id: report.node_id,
+            instance_updates: report
+                .instance_updates
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            timestamp: report.timestamp,
+            capabilities: report.capabilities,
+            replace_capabilities: report.replace_capabilities,
+        })
+    }
+}
+
+// StatusReportResponse
+impl From<protocol::StatusReportResponse> for v1::StatusReportResponse {
+    fn from(res: protocol::StatusReportResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+        }
+    }
+}
+
+impl From<v1::StatusReportResponse> for protocol::StatusReportResponse {
+    fn from(res: v1::StatusReportResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+        }
+    }
+}
+
+// LocateInstanceRequest
+impl From<protocol::LocateInstanceRequest> for v1::LocateInstanceRequest {
+    fn from(req: protocol::LocateInstanceRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
+    }
+}
+
+impl From<v1::LocateInstanceRequest> for protocol::LocateInstanceRequest {
+    fn from(req: v1::LocateInstanceRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
+    }
+}
+
+// LocateInstanceResponse
+impl From<protocol::LocateInstanceResponse> for v1::LocateInstanceResponse {
+    fn from(res: protocol::LocateInstanceResponse) -> Self {
+        Self {
+            success: res.success,
+            node_id: res.node_id,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::LocateInstanceResponse> for protocol::LocateInstanceResponse {
+    fn from(res: v1::LocateInstanceResponse) -> Self {
+        Self {
+            success: res.success,
+            node_id: res.node_id,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// ReadInstanceLogsRequest
+impl From<protocol::ReadInstanceLogsRequest> for v1::ReadInstanceLogsRequest {
+    fn from(req: protocol::ReadInstanceLogsRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+            max_bytes: req.max_bytes,
+        }
+    }
+}
+
+impl From<v1::ReadInstanceLogsRequest> for protocol::ReadInstanceLogsRequest {
+    fn from(req: v1::ReadInstanceLogsRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+            max_bytes: req.max_bytes,
+        }
+    }
+}
+
+// ReadInstanceLogsResponse
+impl From<protocol::ReadInstanceLogsResponse> for v1::ReadInstanceLogsResponse {
+    fn from(res: protocol::ReadInstanceLogsResponse) -> Self {
+        Self {
+            success: res.success,
+            data: res.data,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::ReadInstanceLogsResponse> for protocol::ReadInstanceLogsResponse {
+    fn from(res: v1::ReadInstanceLogsResponse) -> Self {
+        Self {
+            success: res.success,
+            data: res.data,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// SetInstanceCapabilitiesRequest
+impl From<protocol::SetInstanceCapabilitiesRequest> for v1::SetInstanceCapabilitiesRequest {
+    fn from(req: protocol::SetInstanceCapabilitiesRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+            capabilities: req.capabilities.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<v1::SetInstanceCapabilitiesRequest> for protocol::SetInstanceCapabilitiesRequest {
+    type Error = ConversionError;
+
+    fn try_from(req: v1::SetInstanceCapabilitiesRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            instance_id: req.instance_id,
+            capabilities: req
+                .capabilities
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+// SetInstanceCapabilitiesResponse
+impl From<protocol::SetInstanceCapabilitiesResponse> for v1::SetInstanceCapabilitiesResponse {
+    fn from(res: protocol::SetInstanceCapabilitiesResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::SetInstanceCapabilitiesResponse> for protocol::SetInstanceCapabilitiesResponse {
+    fn from(res: v1::SetInstanceCapabilitiesResponse) -> Self {
+        Self {
+            success: res.success,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// GetInstanceHistoryRequest
+impl From<protocol::GetInstanceHistoryRequest> for v1::GetInstanceHistoryRequest {
+    fn from(req: protocol::GetInstanceHistoryRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
+    }
+}
+
+impl From<v1::GetInstanceHistoryRequest> for protocol::GetInstanceHistoryRequest {
+    fn from(req: v1::GetInstanceHistoryRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
+    }
+}
+
+// InstanceHistoryEntry
+impl From<protocol::InstanceHistoryEntry> for v1::InstanceHistoryEntry {
+    fn from(entry: protocol::InstanceHistoryEntry) -> Self {
+        Self {
+            event_type: entry.event_type,
+            timestamp: entry.timestamp,
+        }
+    }
+}
+
+impl From<v1::InstanceHistoryEntry> for protocol::InstanceHistoryEntry {
+    fn from(entry: v1::InstanceHistoryEntry) -> Self {
+        Self {
+            event_type: entry.event_type,
+            timestamp: entry.timestamp,
+        }
+    }
+}
+
+// GetInstanceHistoryResponse
+impl From<protocol::GetInstanceHistoryResponse> for v1::GetInstanceHistoryResponse {
+    fn from(res: protocol::GetInstanceHistoryResponse) -> Self {
+        Self {
+            success: res.success,
+            entries: res.entries.into_iter().map(Into::into).collect(),
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::GetInstanceHistoryResponse> for protocol::GetInstanceHistoryResponse {
+    fn from(res: v1::GetInstanceHistoryResponse) -> Self {
+        Self {
+            success: res.success,
+            entries: res.entries.into_iter().map(Into::into).collect(),
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// ListCrashLoopingRequest
+impl From<protocol::ListCrashLoopingRequest> for v1::ListCrashLoopingRequest {
+    fn from(req: protocol::ListCrashLoopingRequest) -> Self {
+        Self {
+            threshold: req.threshold,
+            window_seconds: req.window_seconds,
+        }
+    }
+}
+
+impl From<v1::ListCrashLoopingRequest> for protocol::ListCrashLoopingRequest {
+    fn from(req: v1::ListCrashLoopingRequest) -> Self {
+        Self {
+            threshold: req.threshold,
+            window_seconds: req.window_seconds,
+        }
+    }
+}
+
+// CrashLoopingInstance
+impl From<protocol::CrashLoopingInstance> for v1::CrashLoopingInstance {
+    fn from(instance: protocol::CrashLoopingInstance) -> Self {
+        Self {
+            instance_id: instance.instance_id,
+            crash_count: instance.crash_count,
+        }
+    }
+}
+
+impl From<v1::CrashLoopingInstance> for protocol::CrashLoopingInstance {
+    fn from(instance: v1::CrashLoopingInstance) -> Self {
+        Self {
+            instance_id: instance.instance_id,
+            crash_count: instance.crash_count,
+        }
+    }
+}
+
+// ListCrashLoopingResponse
+impl From<protocol::ListCrashLoopingResponse> for v1::ListCrashLoopingResponse {
+    fn from(res: protocol::ListCrashLoopingResponse) -> Self {
+        Self {
+            success: res.success,
+            instances: res.instances.into_iter().map(Into::into).collect(),
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::ListCrashLoopingResponse> for protocol::ListCrashLoopingResponse {
+    fn from(res: v1::ListCrashLoopingResponse) -> Self {
+        Self {
+            success: res.success,
+            instances: res.instances.into_iter().map(Into::into).collect(),
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// GetClusterCapacityRequest
+impl From<protocol::GetClusterCapacityRequest> for v1::GetClusterCapacityRequest {
+    fn from(_req: protocol::GetClusterCapacityRequest) -> Self {
+        Self {}
+    }
+}
+
+impl From<v1::GetClusterCapacityRequest> for protocol::GetClusterCapacityRequest {
+    fn from(_req: v1::GetClusterCapacityRequest) -> Self {
+        Self {}
+    }
+}
+
+// GetClusterCapacityResponse
+impl From<protocol::GetClusterCapacityResponse> for v1::GetClusterCapacityResponse {
+    fn from(res: protocol::GetClusterCapacityResponse) -> Self {
+        Self {
+            success: res.success,
+            total_slots: res.total_slots,
+            used_slots: res.used_slots,
+            available_nodes: res.available_nodes,
+            total_nodes: res.total_nodes,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+impl From<v1::GetClusterCapacityResponse> for protocol::GetClusterCapacityResponse {
+    fn from(res: v1::GetClusterCapacityResponse) -> Self {
+        Self {
+            success: res.success,
+            total_slots: res.total_slots,
+            used_slots: res.used_slots,
+            available_nodes: res.available_nodes,
+            total_nodes: res.total_nodes,
+            message: res.message,
+            error_code: res.error_code,
+        }
+    }
+}
+
+// GetPersistenceStatusRequest
+impl From<protocol::GetPersistenceStatusRequest> for v1::GetPersistenceStatusRequest {
+    fn from(_req: protocol::GetPersistenceStatusRequest) -> Self {
+        Self {}
+    }
+}
+
+impl From<v1::GetPersistenceStatusRequest> for protocol::GetPersistenceStatusRequest {
+    fn from(_req: v1::GetPersistenceStatusRequest) -> Self {
+        Self {}
+    }
+}
+
+// GetPersistenceStatusResponse
+impl From<protocol::GetPersistenceStatusResponse> for v1::GetPersistenceStatusResponse {
+    fn from(res: protocol::GetPersistenceStatusResponse) -> Self {
+        Self {
+            enabled: res.enabled,
+            healthy: res.healthy,
+            last_error: res.last_error,
+        }
+    }
+}
+
+impl From<v1::GetPersistenceStatusResponse> for protocol::GetPersistenceStatusResponse {
+    fn from(res: v1::GetPersistenceStatusResponse) -> Self {
+        Self {
+            enabled: res.enabled,
+            healthy: res.healthy,
+            last_error: res.last_error,
+        }
+    }
+}
+
+// GetEventCountsRequest
+impl From<protocol::GetEventCountsRequest> for v1::GetEventCountsRequest {
+    fn from(_req: protocol::GetEventCountsRequest) -> Self {
+        Self {}
+    }
+}
+
+impl From<v1::GetEventCountsRequest> for protocol::GetEventCountsRequest {
+    fn from(_req: v1::GetEventCountsRequest) -> Self {
+        Self {}
+    }
+}
+
+// GetEventCountsResponse
+impl From<protocol::GetEventCountsResponse> for v1::GetEventCountsResponse {
+    fn from(res: protocol::GetEventCountsResponse) -> Self {
+        Self { counts: res.counts }
+    }
+}
+
+impl From<v1::GetEventCountsResponse> for protocol::GetEventCountsResponse {
+    fn from(res: v1::GetEventCountsResponse) -> Self {
+        Self { counts: res.counts }
+    }
+}
+
+// GetEffectivePermissionsRequest
+impl From<protocol::GetEffectivePermissionsRequest> for v1::GetEffectivePermissionsRequest {
+    fn from(req: protocol::GetEffectivePermissionsRequest) -> Self {
         Self {
             instance_id: req.instance_id,
+            permission: req.permission,
         }
     }
 }
 
-impl From<v1::QueryInstanceRequest> for protocol::QueryInstanceRequest {
-    fn from(req: v1::QueryInstanceRequest) -> Self {
+impl From<v1::GetEffectivePermissionsRequest> for protocol::GetEffectivePermissionsRequest {
+    fn from(req: v1::GetEffectivePermissionsRequest) -> Self {
         Self {
             instance_id: req.instance_id,
+            permission: req.permission,
         }
     }
 }
 
-// QueryInstanceResponse
-impl From<protocol::QueryInstanceResponse> for v1::QueryInstanceResponse {
-    fn from(res: protocol::QueryInstanceResponse) -> Self {
+// GetEffectivePermissionsResponse
+impl From<protocol::GetEffectivePermissionsResponse> for v1::GetEffectivePermissionsResponse {
+    fn from(res: protocol::GetEffectivePermissionsResponse) -> Self {
         Self {
             success: res.success,
-            instance: res.instance.map(Into::into),
+            permissions: res.permissions,
+            allowed: res.allowed,
+            message: res.message,
             error_code: res.error_code,
         }
     }
 }
 
-impl TryFrom<v1::QueryInstanceResponse> for protocol::QueryInstanceResponse {
-    type Error = String;
-
-    fn try_from(res: v1::QueryInstanceResponse) -> Result<Self, Self::Error> {
-        Ok(Self {
+impl From<v1::GetEffectivePermissionsResponse> for protocol::GetEffectivePermissionsResponse {
+    fn from(res: v1::GetEffectivePermissionsResponse) -> Self {
+        Self {
             success: res.success,
-            instance: res.instance.map(TryInto::try_into).transpose()?,
+            permissions: res.permissions,
+            allowed: res.allowed,
+            message: res.message,
             error_code: res.error_code,
-        })
+        }
     }
 }
 
-// ListInstancesRequest
-impl From<protocol::ListInstancesRequest> for v1::ListInstancesRequest {
-    fn from(_req: protocol::ListInstancesRequest) -> Self {
-        Self {}
+// WatchInstanceRequest
+impl From<protocol::WatchInstanceRequest> for v1::WatchInstanceRequest {
+    fn from(req: protocol::WatchInstanceRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
     }
 }
 
-impl From<v1::ListInstancesRequest> for protocol::ListInstancesRequest {
-    fn from(_req: v1::ListInstancesRequest) -> Self {
-        Self {}
+impl From<v1::WatchInstanceRequest> for protocol::WatchInstanceRequest {
+    fn from(req: v1::WatchInstanceRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
     }
 }
 
-// ListInstancesResponse
-impl From<protocol::ListInstancesResponse> for v1::ListInstancesResponse {
-    fn from(res: protocol::ListInstancesResponse) -> Self {
+// WatchInstanceResponse
+impl From<protocol::WatchInstanceResponse> for v1::WatchInstanceResponse {
+    fn from(res: protocol::WatchInstanceResponse) -> Self {
         Self {
-            success: res.success,
-            instances: res.instances.into_iter().map(Into::into).collect(),
+            instance_id: res.instance_id,
+            status: v1::InstanceStatus::from(res.status).into(),
         }
     }
 }
 
-impl TryFrom<v1::ListInstancesResponse> for protocol::ListInstancesResponse {
-    type Error = String;
+impl TryFrom<v1::WatchInstanceResponse> for protocol::WatchInstanceResponse {
+    type Error = ConversionError;
 
-    fn try_from(res: v1::ListInstancesResponse) -> Result<Self, Self::Error> {
+    fn try_from(res: v1::WatchInstanceResponse) -> Result<Self, Self::Error> {
         Ok(Self {
-            success: res.success,
-            instances: res
-                .instances
-                .into_iter()
-                .map(TryInto::try_into)
-                .collect::<Result<_, _>>()?,
+            instance_id: res.instance_id,
+            status: v1::InstanceStatus::try_from(res.status)
+                .map_err(|_| ConversionError::InvalidEnum {
+                    field: "status",
+                    value: res.status,
+                })?
+                .try_into()?,
         })
     }
 }
 
-// InvokeCapabilityRequest
-impl From<protocol::InvokeCapabilityRequest> for v1::InvokeCapabilityRequest {
-    fn from(req: protocol::InvokeCapabilityRequest) -> Self {
+// AssignCapabilityRequest
+impl From<protocol::AssignCapabilityRequest> for v1::AssignCapabilityRequest {
+    fn from(req: protocol::AssignCapabilityRequest) -> Self {
         Self {
-            instance_id: req.instance_id,
-            capability_id: req.capability_id,
-            provider_type: v1::ProviderType::from(req.provider_type).into(),
-            operation: req.operation,
-            params_json: req.params_json,
-            permissions: req.permissions,
+            assignment: Some(req.assignment.into()),
         }
     }
 }
 
-impl TryFrom<v1::InvokeCapabilityRequest> for protocol::InvokeCapabilityRequest {
-    type Error = String;
+impl TryFrom<v1::AssignCapabilityRequest> for protocol::AssignCapabilityRequest {
+    type Error = ConversionError;
 
-    fn try_from(req: v1::InvokeCapabilityRequest) -> Result<Self, Self::Error> {
+    fn try_from(req: v1::AssignCapabilityRequest) -> Result<Self, Self::Error> {
         Ok(Self {
-            instance_id: req.instance_id,
-            capability_id: req.capability_id,
-            provider_type: v1::ProviderType::try_from(req.provider_type)
-                .map_err(|_| "Invalid ProviderType")?
+            assignment: req
+                .assignment
+                .ok_or(ConversionError::MissingField("assignment"))?
                 .try_into()?,
-            operation: req.operation,
-            params_json: req.params_json,
-            permissions: req.permissions,
         })
     }
 }
 
-// InvokeCapabilityResponse
-impl From<protocol::InvokeCapabilityResponse> for v1::InvokeCapabilityResponse {
-    fn from(res: protocol::InvokeCapabilityResponse) -> Self {
+// AssignCapabilityResponse
+impl From<protocol::AssignCapabilityResponse> for v1::AssignCapabilityResponse {
+    fn from(res: protocol::AssignCapabilityResponse) -> Self {
         Self {
             success: res.success,
             message: res.message,
-            result_json: res.result_json,
             error_code: res.error_code,
         }
     }
 }
 
-impl From<v1::InvokeCapabilityResponse> for protocol::InvokeCapabilityResponse {
-    fn from(res: v1::InvokeCapabilityResponse) -> Self {
+impl From<v1::AssignCapabilityResponse> for protocol::AssignCapabilityResponse {
+    fn from(res: v1::AssignCapabilityResponse) -> Self {
         Self {
             success: res.success,
             message: res.message,
-            result_json: res.result_json,
             error_code: res.error_code,
         }
     }
 }
 
-// RegisterNodeRequest
-impl From<protocol::RegisterNodeRequest> for v1::RegisterNodeRequest {
-    fn from(req: protocol::RegisterNodeRequest) -> Self {
+// RevokeCapabilityRequest
+impl From<protocol::RevokeCapabilityRequest> for v1::RevokeCapabilityRequest {
+    fn from(req: protocol::RevokeCapabilityRequest) -> Self {
         Self {
-            node_id: req.node_id,
-            node_address: req.node_address,
-            capabilities: req.capabilities,
-            max_instances: req.max_instances,
+            instance_id: req.instance_id,
+            capability_id: req.capability_id,
         }
     }
 }
 
-impl From<v1::RegisterNodeRequest> for protocol::RegisterNodeRequest {
-    fn from(req: v1::RegisterNodeRequest) -> Self {
+impl From<v1::RevokeCapabilityRequest> for protocol::RevokeCapabilityRequest {
+    fn from(req: v1::RevokeCapabilityRequest) -> Self {
         Self {
-            node_id: req.node_id,
-            node_address: req.node_address,
-            capabilities: req.capabilities,
-            max_instances: req.max_instances,
+            instance_id: req.instance_id,
+            capability_id: req.capability_id,
         }
     }
 }
 
-// RegisterNodeResponse
-impl From<protocol::RegisterNodeResponse> for v1::RegisterNodeResponse {
-    fn from(res: protocol::RegisterNodeResponse) -> Self {
+// RevokeCapabilityResponse
+impl From<protocol::RevokeCapabilityResponse> for v1::RevokeCapabilityResponse {
+    fn from(res: protocol::RevokeCapabilityResponse) -> Self {
         Self {
             success: res.success,
             message: res.message,
@@ -259,8 +913,8 @@ impl From<protocol::RegisterNodeResponse> for v1::RegisterNodeResponse {
     }
 }
 
-impl From<v1::RegisterNodeResponse> for protocol::RegisterNodeResponse {
-    fn from(res: v1::RegisterNodeResponse) -> Self {
+impl From<v1::RevokeCapabilityResponse> for protocol::RevokeCapabilityResponse {
+    fn from(res: v1::RevokeCapabilityResponse) -> Self {
         Self {
             success: res.success,
             message: res.message,
@@ -269,53 +923,49 @@ impl From<v1::RegisterNodeResponse> for protocol::RegisterNodeResponse {
     }
 }
 
-// StatusReport
-impl From<protocol::StatusReport> for v1::StatusReport {
-    fn from(report: protocol::StatusReport) -> Self {
+// GetCapabilitiesRequest
+impl From<protocol::GetCapabilitiesRequest> for v1::GetCapabilitiesRequest {
+    fn from(req: protocol::GetCapabilitiesRequest) -> Self {
         Self {
-            node_id: report.node_id,
-            instance_updates: report
-                .instance_updates
-                .into_iter()
-                .map(Into::into)
-                .collect(),
-            timestamp: report.timestamp,
+            instance_id: req.instance_id,
         }
     }
 }
 
-impl TryFrom<v1::StatusReport> for protocol::StatusReport {
-    type Error = String;
-
-    fn try_from(report: v1::StatusReport) -> Result<Self, Self::Error> {
-        Ok(Self {
-            node_id: report.node_id,
-            instance_updates: report
-                .instance_updates
-                .into_iter()
-                .map(TryInto::try_into)
-                .collect::<Result<_, _>>()?,
-            timestamp: report.timestamp,
-        })
+impl From<v1::GetCapabilitiesRequest> for protocol::GetCapabilitiesRequest {
+    fn from(req: v1::GetCapabilitiesRequest) -> Self {
+        Self {
+            instance_id: req.instance_id,
+        }
     }
 }
 
-// StatusReportResponse
-impl From<protocol::StatusReportResponse> for v1::StatusReportResponse {
-    fn from(res: protocol::StatusReportResponse) -> Self {
+// GetCapabilitiesResponse
+impl From<protocol::GetCapabilitiesResponse> for v1::GetCapabilitiesResponse {
+    fn from(res: protocol::GetCapabilitiesResponse) -> Self {
         Self {
             success: res.success,
+            capabilities: res.capabilities.into_iter().map(Into::into).collect(),
             message: res.message,
+            error_code: res.error_code,
         }
     }
 }
 
-impl From<v1::StatusReportResponse> for protocol::StatusReportResponse {
-    fn from(res: v1::StatusReportResponse) -> Self {
-        Self {
+impl TryFrom<v1::GetCapabilitiesResponse> for protocol::GetCapabilitiesResponse {
+    type Error = ConversionError;
+
+    fn try_from(res: v1::GetCapabilitiesResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
             success: res.success,
+            capabilities: res
+                .capabilities
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
             message: res.message,
-        }
+            error_code: res.error_code,
+        })
     }
 }
 
@@ -331,13 +981,16 @@ impl From<protocol::InstanceStatusUpdate> for v1::InstanceStatusUpdate {
 }
 
 impl TryFrom<v1::InstanceStatusUpdate> for protocol::InstanceStatusUpdate {
-    type Error = String;
+    type Error = ConversionError;
 
     fn try_from(update: v1::InstanceStatusUpdate) -> Result<Self, Self::Error> {
         Ok(Self {
             instance_id: update.instance_id,
             status: v1::InstanceStatus::try_from(update.status)
-                .map_err(|_| "Invalid InstanceStatus")?
+                .map_err(|_| ConversionError::InvalidEnum {
+                    field: "status",
+                    value: update.status,
+                })?
                 .try_into()?,
             error_message: update.error_message,
         })
@@ -352,21 +1005,33 @@ impl From<protocol::CapabilityAssignment> for v1::CapabilityAssignment {
             capability_id: assignment.capability_id,
             provider_type: v1::ProviderType::from(assignment.provider_type).into(),
             permissions: assignment.permissions,
+            expires_at: assignment.expires_at,
+            state: v1::CapabilityState::from(assignment.state).into(),
         }
     }
 }
 
 impl TryFrom<v1::CapabilityAssignment> for protocol::CapabilityAssignment {
-    type Error = String;
+    type Error = ConversionError;
 
     fn try_from(assignment: v1::CapabilityAssignment) -> Result<Self, Self::Error> {
         Ok(Self {
             instance_id: assignment.instance_id,
             capability_id: assignment.capability_id,
             provider_type: v1::ProviderType::try_from(assignment.provider_type)
-                .map_err(|_| "Invalid ProviderType")?
+                .map_err(|_| ConversionError::InvalidEnum {
+                    field: "provider_type",
+                    value: assignment.provider_type,
+                })?
                 .try_into()?,
             permissions: assignment.permissions,
+            expires_at: assignment.expires_at,
+            state: v1::CapabilityState::try_from(assignment.state)
+                .map_err(|_| ConversionError::InvalidEnum {
+                    field: "state",
+                    value: assignment.state,
+                })?
+                .into(),
         })
     }
 }
@@ -380,12 +1045,13 @@ impl From<protocol::InstanceMetadata> for v1::InstanceMetadata {
             module_hash: meta.module_hash,
             created_at: meta.created_at,
             status: v1::InstanceStatus::from(meta.status).into(),
+            capabilities: meta.capabilities.into_iter().map(Into::into).collect(),
         }
     }
 }
 
 impl TryFrom<v1::InstanceMetadata> for protocol::InstanceMetadata {
-    type Error = String;
+    type Error = ConversionError;
 
     fn try_from(meta: v1::InstanceMetadata) -> Result<Self, Self::Error> {
         Ok(Self {
@@ -394,8 +1060,16 @@ impl TryFrom<v1::InstanceMetadata> for protocol::InstanceMetadata {
             module_hash: meta.module_hash,
             created_at: meta.created_at,
             status: v1::InstanceStatus::try_from(meta.status)
-                .map_err(|_| "Invalid InstanceStatus")?
+                .map_err(|_| ConversionError::InvalidEnum {
+                    field: "status",
+                    value: meta.status,
+                })?
                 .try_into()?,
+            capabilities: meta
+                .capabilities
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?,
         })
     }
 }
@@ -407,20 +1081,25 @@ impl From<protocol::RestartPolicy> for v1::RestartPolicy {
             policy_type: v1::RestartPolicyType::from(policy.policy_type).into(),
             max_retries: policy.max_retries,
             backoff_seconds: policy.backoff_seconds,
+            max_backoff_seconds: policy.max_backoff_seconds,
         }
     }
 }
 
 impl TryFrom<v1::RestartPolicy> for protocol::RestartPolicy {
-    type Error = String;
+    type Error = ConversionError;
 
     fn try_from(policy: v1::RestartPolicy) -> Result<Self, Self::Error> {
         Ok(Self {
             policy_type: v1::RestartPolicyType::try_from(policy.policy_type)
-                .map_err(|_| "Invalid RestartPolicyType")?
+                .map_err(|_| ConversionError::InvalidEnum {
+                    field: "policy_type",
+                    value: policy.policy_type,
+                })?
                 .try_into()?,
             max_retries: policy.max_retries,
             backoff_seconds: policy.backoff_seconds,
+            max_backoff_seconds: policy.max_backoff_seconds,
         })
     }
 }
@@ -433,19 +1112,46 @@ impl From<protocol::ProviderType> for v1::ProviderType {
             protocol::ProviderType::Kv => v1::ProviderType::Kv,
             protocol::ProviderType::Http => v1::ProviderType::Http,
             protocol::ProviderType::Messaging => v1::ProviderType::Messaging,
+            protocol::ProviderType::Introspection => v1::ProviderType::Introspection,
         }
     }
 }
 
 impl TryFrom<v1::ProviderType> for protocol::ProviderType {
-    type Error = String;
+    type Error = ConversionError;
 
     fn try_from(t: v1::ProviderType) -> Result<Self, Self::Error> {
         match t {
             v1::ProviderType::Kv => Ok(protocol::ProviderType::Kv),
             v1::ProviderType::Http => Ok(protocol::ProviderType::Http),
             v1::ProviderType::Messaging => Ok(protocol::ProviderType::Messaging),
-            v1::ProviderType::Unspecified => Err("ProviderType is UNSPECIFIED".to_string()),
+            v1::ProviderType::Introspection => Ok(protocol::ProviderType::Introspection),
+            v1::ProviderType::Unspecified => Err(ConversionError::InvalidEnum {
+                field: "provider_type",
+                value: t as i32,
+            }),
+        }
+    }
+}
+
+impl From<protocol::CapabilityState> for v1::CapabilityState {
+    fn from(state: protocol::CapabilityState) -> Self {
+        match state {
+            protocol::CapabilityState::Pending => v1::CapabilityState::Pending,
+            protocol::CapabilityState::Active => v1::CapabilityState::Active,
+            protocol::CapabilityState::Revoked => v1::CapabilityState::Revoked,
+        }
+    }
+}
+
+impl From<v1::CapabilityState> for protocol::CapabilityState {
+    fn from(state: v1::CapabilityState) -> Self {
+        match state {
+            v1::CapabilityState::Pending => protocol::CapabilityState::Pending,
+            v1::CapabilityState::Active | v1::CapabilityState::Unspecified => {
+                protocol::CapabilityState::Active
+            }
+            v1::CapabilityState::Revoked => protocol::CapabilityState::Revoked,
         }
     }
 }
@@ -462,7 +1168,7 @@ impl From<protocol::InstanceStatus> for v1::InstanceStatus {
 }
 
 impl TryFrom<v1::InstanceStatus> for protocol::InstanceStatus {
-    type Error = String;
+    type Error = ConversionError;
 
     fn try_from(s: v1::InstanceStatus) -> Result<Self, Self::Error> {
         match s {
@@ -470,7 +1176,10 @@ impl TryFrom<v1::InstanceStatus> for protocol::InstanceStatus {
             v1::InstanceStatus::Running => Ok(protocol::InstanceStatus::Running),
             v1::InstanceStatus::Stopped => Ok(protocol::InstanceStatus::Stopped),
             v1::InstanceStatus::Crashed => Ok(protocol::InstanceStatus::Crashed),
-            v1::InstanceStatus::Unspecified => Err("InstanceStatus is UNSPECIFIED".to_string()),
+            v1::InstanceStatus::Unspecified => Err(ConversionError::InvalidEnum {
+                field: "status",
+                value: s as i32,
+            }),
         }
     }
 }
@@ -486,16 +1195,17 @@ impl From<protocol::RestartPolicyType> for v1::RestartPolicyType {
 }
 
 impl TryFrom<v1::RestartPolicyType> for protocol::RestartPolicyType {
-    type Error = String;
+    type Error = ConversionError;
 
     fn try_from(t: v1::RestartPolicyType) -> Result<Self, Self::Error> {
         match t {
             v1::RestartPolicyType::Never => Ok(protocol::RestartPolicyType::Never),
             v1::RestartPolicyType::Always => Ok(protocol::RestartPolicyType::Always),
             v1::RestartPolicyType::OnFailure => Ok(protocol::RestartPolicyType::OnFailure),
-            v1::RestartPolicyType::Unspecified => {
-                Err("RestartPolicyType is UNSPECIFIED".to_string())
-            }
+            v1::RestartPolicyType::Unspecified => Err(ConversionError::InvalidEnum {
+                field: "policy_type",
+                value: t as i32,
+            }),
         }
     }
 }
@@ -503,6 +1213,7 @@ impl TryFrom<v1::RestartPolicyType> for protocol::RestartPolicyType {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     fn sample_assignment() -> protocol::CapabilityAssignment {
         protocol::CapabilityAssignment {
@@ -510,6 +1221,8 @@ mod tests {
             capability_id: "kv-1".to_string(),
             provider_type: protocol::ProviderType::Kv,
             permissions: vec!["kv:read".to_string()],
+            expires_at: Some(1_700_000_000),
+            state: protocol::CapabilityState::Pending,
         }
     }
 
@@ -523,7 +1236,11 @@ mod tests {
                 policy_type: protocol::RestartPolicyType::OnFailure,
                 max_retries: Some(3),
                 backoff_seconds: Some(5),
+                max_backoff_seconds: None,
             },
+            env: vec![("GREETING".to_string(), "hi".to_string())],
+            args: vec!["app".to_string()],
+            annotations: HashMap::from([("trace_id".to_string(), "abc123".to_string())]),
         };
 
         let v1_req: v1::StartInstanceRequest = req.clone().into();
@@ -538,10 +1255,16 @@ mod tests {
             module_bytes: vec![0x00, 0x61, 0x73, 0x6d],
             capabilities: vec![],
             restart_policy: None,
+            env: vec![],
+            args: vec![],
+            annotations: HashMap::new(),
         };
 
         let result = protocol::StartInstanceRequest::try_from(req);
-        assert!(result.is_err());
+        assert_eq!(
+            result.err(),
+            Some(ConversionError::MissingField("restart_policy"))
+        );
     }
 
     #[test]
@@ -556,9 +1279,12 @@ mod tests {
 
         let stop_req = protocol::StopInstanceRequest {
             instance_id: "instance-1".to_string(),
+            reason: Some("scale-down".to_string()),
+            annotations: HashMap::from([("deploy_id".to_string(), "d-42".to_string())]),
         };
-        let _: protocol::StopInstanceRequest =
+        let stop_back: protocol::StopInstanceRequest =
             v1::StopInstanceRequest::from(stop_req.clone()).into();
+        assert_eq!(stop_back, stop_req);
 
         let stop_res = protocol::StopInstanceResponse {
             success: true,
@@ -582,11 +1308,14 @@ mod tests {
                 module_hash: "abc".to_string(),
                 created_at: 42,
                 status: protocol::InstanceStatus::Running,
+                capabilities: vec![],
             }),
             error_code: None,
+            ready: true,
         };
         let v1_query: v1::QueryInstanceResponse = query_res.clone().into();
-        let _: protocol::QueryInstanceResponse = v1_query.try_into().unwrap();
+        let query_back: protocol::QueryInstanceResponse = v1_query.try_into().unwrap();
+        assert_eq!(query_back, query_res);
 
         let _: protocol::ListInstancesRequest =
             v1::ListInstancesRequest::from(protocol::ListInstancesRequest {}).into();
@@ -599,6 +1328,7 @@ mod tests {
                 module_hash: "hash".to_string(),
                 created_at: 1,
                 status: protocol::InstanceStatus::Running,
+                capabilities: vec![],
             }],
         };
         let v1_list: v1::ListInstancesResponse = list_res.clone().into();
@@ -624,11 +1354,29 @@ mod tests {
         let _: protocol::InvokeCapabilityResponse =
             v1::InvokeCapabilityResponse::from(invoke_res.clone()).into();
 
+        let restart_req = protocol::RestartInstanceRequest {
+            instance_id: "instance-1".to_string(),
+            capabilities: vec![],
+        };
+        let _: protocol::RestartInstanceRequest =
+            v1::RestartInstanceRequest::from(restart_req.clone())
+                .try_into()
+                .unwrap();
+
+        let restart_res = protocol::RestartInstanceResponse {
+            success: true,
+            message: "restarted".to_string(),
+            error_code: None,
+        };
+        let _: protocol::RestartInstanceResponse =
+            v1::RestartInstanceResponse::from(restart_res.clone()).into();
+
         let reg_req = protocol::RegisterNodeRequest {
             node_id: "node-1".to_string(),
             node_address: "127.0.0.1:50051".to_string(),
             capabilities: vec!["kv".to_string()],
-            max_instances: 10,
+            max_instances: Some(10),
+            weight: Some(3),
         };
         let _: protocol::RegisterNodeRequest =
             v1::RegisterNodeRequest::from(reg_req.clone()).into();
@@ -649,6 +1397,8 @@ mod tests {
                 error_message: Some("trap".to_string()),
             }],
             timestamp: 100,
+            capabilities: vec![],
+            replace_capabilities: false,
         };
         let v1_status: v1::StatusReport = status_report.clone().into();
         let _: protocol::StatusReport = v1_status.try_into().unwrap();
@@ -659,6 +1409,237 @@ mod tests {
         };
         let _: protocol::StatusReportResponse =
             v1::StatusReportResponse::from(status_res.clone()).into();
+
+        let locate_req = protocol::LocateInstanceRequest {
+            instance_id: "instance-1".to_string(),
+        };
+        let _: protocol::LocateInstanceRequest =
+            v1::LocateInstanceRequest::from(locate_req.clone()).into();
+
+        let locate_res = protocol::LocateInstanceResponse {
+            success: true,
+            node_id: Some("node-1".to_string()),
+            message: "ok".to_string(),
+            error_code: None,
+        };
+        let _: protocol::LocateInstanceResponse =
+            v1::LocateInstanceResponse::from(locate_res.clone()).into();
+
+        let history_req = protocol::GetInstanceHistoryRequest {
+            instance_id: "instance-1".to_string(),
+        };
+        let _: protocol::GetInstanceHistoryRequest =
+            v1::GetInstanceHistoryRequest::from(history_req.clone()).into();
+
+        let history_res = protocol::GetInstanceHistoryResponse {
+            success: true,
+            entries: vec![protocol::InstanceHistoryEntry {
+                event_type: "instance_started".to_string(),
+                timestamp: 1_700_000_000,
+            }],
+            message: "ok".to_string(),
+            error_code: None,
+        };
+        let round_trip: protocol::GetInstanceHistoryResponse =
+            v1::GetInstanceHistoryResponse::from(history_res.clone()).into();
+        assert_eq!(round_trip, history_res);
+
+        let crash_req = protocol::ListCrashLoopingRequest {
+            threshold: 3,
+            window_seconds: 600,
+        };
+        let round_trip: protocol::ListCrashLoopingRequest =
+            v1::ListCrashLoopingRequest::from(crash_req.clone()).into();
+        assert_eq!(round_trip, crash_req);
+
+        let crash_res = protocol::ListCrashLoopingResponse {
+            success: true,
+            instances: vec![protocol::CrashLoopingInstance {
+                instance_id: "instance-1".to_string(),
+                crash_count: 5,
+            }],
+            message: "ok".to_string(),
+            error_code: None,
+        };
+        let round_trip: protocol::ListCrashLoopingResponse =
+            v1::ListCrashLoopingResponse::from(crash_res.clone()).into();
+        assert_eq!(round_trip, crash_res);
+
+        let _: protocol::GetClusterCapacityRequest =
+            v1::GetClusterCapacityRequest::from(protocol::GetClusterCapacityRequest {}).into();
+
+        let capacity_res = protocol::GetClusterCapacityResponse {
+            success: true,
+            total_slots: None,
+            used_slots: 7,
+            available_nodes: 2,
+            total_nodes: 3,
+            message: "ok".to_string(),
+            error_code: None,
+        };
+        let round_trip: protocol::GetClusterCapacityResponse =
+            v1::GetClusterCapacityResponse::from(capacity_res.clone()).into();
+        assert_eq!(round_trip, capacity_res);
+
+        let _: protocol::GetPersistenceStatusRequest =
+            v1::GetPersistenceStatusRequest::from(protocol::GetPersistenceStatusRequest {}).into();
+
+        let persistence_res = protocol::GetPersistenceStatusResponse {
+            enabled: true,
+            healthy: false,
+            last_error: Some("connection refused".to_string()),
+        };
+        let round_trip: protocol::GetPersistenceStatusResponse =
+            v1::GetPersistenceStatusResponse::from(persistence_res.clone()).into();
+        assert_eq!(round_trip, persistence_res);
+
+        let _: protocol::GetEventCountsRequest =
+            v1::GetEventCountsRequest::from(protocol::GetEventCountsRequest {}).into();
+
+        let counts_res = protocol::GetEventCountsResponse {
+            counts: [
+                ("instance_crashed".to_string(), 42),
+                ("instance_started".to_string(), 120),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let round_trip: protocol::GetEventCountsResponse =
+            v1::GetEventCountsResponse::from(counts_res.clone()).into();
+        assert_eq!(round_trip, counts_res);
+
+        let watch_req = protocol::WatchInstanceRequest {
+            instance_id: "instance-1".to_string(),
+        };
+        let round_trip: protocol::WatchInstanceRequest =
+            v1::WatchInstanceRequest::from(watch_req.clone()).into();
+        assert_eq!(round_trip, watch_req);
+
+        let watch_res = protocol::WatchInstanceResponse {
+            instance_id: "instance-1".to_string(),
+            status: protocol::InstanceStatus::Crashed,
+        };
+        let round_trip: protocol::WatchInstanceResponse =
+            v1::WatchInstanceResponse::from(watch_res.clone())
+                .try_into()
+                .unwrap();
+        assert_eq!(round_trip, watch_res);
+
+        let assign_req = protocol::AssignCapabilityRequest {
+            assignment: sample_assignment(),
+        };
+        let round_trip: protocol::AssignCapabilityRequest =
+            v1::AssignCapabilityRequest::from(assign_req.clone())
+                .try_into()
+                .unwrap();
+        assert_eq!(round_trip, assign_req);
+        assert_eq!(
+            protocol::AssignCapabilityRequest::try_from(v1::AssignCapabilityRequest {
+                assignment: None
+            }),
+            Err(ConversionError::MissingField("assignment"))
+        );
+
+        let assign_res = protocol::AssignCapabilityResponse {
+            success: true,
+            message: "assigned".to_string(),
+            error_code: None,
+        };
+        let round_trip: protocol::AssignCapabilityResponse =
+            v1::AssignCapabilityResponse::from(assign_res.clone()).into();
+        assert_eq!(round_trip, assign_res);
+
+        let revoke_req = protocol::RevokeCapabilityRequest {
+            instance_id: "instance-1".to_string(),
+            capability_id: "kv-1".to_string(),
+        };
+        let round_trip: protocol::RevokeCapabilityRequest =
+            v1::RevokeCapabilityRequest::from(revoke_req.clone()).into();
+        assert_eq!(round_trip, revoke_req);
+
+        let revoke_res = protocol::RevokeCapabilityResponse {
+            success: false,
+            message: "missing".to_string(),
+            error_code: Some("CAPABILITY_NOT_FOUND".to_string()),
+        };
+        let round_trip: protocol::RevokeCapabilityResponse =
+            v1::RevokeCapabilityResponse::from(revoke_res.clone()).into();
+        assert_eq!(round_trip, revoke_res);
+
+        let capabilities_req = protocol::GetCapabilitiesRequest {
+            instance_id: "instance-1".to_string(),
+        };
+        let round_trip: protocol::GetCapabilitiesRequest =
+            v1::GetCapabilitiesRequest::from(capabilities_req.clone()).into();
+        assert_eq!(round_trip, capabilities_req);
+
+        let capabilities_res = protocol::GetCapabilitiesResponse {
+            success: true,
+            capabilities: vec![sample_assignment()],
+            message: "ok".to_string(),
+            error_code: None,
+        };
+        let round_trip: protocol::GetCapabilitiesResponse =
+            v1::GetCapabilitiesResponse::from(capabilities_res.clone())
+                .try_into()
+                .unwrap();
+        assert_eq!(round_trip, capabilities_res);
+
+        let permissions_req = protocol::GetEffectivePermissionsRequest {
+            instance_id: "instance-1".to_string(),
+            permission: Some("kv:write".to_string()),
+        };
+        let round_trip: protocol::GetEffectivePermissionsRequest =
+            v1::GetEffectivePermissionsRequest::from(permissions_req.clone()).into();
+        assert_eq!(round_trip, permissions_req);
+
+        let permissions_res = protocol::GetEffectivePermissionsResponse {
+            success: true,
+            permissions: vec!["kv:*".to_string()],
+            allowed: Some(true),
+            message: "ok".to_string(),
+            error_code: None,
+        };
+        let round_trip: protocol::GetEffectivePermissionsResponse =
+            v1::GetEffectivePermissionsResponse::from(permissions_res.clone()).into();
+        assert_eq!(round_trip, permissions_res);
+
+        let logs_req = protocol::ReadInstanceLogsRequest {
+            instance_id: "instance-1".to_string(),
+            max_bytes: 1024,
+        };
+        let round_trip: protocol::ReadInstanceLogsRequest =
+            v1::ReadInstanceLogsRequest::from(logs_req.clone()).into();
+        assert_eq!(round_trip, logs_req);
+
+        let logs_res = protocol::ReadInstanceLogsResponse {
+            success: true,
+            data: b"hello\n".to_vec(),
+            message: "ok".to_string(),
+            error_code: None,
+        };
+        let round_trip: protocol::ReadInstanceLogsResponse =
+            v1::ReadInstanceLogsResponse::from(logs_res.clone()).into();
+        assert_eq!(round_trip, logs_res);
+
+        let set_caps_req = protocol::SetInstanceCapabilitiesRequest {
+            instance_id: "instance-1".to_string(),
+            capabilities: vec![sample_assignment()],
+        };
+        let round_trip: protocol::SetInstanceCapabilitiesRequest =
+            v1::SetInstanceCapabilitiesRequest::from(set_caps_req.clone())
+                .try_into()
+                .unwrap();
+        assert_eq!(round_trip, set_caps_req);
+
+        let set_caps_res = protocol::SetInstanceCapabilitiesResponse {
+            success: false,
+            message: "Instance not found".to_string(),
+            error_code: Some("INSTANCE_NOT_FOUND".to_string()),
+        };
+        let round_trip: protocol::SetInstanceCapabilitiesResponse =
+            v1::SetInstanceCapabilitiesResponse::from(set_caps_res.clone()).into();
+        assert_eq!(round_trip, set_caps_res);
     }
 
     #[test]
@@ -678,6 +1659,7 @@ mod tests {
             module_hash: "hash".to_string(),
             created_at: 7,
             status: protocol::InstanceStatus::Starting,
+            capabilities: vec![sample_assignment()],
         };
         let v1_meta: v1::InstanceMetadata = meta.clone().into();
         let meta_rt: protocol::InstanceMetadata = v1_meta.try_into().unwrap();
@@ -712,13 +1694,21 @@ mod tests {
             status: v1::InstanceStatus::Unspecified as i32,
             error_message: None,
         };
-        assert!(protocol::InstanceStatusUpdate::try_from(invalid_update).is_err());
+        assert_eq!(
+            protocol::InstanceStatusUpdate::try_from(invalid_update).err(),
+            Some(ConversionError::InvalidEnum {
+                field: "status",
+                value: 0
+            })
+        );
 
         let invalid_assignment = v1::CapabilityAssignment {
             instance_id: "instance-1".to_string(),
             capability_id: "kv-1".to_string(),
             provider_type: v1::ProviderType::Unspecified as i32,
             permissions: vec!["kv:read".to_string()],
+            expires_at: None,
+            state: v1::CapabilityState::Active as i32,
         };
         assert!(protocol::CapabilityAssignment::try_from(invalid_assignment).is_err());
 
@@ -726,8 +1716,27 @@ mod tests {
             policy_type: v1::RestartPolicyType::Unspecified as i32,
             max_retries: None,
             backoff_seconds: None,
+            max_backoff_seconds: None,
         };
         assert!(protocol::RestartPolicy::try_from(invalid_policy).is_err());
+
+        let out_of_range = v1::CapabilityAssignment {
+            instance_id: "instance-1".to_string(),
+            capability_id: "kv-1".to_string(),
+            provider_type: 42,
+            permissions: vec![],
+            expires_at: None,
+            state: v1::CapabilityState::Active as i32,
+        };
+        let error = protocol::CapabilityAssignment::try_from(out_of_range).unwrap_err();
+        assert_eq!(
+            error,
+            ConversionError::InvalidEnum {
+                field: "provider_type",
+                value: 42
+            }
+        );
+        assert_eq!(error.to_string(), "invalid provider_type value 42");
     }
 
     #[test]
@@ -1,5 +1,7 @@
 pub mod conversion;
 pub mod protocol;
+pub mod reflection;
+pub mod tls;
 
 #[cfg(test)]
 mod protocol_tests;
@@ -2,6 +2,7 @@
 // Generated types (manually defined instead of using protoc)
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // Version: 1.0.0
 
@@ -12,6 +13,15 @@ pub struct StartInstanceRequest {
     pub module_bytes: Vec<u8>,
     pub capabilities: Vec<CapabilityAssignment>,
     pub restart_policy: RestartPolicy,
+    /// WASI environment variables
+    #[serde(default)]
+    pub env: Vec<(String, String)>,
+    /// WASI argv
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Copied onto the `instance_started` event
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -24,6 +34,10 @@ pub struct StartInstanceResponse {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct StopInstanceRequest {
     pub instance_id: String,
+    pub reason: Option<String>,
+    /// Copied onto the `instance_stopped` event
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -43,6 +57,7 @@ pub struct QueryInstanceResponse {
     pub success: bool,
     pub instance: Option<InstanceMetadata>,
     pub error_code: Option<String>,
+    pub ready: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -61,6 +76,7 @@ pub struct InvokeCapabilityRequest {
     pub provider_type: ProviderType,
     pub operation: String,
     pub params_json: String,
+    /// Informational; node agents authorise with the grant they hold for the instance
     pub permissions: Vec<String>,
 }
 
@@ -72,13 +88,61 @@ pub struct InvokeCapabilityResponse {
     pub error_code: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RestartInstanceRequest {
+    pub instance_id: String,
+    /// Grants replacing the instance's current ones; empty keeps them
+    #[serde(default)]
+    pub capabilities: Vec<CapabilityAssignment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RestartInstanceResponse {
+    pub success: bool,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReadInstanceLogsRequest {
+    pub instance_id: String,
+    pub max_bytes: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReadInstanceLogsResponse {
+    pub success: bool,
+    pub data: Vec<u8>,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+/// Replace every grant a running instance holds
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SetInstanceCapabilitiesRequest {
+    pub instance_id: String,
+    pub capabilities: Vec<CapabilityAssignment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SetInstanceCapabilitiesResponse {
+    pub success: bool,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
 // Control Plane Service Messages
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RegisterNodeRequest {
     pub node_id: String,
     pub node_address: String,
     pub capabilities: Vec<String>,
-    pub max_instances: u32,
+    /// `None` means unlimited; `Some(0)` means the node accepts no instances
+    #[serde(default)]
+    pub max_instances: Option<u32>,
+    /// Relative share of placements under weighted round-robin; `None` means 1
+    #[serde(default)]
+    pub weight: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -93,6 +157,10 @@ pub struct StatusReport {
     pub node_id: String,
     pub instance_updates: Vec<InstanceStatusUpdate>,
     pub timestamp: i64,
+    /// Provider types the node currently offers; empty leaves the registered set alone
+    pub capabilities: Vec<String>,
+    /// Replace the registered set instead of adding to it
+    pub replace_capabilities: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -101,6 +169,158 @@ pub struct StatusReportResponse {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LocateInstanceRequest {
+    pub instance_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LocateInstanceResponse {
+    pub success: bool,
+    pub node_id: Option<String>,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetInstanceHistoryRequest {
+    pub instance_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InstanceHistoryEntry {
+    pub event_type: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetInstanceHistoryResponse {
+    pub success: bool,
+    pub entries: Vec<InstanceHistoryEntry>,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ListCrashLoopingRequest {
+    pub threshold: u32,
+    pub window_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CrashLoopingInstance {
+    pub instance_id: String,
+    pub crash_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ListCrashLoopingResponse {
+    pub success: bool,
+    pub instances: Vec<CrashLoopingInstance>,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetClusterCapacityRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetClusterCapacityResponse {
+    pub success: bool,
+    /// `None` when any available node has unlimited capacity
+    pub total_slots: Option<u64>,
+    pub used_slots: u64,
+    pub available_nodes: u32,
+    pub total_nodes: u32,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetEffectivePermissionsRequest {
+    pub instance_id: String,
+    pub permission: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetEffectivePermissionsResponse {
+    pub success: bool,
+    pub permissions: Vec<String>,
+    /// Set when the request named a permission to check
+    pub allowed: Option<bool>,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetPersistenceStatusRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetPersistenceStatusResponse {
+    pub enabled: bool,
+    /// Only meaningful when `enabled`
+    pub healthy: bool,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetEventCountsRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetEventCountsResponse {
+    /// Recorded execution events per event type
+    pub counts: HashMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchInstanceRequest {
+    pub instance_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchInstanceResponse {
+    pub instance_id: String,
+    pub status: InstanceStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AssignCapabilityRequest {
+    pub assignment: CapabilityAssignment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AssignCapabilityResponse {
+    pub success: bool,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RevokeCapabilityRequest {
+    pub instance_id: String,
+    pub capability_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RevokeCapabilityResponse {
+    pub success: bool,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetCapabilitiesRequest {
+    pub instance_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetCapabilitiesResponse {
+    pub success: bool,
+    pub capabilities: Vec<CapabilityAssignment>,
+    pub message: String,
+    pub error_code: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InstanceStatusUpdate {
     pub instance_id: String,
@@ -115,6 +335,12 @@ pub struct CapabilityAssignment {
     pub capability_id: String,
     pub provider_type: ProviderType,
     pub permissions: Vec<String>,
+    /// Unix seconds after which the grant lapses; `None` never expires
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// Only `Active` grants carry their permissions onto the node
+    #[serde(default)]
+    pub state: CapabilityState,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -124,6 +350,9 @@ pub struct InstanceMetadata {
     pub module_hash: String,
     pub created_at: i64,
     pub status: InstanceStatus,
+    /// Capabilities assigned to the instance, reported so recovery can restore them
+    #[serde(default)]
+    pub capabilities: Vec<CapabilityAssignment>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -132,6 +361,16 @@ pub enum ProviderType {
     Kv,
     Http,
     Messaging,
+    Introspection,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CapabilityState {
+    Pending,
+    #[default]
+    Active,
+    Revoked,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -148,6 +387,8 @@ pub struct RestartPolicy {
     pub policy_type: RestartPolicyType,
     pub max_retries: Option<u32>,
     pub backoff_seconds: Option<u64>,
+    #[serde(default)]
+    pub max_backoff_seconds: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -164,6 +405,7 @@ impl Default for RestartPolicy {
             policy_type: RestartPolicyType::Never,
             max_retries: None,
             backoff_seconds: None,
+            max_backoff_seconds: None,
         }
     }
 }
@@ -197,6 +439,7 @@ impl From<wasmatrix_core::ProviderType> for ProviderType {
             wasmatrix_core::ProviderType::Kv => ProviderType::Kv,
             wasmatrix_core::ProviderType::Http => ProviderType::Http,
             wasmatrix_core::ProviderType::Messaging => ProviderType::Messaging,
+            wasmatrix_core::ProviderType::Introspection => ProviderType::Introspection,
         }
     }
 }
@@ -207,6 +450,57 @@ impl From<ProviderType> for wasmatrix_core::ProviderType {
             ProviderType::Kv => wasmatrix_core::ProviderType::Kv,
             ProviderType::Http => wasmatrix_core::ProviderType::Http,
             ProviderType::Messaging => wasmatrix_core::ProviderType::Messaging,
+            ProviderType::Introspection => wasmatrix_core::ProviderType::Introspection,
+        }
+    }
+}
+
+impl From<wasmatrix_core::CapabilityAssignment> for CapabilityAssignment {
+    fn from(assignment: wasmatrix_core::CapabilityAssignment) -> Self {
+        Self {
+            permissions: assignment.permissions().to_vec(),
+            instance_id: assignment.instance_id,
+            capability_id: assignment.capability_id,
+            provider_type: assignment.provider_type.into(),
+            expires_at: assignment.expires_at.map(|at| at.timestamp()),
+            state: assignment.state.into(),
+        }
+    }
+}
+
+impl From<CapabilityAssignment> for wasmatrix_core::CapabilityAssignment {
+    fn from(assignment: CapabilityAssignment) -> Self {
+        let mut core = wasmatrix_core::CapabilityAssignment::new(
+            assignment.instance_id,
+            assignment.capability_id,
+            assignment.provider_type.into(),
+            assignment.permissions,
+        );
+        // An out-of-range timestamp is treated as already expired rather than permanent
+        core.expires_at = assignment.expires_at.map(|secs| {
+            chrono::DateTime::from_timestamp(secs, 0).unwrap_or(chrono::DateTime::UNIX_EPOCH)
+        });
+        core.state = assignment.state.into();
+        core
+    }
+}
+
+impl From<wasmatrix_core::CapabilityState> for CapabilityState {
+    fn from(state: wasmatrix_core::CapabilityState) -> Self {
+        match state {
+            wasmatrix_core::CapabilityState::Pending => CapabilityState::Pending,
+            wasmatrix_core::CapabilityState::Active => CapabilityState::Active,
+            wasmatrix_core::CapabilityState::Revoked => CapabilityState::Revoked,
+        }
+    }
+}
+
+impl From<CapabilityState> for wasmatrix_core::CapabilityState {
+    fn from(state: CapabilityState) -> Self {
+        match state {
+            CapabilityState::Pending => wasmatrix_core::CapabilityState::Pending,
+            CapabilityState::Active => wasmatrix_core::CapabilityState::Active,
+            CapabilityState::Revoked => wasmatrix_core::CapabilityState::Revoked,
         }
     }
 }
@@ -221,6 +515,7 @@ impl From<wasmatrix_core::RestartPolicy> for RestartPolicy {
             },
             max_retries: policy.max_retries,
             backoff_seconds: policy.backoff_seconds,
+            max_backoff_seconds: policy.max_backoff_seconds,
         }
     }
 }
@@ -235,6 +530,7 @@ impl From<RestartPolicy> for wasmatrix_core::RestartPolicy {
             },
             max_retries: policy.max_retries,
             backoff_seconds: policy.backoff_seconds,
+            max_backoff_seconds: policy.max_backoff_seconds,
         }
     }
 }
@@ -273,6 +569,7 @@ mod tests {
             wasmatrix_core::ProviderType::Kv,
             wasmatrix_core::ProviderType::Http,
             wasmatrix_core::ProviderType::Messaging,
+            wasmatrix_core::ProviderType::Introspection,
         ];
 
         for provider in providers {
@@ -289,16 +586,19 @@ mod tests {
                 policy_type: wasmatrix_core::RestartPolicyType::Never,
                 max_retries: None,
                 backoff_seconds: None,
+                max_backoff_seconds: None,
             },
             wasmatrix_core::RestartPolicy {
                 policy_type: wasmatrix_core::RestartPolicyType::Always,
                 max_retries: Some(1),
                 backoff_seconds: Some(1),
+                max_backoff_seconds: None,
             },
             wasmatrix_core::RestartPolicy {
                 policy_type: wasmatrix_core::RestartPolicyType::OnFailure,
                 max_retries: Some(5),
                 backoff_seconds: Some(10),
+                max_backoff_seconds: None,
             },
         ];
 
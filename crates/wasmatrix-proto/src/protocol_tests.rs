@@ -13,8 +13,13 @@ mod tests {
                 capability_id: "kv-1".to_string(),
                 provider_type: ProviderType::Kv,
                 permissions: vec!["kv:read".to_string()],
+                expires_at: None,
+                state: CapabilityState::Active,
             }],
             restart_policy: RestartPolicy::default(),
+            env: vec![],
+            args: vec![],
+            annotations: Default::default(),
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -31,6 +36,7 @@ mod tests {
             module_hash: "abc123".to_string(),
             created_at: 1234567890,
             status: InstanceStatus::Running,
+            capabilities: vec![],
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
@@ -65,6 +71,7 @@ mod tests {
             policy_type: RestartPolicyType::OnFailure,
             max_retries: Some(3),
             backoff_seconds: Some(5),
+            max_backoff_seconds: None,
         };
 
         let json = serde_json::to_string(&policy).unwrap();
@@ -80,6 +87,8 @@ mod tests {
             capability_id: "http-1".to_string(),
             provider_type: ProviderType::Http,
             permissions: vec!["http:get".to_string(), "http:post".to_string()],
+            expires_at: Some(1_700_000_000),
+            state: CapabilityState::Active,
         };
 
         let json = serde_json::to_string(&assignment).unwrap();
@@ -88,6 +97,24 @@ mod tests {
         assert_eq!(deserialized, assignment);
     }
 
+    #[test]
+    fn test_core_capability_expiry_survives_grpc_hop() {
+        let mut core = wasmatrix_core::CapabilityAssignment::new(
+            "instance-1".to_string(),
+            "kv-1".to_string(),
+            wasmatrix_core::ProviderType::Kv,
+            vec!["kv:read".to_string()],
+        );
+        core.expires_at = chrono::DateTime::from_timestamp(1_700_000_000, 0);
+
+        let wire: v1::CapabilityAssignment = CapabilityAssignment::from(core.clone()).into();
+        assert_eq!(wire.expires_at, Some(1_700_000_000));
+        let received: wasmatrix_core::CapabilityAssignment =
+            CapabilityAssignment::try_from(wire).unwrap().into();
+        assert_eq!(received.expires_at, core.expires_at);
+        assert!(received.is_expired());
+    }
+
     #[test]
     fn test_status_report_serialization() {
         let report = StatusReport {
@@ -98,6 +125,8 @@ mod tests {
                 error_message: None,
             }],
             timestamp: 1234567890,
+            capabilities: vec![],
+            replace_capabilities: false,
         };
 
         let json = serde_json::to_string(&report).unwrap();
@@ -112,7 +141,8 @@ mod tests {
             node_id: "node-1".to_string(),
             node_address: "localhost:50051".to_string(),
             capabilities: vec!["kv".to_string(), "http".to_string()],
-            max_instances: 100,
+            max_instances: Some(100),
+            weight: Some(2),
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -218,6 +248,8 @@ mod tests {
                     capability_id: format!("kv-{i}"),
                     provider_type: ProviderType::Kv,
                     permissions: vec!["kv:read".to_string(), format!("kv:scope:{i}")],
+                    expires_at: None,
+                    state: CapabilityState::Active,
                 }],
                 restart_policy: RestartPolicy {
                     policy_type: if i % 2 == 0 {
@@ -227,7 +259,11 @@ mod tests {
                     },
                     max_retries: Some((i % 5) as u32),
                     backoff_seconds: Some((i % 10 + 1) as u64),
+                    max_backoff_seconds: None,
                 },
+                env: vec![("INDEX".to_string(), i.to_string())],
+                args: vec![format!("arg-{i}")],
+                annotations: Default::default(),
             };
 
             let v1_req: v1::StartInstanceRequest = request.clone().into();
@@ -262,6 +298,8 @@ mod tests {
                     })
                     .collect(),
                 timestamp: 1_700_000_000 + i as i64,
+                capabilities: vec![],
+                replace_capabilities: false,
             };
 
             let v1_report: v1::StatusReport = report.clone().into();
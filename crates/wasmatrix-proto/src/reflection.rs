@@ -0,0 +1,88 @@
+use tonic_reflection::server::{ServerReflection, ServerReflectionServer};
+
+/// Encoded `FileDescriptorSet` for the `wasmatrix.v1` package.
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("wasmatrix_descriptor");
+
+/// Environment variable that toggles gRPC server reflection.
+pub const REFLECTION_ENV_VAR: &str = "GRPC_REFLECTION_ENABLED";
+
+/// Build a gRPC reflection service exposing the `wasmatrix.v1` descriptors.
+pub fn reflection_service(
+) -> Result<ServerReflectionServer<impl ServerReflection>, tonic_reflection::server::Error> {
+    tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build()
+}
+
+/// Reflection is on by default; hardened deployments set
+/// `GRPC_REFLECTION_ENABLED=false` to turn it off.
+pub fn reflection_enabled_from_env() -> bool {
+    reflection_enabled(std::env::var(REFLECTION_ENV_VAR).ok().as_deref())
+}
+
+fn reflection_enabled(value: Option<&str>) -> bool {
+    match value.map(|v| v.trim().to_ascii_lowercase()) {
+        Some(v) => !matches!(v.as_str(), "false" | "0" | "no" | "off"),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+    use tonic::transport::Server;
+    use tonic_reflection::pb::server_reflection_client::ServerReflectionClient;
+    use tonic_reflection::pb::server_reflection_request::MessageRequest;
+    use tonic_reflection::pb::server_reflection_response::MessageResponse;
+    use tonic_reflection::pb::ServerReflectionRequest;
+
+    #[test]
+    fn test_reflection_flag_parsing() {
+        assert!(reflection_enabled(None));
+        assert!(reflection_enabled(Some("true")));
+        assert!(reflection_enabled(Some("1")));
+        assert!(!reflection_enabled(Some("false")));
+        assert!(!reflection_enabled(Some(" OFF ")));
+        assert!(!reflection_enabled(Some("0")));
+    }
+
+    #[tokio::test]
+    async fn test_reflection_lists_wasmatrix_services() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(
+            Server::builder()
+                .add_service(reflection_service().unwrap())
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        let channel = tonic::transport::Endpoint::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let mut client = ServerReflectionClient::new(channel);
+        let request = ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(MessageRequest::ListServices(String::new())),
+        };
+        let mut responses = client
+            .server_reflection_info(tokio_stream::iter(vec![request]))
+            .await
+            .unwrap()
+            .into_inner();
+        let response = responses.next().await.unwrap().unwrap();
+
+        let services: Vec<String> = match response.message_response {
+            Some(MessageResponse::ListServicesResponse(list)) => {
+                list.service.into_iter().map(|s| s.name).collect()
+            }
+            other => panic!("unexpected reflection response: {:?}", other),
+        };
+        assert!(services.contains(&"wasmatrix.v1.ControlPlaneService".to_string()));
+        assert!(services.contains(&"wasmatrix.v1.NodeAgentService".to_string()));
+
+        server.abort();
+    }
+}
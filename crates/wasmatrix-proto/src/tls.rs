@@ -0,0 +1,270 @@
+use std::path::{Path, PathBuf};
+use tonic::transport::{Certificate, ClientTlsConfig, Endpoint, Identity, ServerTlsConfig};
+
+/// PEM certificate this process presents, both when serving and as its mTLS client certificate.
+pub const TLS_CERT_ENV_VAR: &str = "GRPC_TLS_CERT";
+/// PEM private key for [`TLS_CERT_ENV_VAR`].
+pub const TLS_KEY_ENV_VAR: &str = "GRPC_TLS_KEY";
+/// PEM CA bundle used to verify peers; servers also require client certificates signed by it.
+pub const TLS_CA_ENV_VAR: &str = "GRPC_TLS_CA";
+/// Name expected in peer certificates when it differs from the host being dialed.
+pub const TLS_DOMAIN_ENV_VAR: &str = "GRPC_TLS_DOMAIN";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("{set} is set but {missing} is not")]
+    Incomplete {
+        set: &'static str,
+        missing: &'static str,
+    },
+    #[error("{missing} must be set to {purpose}")]
+    Missing {
+        missing: &'static str,
+        purpose: &'static str,
+    },
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{path} does not contain a PEM {kind}")]
+    InvalidPem { path: PathBuf, kind: &'static str },
+    #[error("invalid TLS configuration: {0}")]
+    Config(String),
+}
+
+/// Certificates for gRPC between the control plane and node agents
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    identity: Option<Identity>,
+    ca: Option<Certificate>,
+    domain_name: Option<String>,
+}
+
+impl TlsConfig {
+    /// Load TLS settings from `GRPC_TLS_*`.
+    ///
+    /// Returns `None` only when none of the variables are set, in which case
+    /// callers fall back to plaintext. A certificate without its key (or the
+    /// reverse) or an unreadable file is an error rather than a silent downgrade.
+    pub fn from_env() -> Result<Option<Self>, TlsError> {
+        let var = |name| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let cert = var(TLS_CERT_ENV_VAR);
+        let key = var(TLS_KEY_ENV_VAR);
+        let ca = var(TLS_CA_ENV_VAR);
+        if cert.is_none() && key.is_none() && ca.is_none() {
+            return Ok(None);
+        }
+
+        let identity = match (cert, key) {
+            (Some(cert), Some(key)) => Some((PathBuf::from(cert), PathBuf::from(key))),
+            (Some(_), None) => {
+                return Err(TlsError::Incomplete {
+                    set: TLS_CERT_ENV_VAR,
+                    missing: TLS_KEY_ENV_VAR,
+                })
+            }
+            (None, Some(_)) => {
+                return Err(TlsError::Incomplete {
+                    set: TLS_KEY_ENV_VAR,
+                    missing: TLS_CERT_ENV_VAR,
+                })
+            }
+            (None, None) => None,
+        };
+        let config = Self::from_pem_files(
+            identity
+                .as_ref()
+                .map(|(cert, key)| (cert.as_path(), key.as_path())),
+            ca.as_deref().map(Path::new),
+        )?;
+        Ok(Some(match var(TLS_DOMAIN_ENV_VAR) {
+            Some(domain_name) => config.with_domain_name(domain_name),
+            None => config,
+        }))
+    }
+
+    /// Read a certificate/key pair and CA bundle from PEM files
+    pub fn from_pem_files(
+        identity: Option<(&Path, &Path)>,
+        ca: Option<&Path>,
+    ) -> Result<Self, TlsError> {
+        let identity = match identity {
+            Some((cert, key)) => Some(Identity::from_pem(
+                read_pem(cert, "CERTIFICATE")?,
+                read_pem(key, "PRIVATE KEY")?,
+            )),
+            None => None,
+        };
+        let ca = match ca {
+            Some(path) => Some(Certificate::from_pem(read_pem(path, "CERTIFICATE")?)),
+            None => None,
+        };
+        Ok(Self {
+            identity,
+            ca,
+            domain_name: None,
+        })
+    }
+
+    /// Verify peers against this name instead of the dialed host
+    pub fn with_domain_name(mut self, domain_name: impl Into<String>) -> Self {
+        self.domain_name = Some(domain_name.into());
+        self
+    }
+
+    /// Server-side settings; requires client certificates when a CA is configured
+    pub fn server_config(&self) -> Result<ServerTlsConfig, TlsError> {
+        let identity = self.identity.clone().ok_or(TlsError::Missing {
+            missing: TLS_CERT_ENV_VAR,
+            purpose: "serve gRPC over TLS",
+        })?;
+        let config = ServerTlsConfig::new().identity(identity);
+        Ok(match &self.ca {
+            Some(ca) => config.client_ca_root(ca.clone()),
+            None => config,
+        })
+    }
+
+    /// Client-side settings; presents the certificate for mTLS when one is configured
+    pub fn client_config(&self) -> Result<ClientTlsConfig, TlsError> {
+        let ca = self.ca.clone().ok_or(TlsError::Missing {
+            missing: TLS_CA_ENV_VAR,
+            purpose: "verify gRPC peers over TLS",
+        })?;
+        let mut config = ClientTlsConfig::new().ca_certificate(ca);
+        if let Some(identity) = &self.identity {
+            config = config.identity(identity.clone());
+        }
+        if let Some(domain_name) = &self.domain_name {
+            config = config.domain_name(domain_name.clone());
+        }
+        Ok(config)
+    }
+}
+
+/// Build an endpoint for `address`, over TLS when `tls` is set.
+///
+/// Addresses are stored with an `http://` scheme; with TLS it is switched to
+/// `https://` so tonic actually negotiates TLS on the connection.
+pub fn endpoint(address: &str, tls: Option<&TlsConfig>) -> Result<Endpoint, TlsError> {
+    let Some(tls) = tls else {
+        return Endpoint::from_shared(address.to_string())
+            .map_err(|e| TlsError::Config(e.to_string()));
+    };
+    let address = match address.strip_prefix("http://") {
+        Some(rest) => format!("https://{}", rest),
+        None => address.to_string(),
+    };
+    let client_config = tls.client_config()?;
+    Endpoint::from_shared(address)
+        .and_then(|endpoint| endpoint.tls_config(client_config))
+        .map_err(|e| TlsError::Config(e.to_string()))
+}
+
+fn read_pem(path: &Path, kind: &'static str) -> Result<Vec<u8>, TlsError> {
+    let pem = std::fs::read(path).map_err(|source| TlsError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let marker = format!("{}-----", kind);
+    if !String::from_utf8_lossy(&pem).contains(&marker) {
+        return Err(TlsError::InvalidPem {
+            path: path.to_path_buf(),
+            kind,
+        });
+    }
+    Ok(pem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+    use tonic::transport::Server;
+    use tonic_reflection::pb::server_reflection_client::ServerReflectionClient;
+    use tonic_reflection::pb::server_reflection_request::MessageRequest;
+    use tonic_reflection::pb::ServerReflectionRequest;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/tls")
+            .join(name)
+    }
+
+    fn node_tls() -> TlsConfig {
+        TlsConfig::from_pem_files(
+            Some((&fixture("node.pem"), &fixture("node.key"))),
+            Some(&fixture("ca.pem")),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_loads_cert_bundle() {
+        let tls = node_tls();
+
+        let secure = endpoint("http://127.0.0.1:50052", Some(&tls)).unwrap();
+        assert_eq!(secure.uri().scheme_str(), Some("https"));
+        assert!(Server::builder()
+            .tls_config(tls.server_config().unwrap())
+            .is_ok());
+
+        let plaintext = endpoint("http://127.0.0.1:50052", None).unwrap();
+        assert_eq!(plaintext.uri().scheme_str(), Some("http"));
+    }
+
+    #[test]
+    fn test_bad_pem_files_are_reported() {
+        let swapped =
+            TlsConfig::from_pem_files(Some((&fixture("node.key"), &fixture("node.key"))), None);
+        assert!(matches!(
+            swapped,
+            Err(TlsError::InvalidPem {
+                kind: "CERTIFICATE",
+                ..
+            })
+        ));
+
+        let missing = TlsConfig::from_pem_files(None, Some(&fixture("missing.pem")));
+        assert!(matches!(missing, Err(TlsError::Read { .. })));
+
+        let server_only = TlsConfig::from_pem_files(None, Some(&fixture("ca.pem"))).unwrap();
+        assert!(matches!(
+            server_only.server_config(),
+            Err(TlsError::Missing { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls_round_trip() {
+        let tls = node_tls();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(
+            Server::builder()
+                .tls_config(tls.server_config().unwrap())
+                .unwrap()
+                .add_service(crate::reflection::reflection_service().unwrap())
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        let channel = endpoint(&format!("http://{}", addr), Some(&tls))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let request = ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(MessageRequest::ListServices(String::new())),
+        };
+        let mut responses = ServerReflectionClient::new(channel)
+            .server_reflection_info(tokio_stream::iter(vec![request]))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(responses.next().await.unwrap().is_ok());
+
+        server.abort();
+    }
+}
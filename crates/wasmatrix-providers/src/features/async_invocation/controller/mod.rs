@@ -0,0 +1,28 @@
+use crate::features::async_invocation::service::AsyncInvocationService;
+use serde_json::Value;
+use std::future::Future;
+use wasmatrix_core::Result;
+
+pub struct AsyncInvocationController {
+    service: AsyncInvocationService,
+}
+
+impl AsyncInvocationController {
+    pub fn new(service: AsyncInvocationService) -> Self {
+        Self { service }
+    }
+
+    /// Start `invocation` in the background; the result arrives in the
+    /// instance's inbox under the topic for the returned correlation id
+    pub fn dispatch<F>(&self, instance_id: &str, invocation: F) -> String
+    where
+        F: Future<Output = Result<Value>> + Send + 'static,
+    {
+        self.service.dispatch(instance_id, invocation)
+    }
+
+    /// Abort the instance's pending invocations and drop its inbox
+    pub fn cancel_instance(&self, instance_id: &str) -> Result<usize> {
+        self.service.cancel_instance(instance_id)
+    }
+}
@@ -0,0 +1,244 @@
+use crate::features::messaging_provider::repo::MessagingProviderRepository;
+use crate::features::messaging_provider::service::{invoke_result_topic, MessagingProviderService};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use tracing::warn;
+use wasmatrix_core::{CoreError, Result};
+
+/// How long a background invocation may run before it is reported as timed out
+pub const DEFAULT_ASYNC_INVOCATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a delivered result waits in the inbox before it is discarded unread
+pub const DEFAULT_INVOKE_RESULT_TTL: Duration = Duration::from_secs(300);
+
+/// Pending invocations per instance, keyed by correlation id
+type PendingInvocations = Arc<Mutex<HashMap<String, HashMap<String, AbortHandle>>>>;
+
+/// Runs provider invocations in the background and delivers each outcome to
+/// the calling instance's messaging inbox under `_invoke_result.<correlation_id>`.
+pub struct AsyncInvocationService {
+    repo: Arc<dyn MessagingProviderRepository>,
+    messaging: Arc<MessagingProviderService>,
+    pending: PendingInvocations,
+    timeout: Duration,
+    result_ttl: Duration,
+}
+
+impl AsyncInvocationService {
+    pub fn new(repo: Arc<dyn MessagingProviderRepository>) -> Self {
+        Self {
+            messaging: Arc::new(MessagingProviderService::new(repo.clone())),
+            repo,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            timeout: DEFAULT_ASYNC_INVOCATION_TIMEOUT,
+            result_ttl: DEFAULT_INVOKE_RESULT_TTL,
+        }
+    }
+
+    /// Report invocations still running after `timeout` as failed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Discard results nobody polled within `ttl` of delivery
+    pub fn with_result_ttl(mut self, ttl: Duration) -> Self {
+        self.result_ttl = ttl;
+        self
+    }
+
+    /// Spawn `invocation` on the current tokio runtime and return its correlation id
+    pub fn dispatch<F>(&self, instance_id: &str, invocation: F) -> String
+    where
+        F: Future<Output = Result<Value>> + Send + 'static,
+    {
+        let correlation_id = uuid::Uuid::new_v4().to_string();
+        let messaging = self.messaging.clone();
+        let repo = self.repo.clone();
+        let pending = self.pending.clone();
+        let timeout = self.timeout;
+        let result_ttl = self.result_ttl;
+        let instance_id = instance_id.to_string();
+        let task_instance_id = instance_id.clone();
+        let task_correlation_id = correlation_id.clone();
+
+        // Hold the lock across the spawn so the task cannot finish and
+        // deregister before its handle is recorded
+        let mut pending_guard = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let task = tokio::spawn(async move {
+            let instance_id = task_instance_id;
+            let outcome = match tokio::time::timeout(timeout, invocation).await {
+                Ok(outcome) => outcome,
+                Err(_) => Err(CoreError::Timeout(format!(
+                    "Async invocation did not finish within {}ms",
+                    timeout.as_millis()
+                ))),
+            };
+            forget_pending(&pending, &instance_id, &task_correlation_id);
+            if let Err(error) =
+                messaging.deliver_invoke_result(&instance_id, &task_correlation_id, &outcome)
+            {
+                warn!(
+                    %instance_id,
+                    correlation_id = %task_correlation_id,
+                    error = %error,
+                    "Failed to deliver async invocation result"
+                );
+                return;
+            }
+
+            tokio::time::sleep(result_ttl).await;
+            let _ = repo.take_messages(&instance_id, &invoke_result_topic(&task_correlation_id));
+        });
+        pending_guard
+            .entry(instance_id)
+            .or_default()
+            .insert(correlation_id.clone(), task.abort_handle());
+
+        correlation_id
+    }
+
+    /// Invocations dispatched for `instance_id` that have not finished yet
+    pub fn pending_count(&self, instance_id: &str) -> usize {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(instance_id)
+            .map_or(0, HashMap::len)
+    }
+
+    /// Abort the instance's pending invocations and drop its inbox, returning
+    /// how many invocations were aborted
+    pub fn cancel_instance(&self, instance_id: &str) -> Result<usize> {
+        let aborted = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(instance_id)
+            .unwrap_or_default();
+        for handle in aborted.values() {
+            handle.abort();
+        }
+        self.repo.clear_instance(instance_id)?;
+        Ok(aborted.len())
+    }
+}
+
+fn forget_pending(pending: &PendingInvocations, instance_id: &str, correlation_id: &str) {
+    let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(invocations) = pending.get_mut(instance_id) {
+        invocations.remove(correlation_id);
+        if invocations.is_empty() {
+            pending.remove(instance_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::messaging_provider::repo::InMemoryMessagingProviderRepository;
+
+    async fn wait_for_result(
+        repo: &InMemoryMessagingProviderRepository,
+        instance_id: &str,
+        correlation_id: &str,
+    ) -> Value {
+        for _ in 0..100 {
+            let messages = repo
+                .take_messages(instance_id, &invoke_result_topic(correlation_id))
+                .unwrap();
+            if let Some(message) = messages.first() {
+                return serde_json::from_str(&message.payload).unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("async invocation result was never delivered");
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_delivers_success_and_failure() {
+        let repo = Arc::new(InMemoryMessagingProviderRepository::new());
+        let service = AsyncInvocationService::new(repo.clone());
+
+        let ok_id = service.dispatch("i-1", async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(serde_json::json!({"status": 200}))
+        });
+        let err_id = service.dispatch("i-1", async {
+            Err(CoreError::WasmRuntimeError("upstream down".to_string()))
+        });
+        assert_ne!(ok_id, err_id);
+
+        let ok = wait_for_result(&repo, "i-1", &ok_id).await;
+        assert_eq!(ok["success"].as_bool(), Some(true));
+        assert_eq!(ok["result"]["status"].as_u64(), Some(200));
+
+        let err = wait_for_result(&repo, "i-1", &err_id).await;
+        assert_eq!(err["success"].as_bool(), Some(false));
+        assert!(err["error"].as_str().unwrap().contains("upstream down"));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_times_out_slow_invocation() {
+        let repo = Arc::new(InMemoryMessagingProviderRepository::new());
+        let service =
+            AsyncInvocationService::new(repo.clone()).with_timeout(Duration::from_millis(20));
+
+        let id = service.dispatch("i-1", async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(serde_json::json!({}))
+        });
+
+        let result = wait_for_result(&repo, "i-1", &id).await;
+        assert_eq!(result["success"].as_bool(), Some(false));
+        assert!(result["error"].as_str().unwrap().contains("did not finish"));
+        assert_eq!(service.pending_count("i-1"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unpolled_result_expires() {
+        let repo = Arc::new(InMemoryMessagingProviderRepository::new());
+        let service =
+            AsyncInvocationService::new(repo.clone()).with_result_ttl(Duration::from_millis(20));
+
+        let id = service.dispatch("i-1", async { Ok(serde_json::json!({})) });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(repo
+            .take_messages("i-1", &invoke_result_topic(&id))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_instance_aborts_pending_and_clears_inbox() {
+        let repo = Arc::new(InMemoryMessagingProviderRepository::new());
+        let service = AsyncInvocationService::new(repo.clone());
+        repo.deliver("i-1", "orders", "created").unwrap();
+
+        let id = service.dispatch("i-1", async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(serde_json::json!({}))
+        });
+        service.dispatch("i-2", async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(serde_json::json!({}))
+        });
+        assert_eq!(service.pending_count("i-1"), 1);
+
+        assert_eq!(service.cancel_instance("i-1").unwrap(), 1);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(service.pending_count("i-1"), 0);
+        assert_eq!(service.pending_count("i-2"), 1);
+        assert!(repo.take_messages("i-1", "orders").unwrap().is_empty());
+        assert!(repo
+            .take_messages("i-1", &invoke_result_topic(&id))
+            .unwrap()
+            .is_empty());
+    }
+}
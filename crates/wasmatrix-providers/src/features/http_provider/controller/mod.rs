@@ -1,23 +1,41 @@
-use crate::features::http_provider::service::HttpProviderService;
+use crate::features::http_provider::service::{HttpProviderService, HttpProviderSettings};
+use crate::{deny_without_permissions, enforce_result_size, DEFAULT_MAX_RESULT_BYTES};
 use serde_json::Value;
 use std::collections::HashMap;
 use wasmatrix_core::{CapabilityAssignment, CoreError, Result};
 
 pub struct HttpProviderController {
     service: HttpProviderService,
+    max_result_bytes: usize,
 }
 
 impl HttpProviderController {
     pub fn new(service: HttpProviderService) -> Self {
-        Self { service }
+        Self {
+            service,
+            max_result_bytes: DEFAULT_MAX_RESULT_BYTES,
+        }
+    }
+
+    pub fn set_max_result_bytes(&mut self, max_result_bytes: usize) {
+        self.max_result_bytes = max_result_bytes;
+    }
+
+    pub fn set_injected_headers(&self, domain: &str, headers: HashMap<String, String>) {
+        self.service.set_injected_headers(domain, headers);
     }
 
-    pub fn handle_invoke(
+    pub fn replace_settings(&self, settings: HttpProviderSettings) {
+        self.service.replace_settings(settings);
+    }
+
+    pub async fn handle_invoke(
         &self,
-        instance_id: &str,
+        assignment: &CapabilityAssignment,
         operation: &str,
         params: Value,
     ) -> Result<Value> {
+        deny_without_permissions(assignment)?;
         if operation != "request" {
             return Err(CoreError::InvalidCapabilityAssignment(format!(
                 "Unknown HTTP operation: {operation}"
@@ -49,43 +67,38 @@ impl HttpProviderController {
 
         let body = params.get("body").cloned();
         let timeout_ms = params.get("timeout_ms").and_then(Value::as_u64);
-        let permissions = extract_permissions(&params);
-        let assignment = CapabilityAssignment::new(
-            instance_id.to_string(),
-            "http-provider".to_string(),
-            wasmatrix_core::ProviderType::Http,
-            permissions,
-        );
 
-        self.service
-            .execute_request(&assignment, method, url, headers, body, timeout_ms)
+        let result = self
+            .service
+            .execute_request(assignment, method, url, headers, body, timeout_ms)
+            .await?;
+        enforce_result_size(result, self.max_result_bytes)
     }
 }
 
-fn extract_permissions(params: &Value) -> Vec<String> {
-    params
-        .get("permissions")
-        .and_then(Value::as_array)
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                .collect()
-        })
-        .unwrap_or_default()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::features::http_provider::repo::HttpProviderRepository;
     use crate::features::http_provider::repo::{HttpRequest, HttpResponse};
     use crate::features::http_provider::service::HttpProviderService;
+    use async_trait::async_trait;
     use std::sync::Arc;
 
+    fn assignment(permissions: &[&str]) -> CapabilityAssignment {
+        CapabilityAssignment::new(
+            "i-1".to_string(),
+            "http-provider".to_string(),
+            wasmatrix_core::ProviderType::Http,
+            permissions.iter().map(|p| p.to_string()).collect(),
+        )
+    }
+
     struct DummyRepo;
 
+    #[async_trait]
     impl HttpProviderRepository for DummyRepo {
-        fn execute(&self, _request: &HttpRequest) -> Result<HttpResponse> {
+        async fn execute(&self, _request: &HttpRequest) -> Result<HttpResponse> {
             Ok(HttpResponse {
                 status: 200,
                 headers: HashMap::new(),
@@ -94,21 +107,86 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_handle_invoke_rejects_unknown_operation() {
+    #[tokio::test]
+    async fn test_handle_invoke_rejects_unknown_operation() {
         let controller = HttpProviderController::new(HttpProviderService::new(Arc::new(DummyRepo)));
-        let result = controller.handle_invoke("i-1", "unknown", serde_json::json!({}));
+        let result = controller
+            .handle_invoke(
+                &assignment(&["http:request"]),
+                "unknown",
+                serde_json::json!({}),
+            )
+            .await;
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_handle_invoke_rejects_missing_method() {
+    struct LargeBodyRepo;
+
+    #[async_trait]
+    impl HttpProviderRepository for LargeBodyRepo {
+        async fn execute(&self, _request: &HttpRequest) -> Result<HttpResponse> {
+            Ok(HttpResponse {
+                status: 200,
+                headers: HashMap::new(),
+                body: "x".repeat(4096),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_invoke_rejects_oversized_result() {
+        let mut controller =
+            HttpProviderController::new(HttpProviderService::new(Arc::new(LargeBodyRepo)));
+        controller.set_max_result_bytes(1024);
+        let params = serde_json::json!({
+            "method": "GET",
+            "url": "https://example.com"
+        });
+
+        let result = controller
+            .handle_invoke(&assignment(&["http:request"]), "request", params.clone())
+            .await;
+        assert!(matches!(result, Err(CoreError::ResourceExhausted(_))));
+
+        controller.set_max_result_bytes(8192);
+        assert!(controller
+            .handle_invoke(&assignment(&["http:request"]), "request", params)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handle_invoke_rejects_missing_method() {
         let controller = HttpProviderController::new(HttpProviderService::new(Arc::new(DummyRepo)));
         let params = serde_json::json!({
-            "url": "https://example.com",
-            "permissions": ["http:request"]
+            "url": "https://example.com"
         });
-        let result = controller.handle_invoke("i-1", "request", params);
+        let result = controller
+            .handle_invoke(&assignment(&["http:request"]), "request", params)
+            .await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_handle_invoke_without_permissions_is_denied_for_every_operation() {
+        let controller = HttpProviderController::new(HttpProviderService::new(Arc::new(DummyRepo)));
+
+        for operation in ["request", "unknown"] {
+            let result = controller
+                .handle_invoke(
+                    &assignment(&[]),
+                    operation,
+                    serde_json::json!({
+                        "method": "GET",
+                        "url": "https://example.com",
+                        "permissions": ["http:request"]
+                    }),
+                )
+                .await;
+            assert!(
+                matches!(&result, Err(CoreError::PermissionDenied(_))),
+                "{operation}: {result:?}"
+            );
+        }
+    }
 }
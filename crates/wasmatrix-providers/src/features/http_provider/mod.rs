@@ -2,12 +2,15 @@ pub mod controller;
 pub mod repo;
 pub mod service;
 
-use crate::{CapabilityProvider, ProviderMetadata};
+use crate::{operation_names, CapabilityProvider, ProviderMetadata};
 use controller::HttpProviderController;
-use repo::ReqwestHttpProviderRepository;
-use service::HttpProviderService;
+use repo::{HttpProviderRepository, ReqwestHttpProviderRepository};
+use service::{HttpProviderService, HttpProviderSettings};
 use std::sync::Arc;
-use wasmatrix_core::{ProviderType, Result};
+use wasmatrix_core::{CapabilityAssignment, CoreError, ProviderType, Result};
+
+/// Operations accepted by the HTTP provider
+const HTTP_OPERATIONS: &[&str] = &["request"];
 
 /// HTTP capability provider for outbound requests with permission checks.
 pub struct HttpCapabilityProvider {
@@ -17,34 +20,87 @@ pub struct HttpCapabilityProvider {
 
 impl HttpCapabilityProvider {
     pub fn new(provider_id: String) -> Result<Self> {
-        let repo = Arc::new(ReqwestHttpProviderRepository::new()?);
+        Ok(Self::with_repository(
+            provider_id,
+            Arc::new(ReqwestHttpProviderRepository::new()?),
+        ))
+    }
+
+    /// Create a provider over `repo`, e.g. a [`ReqwestHttpProviderRepository`]
+    /// with custom header limits
+    pub fn with_repository(provider_id: String, repo: Arc<dyn HttpProviderRepository>) -> Self {
         let service = HttpProviderService::new(repo);
         let controller = HttpProviderController::new(service);
 
-        Ok(Self {
+        Self {
             controller,
             metadata: ProviderMetadata {
                 provider_id,
                 provider_type: ProviderType::Http,
                 version: "0.1.0".to_string(),
+                operations: operation_names(HTTP_OPERATIONS),
             },
-        })
+        }
+    }
+
+    /// Limit the serialized size of invocation results
+    pub fn with_max_result_bytes(mut self, max_result_bytes: usize) -> Self {
+        self.controller.set_max_result_bytes(max_result_bytes);
+        self
+    }
+
+    /// Invoke without blocking the calling executor; prefer this from async code
+    pub async fn invoke_async(
+        &self,
+        assignment: &CapabilityAssignment,
+        operation: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.controller
+            .handle_invoke(assignment, operation, params)
+            .await
     }
 }
 
 impl CapabilityProvider for HttpCapabilityProvider {
-    fn initialize(&mut self, _config: serde_json::Value) -> Result<()> {
-        Ok(())
+    /// Accepts the settings described at [`HttpProviderSettings::from_config`]
+    fn initialize(&mut self, config: serde_json::Value) -> Result<()> {
+        self.reconfigure(config)
     }
 
     fn invoke(
         &self,
-        instance_id: &str,
+        assignment: &CapabilityAssignment,
         operation: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value> {
-        self.controller
-            .handle_invoke(instance_id, operation, params)
+        // Drive the request on a dedicated thread so this also works when the
+        // caller is already inside a tokio runtime.
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .map_err(|e| {
+                            CoreError::WasmRuntimeError(format!(
+                                "failed to build HTTP provider runtime: {e}"
+                            ))
+                        })?;
+                    runtime.block_on(self.invoke_async(assignment, operation, params))
+                })
+                .join()
+                .map_err(|_| {
+                    CoreError::WasmRuntimeError("HTTP provider invocation panicked".to_string())
+                })?
+        })
+    }
+
+    /// Replaces every setting, including injected headers, with those in `config`
+    fn reconfigure(&self, config: serde_json::Value) -> Result<()> {
+        let settings = HttpProviderSettings::from_config(&config)?;
+        self.controller.replace_settings(settings);
+        Ok(())
     }
 
     fn shutdown(&mut self) -> Result<()> {
@@ -54,12 +110,46 @@ impl CapabilityProvider for HttpCapabilityProvider {
     fn get_metadata(&self) -> ProviderMetadata {
         self.metadata.clone()
     }
+
+    fn supported_operations(&self) -> Vec<String> {
+        self.metadata.operations.clone()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wasmatrix_core::CoreError;
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn assignment(permissions: &[&str]) -> CapabilityAssignment {
+        CapabilityAssignment::new(
+            "inst-1".to_string(),
+            "http-provider".to_string(),
+            ProviderType::Http,
+            permissions.iter().map(|p| p.to_string()).collect(),
+        )
+    }
+
+    /// Serve one HTTP response after `delay`, returning the base URL
+    async fn spawn_stub_server(body: &'static str, delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            tokio::time::sleep(delay).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nx-stub: yes\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{addr}")
+    }
 
     #[test]
     fn test_http_provider_metadata() {
@@ -68,20 +158,153 @@ mod tests {
 
         assert_eq!(metadata.provider_id, "http-provider");
         assert_eq!(metadata.provider_type, ProviderType::Http);
+        assert_eq!(provider.supported_operations(), vec!["request"]);
+        assert_eq!(metadata.operations, provider.supported_operations());
     }
 
     #[test]
     fn test_http_provider_invoke_requires_permissions() {
         let provider = HttpCapabilityProvider::new("http-provider".to_string()).unwrap();
+        // Permissions in params are not an assignment and grant nothing
         let params = serde_json::json!({
             "method": "GET",
             "url": "https://example.com"
         });
 
-        let result = provider.invoke("inst-1", "request", params);
+        let result = provider.invoke(&assignment(&["kv:read"]), "request", params);
         assert!(matches!(
             result,
             Err(CoreError::InvalidCapabilityAssignment(_))
         ));
     }
+
+    #[test]
+    fn test_http_provider_initialize_validates_inject_headers() {
+        let mut provider = HttpCapabilityProvider::new("http-provider".to_string()).unwrap();
+
+        assert!(provider
+            .initialize(serde_json::json!({
+                "inject_headers": {"api.example.com": {"Authorization": "Bearer k"}}
+            }))
+            .is_ok());
+        assert!(provider
+            .initialize(serde_json::json!({
+                "inject_headers": {"api.example.com": {"Authorization": 1}}
+            }))
+            .is_err());
+        assert!(provider.initialize(serde_json::json!({})).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_reconfigure_applies_new_timeout() {
+        let provider = HttpCapabilityProvider::new("http-provider".to_string()).unwrap();
+        let request = |url: String| serde_json::json!({"method": "GET", "url": url});
+
+        provider
+            .reconfigure(serde_json::json!({"timeout_ms": 50}))
+            .unwrap();
+        let url = spawn_stub_server("slow", Duration::from_millis(300)).await;
+        let result = provider
+            .invoke_async(&assignment(&["http:request"]), "request", request(url))
+            .await;
+        assert!(result.is_err(), "{result:?}");
+
+        provider
+            .reconfigure(serde_json::json!({"timeout_ms": 5_000}))
+            .unwrap();
+        let url = spawn_stub_server("slow", Duration::from_millis(300)).await;
+        let result = provider
+            .invoke_async(&assignment(&["http:request"]), "request", request(url))
+            .await
+            .unwrap();
+        assert_eq!(result["body"].as_str(), Some("slow"));
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_invalid_reconfigure_keeps_settings() {
+        let provider = HttpCapabilityProvider::new("http-provider".to_string()).unwrap();
+        provider
+            .reconfigure(serde_json::json!({"allowed_domains": ["127.0.0.1"]}))
+            .unwrap();
+
+        assert!(provider
+            .reconfigure(serde_json::json!({
+                "allowed_domains": ["example.com"],
+                "proxy": 3128
+            }))
+            .is_err());
+        let url = spawn_stub_server("kept", Duration::ZERO).await;
+        let result = provider
+            .invoke_async(
+                &assignment(&["http:request"]),
+                "request",
+                serde_json::json!({"method": "GET", "url": url}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result["body"].as_str(), Some("kept"));
+
+        let denied = provider
+            .invoke_async(
+                &assignment(&["http:request"]),
+                "request",
+                serde_json::json!({"method": "GET", "url": "http://localhost:9/"}),
+            )
+            .await;
+        assert!(
+            matches!(&denied, Err(CoreError::InvalidCapabilityAssignment(msg)) if msg.contains("not an allowed domain")),
+            "{denied:?}"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_http_provider_invoke_async_does_not_block_executor() {
+        let url = spawn_stub_server("hello", Duration::from_millis(200)).await;
+        let provider = HttpCapabilityProvider::new("http-provider".to_string()).unwrap();
+        let params = serde_json::json!({
+            "method": "GET",
+            "url": format!("{url}/greeting")
+        });
+
+        // On a single-threaded runtime the ticker only advances if the
+        // request yields while waiting for the stub server.
+        let ticker = tokio::spawn(async {
+            let mut ticks = 0u32;
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_millis(150) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                ticks += 1;
+            }
+            ticks
+        });
+
+        let result = provider
+            .invoke_async(&assignment(&["http:request"]), "request", params)
+            .await
+            .unwrap();
+
+        assert_eq!(result["status"].as_u64(), Some(200));
+        assert_eq!(result["body"].as_str(), Some("hello"));
+        assert_eq!(result["headers"]["x-stub"].as_str(), Some("yes"));
+        assert!(ticker.await.unwrap() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_sync_invoke_works_inside_runtime() {
+        let url = spawn_stub_server("sync", Duration::ZERO).await;
+        let provider = HttpCapabilityProvider::new("http-provider".to_string()).unwrap();
+        let params = serde_json::json!({
+            "method": "GET",
+            "url": url
+        });
+
+        let result = tokio::task::spawn_blocking(move || {
+            provider.invoke(&assignment(&["http:request"]), "request", params)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(result["body"].as_str(), Some("sync"));
+    }
 }
@@ -1,7 +1,9 @@
-use reqwest::blocking::Client;
+use async_trait::async_trait;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::Duration;
 use wasmatrix_core::{CoreError, Result};
 
@@ -12,6 +14,8 @@ pub struct HttpRequest {
     pub headers: HashMap<String, String>,
     pub body: Option<Value>,
     pub timeout_ms: Option<u64>,
+    /// Proxy URL to send the request through
+    pub proxy: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -21,12 +25,22 @@ pub struct HttpResponse {
     pub body: String,
 }
 
+#[async_trait]
 pub trait HttpProviderRepository: Send + Sync {
-    fn execute(&self, request: &HttpRequest) -> Result<HttpResponse>;
+    async fn execute(&self, request: &HttpRequest) -> Result<HttpResponse>;
 }
 
+/// Default cap on the number of headers in one outbound request
+pub const DEFAULT_MAX_REQUEST_HEADERS: usize = 64;
+/// Default cap on the summed name and value bytes of one request's headers (16 KiB)
+pub const DEFAULT_MAX_REQUEST_HEADER_BYTES: usize = 16 * 1024;
+
 pub struct ReqwestHttpProviderRepository {
     client: Client,
+    /// Client for the last proxy requests asked for, reused while it is unchanged
+    proxied: Mutex<Option<(String, Client)>>,
+    max_headers: usize,
+    max_header_bytes: usize,
 }
 
 impl ReqwestHttpProviderRepository {
@@ -34,15 +48,67 @@ impl ReqwestHttpProviderRepository {
         let client = Client::builder().build().map_err(|e| {
             CoreError::WasmRuntimeError(format!("failed to build http client: {e}"))
         })?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            proxied: Mutex::new(None),
+            max_headers: DEFAULT_MAX_REQUEST_HEADERS,
+            max_header_bytes: DEFAULT_MAX_REQUEST_HEADER_BYTES,
+        })
+    }
+
+    /// Limit how many headers a request may carry and their combined size
+    pub fn with_header_limits(mut self, max_headers: usize, max_header_bytes: usize) -> Self {
+        self.max_headers = max_headers;
+        self.max_header_bytes = max_header_bytes;
+        self
+    }
+
+    fn client_for(&self, proxy: Option<&str>) -> Result<Client> {
+        let Some(proxy) = proxy else {
+            return Ok(self.client.clone());
+        };
+        let mut proxied = self.proxied.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, client)) = proxied.as_ref().filter(|(url, _)| url == proxy) {
+            return Ok(client.clone());
+        }
+        let client = reqwest::Proxy::all(proxy)
+            .and_then(|proxy| Client::builder().proxy(proxy).build())
+            .map_err(|e| {
+                CoreError::WasmRuntimeError(format!("failed to build proxied http client: {e}"))
+            })?;
+        *proxied = Some((proxy.to_string(), client.clone()));
+        Ok(client)
+    }
+
+    fn check_header_limits(&self, headers: &HashMap<String, String>) -> Result<()> {
+        if headers.len() > self.max_headers {
+            return Err(CoreError::ResourceExhausted(format!(
+                "Request has {} headers, exceeding limit of {}",
+                headers.len(),
+                self.max_headers
+            )));
+        }
+        let bytes: usize = headers
+            .iter()
+            .map(|(name, value)| name.len() + value.len())
+            .sum();
+        if bytes > self.max_header_bytes {
+            return Err(CoreError::ResourceExhausted(format!(
+                "Request headers total {bytes} bytes, exceeding limit of {} bytes",
+                self.max_header_bytes
+            )));
+        }
+        Ok(())
     }
 }
 
+#[async_trait]
 impl HttpProviderRepository for ReqwestHttpProviderRepository {
-    fn execute(&self, request: &HttpRequest) -> Result<HttpResponse> {
+    async fn execute(&self, request: &HttpRequest) -> Result<HttpResponse> {
         let method = reqwest::Method::from_bytes(request.method.as_bytes()).map_err(|e| {
             CoreError::InvalidCapabilityAssignment(format!("invalid HTTP method: {e}"))
         })?;
+        self.check_header_limits(&request.headers)?;
 
         let mut headers = HeaderMap::new();
         for (key, value) in &request.headers {
@@ -57,7 +123,10 @@ impl HttpProviderRepository for ReqwestHttpProviderRepository {
             headers.insert(key, value);
         }
 
-        let mut builder = self.client.request(method, &request.url).headers(headers);
+        let mut builder = self
+            .client_for(request.proxy.as_deref())?
+            .request(method, &request.url)
+            .headers(headers);
         if let Some(timeout) = request.timeout_ms {
             builder = builder.timeout(Duration::from_millis(timeout));
         }
@@ -65,7 +134,7 @@ impl HttpProviderRepository for ReqwestHttpProviderRepository {
             builder = builder.json(body);
         }
 
-        let response = builder.send().map_err(|e| {
+        let response = builder.send().await.map_err(|e| {
             CoreError::WasmRuntimeError(format!("failed to execute HTTP request: {e}"))
         })?;
         let status = response.status().as_u16();
@@ -75,7 +144,7 @@ impl HttpProviderRepository for ReqwestHttpProviderRepository {
             response_headers.insert(k.to_string(), v.to_str().unwrap_or_default().to_string());
         }
 
-        let body = response.text().map_err(|e| {
+        let body = response.text().await.map_err(|e| {
             CoreError::SerializationError(format!("failed to read HTTP response body: {e}"))
         })?;
 
@@ -90,9 +159,12 @@ impl HttpProviderRepository for ReqwestHttpProviderRepository {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-    #[test]
-    fn test_repo_execute_rejects_invalid_http_method_before_send() {
+    #[tokio::test]
+    async fn test_repo_execute_rejects_invalid_http_method_before_send() {
         let repo = ReqwestHttpProviderRepository::new().unwrap();
         let req = HttpRequest {
             method: "???".to_string(),
@@ -100,9 +172,70 @@ mod tests {
             headers: HashMap::new(),
             body: None,
             timeout_ms: Some(1_000),
+            proxy: None,
         };
 
-        let err = repo.execute(&req).unwrap_err();
+        let err = repo.execute(&req).await.unwrap_err();
         assert!(matches!(err, CoreError::InvalidCapabilityAssignment(_)));
     }
+
+    /// Local server answering every request with an empty 200, counting the
+    /// requests that reach it
+    async fn counting_server() -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let served = Arc::new(AtomicUsize::new(0));
+        let counter = served.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => head.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+        (url, served)
+    }
+
+    #[tokio::test]
+    async fn test_repo_execute_rejects_oversized_headers_before_send() {
+        let (url, served) = counting_server().await;
+        let repo = ReqwestHttpProviderRepository::new()
+            .unwrap()
+            .with_header_limits(4, 64);
+        let request = |headers: HashMap<String, String>| HttpRequest {
+            method: "GET".to_string(),
+            url: url.clone(),
+            headers,
+            body: None,
+            timeout_ms: Some(1_000),
+            proxy: None,
+        };
+
+        let too_many = (0..5)
+            .map(|i| (format!("x-h{i}"), "v".to_string()))
+            .collect();
+        let err = repo.execute(&request(too_many)).await.unwrap_err();
+        assert!(matches!(err, CoreError::ResourceExhausted(_)), "{err:?}");
+
+        let too_large = HashMap::from([("x-big".to_string(), "v".repeat(64))]);
+        let err = repo.execute(&request(too_large)).await.unwrap_err();
+        assert!(matches!(err, CoreError::ResourceExhausted(_)), "{err:?}");
+        assert_eq!(served.load(Ordering::SeqCst), 0);
+
+        let within = (0..4)
+            .map(|i| (format!("x-h{i}"), "v".to_string()))
+            .collect();
+        let response = repo.execute(&request(within)).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+    }
 }
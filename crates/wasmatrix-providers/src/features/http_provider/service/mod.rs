@@ -1,19 +1,138 @@
 use crate::features::http_provider::repo::{HttpProviderRepository, HttpRequest};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 use wasmatrix_core::{CapabilityAssignment, CoreError, Result};
 
+/// Permission that lets an assignment's requests carry provider-injected headers
+pub const HTTP_INJECT_PERMISSION: &str = "http:inject";
+
+/// Provider-wide settings every request is made with
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpProviderSettings {
+    /// Timeout for requests that do not set `timeout_ms` themselves
+    pub timeout_ms: Option<u64>,
+    /// Proxy URL all outbound requests go through
+    pub proxy: Option<String>,
+    /// Lowercase hosts requests may reach; `None` allows any host
+    pub allowed_domains: Option<HashSet<String>>,
+    /// Headers added to requests per destination host, keyed by lowercase host
+    pub injected_headers: HashMap<String, HashMap<String, String>>,
+}
+
+impl HttpProviderSettings {
+    /// Parse `{"timeout_ms": 5000, "proxy": "http://proxy:3128",
+    /// "allowed_domains": ["api.example.com"],
+    /// "inject_headers": {"<domain>": {"<header>": "<value>"}}}`; every key is optional
+    pub fn from_config(config: &Value) -> Result<Self> {
+        let invalid = |message: String| CoreError::InvalidCapabilityAssignment(message);
+        let mut settings = Self::default();
+
+        if let Some(timeout_ms) = config.get("timeout_ms") {
+            settings.timeout_ms = Some(
+                timeout_ms
+                    .as_u64()
+                    .filter(|ms| *ms > 0)
+                    .ok_or_else(|| invalid("'timeout_ms' must be a positive integer".into()))?,
+            );
+        }
+
+        if let Some(proxy) = config.get("proxy") {
+            let proxy = proxy
+                .as_str()
+                .ok_or_else(|| invalid("'proxy' must be a string".into()))?;
+            reqwest::Proxy::all(proxy)
+                .map_err(|e| invalid(format!("invalid proxy URL '{proxy}': {e}")))?;
+            settings.proxy = Some(proxy.to_string());
+        }
+
+        if let Some(domains) = config.get("allowed_domains") {
+            let domains = domains
+                .as_array()
+                .ok_or_else(|| invalid("'allowed_domains' must be an array".into()))?;
+            settings.allowed_domains = Some(
+                domains
+                    .iter()
+                    .map(|domain| {
+                        domain
+                            .as_str()
+                            .map(str::to_ascii_lowercase)
+                            .ok_or_else(|| invalid("allowed domains must be strings".into()))
+                    })
+                    .collect::<Result<_>>()?,
+            );
+        }
+
+        if let Some(domains) = config.get("inject_headers") {
+            let domains = domains
+                .as_object()
+                .ok_or_else(|| invalid("'inject_headers' must be an object".into()))?;
+            for (domain, headers) in domains {
+                let headers = headers
+                    .as_object()
+                    .ok_or_else(|| {
+                        invalid(format!("Injected headers for '{domain}' must be an object"))
+                    })?
+                    .iter()
+                    .map(|(name, value)| {
+                        value
+                            .as_str()
+                            .map(|value| (name.clone(), value.to_string()))
+                            .ok_or_else(|| {
+                                invalid(format!(
+                                    "Injected header '{name}' for '{domain}' must be a string"
+                                ))
+                            })
+                    })
+                    .collect::<Result<HashMap<_, _>>>()?;
+                settings
+                    .injected_headers
+                    .insert(domain.to_ascii_lowercase(), headers);
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
 pub struct HttpProviderService {
     repo: Arc<dyn HttpProviderRepository>,
+    /// Swapped whole on reconfiguration; each request works from one snapshot
+    settings: RwLock<Arc<HttpProviderSettings>>,
 }
 
 impl HttpProviderService {
     pub fn new(repo: Arc<dyn HttpProviderRepository>) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            settings: RwLock::new(Arc::new(HttpProviderSettings::default())),
+        }
+    }
+
+    /// Settings new requests are made with
+    pub fn settings(&self) -> Arc<HttpProviderSettings> {
+        self.settings
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the settings; requests already running keep their snapshot
+    pub fn replace_settings(&self, settings: HttpProviderSettings) {
+        *self.settings.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(settings);
+    }
+
+    /// Inject `headers` into requests to `domain` made by assignments holding
+    /// `http:inject`. Injected headers override module-supplied ones and are
+    /// stripped from the returned response headers.
+    pub fn set_injected_headers(&self, domain: &str, headers: HashMap<String, String>) {
+        let mut settings = self.settings.write().unwrap_or_else(|e| e.into_inner());
+        Arc::make_mut(&mut settings)
+            .injected_headers
+            .insert(domain.to_ascii_lowercase(), headers);
     }
 
-    pub fn execute_request(
+    pub async fn execute_request(
         &self,
         assignment: &CapabilityAssignment,
         method: &str,
@@ -22,16 +141,41 @@ impl HttpProviderService {
         body: Option<Value>,
         timeout_ms: Option<u64>,
     ) -> Result<Value> {
-        self.validate_permission(assignment, url)?;
+        let settings = self.settings();
+        let host = self.validate_permission(assignment, url)?;
+        if settings
+            .allowed_domains
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(&host.to_ascii_lowercase()))
+        {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Permission denied: '{host}' is not an allowed domain"
+            )));
+        }
+
+        let injected = settings
+            .injected_headers
+            .get(&host.to_ascii_lowercase())
+            .filter(|_| assignment.has_permission(HTTP_INJECT_PERMISSION));
+        let mut headers = headers;
+        if let Some(injected) = injected {
+            headers.retain(|name, _| !contains_header(injected, name));
+            headers.extend(injected.clone());
+        }
 
         let req = HttpRequest {
             method: method.to_string(),
             url: url.to_string(),
             headers,
             body,
-            timeout_ms,
+            timeout_ms: timeout_ms.or(settings.timeout_ms),
+            proxy: settings.proxy.clone(),
         };
-        let res = self.repo.execute(&req)?;
+        let mut res = self.repo.execute(&req).await?;
+        if let Some(injected) = injected {
+            res.headers
+                .retain(|name, _| !contains_header(injected, name));
+        }
 
         Ok(serde_json::json!({
             "status": res.status,
@@ -40,7 +184,8 @@ impl HttpProviderService {
         }))
     }
 
-    fn validate_permission(&self, assignment: &CapabilityAssignment, url: &str) -> Result<()> {
+    /// Check request permissions and return the destination host
+    fn validate_permission(&self, assignment: &CapabilityAssignment, url: &str) -> Result<String> {
         if !assignment.has_permission("http:request") {
             return Err(CoreError::InvalidCapabilityAssignment(
                 "Permission denied: missing 'http:request' permission".to_string(),
@@ -58,7 +203,7 @@ impl HttpProviderService {
 
         let domain_permission = format!("http:domain:{host}");
         if assignment
-            .permissions
+            .permissions()
             .iter()
             .any(|p| p.starts_with("http:domain:"))
             && !assignment.has_permission(&domain_permission)
@@ -68,14 +213,20 @@ impl HttpProviderService {
             )));
         }
 
-        Ok(())
+        Ok(host)
     }
 }
 
+/// Header names are case-insensitive
+fn contains_header(headers: &HashMap<String, String>, name: &str) -> bool {
+    headers.keys().any(|key| key.eq_ignore_ascii_case(name))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::features::http_provider::repo::{HttpProviderRepository, HttpResponse};
+    use async_trait::async_trait;
     use std::sync::RwLock;
     use wasmatrix_core::ProviderType;
 
@@ -84,8 +235,9 @@ mod tests {
         body: String,
     }
 
+    #[async_trait]
     impl HttpProviderRepository for StubRepo {
-        fn execute(&self, _request: &HttpRequest) -> Result<HttpResponse> {
+        async fn execute(&self, _request: &HttpRequest) -> Result<HttpResponse> {
             Ok(HttpResponse {
                 status: self.status,
                 headers: HashMap::new(),
@@ -106,8 +258,9 @@ mod tests {
         }
     }
 
+    #[async_trait]
     impl HttpProviderRepository for RecordingRepo {
-        fn execute(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        async fn execute(&self, request: &HttpRequest) -> Result<HttpResponse> {
             if let Ok(mut slot) = self.last_request.write() {
                 *slot = Some(request.clone());
             }
@@ -128,48 +281,52 @@ mod tests {
         )
     }
 
-    #[test]
-    fn test_validate_permission_requires_http_request() {
+    #[tokio::test]
+    async fn test_validate_permission_requires_http_request() {
         let service = HttpProviderService::new(Arc::new(StubRepo {
             status: 200,
             body: "ok".to_string(),
         }));
         let assignment = assignment(vec!["http:domain:example.com"]);
 
-        let result = service.execute_request(
-            &assignment,
-            "GET",
-            "https://example.com/path",
-            HashMap::new(),
-            None,
-            None,
-        );
+        let result = service
+            .execute_request(
+                &assignment,
+                "GET",
+                "https://example.com/path",
+                HashMap::new(),
+                None,
+                None,
+            )
+            .await;
 
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_validate_permission_requires_matching_domain_when_domain_scoped() {
+    #[tokio::test]
+    async fn test_validate_permission_requires_matching_domain_when_domain_scoped() {
         let service = HttpProviderService::new(Arc::new(StubRepo {
             status: 200,
             body: "ok".to_string(),
         }));
         let assignment = assignment(vec!["http:request", "http:domain:example.com"]);
 
-        let result = service.execute_request(
-            &assignment,
-            "GET",
-            "https://another.example.org/path",
-            HashMap::new(),
-            None,
-            None,
-        );
+        let result = service
+            .execute_request(
+                &assignment,
+                "GET",
+                "https://another.example.org/path",
+                HashMap::new(),
+                None,
+                None,
+            )
+            .await;
 
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_execute_request_success() {
+    #[tokio::test]
+    async fn test_execute_request_success() {
         let service = HttpProviderService::new(Arc::new(StubRepo {
             status: 201,
             body: "created".to_string(),
@@ -185,34 +342,37 @@ mod tests {
                 Some(serde_json::json!({"name":"item1"})),
                 Some(1_000),
             )
+            .await
             .unwrap();
 
         assert_eq!(result["status"].as_u64(), Some(201));
         assert_eq!(result["body"].as_str(), Some("created"));
     }
 
-    #[test]
-    fn test_execute_request_allows_generic_http_request_without_domain_scope() {
+    #[tokio::test]
+    async fn test_execute_request_allows_generic_http_request_without_domain_scope() {
         let service = HttpProviderService::new(Arc::new(StubRepo {
             status: 200,
             body: "ok".to_string(),
         }));
         let assignment = assignment(vec!["http:request"]);
 
-        let result = service.execute_request(
-            &assignment,
-            "GET",
-            "https://any-host.example/path",
-            HashMap::new(),
-            None,
-            None,
-        );
+        let result = service
+            .execute_request(
+                &assignment,
+                "GET",
+                "https://any-host.example/path",
+                HashMap::new(),
+                None,
+                None,
+            )
+            .await;
 
         assert!(result.is_ok());
     }
 
-    #[test]
-    fn test_execute_request_forwards_http_method() {
+    #[tokio::test]
+    async fn test_execute_request_forwards_http_method() {
         let repo = Arc::new(RecordingRepo::new());
         let service = HttpProviderService::new(repo.clone());
         let assignment = assignment(vec!["http:request"]);
@@ -226,6 +386,7 @@ mod tests {
                 None,
                 None,
             )
+            .await
             .unwrap();
 
         let method = repo
@@ -237,4 +398,110 @@ mod tests {
             .unwrap_or_default();
         assert_eq!(method, "DELETE");
     }
+
+    struct EchoRepo {
+        last_request: RwLock<Option<HttpRequest>>,
+    }
+
+    #[async_trait]
+    impl HttpProviderRepository for EchoRepo {
+        async fn execute(&self, request: &HttpRequest) -> Result<HttpResponse> {
+            if let Ok(mut slot) = self.last_request.write() {
+                *slot = Some(request.clone());
+            }
+            Ok(HttpResponse {
+                status: 200,
+                headers: request.headers.clone(),
+                body: "ok".to_string(),
+            })
+        }
+    }
+
+    fn injecting_service() -> (Arc<EchoRepo>, HttpProviderService) {
+        let repo = Arc::new(EchoRepo {
+            last_request: RwLock::new(None),
+        });
+        let service = HttpProviderService::new(repo.clone());
+        service.set_injected_headers(
+            "api.example.com",
+            HashMap::from([("Authorization".to_string(), "Bearer secret".to_string())]),
+        );
+        (repo, service)
+    }
+
+    fn sent_headers(repo: &EchoRepo) -> HashMap<String, String> {
+        repo.last_request
+            .read()
+            .ok()
+            .and_then(|g| g.clone())
+            .map(|r| r.headers)
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_injected_header_reaches_repo_and_is_not_echoed() {
+        let (repo, service) = injecting_service();
+        let assignment = assignment(vec!["http:request", "http:inject"]);
+        let module_headers = HashMap::from([
+            ("authorization".to_string(), "Bearer module".to_string()),
+            ("x-trace".to_string(), "abc".to_string()),
+        ]);
+
+        let result = service
+            .execute_request(
+                &assignment,
+                "GET",
+                "https://api.example.com/v1/items",
+                module_headers,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let sent = sent_headers(&repo);
+        assert_eq!(
+            sent.get("Authorization").map(String::as_str),
+            Some("Bearer secret")
+        );
+        assert!(!sent.contains_key("authorization"));
+        assert_eq!(sent.get("x-trace").map(String::as_str), Some("abc"));
+
+        let echoed = result["headers"].as_object().unwrap();
+        assert!(echoed
+            .keys()
+            .all(|k| !k.eq_ignore_ascii_case("authorization")));
+        assert_eq!(echoed["x-trace"].as_str(), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_headers_not_injected_without_permission_or_for_other_domains() {
+        let (repo, service) = injecting_service();
+
+        service
+            .execute_request(
+                &assignment(vec!["http:request"]),
+                "GET",
+                "https://api.example.com/v1/items",
+                HashMap::new(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(sent_headers(&repo).is_empty());
+
+        service
+            .execute_request(
+                &assignment(vec!["http:request", "http:inject"]),
+                "GET",
+                "https://other.example.com/",
+                HashMap::new(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(sent_headers(&repo).is_empty());
+    }
 }
@@ -0,0 +1,179 @@
+use crate::{deny_without_permissions, operation_names, CapabilityProvider, ProviderMetadata};
+use serde_json::Value;
+use wasmatrix_core::capability::PermissionEnforcer;
+use wasmatrix_core::{CapabilityAssignment, CoreError, InstanceMetadata, ProviderType, Result};
+
+/// Operations accepted by the introspection provider
+const INTROSPECTION_OPERATIONS: &[&str] = &["read"];
+
+/// Lets an instance read its own metadata instead of hardcoding its identity.
+///
+/// The agent builds one per invocation from the running instance it knows
+/// about; `read` requires the `self:read` permission.
+pub struct IntrospectionProvider {
+    instance: InstanceMetadata,
+    metadata: ProviderMetadata,
+}
+
+impl IntrospectionProvider {
+    pub fn new(provider_id: String, instance: InstanceMetadata) -> Self {
+        Self {
+            instance,
+            metadata: ProviderMetadata {
+                provider_id,
+                provider_type: ProviderType::Introspection,
+                version: "0.1.0".to_string(),
+                operations: operation_names(INTROSPECTION_OPERATIONS),
+            },
+        }
+    }
+
+    /// Fields an instance may see about itself; module bytes, capabilities and
+    /// configuration are deliberately left out
+    fn self_view(&self) -> Value {
+        serde_json::json!({
+            "instance_id": self.instance.instance_id,
+            "node_id": self.instance.node_id,
+            "module_hash": self.instance.module_hash,
+            "created_at": self.instance.created_at.to_rfc3339(),
+            "status": self.instance.status.as_str(),
+            "deployment_id": self.instance.deployment_id,
+        })
+    }
+}
+
+impl CapabilityProvider for IntrospectionProvider {
+    fn initialize(&mut self, _config: Value) -> Result<()> {
+        Ok(())
+    }
+
+    fn invoke(
+        &self,
+        assignment: &CapabilityAssignment,
+        operation: &str,
+        _params: Value,
+    ) -> Result<Value> {
+        deny_without_permissions(assignment)?;
+        if assignment.instance_id != self.instance.instance_id {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Instance '{}' cannot read metadata of instance '{}'",
+                assignment.instance_id, self.instance.instance_id
+            )));
+        }
+
+        let required =
+            PermissionEnforcer::introspection_permission(operation).ok_or_else(|| {
+                CoreError::InvalidCapabilityAssignment(format!(
+                    "Unknown introspection operation: {operation}"
+                ))
+            })?;
+        if !assignment.has_permission(required) {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Permission denied: missing '{}' permission",
+                required
+            )));
+        }
+
+        Ok(self.self_view())
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.metadata.clone()
+    }
+
+    fn supported_operations(&self) -> Vec<String> {
+        self.metadata.operations.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider() -> IntrospectionProvider {
+        let mut instance = InstanceMetadata::new("node-7".to_string(), "abc123".to_string());
+        instance.instance_id = "inst-1".to_string();
+        IntrospectionProvider::new("introspection".to_string(), instance)
+    }
+
+    fn assignment(instance_id: &str, permissions: &[&str]) -> CapabilityAssignment {
+        CapabilityAssignment::new(
+            instance_id.to_string(),
+            "introspection".to_string(),
+            ProviderType::Introspection,
+            permissions.iter().map(|p| p.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_introspection_provider_operations() {
+        let provider = provider();
+
+        assert_eq!(provider.supported_operations(), vec!["read"]);
+        assert_eq!(
+            provider.get_metadata().operations,
+            provider.supported_operations()
+        );
+    }
+
+    #[test]
+    fn test_read_returns_own_identity() {
+        let result = provider()
+            .invoke(
+                &assignment("inst-1", &["self:read"]),
+                "read",
+                serde_json::json!({}),
+            )
+            .unwrap();
+
+        assert_eq!(result["instance_id"], "inst-1");
+        assert_eq!(result["node_id"], "node-7");
+        assert_eq!(result["module_hash"], "abc123");
+    }
+
+    #[test]
+    fn test_read_requires_assigned_permission_and_own_instance() {
+        let provider = provider();
+
+        let denied = provider.invoke(
+            &assignment("inst-1", &["kv:read"]),
+            "read",
+            serde_json::json!({ "permissions": ["self:read"] }),
+        );
+        assert!(matches!(
+            denied,
+            Err(CoreError::InvalidCapabilityAssignment(_))
+        ));
+
+        let other = provider.invoke(
+            &assignment("inst-2", &["self:read"]),
+            "read",
+            serde_json::json!({}),
+        );
+        assert!(matches!(
+            other,
+            Err(CoreError::InvalidCapabilityAssignment(_))
+        ));
+    }
+
+    #[test]
+    fn test_invoke_without_permissions_is_denied_for_every_operation() {
+        let provider = provider();
+
+        for operation in ["read", "unknown"] {
+            let result = provider.invoke(
+                &assignment("inst-1", &[]),
+                operation,
+                serde_json::json!({ "permissions": ["self:read"] }),
+            );
+            assert!(
+                matches!(&result, Err(CoreError::PermissionDenied(_))),
+                "{operation}: {result:?}"
+            );
+        }
+    }
+}
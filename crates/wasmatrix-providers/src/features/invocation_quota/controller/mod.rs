@@ -0,0 +1,33 @@
+use crate::features::invocation_quota::service::InvocationQuotaService;
+use wasmatrix_core::{ProviderType, Result};
+
+pub struct InvocationQuotaController {
+    service: InvocationQuotaService,
+}
+
+impl InvocationQuotaController {
+    pub fn new(service: InvocationQuotaService) -> Self {
+        Self { service }
+    }
+
+    pub fn set_quota(&self, provider_type: ProviderType, max_invocations: u64) -> Result<()> {
+        self.service.set_quota(provider_type, max_invocations)
+    }
+
+    pub fn clear_quota(&self, provider_type: ProviderType) -> Result<()> {
+        self.service.clear_quota(provider_type)
+    }
+
+    pub fn record_invocation(&self, instance_id: &str, provider_type: ProviderType) -> Result<()> {
+        self.service.record_invocation(instance_id, provider_type)
+    }
+
+    pub fn invocation_count(&self, instance_id: &str, provider_type: ProviderType) -> Result<u64> {
+        self.service.invocation_count(instance_id, provider_type)
+    }
+
+    /// Admin operation: forget all invocation counters for an instance
+    pub fn reset_quota(&self, instance_id: &str) -> Result<()> {
+        self.service.reset_quota(instance_id)
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use wasmatrix_core::{CoreError, ProviderType, Result};
+
+pub trait InvocationQuotaRepository: Send + Sync {
+    /// Increment the counter unless it already reached `limit`.
+    /// Returns the new count, or `None` when the limit was already reached.
+    fn increment_within(
+        &self,
+        instance_id: &str,
+        provider_type: ProviderType,
+        limit: u64,
+    ) -> Result<Option<u64>>;
+    fn get_count(&self, instance_id: &str, provider_type: ProviderType) -> Result<u64>;
+    fn clear_instance(&self, instance_id: &str) -> Result<()>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryInvocationQuotaRepository {
+    counters: Arc<RwLock<HashMap<(String, ProviderType), u64>>>,
+}
+
+impl InMemoryInvocationQuotaRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn lock_poisoned() -> CoreError {
+    CoreError::InvalidCapabilityAssignment("invocation quota lock poisoned".to_string())
+}
+
+impl InvocationQuotaRepository for InMemoryInvocationQuotaRepository {
+    fn increment_within(
+        &self,
+        instance_id: &str,
+        provider_type: ProviderType,
+        limit: u64,
+    ) -> Result<Option<u64>> {
+        let mut counters = self.counters.write().map_err(|_| lock_poisoned())?;
+        let count = counters
+            .entry((instance_id.to_string(), provider_type))
+            .or_insert(0);
+        if *count >= limit {
+            return Ok(None);
+        }
+        *count += 1;
+        Ok(Some(*count))
+    }
+
+    fn get_count(&self, instance_id: &str, provider_type: ProviderType) -> Result<u64> {
+        let counters = self.counters.read().map_err(|_| lock_poisoned())?;
+        Ok(counters
+            .get(&(instance_id.to_string(), provider_type))
+            .copied()
+            .unwrap_or(0))
+    }
+
+    fn clear_instance(&self, instance_id: &str) -> Result<()> {
+        let mut counters = self.counters.write().map_err(|_| lock_poisoned())?;
+        counters.retain(|(id, _), _| id != instance_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_increment_stops_at_limit_and_clears() {
+        let repo = InMemoryInvocationQuotaRepository::new();
+        assert_eq!(
+            repo.increment_within("instance-1", ProviderType::Http, 1)
+                .unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            repo.increment_within("instance-1", ProviderType::Http, 1)
+                .unwrap(),
+            None
+        );
+        assert_eq!(repo.get_count("instance-1", ProviderType::Http).unwrap(), 1);
+        assert_eq!(repo.get_count("instance-1", ProviderType::Kv).unwrap(), 0);
+
+        repo.clear_instance("instance-1").unwrap();
+        assert_eq!(repo.get_count("instance-1", ProviderType::Http).unwrap(), 0);
+    }
+}
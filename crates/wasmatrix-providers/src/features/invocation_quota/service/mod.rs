@@ -0,0 +1,154 @@
+use crate::features::invocation_quota::repo::InvocationQuotaRepository;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use wasmatrix_core::{CoreError, ProviderType, Result};
+
+/// Counts capability invocations per `(instance_id, provider_type)` and
+/// rejects attempts beyond the configured quota for that provider type.
+/// Provider types without a quota are unlimited but still counted.
+pub struct InvocationQuotaService {
+    repo: Arc<dyn InvocationQuotaRepository>,
+    quotas: RwLock<HashMap<ProviderType, u64>>,
+}
+
+impl InvocationQuotaService {
+    pub fn new(repo: Arc<dyn InvocationQuotaRepository>) -> Self {
+        Self {
+            repo,
+            quotas: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_quota(&self, provider_type: ProviderType, max_invocations: u64) -> Result<()> {
+        self.quotas
+            .write()
+            .map_err(|_| quota_lock_poisoned())?
+            .insert(provider_type, max_invocations);
+        Ok(())
+    }
+
+    pub fn clear_quota(&self, provider_type: ProviderType) -> Result<()> {
+        self.quotas
+            .write()
+            .map_err(|_| quota_lock_poisoned())?
+            .remove(&provider_type);
+        Ok(())
+    }
+
+    /// Consume one invocation for the instance, failing with
+    /// `ResourceExhausted` once the quota is used up.
+    pub fn record_invocation(&self, instance_id: &str, provider_type: ProviderType) -> Result<()> {
+        let limit = self
+            .quotas
+            .read()
+            .map_err(|_| quota_lock_poisoned())?
+            .get(&provider_type)
+            .copied()
+            .unwrap_or(u64::MAX);
+
+        match self
+            .repo
+            .increment_within(instance_id, provider_type, limit)?
+        {
+            Some(_) => Ok(()),
+            None => Err(CoreError::ResourceExhausted(format!(
+                "Instance {} exceeded its {:?} invocation quota of {}",
+                instance_id, provider_type, limit
+            ))),
+        }
+    }
+
+    pub fn invocation_count(&self, instance_id: &str, provider_type: ProviderType) -> Result<u64> {
+        self.repo.get_count(instance_id, provider_type)
+    }
+
+    pub fn reset_quota(&self, instance_id: &str) -> Result<()> {
+        self.repo.clear_instance(instance_id)
+    }
+}
+
+fn quota_lock_poisoned() -> CoreError {
+    CoreError::InvalidCapabilityAssignment("invocation quota config lock poisoned".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::invocation_quota::repo::InMemoryInvocationQuotaRepository;
+
+    fn create_service() -> InvocationQuotaService {
+        InvocationQuotaService::new(Arc::new(InMemoryInvocationQuotaRepository::new()))
+    }
+
+    #[test]
+    fn test_http_quota_rejects_third_call() {
+        let service = create_service();
+        service.set_quota(ProviderType::Http, 2).unwrap();
+
+        assert!(service
+            .record_invocation("instance-1", ProviderType::Http)
+            .is_ok());
+        assert!(service
+            .record_invocation("instance-1", ProviderType::Http)
+            .is_ok());
+        let third = service.record_invocation("instance-1", ProviderType::Http);
+
+        assert!(matches!(third, Err(CoreError::ResourceExhausted(_))));
+        assert_eq!(
+            service
+                .invocation_count("instance-1", ProviderType::Http)
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_quota_is_per_instance_and_provider_type() {
+        let service = create_service();
+        service.set_quota(ProviderType::Http, 1).unwrap();
+
+        service
+            .record_invocation("instance-1", ProviderType::Http)
+            .unwrap();
+        assert!(service
+            .record_invocation("instance-2", ProviderType::Http)
+            .is_ok());
+        assert!(service
+            .record_invocation("instance-1", ProviderType::Kv)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_reset_quota_allows_invocations_again() {
+        let service = create_service();
+        service.set_quota(ProviderType::Messaging, 1).unwrap();
+        service
+            .record_invocation("instance-1", ProviderType::Messaging)
+            .unwrap();
+        assert!(service
+            .record_invocation("instance-1", ProviderType::Messaging)
+            .is_err());
+
+        service.reset_quota("instance-1").unwrap();
+
+        assert!(service
+            .record_invocation("instance-1", ProviderType::Messaging)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_no_quota_means_unlimited() {
+        let service = create_service();
+        for _ in 0..100 {
+            service
+                .record_invocation("instance-1", ProviderType::Kv)
+                .unwrap();
+        }
+        assert_eq!(
+            service
+                .invocation_count("instance-1", ProviderType::Kv)
+                .unwrap(),
+            100
+        );
+    }
+}
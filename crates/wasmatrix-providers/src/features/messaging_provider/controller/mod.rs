@@ -1,34 +1,41 @@
 use crate::features::messaging_provider::service::MessagingProviderService;
+use crate::{deny_without_permissions, enforce_result_size, DEFAULT_MAX_RESULT_BYTES};
 use serde_json::Value;
 use wasmatrix_core::{CapabilityAssignment, CoreError, Result};
 
 pub struct MessagingProviderController {
     service: MessagingProviderService,
+    max_result_bytes: usize,
 }
 
 impl MessagingProviderController {
     pub fn new(service: MessagingProviderService) -> Self {
-        Self { service }
+        Self {
+            service,
+            max_result_bytes: DEFAULT_MAX_RESULT_BYTES,
+        }
+    }
+
+    pub fn set_max_result_bytes(&mut self, max_result_bytes: usize) {
+        self.max_result_bytes = max_result_bytes;
     }
 
     pub fn handle_invoke(
         &self,
-        instance_id: &str,
+        assignment: &CapabilityAssignment,
         operation: &str,
         params: Value,
     ) -> Result<Value> {
+        deny_without_permissions(assignment)?;
+        if operation == "list_subscriptions" {
+            let result = self.service.list_subscriptions(assignment)?;
+            return enforce_result_size(result, self.max_result_bytes);
+        }
         let topic = params.get("topic").and_then(Value::as_str).ok_or_else(|| {
             CoreError::InvalidCapabilityAssignment("Missing 'topic' parameter".to_string())
         })?;
 
-        let assignment = CapabilityAssignment::new(
-            instance_id.to_string(),
-            "messaging-provider".to_string(),
-            wasmatrix_core::ProviderType::Messaging,
-            extract_permissions(&params),
-        );
-
-        match operation {
+        let result = match operation {
             "publish" => {
                 let payload = params
                     .get("payload")
@@ -38,29 +45,19 @@ impl MessagingProviderController {
                             "Missing 'payload' parameter".to_string(),
                         )
                     })?;
-                self.service.publish(&assignment, topic, payload)
+                self.service.publish(assignment, topic, payload)
             }
-            "subscribe" => self.service.subscribe(&assignment, topic),
-            "unsubscribe" => self.service.unsubscribe(&assignment, topic),
+            "subscribe" => self.service.subscribe(assignment, topic),
+            "unsubscribe" => self.service.unsubscribe(assignment, topic),
+            "poll" => self.service.poll(assignment, topic),
             _ => Err(CoreError::InvalidCapabilityAssignment(format!(
                 "Unknown messaging operation: {operation}"
             ))),
-        }
+        }?;
+        enforce_result_size(result, self.max_result_bytes)
     }
 }
 
-fn extract_permissions(params: &Value) -> Vec<String> {
-    params
-        .get("permissions")
-        .and_then(Value::as_array)
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                .collect()
-        })
-        .unwrap_or_default()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,29 +65,124 @@ mod tests {
     use crate::features::messaging_provider::service::MessagingProviderService;
     use std::sync::Arc;
 
+    fn assignment(permissions: &[&str]) -> CapabilityAssignment {
+        CapabilityAssignment::new(
+            "i-1".to_string(),
+            "messaging-provider".to_string(),
+            wasmatrix_core::ProviderType::Messaging,
+            permissions.iter().map(|p| p.to_string()).collect(),
+        )
+    }
+
     #[test]
     fn test_handle_invoke_rejects_unknown_operation() {
         let controller = MessagingProviderController::new(MessagingProviderService::new(Arc::new(
             InMemoryMessagingProviderRepository::new(),
         )));
         let params = serde_json::json!({
-            "topic": "orders",
-            "permissions": ["msg:publish:orders"]
+            "topic": "orders"
         });
-        let result = controller.handle_invoke("i-1", "unknown", params);
+        let result =
+            controller.handle_invoke(&assignment(&["msg:publish:orders"]), "unknown", params);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_handle_invoke_rejects_oversized_result() {
+        let mut controller = MessagingProviderController::new(MessagingProviderService::new(
+            Arc::new(InMemoryMessagingProviderRepository::new()),
+        ));
+        controller.set_max_result_bytes(8);
+        let params = serde_json::json!({
+            "topic": "orders",
+            "payload": "created"
+        });
+
+        let result =
+            controller.handle_invoke(&assignment(&["msg:publish:orders"]), "publish", params);
+        assert!(matches!(result, Err(CoreError::ResourceExhausted(_))));
+    }
+
+    #[test]
+    fn test_handle_invoke_list_subscriptions() {
+        let controller = MessagingProviderController::new(MessagingProviderService::new(Arc::new(
+            InMemoryMessagingProviderRepository::new(),
+        )));
+        let subscriber = assignment(&["msg:subscribe"]);
+        for topic in ["orders", "billing"] {
+            controller
+                .handle_invoke(
+                    &subscriber,
+                    "subscribe",
+                    serde_json::json!({ "topic": topic }),
+                )
+                .unwrap();
+        }
+
+        let listed = controller
+            .handle_invoke(&subscriber, "list_subscriptions", serde_json::json!({}))
+            .unwrap();
+        assert_eq!(
+            listed,
+            serde_json::json!({ "topics": ["billing", "orders"] })
+        );
+
+        controller
+            .handle_invoke(
+                &subscriber,
+                "unsubscribe",
+                serde_json::json!({ "topic": "orders" }),
+            )
+            .unwrap();
+        let listed = controller
+            .handle_invoke(&subscriber, "list_subscriptions", serde_json::json!({}))
+            .unwrap();
+        assert_eq!(listed, serde_json::json!({ "topics": ["billing"] }));
+
+        let scoped = controller.handle_invoke(
+            &assignment(&["msg:subscribe:orders"]),
+            "list_subscriptions",
+            serde_json::json!({}),
+        );
+        assert!(scoped.is_err());
+    }
+
     #[test]
     fn test_handle_invoke_publish_requires_payload() {
         let controller = MessagingProviderController::new(MessagingProviderService::new(Arc::new(
             InMemoryMessagingProviderRepository::new(),
         )));
         let params = serde_json::json!({
-            "topic": "orders",
-            "permissions": ["msg:publish:orders"]
+            "topic": "orders"
         });
-        let result = controller.handle_invoke("i-1", "publish", params);
+        let result =
+            controller.handle_invoke(&assignment(&["msg:publish:orders"]), "publish", params);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_handle_invoke_without_permissions_is_denied_for_every_operation() {
+        let controller = MessagingProviderController::new(MessagingProviderService::new(Arc::new(
+            InMemoryMessagingProviderRepository::new(),
+        )));
+
+        for operation in [
+            "publish",
+            "subscribe",
+            "unsubscribe",
+            "poll",
+            "list_subscriptions",
+            "unknown",
+        ] {
+            let result = controller.handle_invoke(
+                &assignment(&[]),
+                operation,
+                serde_json::json!({ "topic": "orders", "payload": "created", "permissions": ["msg:publish:orders", "msg:subscribe:orders"] }),
+            );
+            assert!(
+                matches!(&result, Err(CoreError::PermissionDenied(_))),
+                "{operation}: {result:?}"
+            );
+        }
+    }
 }
@@ -2,12 +2,21 @@ pub mod controller;
 pub mod repo;
 pub mod service;
 
-use crate::{CapabilityProvider, ProviderMetadata};
+use crate::{operation_names, CapabilityProvider, ProviderMetadata};
 use controller::MessagingProviderController;
-use repo::InMemoryMessagingProviderRepository;
+use repo::{InMemoryMessagingProviderRepository, MessagingProviderRepository};
 use service::MessagingProviderService;
 use std::sync::Arc;
-use wasmatrix_core::{ProviderType, Result};
+use wasmatrix_core::{CapabilityAssignment, ProviderType, Result};
+
+/// Operations accepted by the messaging provider
+const MESSAGING_OPERATIONS: &[&str] = &[
+    "publish",
+    "subscribe",
+    "unsubscribe",
+    "poll",
+    "list_subscriptions",
+];
 
 pub struct MessagingCapabilityProvider {
     controller: MessagingProviderController,
@@ -16,7 +25,17 @@ pub struct MessagingCapabilityProvider {
 
 impl MessagingCapabilityProvider {
     pub fn new(provider_id: String) -> Self {
-        let repo = Arc::new(InMemoryMessagingProviderRepository::new());
+        Self::with_repository(
+            provider_id,
+            Arc::new(InMemoryMessagingProviderRepository::new()),
+        )
+    }
+
+    /// Create a provider over a shared repository, so inboxes outlive this provider
+    pub fn with_repository(
+        provider_id: String,
+        repo: Arc<dyn MessagingProviderRepository>,
+    ) -> Self {
         let service = MessagingProviderService::new(repo);
         let controller = MessagingProviderController::new(service);
         Self {
@@ -25,9 +44,16 @@ impl MessagingCapabilityProvider {
                 provider_id,
                 provider_type: ProviderType::Messaging,
                 version: "0.1.0".to_string(),
+                operations: operation_names(MESSAGING_OPERATIONS),
             },
         }
     }
+
+    /// Limit the serialized size of invocation results
+    pub fn with_max_result_bytes(mut self, max_result_bytes: usize) -> Self {
+        self.controller.set_max_result_bytes(max_result_bytes);
+        self
+    }
 }
 
 impl CapabilityProvider for MessagingCapabilityProvider {
@@ -37,12 +63,11 @@ impl CapabilityProvider for MessagingCapabilityProvider {
 
     fn invoke(
         &self,
-        instance_id: &str,
+        assignment: &CapabilityAssignment,
         operation: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value> {
-        self.controller
-            .handle_invoke(instance_id, operation, params)
+        self.controller.handle_invoke(assignment, operation, params)
     }
 
     fn shutdown(&mut self) -> Result<()> {
@@ -52,6 +77,10 @@ impl CapabilityProvider for MessagingCapabilityProvider {
     fn get_metadata(&self) -> ProviderMetadata {
         self.metadata.clone()
     }
+
+    fn supported_operations(&self) -> Vec<String> {
+        self.metadata.operations.clone()
+    }
 }
 
 #[cfg(test)]
@@ -59,6 +88,15 @@ mod tests {
     use super::*;
     use wasmatrix_core::CoreError;
 
+    fn assignment(permissions: &[&str]) -> CapabilityAssignment {
+        CapabilityAssignment::new(
+            "inst-1".to_string(),
+            "messaging-provider".to_string(),
+            ProviderType::Messaging,
+            permissions.iter().map(|p| p.to_string()).collect(),
+        )
+    }
+
     #[test]
     fn test_messaging_provider_metadata() {
         let provider = MessagingCapabilityProvider::new("messaging-provider".to_string());
@@ -66,6 +104,17 @@ mod tests {
 
         assert_eq!(metadata.provider_id, "messaging-provider");
         assert_eq!(metadata.provider_type, ProviderType::Messaging);
+        assert_eq!(
+            provider.supported_operations(),
+            vec![
+                "publish",
+                "subscribe",
+                "unsubscribe",
+                "poll",
+                "list_subscriptions"
+            ]
+        );
+        assert_eq!(metadata.operations, provider.supported_operations());
     }
 
     #[test]
@@ -73,23 +122,25 @@ mod tests {
         let provider = MessagingCapabilityProvider::new("messaging-provider".to_string());
         let params = serde_json::json!({
             "topic": "orders",
-            "payload": "created",
-            "permissions": ["msg:publish:orders"]
+            "payload": "created"
         });
 
-        let result = provider.invoke("inst-1", "publish", params).unwrap();
+        let result = provider
+            .invoke(&assignment(&["msg:publish:orders"]), "publish", params)
+            .unwrap();
         assert_eq!(result["published"].as_bool(), Some(true));
     }
 
     #[test]
     fn test_messaging_provider_subscribe_permission_denied() {
         let provider = MessagingCapabilityProvider::new("messaging-provider".to_string());
+        // Permissions in params are not an assignment and grant nothing
         let params = serde_json::json!({
             "topic": "orders",
-            "permissions": ["msg:publish:orders"]
+            "permissions": ["msg:subscribe:orders"]
         });
 
-        let result = provider.invoke("inst-1", "subscribe", params);
+        let result = provider.invoke(&assignment(&["msg:publish:orders"]), "subscribe", params);
         assert!(matches!(
             result,
             Err(CoreError::InvalidCapabilityAssignment(_))
@@ -99,22 +150,23 @@ mod tests {
     #[test]
     fn test_messaging_provider_subscribe_and_unsubscribe_success() {
         let provider = MessagingCapabilityProvider::new("messaging-provider".to_string());
-        let subscribe_params = serde_json::json!({
-            "topic": "orders",
-            "permissions": ["msg:subscribe:orders"]
-        });
-        let unsub_params = serde_json::json!({
-            "topic": "orders",
-            "permissions": ["msg:subscribe:orders"]
-        });
+        let subscriber = assignment(&["msg:subscribe:orders"]);
 
         let subscribed = provider
-            .invoke("inst-1", "subscribe", subscribe_params)
+            .invoke(
+                &subscriber,
+                "subscribe",
+                serde_json::json!({ "topic": "orders" }),
+            )
             .unwrap();
         assert_eq!(subscribed["subscribed"].as_bool(), Some(true));
 
         let unsubscribed = provider
-            .invoke("inst-1", "unsubscribe", unsub_params)
+            .invoke(
+                &subscriber,
+                "unsubscribe",
+                serde_json::json!({ "topic": "orders" }),
+            )
             .unwrap();
         assert_eq!(unsubscribed["unsubscribed"].as_bool(), Some(true));
     }
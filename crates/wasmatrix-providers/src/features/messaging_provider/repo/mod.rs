@@ -1,4 +1,5 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 use wasmatrix_core::{CoreError, Result};
 
@@ -9,29 +10,76 @@ pub struct PublishedMessage {
 }
 
 pub trait MessagingProviderRepository: Send + Sync {
-    fn publish(&self, topic: &str, payload: &str) -> Result<()>;
+    /// Store a message and fan it out to the inbox of every subscribed instance,
+    /// returning how many inboxes received it
+    fn publish(&self, topic: &str, payload: &str) -> Result<usize>;
     fn subscribe(&self, instance_id: &str, topic: &str) -> Result<()>;
     fn unsubscribe(&self, instance_id: &str, topic: &str) -> Result<bool>;
+    /// Put a message directly into one instance's inbox, ignoring subscriptions
+    fn deliver(&self, instance_id: &str, topic: &str, payload: &str) -> Result<()>;
+    /// Remove and return the instance's inbox messages for `topic`
+    fn take_messages(&self, instance_id: &str, topic: &str) -> Result<Vec<PublishedMessage>>;
+    /// Number of (instance, topic) subscriptions currently held
+    fn subscription_count(&self) -> Result<usize>;
+    /// Topics the instance is subscribed to, sorted
+    fn list_subscriptions(&self, instance_id: &str) -> Result<Vec<String>>;
+    /// Drop the instance's inbox and subscriptions, e.g. once it has stopped
+    fn clear_instance(&self, instance_id: &str) -> Result<()>;
 }
 
+/// Published messages kept in the audit log by [`InMemoryMessagingProviderRepository::new`]
+pub const DEFAULT_PUBLISHED_LOG_CAPACITY: usize = 1024;
+
+/// Messages each instance inbox holds by default before the oldest is dropped
+pub const DEFAULT_INBOX_CAPACITY: usize = 1024;
+
 /// In-memory pub/sub repository used by the messaging provider.
+///
+/// Published messages are kept in a bounded audit log that drops the oldest
+/// record once full. Each instance inbox is bounded separately and likewise
+/// drops its oldest message when a new one arrives at capacity.
 pub struct InMemoryMessagingProviderRepository {
     subscriptions: Arc<RwLock<HashMap<String, HashSet<String>>>>,
-    published_messages: Arc<RwLock<Vec<PublishedMessage>>>,
+    published_messages: Arc<RwLock<VecDeque<PublishedMessage>>>,
+    published_capacity: usize,
+    total_published: AtomicUsize,
+    inboxes: Arc<RwLock<HashMap<String, VecDeque<PublishedMessage>>>>,
+    inbox_capacity: usize,
 }
 
 impl InMemoryMessagingProviderRepository {
     pub fn new() -> Self {
+        Self::with_published_capacity(DEFAULT_PUBLISHED_LOG_CAPACITY)
+    }
+
+    /// Retain at most `capacity` published messages in the audit log
+    pub fn with_published_capacity(capacity: usize) -> Self {
         Self {
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
-            published_messages: Arc::new(RwLock::new(Vec::new())),
+            published_messages: Arc::new(RwLock::new(VecDeque::new())),
+            published_capacity: capacity,
+            total_published: AtomicUsize::new(0),
+            inboxes: Arc::new(RwLock::new(HashMap::new())),
+            inbox_capacity: DEFAULT_INBOX_CAPACITY,
         }
     }
 
+    /// Keep at most `capacity` undelivered messages per instance inbox
+    pub fn with_inbox_capacity(mut self, capacity: usize) -> Self {
+        self.inbox_capacity = capacity.max(1);
+        self
+    }
+
+    /// Published messages currently retained in the audit log
     pub fn published_count(&self) -> usize {
         self.published_messages.read().map(|m| m.len()).unwrap_or(0)
     }
 
+    /// Messages published over the repository's lifetime, including dropped ones
+    pub fn total_published(&self) -> usize {
+        self.total_published.load(Ordering::Relaxed)
+    }
+
     pub fn is_subscribed(&self, instance_id: &str, topic: &str) -> bool {
         self.subscriptions
             .read()
@@ -49,15 +97,36 @@ impl Default for InMemoryMessagingProviderRepository {
 }
 
 impl MessagingProviderRepository for InMemoryMessagingProviderRepository {
-    fn publish(&self, topic: &str, payload: &str) -> Result<()> {
+    fn publish(&self, topic: &str, payload: &str) -> Result<usize> {
         let mut messages = self.published_messages.write().map_err(|_| {
             CoreError::InvalidCapabilityAssignment("Messaging lock poisoned".to_string())
         })?;
-        messages.push(PublishedMessage {
-            topic: topic.to_string(),
-            payload: payload.to_string(),
-        });
-        Ok(())
+        self.total_published.fetch_add(1, Ordering::Relaxed);
+        if self.published_capacity > 0 {
+            if messages.len() >= self.published_capacity {
+                messages.pop_front();
+            }
+            messages.push_back(PublishedMessage {
+                topic: topic.to_string(),
+                payload: payload.to_string(),
+            });
+        }
+        drop(messages);
+
+        let subscribers: Vec<String> = self
+            .subscriptions
+            .read()
+            .map_err(|_| {
+                CoreError::InvalidCapabilityAssignment("Messaging lock poisoned".to_string())
+            })?
+            .iter()
+            .filter(|(_, topics)| topics.contains(topic))
+            .map(|(instance_id, _)| instance_id.clone())
+            .collect();
+        for instance_id in &subscribers {
+            self.deliver(instance_id, topic, payload)?;
+        }
+        Ok(subscribers.len())
     }
 
     fn subscribe(&self, instance_id: &str, topic: &str) -> Result<()> {
@@ -90,6 +159,76 @@ impl MessagingProviderRepository for InMemoryMessagingProviderRepository {
 
         Ok(removed)
     }
+
+    fn deliver(&self, instance_id: &str, topic: &str, payload: &str) -> Result<()> {
+        let mut inboxes = self.inboxes.write().map_err(|_| {
+            CoreError::InvalidCapabilityAssignment("Messaging lock poisoned".to_string())
+        })?;
+        let inbox = inboxes.entry(instance_id.to_string()).or_default();
+        if inbox.len() >= self.inbox_capacity {
+            inbox.pop_front();
+        }
+        inbox.push_back(PublishedMessage {
+            topic: topic.to_string(),
+            payload: payload.to_string(),
+        });
+        Ok(())
+    }
+
+    fn take_messages(&self, instance_id: &str, topic: &str) -> Result<Vec<PublishedMessage>> {
+        let mut inboxes = self.inboxes.write().map_err(|_| {
+            CoreError::InvalidCapabilityAssignment("Messaging lock poisoned".to_string())
+        })?;
+        let Some(inbox) = inboxes.get_mut(instance_id) else {
+            return Ok(Vec::new());
+        };
+
+        let (taken, kept): (VecDeque<_>, VecDeque<_>) = std::mem::take(inbox)
+            .into_iter()
+            .partition(|message| message.topic == topic);
+        *inbox = kept;
+        if inbox.is_empty() {
+            inboxes.remove(instance_id);
+        }
+        Ok(taken.into())
+    }
+
+    fn subscription_count(&self) -> Result<usize> {
+        let subscriptions = self.subscriptions.read().map_err(|_| {
+            CoreError::InvalidCapabilityAssignment("Messaging lock poisoned".to_string())
+        })?;
+        Ok(subscriptions.values().map(HashSet::len).sum())
+    }
+
+    fn list_subscriptions(&self, instance_id: &str) -> Result<Vec<String>> {
+        let subscriptions = self.subscriptions.read().map_err(|_| {
+            CoreError::InvalidCapabilityAssignment("Messaging lock poisoned".to_string())
+        })?;
+        let mut topics: Vec<String> = subscriptions
+            .get(instance_id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        topics.sort();
+        Ok(topics)
+    }
+
+    fn clear_instance(&self, instance_id: &str) -> Result<()> {
+        self.inboxes
+            .write()
+            .map_err(|_| {
+                CoreError::InvalidCapabilityAssignment("Messaging lock poisoned".to_string())
+            })?
+            .remove(instance_id);
+        self.subscriptions
+            .write()
+            .map_err(|_| {
+                CoreError::InvalidCapabilityAssignment("Messaging lock poisoned".to_string())
+            })?
+            .remove(instance_id);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -109,4 +248,78 @@ mod tests {
         assert!(removed);
         assert!(!repo.is_subscribed("inst-1", "orders"));
     }
+
+    #[test]
+    fn test_published_log_is_capped_but_delivery_is_not() {
+        let repo = InMemoryMessagingProviderRepository::with_published_capacity(2);
+        repo.subscribe("inst-1", "orders").unwrap();
+        for payload in ["a", "b", "c", "d"] {
+            repo.publish("orders", payload).unwrap();
+        }
+
+        assert_eq!(repo.published_count(), 2);
+        assert_eq!(repo.total_published(), 4);
+        assert_eq!(repo.take_messages("inst-1", "orders").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_publish_fans_out_to_subscriber_inboxes() {
+        let repo = InMemoryMessagingProviderRepository::new();
+        repo.subscribe("inst-1", "orders").unwrap();
+        repo.subscribe("inst-2", "orders").unwrap();
+        repo.subscribe("inst-3", "billing").unwrap();
+
+        assert_eq!(repo.subscription_count().unwrap(), 3);
+        assert_eq!(repo.publish("orders", "created").unwrap(), 2);
+        repo.deliver("inst-1", "direct", "hi").unwrap();
+
+        let taken = repo.take_messages("inst-1", "orders").unwrap();
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].payload, "created");
+        assert!(repo.take_messages("inst-1", "orders").unwrap().is_empty());
+        assert_eq!(repo.take_messages("inst-1", "direct").unwrap().len(), 1);
+        assert_eq!(repo.take_messages("inst-2", "orders").unwrap().len(), 1);
+        assert!(repo.take_messages("inst-3", "orders").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_inbox_drops_oldest_at_capacity() {
+        let repo = InMemoryMessagingProviderRepository::new().with_inbox_capacity(2);
+        for payload in ["a", "b", "c"] {
+            repo.deliver("inst-1", "orders", payload).unwrap();
+        }
+
+        let taken = repo.take_messages("inst-1", "orders").unwrap();
+        let payloads: Vec<&str> = taken.iter().map(|m| m.payload.as_str()).collect();
+        assert_eq!(payloads, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_clear_instance_drops_inbox_and_subscriptions() {
+        let repo = InMemoryMessagingProviderRepository::new();
+        repo.subscribe("inst-1", "orders").unwrap();
+        repo.subscribe("inst-2", "orders").unwrap();
+        repo.publish("orders", "created").unwrap();
+
+        repo.clear_instance("inst-1").unwrap();
+        assert!(!repo.is_subscribed("inst-1", "orders"));
+        assert!(repo.take_messages("inst-1", "orders").unwrap().is_empty());
+        assert_eq!(repo.take_messages("inst-2", "orders").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_list_subscriptions_tracks_subscribe_and_unsubscribe() {
+        let repo = InMemoryMessagingProviderRepository::new();
+        repo.subscribe("inst-1", "orders").unwrap();
+        repo.subscribe("inst-1", "billing").unwrap();
+        repo.subscribe("inst-2", "audit").unwrap();
+
+        assert_eq!(
+            repo.list_subscriptions("inst-1").unwrap(),
+            vec!["billing", "orders"]
+        );
+        repo.unsubscribe("inst-1", "orders").unwrap();
+        assert_eq!(repo.list_subscriptions("inst-1").unwrap(), vec!["billing"]);
+        assert!(repo.list_subscriptions("inst-3").unwrap().is_empty());
+    }
 }
@@ -1,14 +1,57 @@
 use crate::features::messaging_provider::repo::MessagingProviderRepository;
+use crate::features::observability::controller::{
+    global_observability_controller, ObservabilityController,
+};
 use std::sync::Arc;
 use wasmatrix_core::{CapabilityAssignment, CoreError, Result};
 
+/// Reserved topic prefix under which asynchronous invocation results are delivered
+pub const INVOKE_RESULT_TOPIC_PREFIX: &str = "_invoke_result.";
+
+/// Longest topic, in bytes, an instance may subscribe to
+pub const MAX_TOPIC_LENGTH: usize = 256;
+
+/// Inbox topic carrying the result of the async invocation `correlation_id`
+pub fn invoke_result_topic(correlation_id: &str) -> String {
+    format!("{INVOKE_RESULT_TOPIC_PREFIX}{correlation_id}")
+}
+
+/// Reject topics that are empty, longer than [`MAX_TOPIC_LENGTH`], contain
+/// whitespace or control characters, or have an empty `.`-separated segment
+pub fn validate_topic(topic: &str) -> Result<()> {
+    let problem = if topic.is_empty() {
+        "must not be empty".to_string()
+    } else if topic.len() > MAX_TOPIC_LENGTH {
+        format!("exceeds {MAX_TOPIC_LENGTH} bytes")
+    } else if topic.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        "must not contain whitespace or control characters".to_string()
+    } else if topic.split('.').any(str::is_empty) {
+        "must not contain empty '.' segments".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(CoreError::InvalidCapabilityAssignment(format!(
+        "Invalid topic: {problem}"
+    )))
+}
+
 pub struct MessagingProviderService {
     repo: Arc<dyn MessagingProviderRepository>,
+    observability: Arc<ObservabilityController>,
 }
 
 impl MessagingProviderService {
     pub fn new(repo: Arc<dyn MessagingProviderRepository>) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            observability: global_observability_controller(),
+        }
+    }
+
+    /// Record throughput metrics into `observability` instead of the global registry
+    pub fn with_observability(mut self, observability: Arc<ObservabilityController>) -> Self {
+        self.observability = observability;
+        self
     }
 
     pub fn publish(
@@ -17,8 +60,15 @@ impl MessagingProviderService {
         topic: &str,
         payload: &str,
     ) -> Result<serde_json::Value> {
+        if topic.starts_with(INVOKE_RESULT_TOPIC_PREFIX) {
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Permission denied: topic prefix '{INVOKE_RESULT_TOPIC_PREFIX}' is reserved"
+            )));
+        }
         self.validate_publish_permission(assignment, topic)?;
-        self.repo.publish(topic, payload)?;
+        let delivered = self.repo.publish(topic, payload)?;
+        self.observability.record_messaging_published(topic);
+        self.observability.record_messaging_delivered(delivered);
         Ok(serde_json::json!({ "published": true }))
     }
 
@@ -27,8 +77,10 @@ impl MessagingProviderService {
         assignment: &CapabilityAssignment,
         topic: &str,
     ) -> Result<serde_json::Value> {
+        validate_topic(topic)?;
         self.validate_subscribe_permission(assignment, topic)?;
         self.repo.subscribe(&assignment.instance_id, topic)?;
+        self.record_subscriptions();
         Ok(serde_json::json!({ "subscribed": true }))
     }
 
@@ -39,9 +91,78 @@ impl MessagingProviderService {
     ) -> Result<serde_json::Value> {
         self.validate_subscribe_permission(assignment, topic)?;
         let removed = self.repo.unsubscribe(&assignment.instance_id, topic)?;
+        self.record_subscriptions();
         Ok(serde_json::json!({ "unsubscribed": removed }))
     }
 
+    /// Topics the instance is subscribed to; needs the unscoped `msg:subscribe`
+    /// permission since the answer covers every topic
+    pub fn list_subscriptions(
+        &self,
+        assignment: &CapabilityAssignment,
+    ) -> Result<serde_json::Value> {
+        if !assignment.has_permission("msg:subscribe") {
+            return Err(CoreError::InvalidCapabilityAssignment(
+                "Permission denied: missing 'msg:subscribe' permission".to_string(),
+            ));
+        }
+        let topics = self.repo.list_subscriptions(&assignment.instance_id)?;
+        Ok(serde_json::json!({ "topics": topics }))
+    }
+
+    /// Drain the instance's inbox for `topic`. An instance may always poll
+    /// its own invocation result topics.
+    pub fn poll(
+        &self,
+        assignment: &CapabilityAssignment,
+        topic: &str,
+    ) -> Result<serde_json::Value> {
+        if !topic.starts_with(INVOKE_RESULT_TOPIC_PREFIX) {
+            self.validate_subscribe_permission(assignment, topic)?;
+        }
+        let messages: Vec<serde_json::Value> = self
+            .repo
+            .take_messages(&assignment.instance_id, topic)?
+            .into_iter()
+            .map(|m| serde_json::json!({ "topic": m.topic, "payload": m.payload }))
+            .collect();
+        Ok(serde_json::json!({ "messages": messages }))
+    }
+
+    /// Deliver the outcome of an async invocation to the instance's inbox
+    pub fn deliver_invoke_result(
+        &self,
+        instance_id: &str,
+        correlation_id: &str,
+        outcome: &Result<serde_json::Value>,
+    ) -> Result<()> {
+        let payload = match outcome {
+            Ok(result) => serde_json::json!({
+                "correlation_id": correlation_id,
+                "success": true,
+                "result": result,
+            }),
+            Err(error) => serde_json::json!({
+                "correlation_id": correlation_id,
+                "success": false,
+                "error": error.to_string(),
+            }),
+        };
+        self.repo.deliver(
+            instance_id,
+            &invoke_result_topic(correlation_id),
+            &payload.to_string(),
+        )?;
+        self.observability.record_messaging_delivered(1);
+        Ok(())
+    }
+
+    fn record_subscriptions(&self) {
+        if let Ok(count) = self.repo.subscription_count() {
+            self.observability.record_messaging_subscriptions(count);
+        }
+    }
+
     fn validate_publish_permission(
         &self,
         assignment: &CapabilityAssignment,
@@ -109,6 +230,23 @@ mod tests {
         assert_eq!(unsubscribed["unsubscribed"].as_bool(), Some(true));
     }
 
+    #[test]
+    fn test_publish_denied_after_grant_expires() {
+        let service =
+            MessagingProviderService::new(Arc::new(InMemoryMessagingProviderRepository::new()));
+        let active = assignment(vec!["msg:publish"])
+            .with_expiry(chrono::Utc::now() + chrono::Duration::hours(1));
+        assert!(service.publish(&active, "orders", "created").is_ok());
+
+        let expired = assignment(vec!["msg:publish"])
+            .with_expiry(chrono::Utc::now() - chrono::Duration::seconds(1));
+        let result = service.publish(&expired, "orders", "created");
+        assert!(matches!(
+            result,
+            Err(CoreError::InvalidCapabilityAssignment(_))
+        ));
+    }
+
     #[test]
     fn test_publish_with_generic_permission() {
         let service =
@@ -137,4 +275,96 @@ mod tests {
         let result = service.subscribe(&assignment, "inventory").unwrap();
         assert_eq!(result["subscribed"].as_bool(), Some(true));
     }
+
+    #[test]
+    fn test_subscribe_validates_topic() {
+        let service =
+            MessagingProviderService::new(Arc::new(InMemoryMessagingProviderRepository::new()));
+        let assignment = assignment(vec!["msg:subscribe"]);
+
+        for topic in [
+            String::new(),
+            "o".repeat(MAX_TOPIC_LENGTH + 1),
+            "orders..created".to_string(),
+            "orders created".to_string(),
+        ] {
+            assert!(
+                matches!(
+                    service.subscribe(&assignment, &topic),
+                    Err(CoreError::InvalidCapabilityAssignment(_))
+                ),
+                "{topic:?} should be rejected"
+            );
+        }
+
+        let longest = "o".repeat(MAX_TOPIC_LENGTH);
+        assert!(service.subscribe(&assignment, &longest).is_ok());
+        assert!(service.subscribe(&assignment, "orders.created").is_ok());
+    }
+
+    #[test]
+    fn test_poll_returns_subscribed_messages_once() {
+        let service =
+            MessagingProviderService::new(Arc::new(InMemoryMessagingProviderRepository::new()));
+        let assignment = assignment(vec!["msg:subscribe:orders", "msg:publish:orders"]);
+        service.subscribe(&assignment, "orders").unwrap();
+        service.publish(&assignment, "orders", "created").unwrap();
+
+        let polled = service.poll(&assignment, "orders").unwrap();
+        assert_eq!(polled["messages"][0]["payload"].as_str(), Some("created"));
+        let again = service.poll(&assignment, "orders").unwrap();
+        assert!(again["messages"].as_array().unwrap().is_empty());
+
+        assert!(service.poll(&self::assignment(vec![]), "orders").is_err());
+    }
+
+    #[test]
+    fn test_invoke_result_topics_are_reserved_but_pollable() {
+        let service =
+            MessagingProviderService::new(Arc::new(InMemoryMessagingProviderRepository::new()));
+        let topic = invoke_result_topic("abc");
+
+        assert!(service
+            .publish(&assignment(vec!["msg:publish"]), &topic, "forged")
+            .is_err());
+
+        service
+            .deliver_invoke_result("i-1", "abc", &Ok(serde_json::json!({"value": 1})))
+            .unwrap();
+        let polled = service.poll(&assignment(vec![]), &topic).unwrap();
+        let payload: serde_json::Value =
+            serde_json::from_str(polled["messages"][0]["payload"].as_str().unwrap()).unwrap();
+        assert_eq!(payload["success"].as_bool(), Some(true));
+        assert_eq!(payload["result"]["value"].as_u64(), Some(1));
+    }
+
+    #[test]
+    fn test_publish_metrics_advance_per_topic_prefix() {
+        let observability = Arc::new(ObservabilityController::standalone().unwrap());
+        let service =
+            MessagingProviderService::new(Arc::new(InMemoryMessagingProviderRepository::new()))
+                .with_observability(observability.clone());
+        let assignment = assignment(vec!["msg:publish", "msg:subscribe"]);
+
+        service.subscribe(&assignment, "orders.created").unwrap();
+        service.publish(&assignment, "orders.created", "1").unwrap();
+        service
+            .publish(&assignment, "orders.cancelled", "2")
+            .unwrap();
+        service
+            .publish(&assignment, "billing.invoiced", "3")
+            .unwrap();
+
+        let rendered = observability.render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_messaging_published_total{topic_prefix=\"orders\"} 2"));
+        assert!(
+            rendered.contains("wasmatrix_messaging_published_total{topic_prefix=\"billing\"} 1")
+        );
+        assert!(rendered.contains("wasmatrix_messaging_delivered_total 1"));
+        assert!(rendered.contains("wasmatrix_messaging_subscriptions 1"));
+
+        service.unsubscribe(&assignment, "orders.created").unwrap();
+        let rendered = observability.render_metrics().unwrap();
+        assert!(rendered.contains("wasmatrix_messaging_subscriptions 0"));
+    }
 }
@@ -1,3 +1,7 @@
+pub mod async_invocation;
 pub mod http_provider;
+pub mod introspection_provider;
+pub mod invocation_quota;
 pub mod messaging_provider;
+pub mod observability;
 pub mod provider_lifecycle;
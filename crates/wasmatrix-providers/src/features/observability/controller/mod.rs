@@ -0,0 +1,73 @@
+use crate::features::observability::repo::{
+    MetricsRepository, NoopMetricsRepository, ObservabilityRepository,
+};
+use crate::features::observability::service::ObservabilityService;
+use std::sync::{Arc, OnceLock};
+
+pub struct ObservabilityController {
+    service: ObservabilityService,
+}
+
+impl ObservabilityController {
+    pub fn new(service: ObservabilityService) -> Self {
+        Self { service }
+    }
+
+    /// Controller backed by its own registry, independent of the global one
+    pub fn standalone() -> Result<Self, String> {
+        let repo = Arc::new(ObservabilityRepository::new()?);
+        Ok(Self::new(ObservabilityService::new(repo)))
+    }
+
+    pub fn record_messaging_published(&self, topic: &str) {
+        self.service.record_messaging_published(topic);
+    }
+
+    pub fn record_messaging_delivered(&self, count: usize) {
+        self.service.record_messaging_delivered(count);
+    }
+
+    pub fn record_messaging_subscriptions(&self, count: usize) {
+        self.service.record_messaging_subscriptions(count);
+    }
+
+    pub fn render_metrics(&self) -> Result<String, String> {
+        self.service.render_metrics()
+    }
+}
+
+static GLOBAL_OBSERVABILITY: OnceLock<Arc<ObservabilityController>> = OnceLock::new();
+
+pub fn global_observability_controller() -> Arc<ObservabilityController> {
+    GLOBAL_OBSERVABILITY
+        .get_or_init(|| Arc::new(controller_from_metrics_init(ObservabilityRepository::new())))
+        .clone()
+}
+
+/// Build a controller from the result of metrics initialisation, falling back
+/// to no-op metrics so a registry error never takes the process down
+fn controller_from_metrics_init(
+    init: Result<ObservabilityRepository, String>,
+) -> ObservabilityController {
+    let repo = wasmatrix_observability::init_or_fallback(
+        init.map(|repo| Arc::new(repo) as Arc<dyn MetricsRepository>),
+        || Arc::new(NoopMetricsRepository),
+    );
+    ObservabilityController::new(ObservabilityService::new(repo))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_metrics_init_falls_back_to_noop_controller() {
+        let controller =
+            controller_from_metrics_init(Err("duplicate metrics collector".to_string()));
+        controller.record_messaging_published("orders.created");
+        controller.record_messaging_delivered(2);
+        controller.record_messaging_subscriptions(1);
+
+        assert_eq!(controller.render_metrics(), Ok(String::new()));
+    }
+}
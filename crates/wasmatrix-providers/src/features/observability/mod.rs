@@ -0,0 +1,3 @@
+pub mod controller;
+pub mod repo;
+pub mod service;
@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use wasmatrix_observability::prometheus::{IntCounter, IntCounterVec, IntGauge, Opts};
+use wasmatrix_observability::MetricsRegistry;
+
+/// Distinct `topic_prefix` label values kept before new prefixes are
+/// counted under [`OTHER_TOPIC_PREFIX`]
+pub const MAX_TOPIC_PREFIX_LABELS: usize = 64;
+
+/// Label for prefixes first seen after [`MAX_TOPIC_PREFIX_LABELS`] was reached
+pub const OTHER_TOPIC_PREFIX: &str = "_other";
+
+/// Sink for provider metrics
+pub trait MetricsRepository: Send + Sync {
+    fn inc_messaging_published(&self, topic_prefix: &str);
+    fn inc_messaging_delivered(&self, count: u64);
+    fn set_messaging_subscriptions(&self, count: i64);
+    fn render_metrics(&self) -> Result<String, String>;
+}
+
+pub struct ObservabilityRepository {
+    registry: MetricsRegistry,
+    topic_prefixes: Mutex<HashSet<String>>,
+    messaging_published_total: IntCounterVec,
+    messaging_delivered_total: IntCounter,
+    messaging_subscriptions: IntGauge,
+}
+
+impl ObservabilityRepository {
+    pub fn new() -> Result<Self, String> {
+        let registry = MetricsRegistry::new();
+
+        let messaging_published_total = registry.register(IntCounterVec::new(
+            Opts::new(
+                "wasmatrix_messaging_published_total",
+                "Messages published through the messaging provider",
+            ),
+            &["topic_prefix"],
+        ))?;
+        let messaging_delivered_total = registry.register(IntCounter::new(
+            "wasmatrix_messaging_delivered_total",
+            "Messages delivered into instance inboxes",
+        ))?;
+        let messaging_subscriptions = registry.register(IntGauge::new(
+            "wasmatrix_messaging_subscriptions",
+            "Active (instance, topic) messaging subscriptions",
+        ))?;
+
+        Ok(Self {
+            registry,
+            topic_prefixes: Mutex::new(HashSet::new()),
+            messaging_published_total,
+            messaging_delivered_total,
+            messaging_subscriptions,
+        })
+    }
+}
+
+impl MetricsRepository for ObservabilityRepository {
+    fn inc_messaging_published(&self, topic_prefix: &str) {
+        let label = {
+            let mut known = self
+                .topic_prefixes
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            if known.contains(topic_prefix) {
+                topic_prefix
+            } else if known.len() < MAX_TOPIC_PREFIX_LABELS {
+                known.insert(topic_prefix.to_string());
+                topic_prefix
+            } else {
+                OTHER_TOPIC_PREFIX
+            }
+        };
+        self.messaging_published_total
+            .with_label_values(&[label])
+            .inc();
+    }
+
+    fn inc_messaging_delivered(&self, count: u64) {
+        self.messaging_delivered_total.inc_by(count);
+    }
+
+    fn set_messaging_subscriptions(&self, count: i64) {
+        self.messaging_subscriptions.set(count);
+    }
+
+    fn render_metrics(&self) -> Result<String, String> {
+        self.registry.render()
+    }
+}
+
+/// Stand-in used when the Prometheus registry cannot be built; drops every
+/// observation and renders an empty exposition body
+pub struct NoopMetricsRepository;
+
+impl MetricsRepository for NoopMetricsRepository {
+    fn inc_messaging_published(&self, _topic_prefix: &str) {}
+
+    fn inc_messaging_delivered(&self, _count: u64) {}
+
+    fn set_messaging_subscriptions(&self, _count: i64) {}
+
+    fn render_metrics(&self) -> Result<String, String> {
+        Ok(String::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_prefix_labels_are_capped() {
+        let repo = ObservabilityRepository::new().unwrap();
+        for i in 0..MAX_TOPIC_PREFIX_LABELS + 10 {
+            repo.inc_messaging_published(&format!("tenant{i}"));
+        }
+        repo.inc_messaging_published("tenant0");
+
+        let rendered = repo.render_metrics().unwrap();
+        let series = rendered
+            .lines()
+            .filter(|line| line.starts_with("wasmatrix_messaging_published_total{"))
+            .count();
+        assert_eq!(series, MAX_TOPIC_PREFIX_LABELS + 1);
+        assert!(
+            rendered.contains("wasmatrix_messaging_published_total{topic_prefix=\"_other\"} 10")
+        );
+        assert!(
+            rendered.contains("wasmatrix_messaging_published_total{topic_prefix=\"tenant0\"} 2")
+        );
+    }
+}
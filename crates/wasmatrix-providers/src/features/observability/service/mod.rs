@@ -0,0 +1,61 @@
+use crate::features::observability::repo::MetricsRepository;
+use std::sync::Arc;
+
+/// Longest topic prefix used as a metric label
+const MAX_TOPIC_PREFIX_LEN: usize = 32;
+
+/// Coarse label for `topic`: its first `.`, `/` or `:` separated segment.
+/// The repository additionally caps how many distinct prefixes become labels.
+pub fn topic_prefix(topic: &str) -> &str {
+    let segment = topic
+        .split(['.', '/', ':'])
+        .next()
+        .filter(|segment| !segment.is_empty())
+        .unwrap_or("_");
+    match segment.char_indices().nth(MAX_TOPIC_PREFIX_LEN) {
+        Some((end, _)) => &segment[..end],
+        None => segment,
+    }
+}
+
+pub struct ObservabilityService {
+    repo: Arc<dyn MetricsRepository>,
+}
+
+impl ObservabilityService {
+    pub fn new(repo: Arc<dyn MetricsRepository>) -> Self {
+        Self { repo }
+    }
+
+    pub fn record_messaging_published(&self, topic: &str) {
+        self.repo.inc_messaging_published(topic_prefix(topic));
+    }
+
+    pub fn record_messaging_delivered(&self, count: usize) {
+        self.repo.inc_messaging_delivered(count as u64);
+    }
+
+    pub fn record_messaging_subscriptions(&self, count: usize) {
+        self.repo
+            .set_messaging_subscriptions(i64::try_from(count).unwrap_or(i64::MAX));
+    }
+
+    pub fn render_metrics(&self) -> Result<String, String> {
+        self.repo.render_metrics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_prefix_uses_first_segment() {
+        assert_eq!(topic_prefix("orders.created"), "orders");
+        assert_eq!(topic_prefix("billing/invoices/42"), "billing");
+        assert_eq!(topic_prefix("_invoke_result.abc"), "_invoke_result");
+        assert_eq!(topic_prefix("plain"), "plain");
+        assert_eq!(topic_prefix(".hidden"), "_");
+        assert_eq!(topic_prefix(&"x".repeat(100)).len(), MAX_TOPIC_PREFIX_LEN);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::features::provider_lifecycle::service::ProviderLifecycleService;
+use crate::features::provider_lifecycle::service::{InvocationGuard, ProviderLifecycleService};
+use std::time::Duration;
 use wasmatrix_core::Result;
 
 pub struct ProviderLifecycleController {
@@ -14,13 +15,44 @@ impl ProviderLifecycleController {
         self.service.start_provider(provider_id)
     }
 
-    pub fn stop_provider(&self, provider_id: &str) -> Result<()> {
-        self.service.stop_provider(provider_id)
+    pub async fn stop_provider(&self, provider_id: &str) -> Result<()> {
+        self.service.stop_provider(provider_id).await
+    }
+
+    pub async fn stop_provider_with_timeout(
+        &self,
+        provider_id: &str,
+        timeout: Duration,
+    ) -> Result<()> {
+        self.service
+            .stop_provider_with_timeout(provider_id, timeout)
+            .await
+    }
+
+    pub fn register_provider(&self, provider_id: &str, depends_on: Vec<String>) -> Result<()> {
+        self.service.register_provider(provider_id, depends_on)
+    }
+
+    pub fn start_all(&self) -> Result<Vec<String>> {
+        self.service.start_all()
+    }
+
+    pub async fn shutdown_all(&self) -> Result<Vec<String>> {
+        self.service.shutdown_all().await
     }
 
     pub fn ensure_provider_available(&self, provider_id: &str) -> Result<()> {
         self.service.ensure_provider_available(provider_id)
     }
+
+    /// Whether the provider is registered and running, without registering it
+    pub fn is_provider_running(&self, provider_id: &str) -> Result<bool> {
+        self.service.is_provider_running(provider_id)
+    }
+
+    pub fn begin_invocation(&self, provider_id: &str) -> Result<InvocationGuard> {
+        self.service.begin_invocation(provider_id)
+    }
 }
 
 #[cfg(test)]
@@ -30,8 +62,8 @@ mod tests {
     use crate::features::provider_lifecycle::service::ProviderLifecycleService;
     use std::sync::Arc;
 
-    #[test]
-    fn test_controller_provider_lifecycle() {
+    #[tokio::test]
+    async fn test_controller_provider_lifecycle() {
         let controller = ProviderLifecycleController::new(ProviderLifecycleService::new(Arc::new(
             InMemoryProviderLifecycleRepository::new(),
         )));
@@ -39,7 +71,10 @@ mod tests {
         assert!(controller
             .ensure_provider_available("messaging-provider")
             .is_ok());
-        controller.stop_provider("messaging-provider").unwrap();
+        controller
+            .stop_provider("messaging-provider")
+            .await
+            .unwrap();
         assert!(controller
             .ensure_provider_available("messaging-provider")
             .is_err());
@@ -5,17 +5,24 @@ use wasmatrix_core::{CoreError, Result};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProviderState {
     Running,
+    /// Draining in-flight invocations; new invocations are rejected
+    Stopping,
     Stopped,
 }
 
 pub trait ProviderLifecycleRepository: Send + Sync {
     fn upsert_state(&self, provider_id: &str, state: ProviderState) -> Result<()>;
     fn get_state(&self, provider_id: &str) -> Result<Option<ProviderState>>;
+    /// Record the providers `provider_id` needs running before it starts
+    fn set_dependencies(&self, provider_id: &str, depends_on: Vec<String>) -> Result<()>;
+    /// Every registered provider with the providers it depends on
+    fn list_dependencies(&self) -> Result<HashMap<String, Vec<String>>>;
 }
 
 #[derive(Clone, Default)]
 pub struct InMemoryProviderLifecycleRepository {
     states: Arc<RwLock<HashMap<String, ProviderState>>>,
+    dependencies: Arc<RwLock<HashMap<String, Vec<String>>>>,
 }
 
 impl InMemoryProviderLifecycleRepository {
@@ -39,6 +46,21 @@ impl ProviderLifecycleRepository for InMemoryProviderLifecycleRepository {
         })?;
         Ok(states.get(provider_id).copied())
     }
+
+    fn set_dependencies(&self, provider_id: &str, depends_on: Vec<String>) -> Result<()> {
+        let mut dependencies = self.dependencies.write().map_err(|_| {
+            CoreError::InvalidCapabilityAssignment("provider lifecycle lock poisoned".to_string())
+        })?;
+        dependencies.insert(provider_id.to_string(), depends_on);
+        Ok(())
+    }
+
+    fn list_dependencies(&self) -> Result<HashMap<String, Vec<String>>> {
+        let dependencies = self.dependencies.read().map_err(|_| {
+            CoreError::InvalidCapabilityAssignment("provider lifecycle lock poisoned".to_string())
+        })?;
+        Ok(dependencies.clone())
+    }
 }
 
 #[cfg(test)]
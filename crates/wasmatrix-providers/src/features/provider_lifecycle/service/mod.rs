@@ -1,21 +1,191 @@
 use crate::features::provider_lifecycle::repo::{ProviderLifecycleRepository, ProviderState};
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Notify;
 use wasmatrix_core::{CoreError, Result};
 
+/// How long `stop_provider` waits for in-flight invocations before forcing the stop
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Per-provider count of invocations currently running
+#[derive(Default)]
+struct InFlight {
+    counts: Mutex<HashMap<String, usize>>,
+    drained: Notify,
+}
+
+impl InFlight {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, usize>> {
+        self.counts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn count(&self, provider_id: &str) -> usize {
+        self.lock().get(provider_id).copied().unwrap_or(0)
+    }
+}
+
+/// Marks one invocation of a provider as in flight until dropped
+pub struct InvocationGuard {
+    in_flight: Arc<InFlight>,
+    provider_id: String,
+}
+
+impl Drop for InvocationGuard {
+    fn drop(&mut self) {
+        let mut counts = self.in_flight.lock();
+        if let Some(count) = counts.get_mut(&self.provider_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                counts.remove(&self.provider_id);
+            }
+        }
+        drop(counts);
+        self.in_flight.drained.notify_waiters();
+    }
+}
+
 pub struct ProviderLifecycleService {
     repo: Arc<dyn ProviderLifecycleRepository>,
+    in_flight: Arc<InFlight>,
 }
 
 impl ProviderLifecycleService {
     pub fn new(repo: Arc<dyn ProviderLifecycleRepository>) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            in_flight: Arc::new(InFlight::default()),
+        }
     }
 
     pub fn start_provider(&self, provider_id: &str) -> Result<()> {
         self.repo.upsert_state(provider_id, ProviderState::Running)
     }
 
-    pub fn stop_provider(&self, provider_id: &str) -> Result<()> {
+    /// Register a provider with the providers it needs running first.
+    /// Ordering is checked when [`startup_order`](Self::startup_order) runs, so
+    /// providers may be registered in any order.
+    pub fn register_provider(&self, provider_id: &str, depends_on: Vec<String>) -> Result<()> {
+        if provider_id.is_empty() {
+            return Err(CoreError::InvalidCapabilityAssignment(
+                "Provider ID cannot be empty".to_string(),
+            ));
+        }
+        self.repo.set_dependencies(provider_id, depends_on)
+    }
+
+    /// Registered providers ordered so each comes after everything it depends
+    /// on. Ties are broken by provider id so the order is deterministic.
+    /// Fails on a dependency cycle or a dependency that was never registered.
+    pub fn startup_order(&self) -> Result<Vec<String>> {
+        let dependencies = self.repo.list_dependencies()?;
+        let mut pending: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (provider_id, depends_on) in &dependencies {
+            let unique: BTreeSet<&str> = depends_on.iter().map(String::as_str).collect();
+            for dependency in &unique {
+                if !dependencies.contains_key(*dependency) {
+                    return Err(CoreError::InvalidCapabilityAssignment(format!(
+                        "Provider '{}' depends on unregistered provider '{}'",
+                        provider_id, dependency
+                    )));
+                }
+                dependents
+                    .entry(*dependency)
+                    .or_default()
+                    .push(provider_id.as_str());
+            }
+            pending.insert(provider_id.as_str(), unique.len());
+        }
+
+        let mut ready: BTreeSet<&str> = pending
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(provider_id, _)| *provider_id)
+            .collect();
+        let mut order = Vec::with_capacity(pending.len());
+        while let Some(provider_id) = ready.pop_first() {
+            pending.remove(provider_id);
+            for dependent in dependents.get(provider_id).into_iter().flatten() {
+                if let Some(count) = pending.get_mut(dependent) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.insert(dependent);
+                    }
+                }
+            }
+            order.push(provider_id.to_string());
+        }
+
+        if !pending.is_empty() {
+            let cyclic: Vec<&str> = pending.into_keys().collect();
+            return Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Provider dependency cycle among: {}",
+                cyclic.join(", ")
+            )));
+        }
+        Ok(order)
+    }
+
+    /// Start every registered provider in dependency order; returns the order used
+    pub fn start_all(&self) -> Result<Vec<String>> {
+        let order = self.startup_order()?;
+        for provider_id in &order {
+            self.start_provider(provider_id)?;
+        }
+        Ok(order)
+    }
+
+    /// Gracefully stop every registered provider in reverse dependency order,
+    /// so a provider stops before anything it depends on. Nothing is stopped
+    /// if the dependencies form a cycle. Returns the order used.
+    pub async fn shutdown_all(&self) -> Result<Vec<String>> {
+        let mut order = self.startup_order()?;
+        order.reverse();
+        for provider_id in &order {
+            self.stop_provider(provider_id).await?;
+        }
+        Ok(order)
+    }
+
+    /// Gracefully stop a provider, waiting up to [`DEFAULT_SHUTDOWN_TIMEOUT`]
+    /// for in-flight invocations to finish.
+    pub async fn stop_provider(&self, provider_id: &str) -> Result<()> {
+        self.stop_provider_with_timeout(provider_id, DEFAULT_SHUTDOWN_TIMEOUT)
+            .await
+    }
+
+    /// Mark the provider `Stopping`, wait up to `timeout` for its in-flight
+    /// invocations to drain, then mark it `Stopped`. Invocations still running
+    /// when the timeout expires are not cancelled, but the provider is stopped.
+    pub async fn stop_provider_with_timeout(
+        &self,
+        provider_id: &str,
+        timeout: Duration,
+    ) -> Result<()> {
+        {
+            let _counts = self.in_flight.lock();
+            self.repo
+                .upsert_state(provider_id, ProviderState::Stopping)?;
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let drained = self.in_flight.drained.notified();
+            let remaining = self.in_flight.count(provider_id);
+            if remaining == 0 {
+                break;
+            }
+            if tokio::time::timeout_at(deadline, drained).await.is_err() {
+                tracing::warn!(
+                    provider_id,
+                    in_flight = remaining,
+                    "Provider shutdown timed out with invocations still in flight"
+                );
+                break;
+            }
+        }
+
         self.repo.upsert_state(provider_id, ProviderState::Stopped)
     }
 
@@ -25,6 +195,10 @@ impl ProviderLifecycleService {
                 "Provider '{}' is stopped",
                 provider_id
             ))),
+            Some(ProviderState::Stopping) => Err(CoreError::InvalidCapabilityAssignment(format!(
+                "Provider '{}' is shutting down",
+                provider_id
+            ))),
             Some(ProviderState::Running) => Ok(()),
             None => {
                 // Default behavior: first sighting auto-registers as running.
@@ -32,6 +206,34 @@ impl ProviderLifecycleService {
             }
         }
     }
+
+    /// Whether the provider is registered and running; unlike
+    /// [`Self::ensure_provider_available`] an unknown provider is not registered
+    pub fn is_provider_running(&self, provider_id: &str) -> Result<bool> {
+        Ok(matches!(
+            self.repo.get_state(provider_id)?,
+            Some(ProviderState::Running)
+        ))
+    }
+
+    /// Check the provider is available and count an invocation as in flight
+    /// until the returned guard is dropped.
+    pub fn begin_invocation(&self, provider_id: &str) -> Result<InvocationGuard> {
+        // Holding the counts lock keeps a concurrent stop from slipping in
+        // between the availability check and the increment.
+        let mut counts = self.in_flight.lock();
+        self.ensure_provider_available(provider_id)?;
+        *counts.entry(provider_id.to_string()).or_insert(0) += 1;
+        Ok(InvocationGuard {
+            in_flight: self.in_flight.clone(),
+            provider_id: provider_id.to_string(),
+        })
+    }
+
+    /// Number of invocations of `provider_id` currently in flight
+    pub fn in_flight_invocations(&self, provider_id: &str) -> usize {
+        self.in_flight.count(provider_id)
+    }
 }
 
 #[cfg(test)]
@@ -39,14 +241,14 @@ mod tests {
     use super::*;
     use crate::features::provider_lifecycle::repo::InMemoryProviderLifecycleRepository;
 
-    #[test]
-    fn test_start_and_stop_provider_independently() {
+    #[tokio::test]
+    async fn test_start_and_stop_provider_independently() {
         let service =
             ProviderLifecycleService::new(Arc::new(InMemoryProviderLifecycleRepository::new()));
         service.start_provider("http-provider").unwrap();
         assert!(service.ensure_provider_available("http-provider").is_ok());
 
-        service.stop_provider("http-provider").unwrap();
+        service.stop_provider("http-provider").await.unwrap();
         assert!(service.ensure_provider_available("http-provider").is_err());
     }
 
@@ -57,8 +259,21 @@ mod tests {
         assert!(service.ensure_provider_available("new-provider").is_ok());
     }
 
-    #[test]
-    fn property_graceful_provider_shutdown_handling() {
+    #[tokio::test]
+    async fn test_is_provider_running_leaves_unknown_provider_unregistered() {
+        let service =
+            ProviderLifecycleService::new(Arc::new(InMemoryProviderLifecycleRepository::new()));
+        assert!(!service.is_provider_running("new-provider").unwrap());
+        assert!(!service.is_provider_running("new-provider").unwrap());
+
+        service.start_provider("new-provider").unwrap();
+        assert!(service.is_provider_running("new-provider").unwrap());
+        service.stop_provider("new-provider").await.unwrap();
+        assert!(!service.is_provider_running("new-provider").unwrap());
+    }
+
+    #[tokio::test]
+    async fn property_graceful_provider_shutdown_handling() {
         let service =
             ProviderLifecycleService::new(Arc::new(InMemoryProviderLifecycleRepository::new()));
 
@@ -67,11 +282,90 @@ mod tests {
             service.start_provider(&provider_id).unwrap();
             assert!(service.ensure_provider_available(&provider_id).is_ok());
 
-            service.stop_provider(&provider_id).unwrap();
+            service.stop_provider(&provider_id).await.unwrap();
             assert!(service.ensure_provider_available(&provider_id).is_err());
 
             service.start_provider(&provider_id).unwrap();
             assert!(service.ensure_provider_available(&provider_id).is_ok());
         }
     }
+
+    #[tokio::test]
+    async fn test_stop_waits_for_in_flight_invocation() {
+        let service = Arc::new(ProviderLifecycleService::new(Arc::new(
+            InMemoryProviderLifecycleRepository::new(),
+        )));
+        let guard = service.begin_invocation("kv-provider").unwrap();
+        assert_eq!(service.in_flight_invocations("kv-provider"), 1);
+
+        let stopping = {
+            let service = service.clone();
+            tokio::spawn(async move { service.stop_provider("kv-provider").await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(!stopping.is_finished());
+        let rejected = service.begin_invocation("kv-provider");
+        assert!(rejected
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("shutting down"));
+
+        drop(guard);
+        stopping.await.unwrap().unwrap();
+        assert_eq!(service.in_flight_invocations("kv-provider"), 0);
+        assert!(service
+            .ensure_provider_available("kv-provider")
+            .unwrap_err()
+            .to_string()
+            .contains("stopped"));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_all_stops_dependents_first() {
+        let service =
+            ProviderLifecycleService::new(Arc::new(InMemoryProviderLifecycleRepository::new()));
+        service
+            .register_provider("c", vec!["b".to_string()])
+            .unwrap();
+        service.register_provider("a", vec![]).unwrap();
+        service
+            .register_provider("b", vec!["a".to_string()])
+            .unwrap();
+
+        assert_eq!(service.start_all().unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(service.shutdown_all().await.unwrap(), vec!["c", "b", "a"]);
+        for provider_id in ["a", "b", "c"] {
+            assert!(service.ensure_provider_available(provider_id).is_err());
+        }
+
+        service
+            .register_provider("a", vec!["c".to_string()])
+            .unwrap();
+        service.start_provider("c").unwrap();
+        let cycle = service.shutdown_all().await.unwrap_err().to_string();
+        assert!(cycle.contains("cycle"), "{cycle}");
+        assert!(service.ensure_provider_available("c").is_ok());
+
+        service
+            .register_provider("a", vec!["missing".to_string()])
+            .unwrap();
+        assert!(service.startup_order().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stop_gives_up_waiting_after_timeout() {
+        let service =
+            ProviderLifecycleService::new(Arc::new(InMemoryProviderLifecycleRepository::new()));
+        let _guard = service.begin_invocation("http-provider").unwrap();
+
+        service
+            .stop_provider_with_timeout("http-provider", Duration::from_millis(20))
+            .await
+            .unwrap();
+
+        assert!(service.ensure_provider_available("http-provider").is_err());
+        assert_eq!(service.in_flight_invocations("http-provider"), 1);
+    }
 }
@@ -3,12 +3,19 @@ use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use wasmatrix_core::{CapabilityAssignment, CoreError, ProviderType, Result};
 
-use crate::{CapabilityProvider, ProviderMetadata};
+use crate::{
+    deny_without_permissions, enforce_result_size, operation_names, CapabilityProvider,
+    ProviderMetadata, DEFAULT_MAX_RESULT_BYTES,
+};
+
+/// Operations accepted by the KV provider
+const KV_OPERATIONS: &[&str] = &["get", "set", "delete", "list", "exists", "batch"];
 
 /// Thread-safe KV Provider with in-memory storage and permission validation
 pub struct KvProvider {
     storage: Arc<RwLock<HashMap<String, String>>>,
     metadata: ProviderMetadata,
+    max_result_bytes: usize,
 }
 
 impl KvProvider {
@@ -19,10 +26,18 @@ impl KvProvider {
                 provider_id,
                 provider_type: ProviderType::Kv,
                 version: "0.1.0".to_string(),
+                operations: operation_names(KV_OPERATIONS),
             },
+            max_result_bytes: DEFAULT_MAX_RESULT_BYTES,
         }
     }
 
+    /// Limit the serialized size of invocation results
+    pub fn with_max_result_bytes(mut self, max_result_bytes: usize) -> Self {
+        self.max_result_bytes = max_result_bytes;
+        self
+    }
+
     /// Validate that the capability assignment has the required permission
     fn validate_permission(
         &self,
@@ -30,7 +45,7 @@ impl KvProvider {
         operation: &str,
     ) -> Result<()> {
         let required_permission = match operation {
-            "get" | "list" => "kv:read",
+            "get" | "list" | "exists" => "kv:read",
             "set" => "kv:write",
             "delete" => "kv:delete",
             _ => {
@@ -112,10 +127,39 @@ impl CapabilityProvider for KvProvider {
         self.clear()
     }
 
-    fn invoke(&self, _instance_id: &str, operation: &str, params: Value) -> Result<Value> {
-        // For now, we assume the caller has already validated permissions
-        // In a real implementation, we'd look up the capability assignment here
+    fn invoke(
+        &self,
+        assignment: &CapabilityAssignment,
+        operation: &str,
+        params: Value,
+    ) -> Result<Value> {
+        deny_without_permissions(assignment)?;
+        let result = match operation {
+            "batch" => self.invoke_batch(assignment, &params),
+            _ => {
+                self.validate_permission(assignment, operation)?;
+                self.dispatch(operation, &params)
+            }
+        }?;
+        enforce_result_size(result, self.max_result_bytes)
+    }
 
+    fn shutdown(&mut self) -> Result<()> {
+        self.clear()
+    }
+
+    fn get_metadata(&self) -> ProviderMetadata {
+        self.metadata.clone()
+    }
+
+    fn supported_operations(&self) -> Vec<String> {
+        self.metadata.operations.clone()
+    }
+}
+
+impl KvProvider {
+    /// Run one operation; callers check permissions first
+    fn dispatch(&self, operation: &str, params: &Value) -> Result<Value> {
         match operation {
             "get" => {
                 let key = params["key"].as_str().ok_or_else(|| {
@@ -161,12 +205,50 @@ impl CapabilityProvider for KvProvider {
         }
     }
 
-    fn shutdown(&mut self) -> Result<()> {
-        self.clear()
-    }
+    /// Run `params.operations`, an array of `{ "operation", "params" }`, with
+    /// one permission check per distinct operation instead of one per item.
+    ///
+    /// Each item yields `{ "success": true, "result" }` or
+    /// `{ "success": false, "error" }`. The batch stops after the first failure
+    /// unless `params.continue_on_error` is true.
+    fn invoke_batch(&self, assignment: &CapabilityAssignment, params: &Value) -> Result<Value> {
+        let operations = params["operations"].as_array().ok_or_else(|| {
+            CoreError::InvalidCapabilityAssignment("Missing 'operations' parameter".to_string())
+        })?;
+        let continue_on_error = params["continue_on_error"].as_bool().unwrap_or(false);
+
+        let mut checked = std::collections::HashSet::new();
+        for item in operations {
+            let operation = item["operation"].as_str().ok_or_else(|| {
+                CoreError::InvalidCapabilityAssignment(
+                    "Missing 'operation' in batch item".to_string(),
+                )
+            })?;
+            if checked.insert(operation) {
+                self.validate_permission(assignment, operation)?;
+            }
+        }
 
-    fn get_metadata(&self) -> ProviderMetadata {
-        self.metadata.clone()
+        let mut results = Vec::with_capacity(operations.len());
+        for item in operations {
+            let operation = item["operation"].as_str().unwrap_or_default();
+            match self.dispatch(operation, &item["params"]) {
+                Ok(result) => results.push(serde_json::json!({
+                    "success": true,
+                    "result": result,
+                })),
+                Err(error) => {
+                    results.push(serde_json::json!({
+                        "success": false,
+                        "error": error.to_string(),
+                    }));
+                    if !continue_on_error {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(Value::Array(results))
     }
 }
 
@@ -276,6 +358,18 @@ mod tests {
         assert_eq!(provider.list("").unwrap().len(), 0);
     }
 
+    #[test]
+    fn test_wildcard_grant_is_enforced_like_it_is_reported() {
+        let provider = create_test_provider();
+        let assignment = create_test_assignment(vec!["kv:*"]);
+        for operation in ["get", "list", "exists", "set", "delete"] {
+            assert!(
+                provider.validate_permission(&assignment, operation).is_ok(),
+                "{operation}"
+            );
+        }
+    }
+
     #[test]
     fn test_permission_validation() {
         let provider = create_test_provider();
@@ -345,7 +439,9 @@ mod tests {
             .unwrap();
 
         let params = serde_json::json!({"key": "testkey"});
-        let result = provider.invoke("instance-1", "get", params).unwrap();
+        let result = provider
+            .invoke(&create_test_assignment(vec!["kv:read"]), "get", params)
+            .unwrap();
 
         assert_eq!(result, Value::String("testvalue".to_string()));
     }
@@ -355,7 +451,9 @@ mod tests {
         let provider = create_test_provider();
 
         let params = serde_json::json!({"key": "newkey", "value": "newvalue"});
-        let result = provider.invoke("instance-1", "set", params).unwrap();
+        let result = provider
+            .invoke(&create_test_assignment(vec!["kv:write"]), "set", params)
+            .unwrap();
 
         assert_eq!(result, Value::Bool(true));
         assert_eq!(
@@ -372,14 +470,18 @@ mod tests {
             .unwrap();
 
         let params = serde_json::json!({"key": "delete_me"});
-        let result = provider.invoke("instance-1", "delete", params).unwrap();
+        let result = provider
+            .invoke(&create_test_assignment(vec!["kv:delete"]), "delete", params)
+            .unwrap();
 
         assert_eq!(result, Value::Bool(true));
         assert!(!provider.exists("delete_me").unwrap());
 
         // Delete non-existent key
         let params = serde_json::json!({"key": "nonexistent"});
-        let result = provider.invoke("instance-1", "delete", params).unwrap();
+        let result = provider
+            .invoke(&create_test_assignment(vec!["kv:delete"]), "delete", params)
+            .unwrap();
         assert_eq!(result, Value::Bool(false));
     }
 
@@ -397,7 +499,9 @@ mod tests {
             .unwrap();
 
         let params = serde_json::json!({"prefix": "prefix:"});
-        let result = provider.invoke("instance-1", "list", params).unwrap();
+        let result = provider
+            .invoke(&create_test_assignment(vec!["kv:read"]), "list", params)
+            .unwrap();
 
         if let Value::Array(keys) = result {
             assert_eq!(keys.len(), 2);
@@ -414,11 +518,15 @@ mod tests {
             .unwrap();
 
         let params = serde_json::json!({"key": "existing"});
-        let result = provider.invoke("instance-1", "exists", params).unwrap();
+        let result = provider
+            .invoke(&create_test_assignment(vec!["kv:read"]), "exists", params)
+            .unwrap();
         assert_eq!(result, Value::Bool(true));
 
         let params = serde_json::json!({"key": "nonexistent"});
-        let result = provider.invoke("instance-1", "exists", params).unwrap();
+        let result = provider
+            .invoke(&create_test_assignment(vec!["kv:read"]), "exists", params)
+            .unwrap();
         assert_eq!(result, Value::Bool(false));
     }
 
@@ -427,17 +535,21 @@ mod tests {
         let provider = create_test_provider();
 
         // Missing key parameter for get
-        let result = provider.invoke("instance-1", "get", Value::Object(Default::default()));
+        let result = provider.invoke(
+            &create_test_assignment(vec!["kv:read"]),
+            "get",
+            serde_json::json!({}),
+        );
         assert!(result.is_err());
 
         // Missing key parameter for set
         let params = serde_json::json!({"value": "test"});
-        let result = provider.invoke("instance-1", "set", params);
+        let result = provider.invoke(&create_test_assignment(vec!["kv:write"]), "set", params);
         assert!(result.is_err());
 
         // Missing value parameter for set
         let params = serde_json::json!({"key": "test"});
-        let result = provider.invoke("instance-1", "set", params);
+        let result = provider.invoke(&create_test_assignment(vec!["kv:write"]), "set", params);
         assert!(result.is_err());
     }
 
@@ -445,10 +557,88 @@ mod tests {
     fn test_capability_provider_unknown_operation() {
         let provider = create_test_provider();
 
-        let result = provider.invoke("instance-1", "unknown_op", Value::Null);
+        let result = provider.invoke(
+            &create_test_assignment(vec!["kv:read"]),
+            "unknown_op",
+            serde_json::json!({}),
+        );
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_invoke_without_permissions_is_denied_for_every_operation() {
+        let provider = create_test_provider();
+        provider
+            .set("key".to_string(), "value".to_string())
+            .unwrap();
+
+        for operation in [
+            "get",
+            "set",
+            "delete",
+            "list",
+            "exists",
+            "batch",
+            "unknown_op",
+        ] {
+            let result = provider.invoke(
+                &create_test_assignment(vec![]),
+                operation,
+                serde_json::json!({"key": "key", "value": "other"}),
+            );
+            assert!(
+                matches!(&result, Err(CoreError::PermissionDenied(_))),
+                "{operation}: {result:?}"
+            );
+        }
+        assert_eq!(provider.get("key").unwrap(), Some("value".to_string()));
+    }
+
+    #[test]
+    fn test_invoke_ignores_permissions_declared_in_params() {
+        let provider = create_test_provider();
+        let read_only = create_test_assignment(vec!["kv:read"]);
+
+        let result = provider.invoke(
+            &read_only,
+            "set",
+            serde_json::json!({"key": "k", "value": "v", "permissions": ["kv:write"]}),
+        );
+        assert!(result.is_err());
+
+        let batch = provider.invoke(
+            &read_only,
+            "batch",
+            serde_json::json!({
+                "permissions": ["kv:write"],
+                "operations": [{ "operation": "set", "params": { "key": "k", "value": "v" } }],
+            }),
+        );
+        assert!(batch.is_err());
+        assert!(!provider.exists("k").unwrap());
+    }
+
+    #[test]
+    fn test_capability_provider_rejects_oversized_result() {
+        let provider = create_test_provider().with_max_result_bytes(64);
+        provider.set("big".to_string(), "x".repeat(128)).unwrap();
+        provider.set("small".to_string(), "x".to_string()).unwrap();
+
+        let result = provider.invoke(
+            &create_test_assignment(vec!["kv:read"]),
+            "get",
+            serde_json::json!({"key": "big"}),
+        );
+        assert!(matches!(result, Err(CoreError::ResourceExhausted(_))));
+
+        let result = provider.invoke(
+            &create_test_assignment(vec!["kv:read"]),
+            "get",
+            serde_json::json!({"key": "small"}),
+        );
+        assert!(result.is_ok());
+    }
+
     #[test]
     fn test_provider_shutdown() {
         let mut provider = create_test_provider();
@@ -465,6 +655,70 @@ mod tests {
         assert_eq!(provider.list("").unwrap().len(), 0);
     }
 
+    #[test]
+    fn test_batch_of_sets_returns_one_result_each() {
+        let provider = create_test_provider();
+        let result = provider
+            .invoke(
+                &create_test_assignment(vec!["kv:write"]),
+                "batch",
+                serde_json::json!({
+                    "operations": [
+                        { "operation": "set", "params": { "key": "a", "value": "1" } },
+                        { "operation": "set", "params": { "key": "b", "value": "2" } },
+                        { "operation": "set", "params": { "key": "c", "value": "3" } },
+                    ],
+                }),
+            )
+            .unwrap();
+
+        let results = result.as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r["success"] == true));
+        assert_eq!(provider.get("c").unwrap(), Some("3".to_string()));
+    }
+
+    #[test]
+    fn test_batch_checks_permissions_and_honours_continue_on_error() {
+        let provider = create_test_provider();
+        let operations = serde_json::json!([
+            { "operation": "set", "params": { "key": "a" } },
+            { "operation": "get", "params": { "key": "a" } },
+        ]);
+
+        let denied = provider.invoke(
+            &create_test_assignment(vec!["kv:write"]),
+            "batch",
+            serde_json::json!({ "operations": operations }),
+        );
+        assert!(denied.is_err());
+
+        let stopped = provider
+            .invoke(
+                &create_test_assignment(vec!["kv:read", "kv:write"]),
+                "batch",
+                serde_json::json!({
+                    "operations": operations,
+                }),
+            )
+            .unwrap();
+        assert_eq!(stopped.as_array().unwrap().len(), 1);
+        assert_eq!(stopped[0]["success"], false);
+
+        let continued = provider
+            .invoke(
+                &create_test_assignment(vec!["kv:read", "kv:write"]),
+                "batch",
+                serde_json::json!({
+                    "operations": operations,
+                    "continue_on_error": true,
+                }),
+            )
+            .unwrap();
+        assert_eq!(continued.as_array().unwrap().len(), 2);
+        assert_eq!(continued[1]["success"], true);
+    }
+
     #[test]
     fn test_provider_metadata() {
         let provider = create_test_provider();
@@ -473,5 +727,10 @@ mod tests {
         assert_eq!(metadata.provider_id, "test-kv");
         assert_eq!(metadata.provider_type, ProviderType::Kv);
         assert_eq!(metadata.version, "0.1.0");
+        assert_eq!(
+            provider.supported_operations(),
+            vec!["get", "set", "delete", "list", "exists", "batch"]
+        );
+        assert_eq!(metadata.operations, provider.supported_operations());
     }
 }
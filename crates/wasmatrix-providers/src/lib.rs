@@ -1,22 +1,41 @@
 pub mod features;
 pub mod kv_provider;
 
-use wasmatrix_core::Result;
+use wasmatrix_core::{CapabilityAssignment, CoreError, Result};
 
+pub use features::async_invocation::controller::AsyncInvocationController;
 pub use features::http_provider::HttpCapabilityProvider;
+pub use features::introspection_provider::IntrospectionProvider;
+pub use features::invocation_quota::controller::InvocationQuotaController;
 pub use features::messaging_provider::MessagingCapabilityProvider;
 pub use features::provider_lifecycle::controller::ProviderLifecycleController;
 
 pub trait CapabilityProvider {
     fn initialize(&mut self, config: serde_json::Value) -> Result<()>;
+    /// Run `operation` for the instance holding `assignment`. Providers check
+    /// permissions against the assignment only; a `permissions` entry in
+    /// `params` carries no authority.
     fn invoke(
         &self,
-        instance_id: &str,
+        assignment: &CapabilityAssignment,
         operation: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value>;
+    /// Replace the provider's configuration while it is serving invocations.
+    /// `config` is validated in full before anything changes, and invocations
+    /// already running keep the settings they started with. Providers that
+    /// only read their configuration at `initialize` reject this.
+    fn reconfigure(&self, config: serde_json::Value) -> Result<()> {
+        let _ = config;
+        Err(CoreError::InvalidCapabilityAssignment(format!(
+            "Provider {} does not support reconfiguration",
+            self.get_metadata().provider_id
+        )))
+    }
     fn shutdown(&mut self) -> Result<()>;
     fn get_metadata(&self) -> ProviderMetadata;
+    /// Operations `invoke` accepts; anything else is rejected
+    fn supported_operations(&self) -> Vec<String>;
 }
 
 #[derive(Debug, Clone)]
@@ -24,4 +43,79 @@ pub struct ProviderMetadata {
     pub provider_id: String,
     pub provider_type: wasmatrix_core::ProviderType,
     pub version: String,
+    /// Same as [`CapabilityProvider::supported_operations`]
+    pub operations: Vec<String>,
+}
+
+fn operation_names(operations: &[&str]) -> Vec<String> {
+    operations.iter().map(|op| op.to_string()).collect()
+}
+
+/// Default upper bound for a serialized invocation result (1 MiB)
+pub const DEFAULT_MAX_RESULT_BYTES: usize = 1024 * 1024;
+
+/// Fail closed when an invocation's assignment grants no permissions at all,
+/// before the operation or its parameters are looked at
+pub fn deny_without_permissions(assignment: &CapabilityAssignment) -> Result<()> {
+    if assignment
+        .permissions()
+        .iter()
+        .all(|permission| permission.is_empty())
+    {
+        return Err(CoreError::PermissionDenied(
+            "invocation has no assigned permissions".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Reject invocation results whose serialized form exceeds `max_bytes`
+pub fn enforce_result_size(
+    result: serde_json::Value,
+    max_bytes: usize,
+) -> Result<serde_json::Value> {
+    let size = serde_json::to_vec(&result)
+        .map_err(|e| CoreError::SerializationError(e.to_string()))?
+        .len();
+    if size > max_bytes {
+        return Err(CoreError::ResourceExhausted(format!(
+            "Invocation result of {size} bytes exceeds limit of {max_bytes} bytes"
+        )));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enforce_result_size() {
+        let value = serde_json::json!({ "body": "x".repeat(32) });
+
+        assert!(enforce_result_size(value.clone(), 1024).is_ok());
+        assert!(matches!(
+            enforce_result_size(value, 16),
+            Err(CoreError::ResourceExhausted(_))
+        ));
+    }
+
+    #[test]
+    fn test_deny_without_permissions() {
+        let assignment = |permissions: &[&str]| {
+            CapabilityAssignment::new(
+                "instance-1".to_string(),
+                "kv-1".to_string(),
+                wasmatrix_core::ProviderType::Kv,
+                permissions.iter().map(|p| p.to_string()).collect(),
+            )
+        };
+
+        assert!(matches!(
+            deny_without_permissions(&assignment(&[])),
+            Err(CoreError::PermissionDenied(_))
+        ));
+        assert!(deny_without_permissions(&assignment(&[""])).is_err());
+        assert!(deny_without_permissions(&assignment(&["kv:read"])).is_ok());
+    }
 }
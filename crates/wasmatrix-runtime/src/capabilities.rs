@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use wasmatrix_core::{CoreError, Result};
+use wasmatrix_core::{CapabilityAssignment, CoreError, Result};
 use wasmatrix_providers::CapabilityProvider;
 
 pub struct CapabilityManager {
@@ -23,20 +23,23 @@ impl CapabilityManager {
         Ok(())
     }
 
+    /// Invoke the provider registered under `assignment.capability_id`
     pub fn invoke(
         &self,
-        instance_id: &str,
-        capability_id: &str,
+        assignment: &CapabilityAssignment,
         operation: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value> {
-        let provider = self.providers.get(capability_id).ok_or_else(|| {
-            CoreError::InvalidCapabilityAssignment(format!(
-                "Provider '{}' is not registered",
-                capability_id
-            ))
-        })?;
-        provider.invoke(instance_id, operation, params)
+        let provider = self
+            .providers
+            .get(&assignment.capability_id)
+            .ok_or_else(|| {
+                CoreError::InvalidCapabilityAssignment(format!(
+                    "Provider '{}' is not registered",
+                    assignment.capability_id
+                ))
+            })?;
+        provider.invoke(assignment, operation, params)
     }
 }
 
@@ -1,5 +1,5 @@
 use crate::capabilities::CapabilityManager;
-use wasmatrix_core::{CoreError, Result};
+use wasmatrix_core::{CapabilityAssignment, CoreError, Result};
 use wasmatrix_providers::kv_provider::KvProvider;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -62,13 +62,11 @@ impl WasmRuntime {
 
     pub fn invoke_capability(
         &self,
-        instance_id: &str,
-        capability_id: &str,
+        assignment: &CapabilityAssignment,
         operation: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value> {
-        self.capabilities
-            .invoke(instance_id, capability_id, operation, params)
+        self.capabilities.invoke(assignment, operation, params)
     }
 }
 
@@ -118,11 +116,19 @@ mod tests {
         let microkvm_runtime = WasmRuntime::new_with_backend(RuntimeBackend::MicroKvm);
 
         let params = serde_json::json!({"key":"k1","value":"v1"});
+        let assignment = |instance_id: &str| {
+            CapabilityAssignment::new(
+                instance_id.to_string(),
+                "default-kv".to_string(),
+                wasmatrix_core::ProviderType::Kv,
+                vec!["kv:write".to_string()],
+            )
+        };
         assert!(wasmtime_runtime
-            .invoke_capability("inst-1", "default-kv", "set", params.clone())
+            .invoke_capability(&assignment("inst-1"), "set", params.clone())
             .is_ok());
         assert!(microkvm_runtime
-            .invoke_capability("inst-2", "default-kv", "set", params)
+            .invoke_capability(&assignment("inst-2"), "set", params)
             .is_ok());
     }
 }